@@ -0,0 +1,103 @@
+//! Commentary attached to games, rounds and events for replay review, in
+//! the spirit of PGN comments and move symbols in chess.
+
+use std::fmt;
+
+/// A comment with optional tags judging the play it is attached to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation {
+    /// Free text. May be empty when only tags are given.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "String::is_empty")
+    )]
+    pub comment: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub tags: Vec<Tag>,
+}
+
+impl Annotation {
+    /// An annotation with only a comment.
+    pub fn new(comment: impl Into<String>) -> Annotation {
+        Annotation {
+            comment: comment.into(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Adds `tag`, unless the annotation already has it.
+    pub fn with_tag(mut self, tag: Tag) -> Annotation {
+        if !self.has_tag(tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+
+    pub fn has_tag(&self, tag: Tag) -> bool {
+        self.tags.contains(&tag)
+    }
+}
+
+/// Tag symbols first, then the comment: `?! Pushing with a 2-shanten
+/// hand`.
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut words: Vec<&str> = self.tags.iter().map(|tag| tag.symbol()).collect();
+        if !self.comment.is_empty() {
+            words.push(&self.comment);
+        }
+        f.write_str(&words.join(" "))
+    }
+}
+
+/// A judgement of a move or decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tag {
+    Brilliant,
+    Good,
+    Interesting,
+    Dubious,
+    Mistake,
+    Blunder,
+    /// The only reasonable choice.
+    Forced,
+}
+
+impl Tag {
+    /// The chess annotation symbol for the tag, e.g. `?` for a mistake.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Tag::Brilliant => "!!",
+            Tag::Good => "!",
+            Tag::Interesting => "!?",
+            Tag::Dubious => "?!",
+            Tag::Mistake => "?",
+            Tag::Blunder => "??",
+            Tag::Forced => "□",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_come_before_the_comment() {
+        let annotation = Annotation::new("Pushing with a 2-shanten hand")
+            .with_tag(Tag::Dubious)
+            .with_tag(Tag::Dubious);
+        assert_eq!(annotation.tags, vec![Tag::Dubious]);
+        assert_eq!(annotation.to_string(), "?! Pushing with a 2-shanten hand");
+        let tags_only = Annotation::default()
+            .with_tag(Tag::Forced)
+            .with_tag(Tag::Good);
+        assert_eq!(tags_only.to_string(), "□ !");
+        assert_eq!(Annotation::default().to_string(), "");
+    }
+}
@@ -0,0 +1,744 @@
+//! [`proptest`] strategies for tiles, hands, events and games.
+//!
+//! Generated values respect the physical constraints of the tile set: no
+//! hand holds more than four copies of a tile, melds are real sequences,
+//! triplets and quads, and chii is only ever called from the left. Event
+//! sequences are *not* guaranteed to be legal play; they are meant for
+//! exercising parsers and serializers.
+
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::annotation::{Annotation, Tag};
+use crate::game::{
+    AbortiveDraw, EarlyEnd, Game, GameConfig, Length, NumPlayers, Player, PlayerLocation, Round,
+    RoundAction, RoundConfig, RoundEvent, RoundNumber, Seating, Variation,
+};
+use crate::hand::{AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, Meld, OpenKanMeld, PonMeld};
+use crate::rules::{AbortiveDraws, Kuikae, RuleSet, SanmaTsumo, Tobi, UmaOka};
+use crate::scoring::{DoraCount, Limit, Payment, WinDetails, WinType};
+use crate::tile::{self, Direction, DragonColor, MahjongTile, ObservedTile, RedFive, Tile34};
+use crate::yaku::{Yaku, Yakuman};
+
+use crate::wall::WallSetup;
+
+impl Arbitrary for MahjongTile {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        proptest::sample::select(tile::full_set(RedFive::Four)).boxed()
+    }
+}
+
+impl Arbitrary for ObservedTile {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        proptest::option::of(any::<MahjongTile>())
+            .prop_map(ObservedTile::from)
+            .boxed()
+    }
+}
+
+impl Arbitrary for PlayerLocation {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(PlayerLocation::Hero),
+            Just(PlayerLocation::Right),
+            Just(PlayerLocation::Across),
+            Just(PlayerLocation::Left),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Direction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(Direction::East),
+            Just(Direction::South),
+            Just(Direction::West),
+            Just(Direction::North),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Hand {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// A 13-tile hand with up to four melds.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        hand_with_tiles(13)
+    }
+}
+
+/// Hands holding `size` tiles in total (13 or 14), counting each meld as
+/// three tiles.
+pub fn hand_with_tiles(size: usize) -> BoxedStrategy<Hand> {
+    (
+        Just(tile::full_set(RedFive::Three)).prop_shuffle(),
+        vec(meld_spec(), 0..=4),
+    )
+        .prop_map(move |(pool, specs)| build_hand(pool, &specs, size))
+        .boxed()
+}
+
+/// Fully concealed hands of `size` tiles, such as a dealt starting hand.
+pub fn concealed_hand(size: usize) -> BoxedStrategy<Hand> {
+    Just(tile::full_set(RedFive::Three))
+        .prop_shuffle()
+        .prop_map(move |pool| Hand {
+            concealed: pool.into_iter().take(size).collect(),
+            melds: vec![],
+        })
+        .boxed()
+}
+
+/// A meld to try to build: its kind, the Tile34 index it starts at, and
+/// where a called tile came from.
+#[derive(Debug, Clone, Copy)]
+struct MeldSpec {
+    kind: u8,
+    base: u8,
+    source: PlayerLocation,
+}
+
+fn meld_spec() -> impl Strategy<Value = MeldSpec> {
+    (
+        0..5u8,
+        0..Tile34::COUNT as u8,
+        prop_oneof![
+            Just(PlayerLocation::Right),
+            Just(PlayerLocation::Across),
+            Just(PlayerLocation::Left),
+        ],
+    )
+        .prop_map(|(kind, base, source)| MeldSpec { kind, base, source })
+}
+
+fn build_hand(mut pool: Vec<MahjongTile>, specs: &[MeldSpec], size: usize) -> Hand {
+    let melds: Vec<Meld> = specs
+        .iter()
+        .filter_map(|spec| build_meld(&mut pool, spec))
+        .collect();
+    let concealed = pool.into_iter().take(size - 3 * melds.len()).collect();
+    Hand { concealed, melds }
+}
+
+/// Builds a meld from tiles in `pool`, removing them, or returns `None`
+/// if the pool can't supply it.
+fn build_meld(pool: &mut Vec<MahjongTile>, spec: &MeldSpec) -> Option<Meld> {
+    let kinds: Vec<u8> = match spec.kind {
+        0 => {
+            let start = spec.base;
+            if start >= 27 || start % 9 > 6 {
+                return None;
+            }
+            vec![start, start + 1, start + 2]
+        }
+        1 => vec![spec.base; 3],
+        _ => vec![spec.base; 4],
+    };
+    let mut taken = Vec::new();
+    for kind in kinds {
+        match pool.iter().position(|t| Tile34::from(t) == Tile34(kind)) {
+            Some(pos) => taken.push(pool.remove(pos)),
+            None => {
+                pool.extend(taken);
+                return None;
+            }
+        }
+    }
+    let meld = match spec.kind {
+        0 => Meld::Chii(ChiiMeld {
+            tiles: [taken[0], taken[1], taken[2]],
+            chii_tile: taken[spec.base as usize % 3],
+            source: PlayerLocation::Left,
+        }),
+        1 => Meld::Pon(PonMeld {
+            tiles: [taken[0], taken[1], taken[2]],
+            pon_tile: taken[0],
+            source: spec.source,
+        }),
+        2 => Meld::OpenKan(OpenKanMeld {
+            tiles: [taken[0], taken[1], taken[2], taken[3]],
+            kan_tile: taken[0],
+            source: spec.source,
+        }),
+        3 => Meld::ClosedKan(ClosedKanMeld {
+            tiles: [taken[0], taken[1], taken[2], taken[3]],
+        }),
+        _ => Meld::AddedKan(AddedKanMeld {
+            pon: PonMeld {
+                tiles: [taken[0], taken[1], taken[2]],
+                pon_tile: taken[0],
+                source: spec.source,
+            },
+            added_tile: taken[3],
+        }),
+    };
+    Some(meld)
+}
+
+impl Arbitrary for RoundEvent {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// An event with up to two levels of variations below it.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let event = (
+            any::<PlayerLocation>(),
+            round_action(),
+            proptest::option::of((0..60_000u64).prop_map(Duration::from_millis)),
+            proptest::option::of(0..2_000_000_000i64),
+            proptest::option::of(any::<bool>()),
+            proptest::option::of(any::<Annotation>()),
+        )
+            .prop_map(
+                |(subject, action, elapsed, timestamp, tsumogiri, annotation)| RoundEvent {
+                    subject,
+                    action,
+                    elapsed,
+                    time: timestamp.and_then(|t| Local.timestamp_opt(t, 0).single()),
+                    tsumogiri,
+                    annotation,
+                    variations: Vec::new(),
+                },
+            )
+            .boxed();
+        event
+            .clone()
+            .prop_recursive(2, 12, 3, move |inner| {
+                let variation = (vec(inner, 0..3), proptest::option::of(any::<Annotation>()))
+                    .prop_map(|(events, annotation)| Variation { events, annotation });
+                (event.clone(), vec(variation, 1..3)).prop_map(|(mut event, variations)| {
+                    event.variations = variations;
+                    event
+                })
+            })
+            .boxed()
+    }
+}
+
+fn round_action() -> impl Strategy<Value = RoundAction> {
+    let meld_action = hand_with_tiles(14).prop_filter_map("hand has no melds", |hand| {
+        let action = match hand.melds.into_iter().next()? {
+            Meld::Chii(meld) => RoundAction::Chii(meld),
+            Meld::Pon(meld) => RoundAction::Pon(meld),
+            Meld::OpenKan(meld) => RoundAction::OpenKan(meld),
+            Meld::ClosedKan(meld) => RoundAction::ClosedKan(meld),
+            Meld::AddedKan(meld) => RoundAction::AddedKan(meld),
+        };
+        Some(action)
+    });
+    prop_oneof![
+        4 => any::<ObservedTile>().prop_map(RoundAction::Draw),
+        4 => any::<MahjongTile>().prop_map(RoundAction::Discard),
+        1 => Just(RoundAction::Riichi),
+        1 => Just(RoundAction::Kita),
+        1 => meld_action,
+        1 => (hand_with_tiles(14), proptest::option::of(any::<WinDetails>()))
+            .prop_map(|(hand, details)| RoundAction::Tsumo(hand, details)),
+        1 => (hand_with_tiles(14), proptest::option::of(any::<WinDetails>()))
+            .prop_map(|(hand, details)| RoundAction::Ron(hand, details)),
+        1 => vec((any::<PlayerLocation>(), any::<Hand>()), 0..4).prop_map(RoundAction::Exhaustive),
+        1 => prop_oneof![
+            Just(AbortiveDraw::KyuushuKyuuhai),
+            Just(AbortiveDraw::SuufonRenda),
+            Just(AbortiveDraw::SuuchaRiichi),
+            Just(AbortiveDraw::Suukaikan),
+            Just(AbortiveDraw::SanchaHou),
+        ]
+        .prop_map(RoundAction::AbortiveDraw),
+    ]
+}
+
+impl Arbitrary for Yaku {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let dragon = prop_oneof![
+            Just(DragonColor::White),
+            Just(DragonColor::Green),
+            Just(DragonColor::Red),
+        ];
+        let yakuman = proptest::sample::select(vec![
+            Yakuman::KokushiMusou,
+            Yakuman::KokushiMusouJuusanmen,
+            Yakuman::Suuankou,
+            Yakuman::SuuankouTanki,
+            Yakuman::Daisangen,
+            Yakuman::Shousuushii,
+            Yakuman::Daisuushii,
+            Yakuman::Tsuuiisou,
+            Yakuman::Chinroutou,
+            Yakuman::Ryuuiisou,
+            Yakuman::ChuurenPoutou,
+            Yakuman::JunseiChuurenPoutou,
+            Yakuman::Suukantsu,
+            Yakuman::Tenhou,
+            Yakuman::Chiihou,
+        ]);
+        prop_oneof![
+            proptest::sample::select(vec![
+                Yaku::MenzenTsumo,
+                Yaku::Riichi,
+                Yaku::DoubleRiichi,
+                Yaku::Ippatsu,
+                Yaku::Chankan,
+                Yaku::RinshanKaihou,
+                Yaku::HaiteiRaoyue,
+                Yaku::HouteiRaoyui,
+                Yaku::Pinfu,
+                Yaku::Tanyao,
+                Yaku::Iipeikou,
+                Yaku::Chiitoitsu,
+                Yaku::Chanta,
+                Yaku::Ittsuu,
+                Yaku::SanshokuDoujun,
+                Yaku::SanshokuDoukou,
+                Yaku::Sankantsu,
+                Yaku::Toitoi,
+                Yaku::Sanankou,
+                Yaku::Shousangen,
+                Yaku::Honroutou,
+                Yaku::Ryanpeikou,
+                Yaku::Junchan,
+                Yaku::Honitsu,
+                Yaku::Chinitsu,
+            ]),
+            any::<Direction>().prop_map(Yaku::SeatWind),
+            any::<Direction>().prop_map(Yaku::RoundWind),
+            dragon.prop_map(Yaku::Dragon),
+            yakuman.prop_map(Yaku::Yakuman),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for WinDetails {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// The details of a win, with no regard for whether they add up.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let limit = prop_oneof![
+            Just(Limit::Mangan),
+            Just(Limit::Haneman),
+            Just(Limit::Baiman),
+            Just(Limit::Sanbaiman),
+            (1..=6u8).prop_map(Limit::Yakuman),
+        ];
+        let payment = (
+            prop_oneof![Just(WinType::Ron), Just(WinType::Tsumo)],
+            any::<bool>(),
+            [0..50_000u32, 0..50_000u32, 0..50_000u32, 0..10_000u32],
+            proptest::option::of(limit),
+        )
+            .prop_map(|(win_type, dealer, points, limit)| {
+                let [from_discarder, from_dealer, from_non_dealer, deposits] = points;
+                Payment {
+                    win_type,
+                    dealer,
+                    from_discarder,
+                    from_dealer,
+                    from_non_dealer,
+                    deposits,
+                    limit,
+                }
+            });
+        (
+            any::<MahjongTile>(),
+            vec((any::<Yaku>(), 1..=26u8), 0..5),
+            0..=110u8,
+            [0..5u8, 0..3u8, 0..4u8, 0..5u8],
+            payment,
+        )
+            .prop_map(|(winning_tile, yaku, fu, dora, payment)| {
+                let [dora, ura_dora, aka_dora, nuki_dora] = dora;
+                WinDetails {
+                    winning_tile,
+                    yaku,
+                    fu,
+                    dora: DoraCount {
+                        dora,
+                        ura_dora,
+                        aka_dora,
+                        nuki_dora,
+                    },
+                    payment,
+                }
+            })
+            .boxed()
+    }
+}
+
+/// Free text of up to `max` characters for names and comments, including
+/// the characters the text formats have to escape or quote.
+fn text(max: usize) -> BoxedStrategy<String> {
+    proptest::string::string_regex(&format!(
+        "[A-Za-z0-9 .,!?:;=#()\\[\\]{{}}\"\\\\'東é-]{{0,{max}}}"
+    ))
+    .expect("the pattern is valid")
+    .boxed()
+}
+
+/// Points for each seat, such as a game's or a round's result.
+fn scores() -> impl Strategy<Value = Vec<(PlayerLocation, i32)>> {
+    vec((any::<PlayerLocation>(), -50_000..150_000i32), 0..=4)
+}
+
+impl Arbitrary for RoundConfig {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<Direction>(),
+            prop_oneof![
+                Just(RoundNumber::One),
+                Just(RoundNumber::Two),
+                Just(RoundNumber::Three),
+                Just(RoundNumber::Four),
+            ],
+            0..10u8,
+            any::<PlayerLocation>(),
+            vec(any::<MahjongTile>(), 1..=5),
+            vec(any::<MahjongTile>(), 0..=5),
+            concealed_hand(13),
+            proptest::option::of(scores()),
+            (
+                proptest::option::of(any::<u64>()),
+                proptest::option::of([1..=6u8, 1..=6u8]),
+                proptest::option::of(0..136usize),
+            )
+                .prop_map(|(seed, dice, break_position)| WallSetup {
+                    seed,
+                    dice,
+                    break_position,
+                }),
+        )
+            .prop_map(
+                |(wind, number, repeat, dealer, dora, ura_dora, hand, result, wall)| RoundConfig {
+                    wind,
+                    number,
+                    repeat,
+                    dealer,
+                    dora,
+                    ura_dora,
+                    initial_hero_hand_state: hand,
+                    result,
+                    wall,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for Round {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<RoundConfig>(),
+            vec(any::<RoundEvent>(), 0..20),
+            proptest::option::of(any::<Annotation>()),
+        )
+            .prop_map(|(config, events, annotation)| Round {
+                config,
+                events,
+                annotation,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for RuleSet {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// One of the presets, or rules set one by one.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let abortive_draws = [any::<bool>(); 5].prop_map(
+            |[kyuushu_kyuuhai, suufon_renda, suucha_riichi, suukaikan, sancha_hou]| AbortiveDraws {
+                kyuushu_kyuuhai,
+                suufon_renda,
+                suucha_riichi,
+                suukaikan,
+                sancha_hou,
+            },
+        );
+        let uma_oka = (
+            0..50_000i32,
+            0..50_000i32,
+            proptest::array::uniform4(-30_000..30_000i32),
+        )
+            .prop_map(|(starting_points, return_points, uma)| UmaOka {
+                starting_points,
+                return_points,
+                uma,
+            });
+        let flags = [any::<bool>(); 10].prop_map(|flags| {
+            let mut rules = RuleSet::default();
+            [
+                rules.kuitan,
+                rules.atozuke,
+                rules.kiriage_mangan,
+                rules.double_yakuman,
+                rules.stack_yakuman,
+                rules.nagashi_mangan,
+                rules.return_deposits_on_abort,
+                rules.multiple_ron,
+                rules.agari_yame,
+                rules.tenpai_yame,
+            ] = flags;
+            rules
+        });
+        let custom = (
+            flags,
+            prop_oneof![
+                Just(Kuikae::Allowed),
+                Just(Kuikae::SameTile),
+                Just(Kuikae::SameTileAndSuji),
+            ],
+            prop_oneof![Just(SanmaTsumo::Loss), Just(SanmaTsumo::Split)],
+            abortive_draws,
+            proptest::option::of(-10_000..100_000i32),
+            prop_oneof![Just(Tobi::Never), Just(Tobi::BelowZero), Just(Tobi::AtZero)],
+            uma_oka,
+        )
+            .prop_map(
+                |(rules, kuikae, sanma_tsumo, abortive_draws, extension_target, tobi, uma_oka)| {
+                    RuleSet {
+                        kuikae,
+                        sanma_tsumo,
+                        abortive_draws,
+                        extension_target,
+                        tobi,
+                        uma_oka,
+                        ..rules
+                    }
+                },
+            );
+        prop_oneof![
+            Just(RuleSet::default()),
+            Just(RuleSet::tenhou()),
+            Just(RuleSet::mahjong_soul()),
+            Just(RuleSet::mleague()),
+            custom,
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Player {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            text(12),
+            proptest::option::of(text(12)),
+            proptest::option::of(text(8)),
+            proptest::option::of(-500..3000i32),
+        )
+            .prop_map(|(name, id, rank, rating)| Player {
+                name,
+                id,
+                rank,
+                rating,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for GameConfig {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let seating = prop_oneof![
+            Just(Seating::Unknown),
+            Just(Seating::Fixed),
+            Just(Seating::Random),
+            [1..=6u8, 1..=6u8].prop_map(Seating::Dice),
+        ];
+        let early_end = prop_oneof![
+            vec(any::<PlayerLocation>(), 0..3).prop_map(EarlyEnd::Tobi),
+            Just(EarlyEnd::AgariYame),
+            Just(EarlyEnd::TenpaiYame),
+        ];
+        let time_control =
+            [0..60_000u64, 0..600_000u64].prop_map(|ms| ms.map(Duration::from_millis));
+        (
+            (text(12), text(12)),
+            proptest::array::uniform4(any::<Player>()),
+            0..2_000_000_000i64,
+            prop_oneof![Just(NumPlayers::Three), Just(NumPlayers::Four)],
+            prop_oneof![
+                Just(Length::OneRound),
+                Just(Length::Tonpuu),
+                Just(Length::Hanchan),
+            ],
+            prop_oneof![
+                Just(RedFive::Zero),
+                Just(RedFive::Three),
+                Just(RedFive::Four),
+            ],
+            any::<RuleSet>(),
+            (
+                time_control,
+                proptest::option::of(scores()),
+                seating,
+                proptest::option::of(any::<PlayerLocation>()),
+                proptest::option::of(early_end),
+            ),
+        )
+            .prop_map(
+                |(
+                    (event, site),
+                    players,
+                    timestamp,
+                    num_players,
+                    length,
+                    red_five,
+                    rules,
+                    rest,
+                )| {
+                    let ([thinking_time, time_bank], result, seating, first_dealer, early_end) =
+                        rest;
+                    GameConfig {
+                        event,
+                        site,
+                        date: Local
+                            .timestamp_opt(timestamp, 0)
+                            .single()
+                            .unwrap_or_else(Local::now),
+                        players,
+                        num_players,
+                        length,
+                        red_five,
+                        thinking_time,
+                        time_bank,
+                        result,
+                        seating,
+                        first_dealer,
+                        early_end,
+                        rules,
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for Annotation {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let tag = prop_oneof![
+            Just(Tag::Brilliant),
+            Just(Tag::Good),
+            Just(Tag::Interesting),
+            Just(Tag::Dubious),
+            Just(Tag::Mistake),
+            Just(Tag::Blunder),
+            Just(Tag::Forced),
+        ];
+        (text(40), vec(tag, 0..3))
+            .prop_map(|(comment, tags)| Annotation { comment, tags })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Game {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<GameConfig>(),
+            vec(any::<Round>(), 0..4),
+            proptest::option::of(any::<Annotation>()),
+        )
+            .prop_map(|(config, rounds, annotation)| Game {
+                config,
+                rounds,
+                annotation,
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_tiles(hand: &Hand) -> Vec<MahjongTile> {
+        let mut tiles = hand.concealed.clone();
+        for meld in &hand.melds {
+            match meld {
+                Meld::Chii(m) => tiles.extend(m.tiles),
+                Meld::Pon(m) => tiles.extend(m.tiles),
+                Meld::OpenKan(m) => tiles.extend(m.tiles),
+                Meld::ClosedKan(m) => tiles.extend(m.tiles),
+                Meld::AddedKan(m) => {
+                    tiles.extend(m.pon.tiles.iter().copied().chain([m.added_tile]))
+                }
+            }
+        }
+        tiles
+    }
+
+    proptest! {
+        #[test]
+        fn tiles_round_trip_through_notation(tile in any::<MahjongTile>()) {
+            prop_assert_eq!(tile::build(&tile.to_string()), Ok(tile));
+        }
+
+        #[test]
+        fn shorthand_round_trips(tiles in vec(any::<MahjongTile>(), 0..20)) {
+            let mut sorted = tiles.clone();
+            tile::sort_hand(&mut sorted);
+            prop_assert_eq!(tile::parse_tiles(&tile::format_tiles(&tiles)), Ok(sorted));
+        }
+
+        #[test]
+        fn hands_respect_tile_counts(hand in any::<Hand>()) {
+            prop_assert_eq!(hand.concealed.len() + 3 * hand.melds.len(), 13);
+            let mut counts = [0u8; Tile34::COUNT];
+            for tile in all_tiles(&hand) {
+                counts[Tile34::from(tile).index()] += 1;
+            }
+            prop_assert!(counts.iter().all(|&c| c <= 4));
+            prop_assert_eq!(hand.validate(), Ok(()));
+            for meld in &hand.melds {
+                if let Meld::Chii(chii) = meld {
+                    prop_assert_eq!(chii.source, PlayerLocation::Left);
+                    prop_assert!(chii.tiles.contains(&chii.chii_tile));
+                }
+            }
+        }
+
+        #[test]
+        fn games_round_trip_through_mjr(game in any::<Game>()) {
+            prop_assert_eq!(Game::from_mjr_str(&game.to_mjr_string()), Ok(game));
+        }
+    }
+}
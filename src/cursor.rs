@@ -0,0 +1,280 @@
+//! Moving back and forth through a round, as a replay viewer does.
+
+use crate::game::{NumPlayers, Round, RoundEvent, Variation};
+use crate::state::{RoundState, StateError};
+
+/// How many events apart the cached states are.
+const CHECKPOINT_INTERVAL: usize = 16;
+
+/// A position in a [`Round`], with the [`RoundState`] after the events
+/// before it.
+///
+/// States are cached every few events as the cursor first passes them, so
+/// seeking anywhere replays only a handful of events.
+///
+/// The cursor follows the round's events until it enters one of the
+/// [`Variation`]s of the next event. It then follows the line the
+/// variation makes: the events before the branch followed by the
+/// variation's own, and positions count along that line.
+#[derive(Debug, Clone)]
+pub struct RoundCursor<'a> {
+    round: &'a Round,
+    /// The events of the line being followed.
+    line: Vec<&'a RoundEvent>,
+    /// The lines left to enter variations, innermost last, each with the
+    /// position it branched at.
+    parents: Vec<(Vec<&'a RoundEvent>, usize)>,
+    /// The state after every [`CHECKPOINT_INTERVAL`] events, starting with
+    /// the deal.
+    checkpoints: Vec<RoundState>,
+    state: RoundState,
+    position: usize,
+}
+
+impl<'a> RoundCursor<'a> {
+    /// A cursor at the start of a four-player round.
+    pub fn new(round: &'a Round) -> RoundCursor<'a> {
+        RoundCursor::with_players(round, NumPlayers::Four)
+    }
+
+    /// A cursor at the start of a round at a table of `num_players`.
+    pub fn with_players(round: &'a Round, num_players: NumPlayers) -> RoundCursor<'a> {
+        let state = RoundState::with_players(&round.config, num_players);
+        RoundCursor {
+            round,
+            line: round.events.iter().collect(),
+            parents: Vec::new(),
+            checkpoints: vec![state.clone()],
+            state,
+            position: 0,
+        }
+    }
+
+    pub fn round(&self) -> &'a Round {
+        self.round
+    }
+
+    /// How many events have been applied.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The state after the events before the cursor.
+    pub fn state(&self) -> &RoundState {
+        &self.state
+    }
+
+    /// Whether every event of the line has been applied.
+    pub fn at_end(&self) -> bool {
+        self.position == self.line.len()
+    }
+
+    /// How many variations deep the cursor is; 0 on the round's own events.
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// The lines that could have been played instead of the next event.
+    pub fn variations(&self) -> &'a [Variation] {
+        self.line
+            .get(self.position)
+            .map_or(&[], |event| &event.variations)
+    }
+
+    /// Follows the `index`th of [`RoundCursor::variations`] from here on,
+    /// without moving. Returns `false` if there is no such variation.
+    pub fn enter_variation(&mut self, index: usize) -> bool {
+        let Some(variation) = self.variations().get(index) else {
+            return false;
+        };
+        let mut line = self.line[..self.position].to_vec();
+        line.extend(&variation.events);
+        let parent = std::mem::replace(&mut self.line, line);
+        self.parents.push((parent, self.position));
+        self.checkpoints
+            .truncate(self.position / CHECKPOINT_INTERVAL + 1);
+        true
+    }
+
+    /// Leaves the innermost variation, going back to where it branched off.
+    /// Returns `false` on the round's own events.
+    pub fn exit_variation(&mut self) -> bool {
+        let Some((line, branch)) = self.parents.pop() else {
+            return false;
+        };
+        self.line = line;
+        self.checkpoints.truncate(branch / CHECKPOINT_INTERVAL + 1);
+        self.seek(branch)
+            .expect("events before the branch have been applied");
+        true
+    }
+
+    /// Applies the next event and returns it, or `None` at the end of the
+    /// line. If the event can't be applied the cursor stays where it is.
+    pub fn advance(&mut self) -> Result<Option<&'a RoundEvent>, StateError> {
+        let Some(&event) = self.line.get(self.position) else {
+            return Ok(None);
+        };
+        self.state.apply(event)?;
+        self.position += 1;
+        if self.position == self.checkpoints.len() * CHECKPOINT_INTERVAL {
+            self.checkpoints.push(self.state.clone());
+        }
+        Ok(Some(event))
+    }
+
+    /// Steps back one event. Returns `false` at the start of the round.
+    pub fn rewind(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.seek(self.position - 1)
+            .expect("events before the cursor have been applied");
+        true
+    }
+
+    /// Moves to just after the first `position` events of the line, or to
+    /// the end if there are fewer. On error the cursor stops before the
+    /// event that couldn't be applied.
+    pub fn seek(&mut self, position: usize) -> Result<(), StateError> {
+        let position = position.min(self.line.len());
+        let checkpoint = (position / CHECKPOINT_INTERVAL).min(self.checkpoints.len() - 1);
+        let start = checkpoint * CHECKPOINT_INTERVAL;
+        if position < self.position || start > self.position {
+            self.state = self.checkpoints[checkpoint].clone();
+            self.position = start;
+        }
+        while self.position < position {
+            self.advance()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerLocation::{self, *};
+    use crate::game::{RoundAction, RoundConfig, RoundNumber};
+    use crate::hand::Hand;
+    use crate::tile::{build, Direction, ObservedTile};
+
+    fn round(turns: usize) -> Round {
+        let mut events = Vec::new();
+        let mut seat = Hero;
+        for _ in 0..turns {
+            let draw = match seat {
+                Hero => ObservedTile::Known(build("5z").unwrap()),
+                _ => ObservedTile::Hidden,
+            };
+            events.push(RoundEvent::new(seat, RoundAction::Draw(draw)));
+            events.push(RoundEvent::new(
+                seat,
+                RoundAction::Discard(build("5z").unwrap()),
+            ));
+            seat = seat.next();
+        }
+        Round {
+            config: RoundConfig {
+                wind: Direction::East,
+                number: RoundNumber::One,
+                repeat: 0,
+                dealer: Hero,
+                dora: vec![build("1m").unwrap()],
+                ura_dora: vec![],
+                initial_hero_hand_state: Hand::parse("123m456p789s1234z").unwrap(),
+                result: None,
+                wall: Default::default(),
+            },
+            events,
+            annotation: None,
+        }
+    }
+
+    fn replayed(round: &Round, position: usize) -> RoundState {
+        let mut state = RoundState::new(&round.config);
+        for event in &round.events[..position] {
+            state.apply(event).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn seeking_matches_a_fresh_replay() {
+        let round = round(30);
+        let mut cursor = RoundCursor::new(&round);
+        assert!(!cursor.rewind());
+        for position in [45, 3, 60, 17, 16, 59, 0, 1000] {
+            cursor.seek(position).unwrap();
+            let position = position.min(round.events.len());
+            assert_eq!(cursor.position(), position);
+            assert_eq!(cursor.state(), &replayed(&round, position));
+        }
+        assert!(cursor.at_end());
+        assert_eq!(cursor.advance(), Ok(None));
+        assert!(cursor.rewind());
+        assert_eq!(cursor.state(), &replayed(&round, 59));
+        let next: Option<PlayerLocation> = cursor.advance().unwrap().map(|e| e.subject);
+        assert_eq!(next, Some(Right));
+    }
+
+    #[test]
+    fn seeking_stops_at_a_bad_event() {
+        let mut round = round(10);
+        round.events[7].subject = Across;
+        let mut cursor = RoundCursor::new(&round);
+        assert_eq!(cursor.seek(12), Err(StateError::OutOfTurn(Across)));
+        assert_eq!(cursor.position(), 7);
+        assert_eq!(cursor.advance(), Err(StateError::OutOfTurn(Across)));
+        cursor.seek(2).unwrap();
+        assert_eq!(cursor.state(), &replayed(&round, 2));
+    }
+
+    #[test]
+    fn variations_branch_off_and_back() {
+        let mut round = round(30);
+        let t = |s: &str| build(s).unwrap();
+        let instead = Variation {
+            events: vec![
+                RoundEvent::new(Hero, RoundAction::Discard(t("1z"))),
+                RoundEvent::new(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                RoundEvent::new(Right, RoundAction::Discard(t("7z"))),
+            ],
+            annotation: None,
+        };
+        let other = Variation {
+            events: vec![RoundEvent::new(Hero, RoundAction::Discard(t("2z")))],
+            annotation: None,
+        };
+        round.events[41] = round.events[41]
+            .clone()
+            .with_variation(instead.clone())
+            .with_variation(other);
+        let mut branched = round.clone();
+        branched.events.truncate(41);
+        branched.events.extend(instead.events);
+
+        let mut cursor = RoundCursor::new(&round);
+        assert!(cursor.variations().is_empty());
+        assert!(!cursor.exit_variation());
+        cursor.seek(41).unwrap();
+        assert_eq!(cursor.variations().len(), 2);
+        assert!(!cursor.enter_variation(2));
+        assert!(cursor.enter_variation(0));
+        assert_eq!((cursor.depth(), cursor.position()), (1, 41));
+        cursor.seek(100).unwrap();
+        assert_eq!(cursor.position(), 44);
+        assert!(cursor.at_end());
+        assert_eq!(cursor.state(), &replayed(&branched, 44));
+        assert!(cursor.rewind());
+        assert_eq!(cursor.state(), &replayed(&branched, 43));
+        cursor.seek(10).unwrap();
+        assert_eq!(cursor.state(), &replayed(&round, 10));
+
+        assert!(cursor.exit_variation());
+        assert_eq!((cursor.depth(), cursor.position()), (0, 41));
+        assert_eq!(cursor.state(), &replayed(&round, 41));
+        cursor.seek(60).unwrap();
+        assert_eq!(cursor.state(), &replayed(&round, 60));
+    }
+}
@@ -0,0 +1,141 @@
+//! One error type for everything in the crate that can fail.
+//!
+//! The entry points that read whole games return [`Result`]: the importers
+//! in [`formats`](crate::formats) and reading MJR text. The building blocks
+//! beneath them, such as tile and hand parsing, the config builders and
+//! replaying a round's state, return their module's precise error type,
+//! and every one of those converts into [`Error`] with `?`, so an
+//! application that just wants to report a failure can return [`Result`]
+//! throughout. Validation reports a list of
+//! [`ValidationError`]s rather than failing, and scoring can't fail.
+
+use std::fmt;
+
+use crate::formats::mjr::MjrError;
+use crate::formats::FormatError;
+use crate::game::{GameConfigError, RoundConfigError};
+use crate::hand::{HandError, HandParseError, MeldError};
+use crate::state::StateError;
+use crate::tile::{Tile34OutOfRange, TileParseError};
+use crate::validate::ValidationError;
+
+/// A result whose error is the crate-wide [`Error`].
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Any error from this crate, wrapping the error of the module it came
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    TileParse(TileParseError),
+    Tile34(Tile34OutOfRange),
+    HandParse(HandParseError),
+    Hand(HandError),
+    Meld(MeldError),
+    GameConfig(GameConfigError),
+    RoundConfig(RoundConfigError),
+    /// An event that couldn't be applied while replaying a round.
+    State(StateError),
+    /// A recorded round breaking the rules.
+    Validation(ValidationError),
+    /// A log in another format that couldn't be converted.
+    Format(FormatError),
+    /// A game in the MJR text format that couldn't be read.
+    Mjr(MjrError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TileParse(e) => e.fmt(f),
+            Error::Tile34(e) => e.fmt(f),
+            Error::HandParse(e) => e.fmt(f),
+            Error::Hand(e) => e.fmt(f),
+            Error::Meld(e) => e.fmt(f),
+            Error::GameConfig(e) => e.fmt(f),
+            Error::RoundConfig(e) => e.fmt(f),
+            Error::State(e) => e.fmt(f),
+            Error::Validation(e) => e.fmt(f),
+            Error::Format(e) => e.fmt(f),
+            Error::Mjr(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::TileParse(e) => Some(e),
+            Error::Tile34(e) => Some(e),
+            Error::HandParse(e) => Some(e),
+            Error::Hand(e) => Some(e),
+            Error::Meld(e) => Some(e),
+            Error::GameConfig(e) => Some(e),
+            Error::RoundConfig(e) => Some(e),
+            Error::State(e) => Some(e),
+            Error::Validation(e) => Some(e),
+            Error::Format(e) => Some(e),
+            Error::Mjr(e) => Some(e),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($error:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$error> for Error {
+                fn from(e: $error) -> Error {
+                    Error::$variant(e)
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    TileParseError => TileParse,
+    Tile34OutOfRange => Tile34,
+    HandParseError => HandParse,
+    HandError => Hand,
+    MeldError => Meld,
+    GameConfigError => GameConfig,
+    RoundConfigError => RoundConfig,
+    StateError => State,
+    ValidationError => Validation,
+    FormatError => Format,
+    MjrError => Mjr,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Round, RoundConfigBuilder};
+    use crate::hand::Hand;
+    use crate::tile;
+
+    #[test]
+    fn module_errors_convert_with_question_mark() {
+        fn hero_hand(hand: &str, dora: &str) -> Result<Hand> {
+            let config = RoundConfigBuilder::new()
+                .hand(Hand::parse(hand)?)
+                .dora_indicator(tile::build(dora)?)
+                .build()?;
+            let round = Round {
+                config,
+                events: vec![],
+                annotation: None,
+            };
+            Ok(round.hero_hand_at(0)?)
+        }
+        assert!(hero_hand("123m456p789s1234z", "1m").is_ok());
+        let err = hero_hand("123m456p789s1234z", "1x").unwrap_err();
+        assert!(matches!(err, Error::TileParse(_)));
+        assert!(std::error::Error::source(&err).is_some());
+        let err = hero_hand("123m", "1m").unwrap_err();
+        assert!(matches!(err, Error::RoundConfig(_)));
+        assert_eq!(
+            err.to_string(),
+            RoundConfigError::WrongHandSize(3).to_string()
+        );
+    }
+}
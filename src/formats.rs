@@ -0,0 +1,103 @@
+//! Converting logs from other replay formats to and from this crate's
+//! [`Game`](crate::game::Game), and writing games in the crate's own
+//! [`mjr`] text format.
+//!
+//! Formats stored as JSON need the `json` feature, and Mahjong Soul records
+//! the `majsoul` feature.
+
+use std::fmt;
+
+use crate::game::{GameConfigError, RoundConfigError};
+use crate::hand::MeldError;
+
+#[cfg(feature = "majsoul")]
+pub mod majsoul;
+#[cfg(feature = "json")]
+pub mod mjai;
+pub mod mjr;
+#[cfg(feature = "json")]
+mod table;
+#[cfg(feature = "json")]
+pub mod tenhou_json;
+
+/// Why a log could not be converted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The input couldn't be read; the I/O error's message.
+    Io(String),
+    /// The input is not well-formed JSON; the parser's message.
+    Json(String),
+    /// A field is missing or doesn't have the expected type.
+    Field(&'static str),
+    /// The log was to be seen from a player who isn't at its table.
+    NoSuchPlayer(usize),
+    /// A tile written in a way the format doesn't use.
+    UnknownTile(String),
+    /// A call, kan or other string action that couldn't be read.
+    UnknownCall(String),
+    /// A yaku name or id the format doesn't define.
+    UnknownYaku(String),
+    /// A round ending the format names but this crate doesn't know.
+    UnknownResult(String),
+    /// The actions of a round, counted from 0, don't fit together.
+    Inconsistent { round: usize, reason: &'static str },
+    /// The log uses a rule this crate can't represent.
+    Unsupported(&'static str),
+    /// A call that doesn't make a valid meld.
+    InvalidMeld(MeldError),
+    /// A round whose setup this crate rejects.
+    InvalidRound(RoundConfigError),
+    /// A game whose settings this crate rejects.
+    InvalidGame(GameConfigError),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Io(message) => write!(f, "could not read the log: {}", message),
+            FormatError::Json(message) => write!(f, "invalid JSON: {}", message),
+            FormatError::Field(name) => write!(f, "missing or malformed field {}", name),
+            FormatError::NoSuchPlayer(p) => write!(f, "nobody sits at player {}", p),
+            FormatError::UnknownTile(tile) => write!(f, "unknown tile {}", tile),
+            FormatError::UnknownCall(call) => write!(f, "unknown call {}", call),
+            FormatError::UnknownYaku(yaku) => write!(f, "unknown yaku {}", yaku),
+            FormatError::UnknownResult(result) => write!(f, "unknown round result {}", result),
+            FormatError::Inconsistent { round, reason } => {
+                write!(f, "round {}: {}", round, reason)
+            }
+            FormatError::Unsupported(rule) => write!(f, "unsupported rule: {}", rule),
+            FormatError::InvalidMeld(e) => e.fmt(f),
+            FormatError::InvalidRound(e) => e.fmt(f),
+            FormatError::InvalidGame(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FormatError::InvalidMeld(e) => Some(e),
+            FormatError::InvalidRound(e) => Some(e),
+            FormatError::InvalidGame(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<MeldError> for FormatError {
+    fn from(e: MeldError) -> FormatError {
+        FormatError::InvalidMeld(e)
+    }
+}
+
+impl From<RoundConfigError> for FormatError {
+    fn from(e: RoundConfigError) -> FormatError {
+        FormatError::InvalidRound(e)
+    }
+}
+
+impl From<GameConfigError> for FormatError {
+    fn from(e: GameConfigError) -> FormatError {
+        FormatError::InvalidGame(e)
+    }
+}
@@ -0,0 +1,929 @@
+//! Mahjong Soul game records.
+//!
+//! The client fetches a record as protobuf messages of its `lq` package: a
+//! `RecordGame` head and the game's actions, each a message such as
+//! `RecordNewRound`, `RecordDealTile`, `RecordDiscardTile`,
+//! `RecordChiPengGang` or `RecordHule`. This module reads the record once
+//! decoded to JSON, as record downloaders save it:
+//!
+//! ```json
+//! {
+//!     "head": {"config": {"mode": {"mode": 2}}, "accounts": [...], ...},
+//!     "actions": [{"name": ".lq.RecordNewRound", "data": {...}}, ...]
+//! }
+//! ```
+//!
+//! Field names may be the proto's own (`is_liqi`) or the lowerCamelCase of
+//! protobuf's JSON mapping (`isLiqi`), and fields at their default value
+//! may be left out, as the mapping does. Tiles are written in MPSZ
+//! shorthand with `0` for red fives, and players are numbered by seat from
+//! the first East.
+//!
+//! As with [`tenhou_json`](super::tenhou_json), the game is seen from one
+//! seat while every hand is followed.
+
+use chrono::{Local, TimeZone};
+use serde_json::Value;
+
+use super::table::{nth_number, nth_wind, points, riichi_deposits, tiles_array, Holding, Table};
+use super::FormatError;
+use crate::game::{
+    AbortiveDraw, Game, GameConfigBuilder, Length, NumPlayers, Player, PlayerLocation, Round,
+    RoundAction, RoundConfigBuilder, RoundEvent,
+};
+use crate::hand::{AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, Meld, OpenKanMeld, PonMeld};
+use crate::rules::RuleSet;
+use crate::scoring::{self, DoraCount, Limit, Payment, WinDetails, WinType};
+use crate::tile::{self, DragonColor, MahjongTile, ObservedTile};
+use crate::yaku::{Yaku, Yakuman};
+
+/// Converts a decoded Mahjong Soul record, seen from the first East
+/// player.
+pub fn parse(json: &str) -> crate::Result<Game> {
+    parse_as(json, 0)
+}
+
+/// As [`parse`], seen from the player in seat `hero`: 0 for the first
+/// East, 1 for the first South and so on.
+pub fn parse_as(json: &str, hero: usize) -> crate::Result<Game> {
+    Ok(import(json, hero)?)
+}
+
+fn import(json: &str, hero: usize) -> Result<Game, FormatError> {
+    let record: Value = serde_json::from_str(json).map_err(|e| FormatError::Json(e.to_string()))?;
+    let head = record.get("head").ok_or(FormatError::Field("head"))?;
+    // 1 and 2 are four-player East and South games, 11 and 12 the
+    // three-player ones.
+    let mode = match head.pointer("/config/mode") {
+        Some(mode) => number(mode, "mode")?,
+        None => 2,
+    };
+    let num_players = if mode > 10 {
+        NumPlayers::Three
+    } else {
+        NumPlayers::Four
+    };
+    if hero >= num_players.count() {
+        return Err(FormatError::NoSuchPlayer(hero));
+    }
+    let table = Table { num_players, hero };
+
+    let actions = record
+        .get("actions")
+        .and_then(Value::as_array)
+        .ok_or(FormatError::Field("actions"))?;
+    let mut logs: Vec<RoundRecord> = Vec::new();
+    for action in actions {
+        let name = action
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or(FormatError::Field("name"))?;
+        let name = name.strip_prefix(".lq.").unwrap_or(name);
+        let data = action.get("data").ok_or(FormatError::Field("data"))?;
+        if name == "RecordNewRound" {
+            logs.push(RoundRecord::new(data, logs.len(), table)?);
+            continue;
+        }
+        logs.last_mut()
+            .ok_or(FormatError::Inconsistent {
+                round: 0,
+                reason: "an action before the first round",
+            })?
+            .apply(name, data)?;
+    }
+    let starts: Vec<Vec<i32>> = logs.iter().map(|log| log.start.clone()).collect();
+    let rounds = logs
+        .into_iter()
+        .enumerate()
+        .map(|(i, log)| log.into_round(starts.get(i + 1)))
+        .collect::<Result<Vec<Round>, FormatError>>()?;
+
+    let mut players = vec![Player::new(""); table.count()];
+    for account in head
+        .get("accounts")
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice)
+    {
+        let player = seat(account, table)?;
+        players[player].name = text(account, "nickname")?.to_string();
+        players[player].id = Some(number(account, "account_id")?.to_string());
+    }
+    let mut config = GameConfigBuilder::new()
+        .site("Mahjong Soul")
+        .num_players(num_players)
+        .players(
+            table
+                .by_seat(&players)
+                .into_iter()
+                .map(|(_, player)| player),
+        )
+        .length(if mode % 10 == 1 {
+            Length::Tonpuu
+        } else {
+            Length::Hanchan
+        })
+        .rules(RuleSet::mahjong_soul())
+        .first_dealer(table.seat(0));
+    if field(head, "start_time").is_some() {
+        let start = number(head, "start_time")? as i64;
+        if let Some(date) = Local.timestamp_opt(start, 0).single() {
+            config = config.date(date);
+        }
+    }
+    if let Some(results) = head.pointer("/result/players").and_then(Value::as_array) {
+        let mut points = vec![0; table.count()];
+        for result in results {
+            let points_field = field(result, "part_point_1")
+                .map_or(Some(0), Value::as_i64)
+                .ok_or(FormatError::Field("part_point_1"))?;
+            points[seat(result, table)?] = points_field as i32;
+        }
+        config = config.result(table.by_seat(&points));
+    }
+    Ok(Game {
+        config: config.build()?,
+        rounds,
+        annotation: None,
+    })
+}
+
+/// A round of the record being turned into events.
+struct RoundRecord {
+    index: usize,
+    table: Table,
+    /// Rounds of winds played before this one, and rounds of that wind.
+    chang: usize,
+    ju: usize,
+    honba: u8,
+    /// Riichi deposits left on the table by earlier rounds.
+    sticks: u8,
+    dealer: usize,
+    start: Vec<i32>,
+    hero_dealt: Hand,
+    dora: Vec<MahjongTile>,
+    ura_dora: Vec<MahjongTile>,
+    players: Vec<Holding>,
+    events: Vec<RoundEvent>,
+    /// The last tile given up by a player, which a ron would win on: a
+    /// discard, or the tile added to a kan.
+    offered: Option<(usize, MahjongTile)>,
+    /// Each player's point change, once the round has ended.
+    deltas: Option<Vec<i32>>,
+}
+
+impl RoundRecord {
+    fn new(data: &Value, index: usize, table: Table) -> Result<RoundRecord, FormatError> {
+        let chang = number(data, "chang")? as usize;
+        let ju = number(data, "ju")? as usize;
+        let dealer = ju % table.count();
+        let mut record = RoundRecord {
+            index,
+            table,
+            chang,
+            ju,
+            honba: number(data, "ben")? as u8,
+            sticks: number(data, "liqibang")? as u8,
+            dealer,
+            start: points(field(data, "scores"), "scores", table)?,
+            hero_dealt: Hand {
+                concealed: Vec::new(),
+                melds: Vec::new(),
+            },
+            dora: Vec::new(),
+            ura_dora: Vec::new(),
+            players: Vec::new(),
+            events: Vec::new(),
+            offered: None,
+            deltas: None,
+        };
+        record.reveal_dora(data)?;
+        if record.dora.is_empty() && field(data, "dora").is_some() {
+            record.dora.push(tile_field(data, "dora")?);
+        }
+        let mut first_draw = None;
+        for (p, name) in ["tiles0", "tiles1", "tiles2", "tiles3"]
+            .into_iter()
+            .enumerate()
+            .take(table.count())
+        {
+            let mut concealed = tiles(data, name)?;
+            // The dealer is dealt their first draw with the hand.
+            if p == dealer && concealed.len() == 14 {
+                first_draw = concealed.pop();
+            }
+            if p == table.hero {
+                record.hero_dealt = Holding {
+                    concealed: concealed.clone(),
+                    ..Holding::default()
+                }
+                .hand(None);
+            }
+            record.players.push(Holding {
+                concealed,
+                ..Holding::default()
+            });
+        }
+        if let Some(tile) = first_draw {
+            record.draw(dealer, tile);
+        }
+        Ok(record)
+    }
+
+    fn inconsistent(&self, reason: &'static str) -> FormatError {
+        FormatError::Inconsistent {
+            round: self.index,
+            reason,
+        }
+    }
+
+    fn push(&mut self, player: usize, action: RoundAction) {
+        self.events
+            .push(RoundEvent::new(self.table.seat(player), action));
+    }
+
+    fn draw(&mut self, p: usize, tile: MahjongTile) {
+        self.players[p].draw(tile);
+        let observed = if self.table.seat(p) == PlayerLocation::Hero {
+            tile.into()
+        } else {
+            ObservedTile::Hidden
+        };
+        self.push(p, RoundAction::Draw(observed));
+    }
+
+    /// Takes the dora indicators from an action's `doras`, which lists all
+    /// of those revealed so far.
+    fn reveal_dora(&mut self, data: &Value) -> Result<(), FormatError> {
+        let dora = tiles(data, "doras")?;
+        if dora.len() > self.dora.len() {
+            self.dora = dora;
+        }
+        Ok(())
+    }
+
+    /// Applies the action called `name`, other than a new round.
+    fn apply(&mut self, name: &str, data: &Value) -> Result<(), FormatError> {
+        if self.deltas.is_some() {
+            return Err(self.inconsistent("actions left after the round ended"));
+        }
+        let p = seat(data, self.table)?;
+        self.reveal_dora(data)?;
+        match name {
+            "RecordDealTile" => self.draw(p, tile_field(data, "tile")?),
+            "RecordDiscardTile" => {
+                let tile = tile_field(data, "tile")?;
+                self.players[p].drawn = None;
+                self.players[p].take(tile, self.index)?;
+                if flag(data, "is_liqi")? || flag(data, "is_wliqi")? {
+                    self.push(p, RoundAction::Riichi);
+                }
+                self.events.push(
+                    RoundEvent::new(self.table.seat(p), RoundAction::Discard(tile))
+                        .with_tsumogiri(flag(data, "moqie")?),
+                );
+                self.offered = Some((p, tile));
+            }
+            "RecordChiPengGang" => self.call(p, data)?,
+            "RecordAnGangAddGang" => self.declare_kan(p, data)?,
+            "RecordBaBei" => {
+                self.players[p].drawn = None;
+                self.players[p].take(tile::NORTH, self.index)?;
+                self.push(p, RoundAction::Kita);
+            }
+            "RecordHule" => self.hule(data)?,
+            "RecordNoTile" => self.no_tile(data)?,
+            "RecordLiuJu" => self.liu_ju(p, data)?,
+            _ => return Err(FormatError::UnknownCall(name.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Player `q` calls the last discard into a chii, pon or open kan.
+    fn call(&mut self, q: usize, data: &Value) -> Result<(), FormatError> {
+        let tiles = tiles(data, "tiles")?;
+        let froms = field(data, "froms")
+            .and_then(Value::as_array)
+            .ok_or(FormatError::Field("froms"))?
+            .iter()
+            .map(|from| from.as_u64().map(|from| from as usize))
+            .collect::<Option<Vec<usize>>>()
+            .ok_or(FormatError::Field("froms"))?;
+        let (from, called) = froms
+            .iter()
+            .zip(&tiles)
+            .find(|(&from, _)| from != q)
+            .map(|(&from, &tile)| (from, tile))
+            .ok_or_else(|| self.inconsistent("a call without a called tile"))?;
+        if self.offered != Some((from, called)) {
+            return Err(self.inconsistent("a call on a tile that wasn't just discarded"));
+        }
+        let source = self.table.source(from, q);
+        let kind = number(data, "type")?;
+        let (meld, action) = match kind {
+            0 => {
+                let meld = ChiiMeld::new(tiles_array(&tiles, "chii")?, called)?;
+                (Meld::Chii(meld.clone()), RoundAction::Chii(meld))
+            }
+            1 => {
+                let meld = PonMeld::new(tiles_array(&tiles, "pon")?, called, source)?;
+                (Meld::Pon(meld.clone()), RoundAction::Pon(meld))
+            }
+            2 => {
+                let meld = OpenKanMeld::new(tiles_array(&tiles, "open kan")?, called, source)?;
+                (Meld::OpenKan(meld.clone()), RoundAction::OpenKan(meld))
+            }
+            _ => return Err(FormatError::UnknownCall(format!("call type {}", kind))),
+        };
+        self.players[q].take_meld(&tiles, Some(called), self.index)?;
+        self.players[q].melds.push(meld);
+        self.push(q, action);
+        Ok(())
+    }
+
+    /// Player `p` declares a closed kan (type 3) or adds to a pon (type 2)
+    /// with the kind of tile in `tiles`.
+    fn declare_kan(&mut self, p: usize, data: &Value) -> Result<(), FormatError> {
+        let kind = tile_field(data, "tiles")?;
+        self.players[p].drawn = None;
+        match number(data, "type")? {
+            3 => {
+                let tiles: Vec<MahjongTile> = self.players[p]
+                    .concealed
+                    .iter()
+                    .filter(|t| t.eq_rank(&kind))
+                    .copied()
+                    .collect();
+                let meld = ClosedKanMeld::new(tiles_array(&tiles, "closed kan")?)?;
+                self.players[p].take_meld(&tiles, None, self.index)?;
+                self.players[p].melds.push(Meld::ClosedKan(meld.clone()));
+                self.push(p, RoundAction::ClosedKan(meld));
+            }
+            2 => {
+                let added = self.players[p]
+                    .concealed
+                    .iter()
+                    .copied()
+                    .find(|t| t.eq_rank(&kind))
+                    .ok_or_else(|| self.inconsistent("an added kan without the fourth tile"))?;
+                let pon = self.players[p]
+                    .melds
+                    .iter()
+                    .position(|m| matches!(m, Meld::Pon(pon) if pon.pon_tile.eq_rank(&added)))
+                    .ok_or_else(|| self.inconsistent("an added kan without a pon"))?;
+                let Meld::Pon(pon_meld) = self.players[p].melds[pon].clone() else {
+                    unreachable!("found a pon");
+                };
+                let meld = AddedKanMeld::new(pon_meld, added)?;
+                self.players[p].take(added, self.index)?;
+                self.players[p].melds[pon] = Meld::AddedKan(meld.clone());
+                self.push(p, RoundAction::AddedKan(meld));
+                self.offered = Some((p, added));
+            }
+            kind => return Err(FormatError::UnknownCall(format!("kan type {}", kind))),
+        }
+        Ok(())
+    }
+
+    /// Ends the round with the wins listed in `hules`. Honba and the riichi
+    /// deposits are credited to the first winner, the one nearest the
+    /// discarder.
+    fn hule(&mut self, data: &Value) -> Result<(), FormatError> {
+        let hules = field(data, "hules")
+            .and_then(Value::as_array)
+            .ok_or(FormatError::Field("hules"))?;
+        let first = self.events.len();
+        for hule in hules {
+            let who = seat(hule, self.table)?;
+            let (win_type, winning_tile, hand) = if flag(hule, "zimo")? {
+                let drawn = self.players[who]
+                    .drawn
+                    .ok_or_else(|| self.inconsistent("tsumo without a draw"))?;
+                (WinType::Tsumo, drawn, self.players[who].hand(None))
+            } else {
+                let (_, tile) = self
+                    .offered
+                    .filter(|&(p, _)| p != who)
+                    .ok_or_else(|| self.inconsistent("ron without a discard"))?;
+                (WinType::Ron, tile, self.players[who].hand(Some(tile)))
+            };
+            if tile_field(hule, "hu_tile")? != winning_tile {
+                return Err(self.inconsistent("a win on a tile that wasn't played last"));
+            }
+            if self.ura_dora.is_empty() {
+                self.ura_dora = tiles(hule, "li_doras")?;
+            }
+            let details = self.win_details(hule, who, win_type, winning_tile)?;
+            let action = match win_type {
+                WinType::Tsumo => RoundAction::Tsumo(hand, Some(details)),
+                WinType::Ron => RoundAction::Ron(hand, Some(details)),
+            };
+            self.push(who, action);
+        }
+        let sticks = self.sticks + riichi_deposits(&self.events).len() as u8;
+        if let Some(RoundEvent {
+            action: RoundAction::Tsumo(_, Some(details)) | RoundAction::Ron(_, Some(details)),
+            ..
+        }) = self.events.get_mut(first)
+        {
+            details.payment = details
+                .payment
+                .with_honba(self.honba)
+                .with_riichi_sticks(sticks);
+        }
+        self.deltas = Some(points(
+            field(data, "delta_scores"),
+            "delta_scores",
+            self.table,
+        )?);
+        Ok(())
+    }
+
+    fn win_details(
+        &self,
+        hule: &Value,
+        who: usize,
+        win_type: WinType,
+        winning_tile: MahjongTile,
+    ) -> Result<WinDetails, FormatError> {
+        let yakuman = flag(hule, "yiman")?;
+        let mut yaku = Vec::new();
+        let mut dora = DoraCount::default();
+        for fan in field(hule, "fans")
+            .and_then(Value::as_array)
+            .ok_or(FormatError::Field("fans"))?
+        {
+            let id = number(fan, "id")?;
+            let han = number(fan, "val")? as u8;
+            match id {
+                31 => dora.dora += han,
+                32 => dora.aka_dora += han,
+                33 => dora.ura_dora += han,
+                34 => dora.nuki_dora += han,
+                _ => {
+                    let found = self
+                        .yaku_numbered(id, who)
+                        .ok_or_else(|| FormatError::UnknownYaku(id.to_string()))?;
+                    yaku.push((found, if yakuman { 13 } else { han }));
+                }
+            }
+        }
+        let han = number(hule, "count")? as u8;
+        let fu = number(hule, "fu")? as u8;
+        let dealer = who == self.dealer;
+        let limit = if yakuman {
+            Some(Limit::Yakuman(han.max(1)))
+        } else {
+            scoring::score(han, fu, dealer, win_type).limit
+        };
+        let paid = |name: &'static str| number(hule, name).map(|x| x as u32);
+        let mut payment = Payment {
+            win_type,
+            dealer,
+            from_discarder: 0,
+            from_dealer: 0,
+            from_non_dealer: 0,
+            deposits: 0,
+            limit,
+        };
+        match win_type {
+            WinType::Ron => payment.from_discarder = paid("point_rong")?,
+            WinType::Tsumo => {
+                if !dealer {
+                    payment.from_dealer = paid("point_zimo_qin")?;
+                }
+                payment.from_non_dealer = paid("point_zimo_xian")?;
+            }
+        }
+        Ok(WinDetails {
+            winning_tile,
+            yaku,
+            fu,
+            dora,
+            payment,
+        })
+    }
+
+    /// The yaku with Mahjong Soul's ID `id`, won by player `who`.
+    fn yaku_numbered(&self, id: u64, who: usize) -> Option<Yaku> {
+        let num_players = self.table.num_players;
+        let yaku = match id {
+            1 => Yaku::MenzenTsumo,
+            2 => Yaku::Riichi,
+            3 => Yaku::Chankan,
+            4 => Yaku::RinshanKaihou,
+            5 => Yaku::HaiteiRaoyue,
+            6 => Yaku::HouteiRaoyui,
+            7 => Yaku::Dragon(DragonColor::White),
+            8 => Yaku::Dragon(DragonColor::Green),
+            9 => Yaku::Dragon(DragonColor::Red),
+            10 => Yaku::SeatWind(
+                self.table
+                    .seat(who)
+                    .seat_wind_in(self.table.seat(self.dealer), num_players),
+            ),
+            11 => Yaku::RoundWind(nth_wind(self.chang)),
+            12 => Yaku::Tanyao,
+            13 => Yaku::Iipeikou,
+            14 => Yaku::Pinfu,
+            15 => Yaku::Chanta,
+            16 => Yaku::Ittsuu,
+            17 => Yaku::SanshokuDoujun,
+            18 => Yaku::DoubleRiichi,
+            19 => Yaku::SanshokuDoukou,
+            20 => Yaku::Sankantsu,
+            21 => Yaku::Toitoi,
+            22 => Yaku::Sanankou,
+            23 => Yaku::Shousangen,
+            24 => Yaku::Honroutou,
+            25 => Yaku::Chiitoitsu,
+            26 => Yaku::Junchan,
+            27 => Yaku::Honitsu,
+            28 => Yaku::Ryanpeikou,
+            29 => Yaku::Chinitsu,
+            30 => Yaku::Ippatsu,
+            35 => Yaku::Yakuman(Yakuman::Tenhou),
+            36 => Yaku::Yakuman(Yakuman::Chiihou),
+            37 => Yaku::Yakuman(Yakuman::Daisangen),
+            38 => Yaku::Yakuman(Yakuman::Suuankou),
+            39 => Yaku::Yakuman(Yakuman::Tsuuiisou),
+            40 => Yaku::Yakuman(Yakuman::Ryuuiisou),
+            41 => Yaku::Yakuman(Yakuman::Chinroutou),
+            42 => Yaku::Yakuman(Yakuman::KokushiMusou),
+            43 => Yaku::Yakuman(Yakuman::Shousuushii),
+            44 => Yaku::Yakuman(Yakuman::Suukantsu),
+            45 => Yaku::Yakuman(Yakuman::ChuurenPoutou),
+            47 => Yaku::Yakuman(Yakuman::JunseiChuurenPoutou),
+            48 => Yaku::Yakuman(Yakuman::SuuankouTanki),
+            49 => Yaku::Yakuman(Yakuman::KokushiMusouJuusanmen),
+            50 => Yaku::Yakuman(Yakuman::Daisuushii),
+            _ => return None,
+        };
+        Some(yaku)
+    }
+
+    /// Ends the round on an exhausted wall, with the tenpai players'
+    /// hands shown.
+    fn no_tile(&mut self, data: &Value) -> Result<(), FormatError> {
+        let mut deltas = vec![0; self.table.count()];
+        for score in field(data, "scores")
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice)
+        {
+            if field(score, "delta_scores").is_some() {
+                let changes = points(field(score, "delta_scores"), "delta_scores", self.table)?;
+                for (d, change) in deltas.iter_mut().zip(changes) {
+                    *d += change;
+                }
+            }
+        }
+        let revealed = field(data, "players")
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        let n = self.table.count();
+        let mut hands = Vec::new();
+        for p in (0..n).map(|i| (self.dealer + i) % n) {
+            if let Some(player) = revealed.get(p) {
+                if flag(player, "tingpai")? {
+                    hands.push((self.table.seat(p), self.players[p].hand(None)));
+                }
+            }
+        }
+        let last = self.offered.map_or(self.dealer, |(p, _)| p);
+        self.push(last, RoundAction::Exhaustive(hands));
+        self.deltas = Some(deltas);
+        Ok(())
+    }
+
+    /// Ends the round as abandoned. Nine terminals is declared by player
+    /// `p`, the other draws follow the last discard.
+    fn liu_ju(&mut self, p: usize, data: &Value) -> Result<(), FormatError> {
+        let kind = number(data, "type")?;
+        let abort = match kind {
+            1 => AbortiveDraw::KyuushuKyuuhai,
+            2 => AbortiveDraw::SuufonRenda,
+            3 => AbortiveDraw::Suukaikan,
+            4 => AbortiveDraw::SuuchaRiichi,
+            5 => AbortiveDraw::SanchaHou,
+            _ => {
+                return Err(FormatError::UnknownResult(format!(
+                    "abortive draw {}",
+                    kind
+                )))
+            }
+        };
+        let subject = match abort {
+            AbortiveDraw::KyuushuKyuuhai => p,
+            _ => self.offered.map_or(p, |(p, _)| p),
+        };
+        self.push(subject, RoundAction::AbortiveDraw(abort));
+        self.deltas = Some(vec![0; self.table.count()]);
+        Ok(())
+    }
+
+    /// The finished round. `next_start` are the scores the next round
+    /// starts with, if there is one.
+    fn into_round(self, next_start: Option<&Vec<i32>>) -> Result<Round, FormatError> {
+        let deltas = self
+            .deltas
+            .as_ref()
+            .ok_or_else(|| self.inconsistent("the round has no result"))?;
+        let result = match next_start {
+            Some(next) => self.table.by_seat(next),
+            None => {
+                let riichi = riichi_deposits(&self.events);
+                let points: Vec<i32> = (0..self.table.count())
+                    .map(|p| {
+                        let seat = self.table.seat(p);
+                        let deposits = riichi.iter().filter(|&&s| s == seat).count() as i32;
+                        self.start[p] + deltas[p] - 1000 * deposits
+                    })
+                    .collect();
+                self.table.by_seat(&points)
+            }
+        };
+        let mut config = RoundConfigBuilder::new()
+            .num_players(self.table.num_players)
+            .wind(nth_wind(self.chang))
+            .number(nth_number(self.ju))
+            .repeat(self.honba)
+            .dealer(self.table.seat(self.dealer))
+            .hand(self.hero_dealt)
+            .result(result);
+        for tile in self.dora {
+            config = config.dora_indicator(tile);
+        }
+        for tile in self.ura_dora {
+            config = config.ura_dora_indicator(tile);
+        }
+        Ok(Round {
+            config: config.build()?,
+            events: self.events,
+            annotation: None,
+        })
+    }
+}
+
+/// The field `name`, given as in the proto, under that name or the
+/// lowerCamelCase one of the JSON mapping.
+fn field<'a>(json: &'a Value, name: &str) -> Option<&'a Value> {
+    json.get(name).or_else(|| {
+        let mut camel = String::new();
+        let mut upper = false;
+        for c in name.chars() {
+            if c == '_' {
+                upper = true;
+            } else if upper {
+                camel.push(c.to_ascii_uppercase());
+                upper = false;
+            } else {
+                camel.push(c);
+            }
+        }
+        json.get(camel)
+    })
+}
+
+/// A number field, which the JSON mapping leaves out when it is 0.
+fn number(json: &Value, name: &'static str) -> Result<u64, FormatError> {
+    field(json, name).map_or(Ok(0), |v| v.as_u64().ok_or(FormatError::Field(name)))
+}
+
+/// A boolean field, which the JSON mapping leaves out when it is false.
+fn flag(json: &Value, name: &'static str) -> Result<bool, FormatError> {
+    field(json, name).map_or(Ok(false), |v| v.as_bool().ok_or(FormatError::Field(name)))
+}
+
+fn text<'a>(json: &'a Value, name: &'static str) -> Result<&'a str, FormatError> {
+    field(json, name).map_or(Ok(""), |v| v.as_str().ok_or(FormatError::Field(name)))
+}
+
+/// The player numbered by the `seat` field.
+fn seat(json: &Value, table: Table) -> Result<usize, FormatError> {
+    Some(number(json, "seat")? as usize)
+        .filter(|&p| p < table.count())
+        .ok_or(FormatError::Field("seat"))
+}
+
+fn tile_field(json: &Value, name: &'static str) -> Result<MahjongTile, FormatError> {
+    let name = field(json, name)
+        .and_then(Value::as_str)
+        .ok_or(FormatError::Field(name))?;
+    MahjongTile::from_majsoul_str(name).map_err(|_| FormatError::UnknownTile(name.to_string()))
+}
+
+/// A list of tiles, which the JSON mapping leaves out when it is empty.
+fn tiles(json: &Value, name: &'static str) -> Result<Vec<MahjongTile>, FormatError> {
+    let Some(list) = field(json, name) else {
+        return Ok(Vec::new());
+    };
+    list.as_array()
+        .ok_or(FormatError::Field(name))?
+        .iter()
+        .map(|t| {
+            let t = t.as_str().ok_or(FormatError::Field(name))?;
+            MahjongTile::from_majsoul_str(t).map_err(|_| FormatError::UnknownTile(t.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerLocation::*;
+    use crate::Error;
+
+    /// East 1 with a repeat and a deposit on the table: the dealer declares
+    /// a closed kan, a chii and a pon are called by `froms`, the pon grows
+    /// into an added kan, and its rinshan discard is ronned by two players.
+    /// The head uses the proto's field names and the actions the
+    /// lowerCamelCase ones.
+    const RECORD: &str = r#"{
+        "head": {
+            "start_time": 1704078000,
+            "config": {"mode": {"mode": 1}},
+            "accounts": [
+                {"nickname": "A", "account_id": 11},
+                {"seat": 1, "nickname": "B", "account_id": 12},
+                {"seat": 2, "nickname": "C", "account_id": 13},
+                {"seat": 3, "nickname": "D", "account_id": 14}
+            ],
+            "result": {"players": [
+                {"part_point_1": 28400},
+                {"seat": 1, "part_point_1": 25000},
+                {"seat": 2, "part_point_1": 19000},
+                {"seat": 3, "part_point_1": 27600}
+            ]}
+        },
+        "actions": [
+            {"name": ".lq.RecordNewRound", "data": {
+                "ben": 1,
+                "liqibang": 1,
+                "scores": [25000, 25000, 24000, 25000],
+                "doras": ["9p"],
+                "tiles0": ["1m", "1m", "1m", "1m", "2p", "3p", "4p", "6p", "7p",
+                           "8p", "9s", "9s", "4s", "7z"],
+                "tiles1": ["3s", "5s", "1z", "2m", "3m", "4m", "5m", "6m", "7m",
+                           "7p", "7p", "8s", "8s"],
+                "tiles2": ["1z", "1z", "4m", "5m", "6m", "2p", "2p", "3s", "3s",
+                           "3s", "6z", "7s", "8s"],
+                "tiles3": ["1p", "2p", "3p", "4p", "5p", "6p", "7z", "7z", "7z",
+                           "7s", "8s", "9p", "9p"]
+            }},
+            {"name": ".lq.RecordAnGangAddGang",
+             "data": {"type": 3, "tiles": "1m", "doras": ["9p", "3m"]}},
+            {"name": ".lq.RecordDealTile", "data": {"tile": "5z"}},
+            {"name": ".lq.RecordDiscardTile", "data": {"tile": "4s"}},
+            {"name": ".lq.RecordChiPengGang", "data": {
+                "seat": 1, "tiles": ["3s", "4s", "5s"], "froms": [1, 0, 1]
+            }},
+            {"name": ".lq.RecordDiscardTile", "data": {"seat": 1, "tile": "1z"}},
+            {"name": ".lq.RecordChiPengGang", "data": {
+                "seat": 2, "type": 1, "tiles": ["1z", "1z", "1z"], "froms": [2, 2, 1]
+            }},
+            {"name": ".lq.RecordDiscardTile", "data": {"seat": 2, "tile": "6z"}},
+            {"name": ".lq.RecordDealTile", "data": {"seat": 3, "tile": "6m"}},
+            {"name": ".lq.RecordDiscardTile",
+             "data": {"seat": 3, "tile": "6m", "moqie": true}},
+            {"name": ".lq.RecordDealTile", "data": {"tile": "5z"}},
+            {"name": ".lq.RecordDiscardTile", "data": {"tile": "7z"}},
+            {"name": ".lq.RecordDealTile", "data": {"seat": 1, "tile": "9m"}},
+            {"name": ".lq.RecordDiscardTile",
+             "data": {"seat": 1, "tile": "9m", "moqie": true}},
+            {"name": ".lq.RecordDealTile", "data": {"seat": 2, "tile": "1z"}},
+            {"name": ".lq.RecordAnGangAddGang",
+             "data": {"seat": 2, "type": 2, "tiles": "1z"}},
+            {"name": ".lq.RecordDealTile", "data": {"seat": 2, "tile": "9s"}},
+            {"name": ".lq.RecordDiscardTile", "data": {
+                "seat": 2, "tile": "9s", "moqie": true, "doras": ["9p", "3m", "2s"]
+            }},
+            {"name": ".lq.RecordHule", "data": {
+                "hules": [{
+                    "seat": 3, "huTile": "9s",
+                    "count": 1, "fu": 40, "pointRong": 1300,
+                    "fans": [{"id": 9, "val": 1}]
+                }, {
+                    "huTile": "9s", "qinjia": true,
+                    "count": 1, "fu": 70, "pointRong": 3400,
+                    "fans": [{"id": 6, "val": 1}]
+                }],
+                "deltaScores": [3400, 0, -5000, 2600]
+            }}
+        ]
+    }"#;
+
+    fn t(s: &str) -> MahjongTile {
+        tile::build(s).unwrap()
+    }
+
+    fn payments(round: &Round) -> Vec<(PlayerLocation, u32, u32)> {
+        round
+            .events
+            .iter()
+            .filter_map(|e| match &e.action {
+                RoundAction::Ron(_, Some(details)) => Some((
+                    e.subject,
+                    details.payment.from_discarder,
+                    details.payment.deposits,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_kans_froms_and_a_double_ron() {
+        let game = parse(RECORD).unwrap();
+        assert_eq!(game.config.player(Left).id.as_deref(), Some("14"));
+        assert_eq!(game.config.result.as_ref().unwrap()[2], (Across, 19000));
+        let round = &game.rounds[0];
+        assert_eq!(round.config.repeat, 1);
+        assert_eq!(round.config.dora, vec![t("9p"), t("3m"), t("2s")]);
+        let kan = ClosedKanMeld::new([t("1m"); 4]).unwrap();
+        let chii = ChiiMeld::new([t("3s"), t("4s"), t("5s")], t("4s")).unwrap();
+        let pon = PonMeld::new([t("1z"); 3], t("1z"), Left).unwrap();
+        assert_eq!(round.events[1].action, RoundAction::ClosedKan(kan));
+        assert_eq!(round.events[4].action, RoundAction::Chii(chii));
+        assert_eq!(round.events[6].action, RoundAction::Pon(pon.clone()));
+        assert_eq!(
+            (round.events[15].subject, &round.events[15].action),
+            (
+                Across,
+                &RoundAction::AddedKan(AddedKanMeld::new(pon, t("1z")).unwrap())
+            )
+        );
+        // Only the winner nearest the discarder takes the honba and the
+        // deposit.
+        assert_eq!(payments(round), vec![(Left, 1600, 1000), (Hero, 3400, 0)]);
+        let RoundAction::Ron(hand, _) = &round.events[19].action else {
+            panic!("expected a ron, got {:?}", round.events[19]);
+        };
+        assert_eq!(hand, &Hand::parse("234p678p999s55z + ck1111m").unwrap());
+        assert_eq!(
+            round.config.result.as_ref().unwrap(),
+            &vec![
+                (Hero, 28400),
+                (Right, 25000),
+                (Across, 19000),
+                (Left, 27600)
+            ]
+        );
+        let camel_case = RECORD
+            .replace("start_time", "startTime")
+            .replace("account_id", "accountId")
+            .replace("part_point_1", "partPoint1");
+        assert_eq!(parse(&camel_case), Ok(game));
+    }
+
+    #[test]
+    fn reads_calls_from_the_caller_seat() {
+        let game = parse_as(RECORD, 1).unwrap();
+        let round = &game.rounds[0];
+        assert_eq!(round.config.dealer, Left);
+        assert_eq!(
+            (round.events[4].subject, round.events[6].subject),
+            (Hero, Right)
+        );
+        let pon = PonMeld::new([t("1z"); 3], t("1z"), Left).unwrap();
+        assert_eq!(round.events[6].action, RoundAction::Pon(pon));
+        assert_eq!(payments(round), vec![(Across, 1600, 1000), (Left, 3400, 0)]);
+    }
+
+    #[test]
+    fn reports_malformed_records() {
+        assert_eq!(parse("{}"), Err(Error::Format(FormatError::Field("head"))));
+        let sanma = r#"{"head": {"config": {"mode": {"mode": 11}}}, "actions": []}"#;
+        assert_eq!(
+            parse_as(sanma, 3),
+            Err(Error::Format(FormatError::NoSuchPlayer(3)))
+        );
+        let no_froms = RECORD.replace(r#", "froms": [1, 0, 1]"#, "");
+        assert_eq!(
+            parse(&no_froms),
+            Err(Error::Format(FormatError::Field("froms")))
+        );
+        let wrong_from = RECORD.replace("[1, 0, 1]", "[1, 2, 1]");
+        assert!(matches!(
+            parse(&wrong_from),
+            Err(Error::Format(FormatError::Inconsistent { round: 0, .. }))
+        ));
+        let bad_call = RECORD.replace(r#""seat": 2, "type": 1"#, r#""seat": 2, "type": 5"#);
+        assert_eq!(
+            parse(&bad_call),
+            Err(Error::Format(FormatError::UnknownCall(
+                "call type 5".to_string()
+            )))
+        );
+        let bad_kan = RECORD.replace(r#""type": 3"#, r#""type": 1"#);
+        assert_eq!(
+            parse(&bad_kan),
+            Err(Error::Format(FormatError::UnknownCall(
+                "kan type 1".to_string()
+            )))
+        );
+        let bad_yaku = RECORD.replace(r#""id": 9,"#, r#""id": 46,"#);
+        assert_eq!(
+            parse(&bad_yaku),
+            Err(Error::Format(FormatError::UnknownYaku("46".to_string())))
+        );
+    }
+}
@@ -0,0 +1,1268 @@
+//! The MJAI protocol spoken between mahjong servers and AI players, one
+//! JSON message per line.
+//!
+//! A round runs from `start_kyoku` to `end_kyoku`, with messages such as
+//! `{"type":"dahai","actor":1,"pai":"5mr","tsumogiri":false}`. Players are
+//! numbered from 0 for the first East. Tiles are written `1m`–`9m`,
+//! `1p`–`9p` and `1s`–`9s`, `5mr`, `5pr` and `5sr` for red fives, `E`, `S`,
+//! `W` and `N` for the winds and `P`, `F` and `C` for the white, green and
+//! red dragons. A bot's log shows `?` for the tiles it can't see.
+//!
+//! Rounds are seen from the player the `start_game` message was sent to,
+//! or from player 0 when there is none. Other players' hands are followed
+//! when the log shows them; otherwise their winning and tenpai hands are
+//! taken from the `hora` and `ryukyoku` messages.
+
+use std::io::{BufRead, BufReader, Read};
+
+use serde_json::{json, Value};
+
+use super::table::{nth_number, points, riichi_deposits, tiles_array, Holding, Table};
+use super::FormatError;
+use crate::game::{
+    AbortiveDraw, NumPlayers, PlayerLocation, Round, RoundAction, RoundConfigBuilder, RoundEvent,
+};
+use crate::hand::{AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, Meld, OpenKanMeld, PonMeld};
+use crate::rules::RuleSet;
+use crate::scoring::{self, DoraCount, Limit, WinDetails, WinType};
+use crate::tile::{self, Direction, DragonColor, HonorTile, MahjongTile, ObservedTile};
+use crate::yaku::{Yaku, Yakuman};
+
+/// Reads newline-delimited MJAI messages from `reader` and assembles the
+/// rounds they describe. Blank lines are skipped.
+pub fn parse_events<R: Read>(reader: R) -> crate::Result<Vec<Round>> {
+    Ok(read_events(reader)?)
+}
+
+fn read_events<R: Read>(reader: R) -> Result<Vec<Round>, FormatError> {
+    let mut hero = 0;
+    let mut rounds = Vec::new();
+    let mut current: Option<Kyoku> = None;
+    for (line, text) in BufReader::new(reader).lines().enumerate() {
+        let text = text.map_err(|e| FormatError::Io(e.to_string()))?;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let message: Value = serde_json::from_str(&text)
+            .map_err(|e| FormatError::Json(format!("line {}: {}", line + 1, e)))?;
+        let kind = message
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or(FormatError::Field("type"))?;
+        match kind {
+            "start_game" => {
+                hero = message.get("id").and_then(Value::as_u64).unwrap_or(0) as usize;
+            }
+            "start_kyoku" => {
+                if let Some(kyoku) = current.take() {
+                    rounds.push(kyoku.into_round()?);
+                }
+                current = Some(Kyoku::new(&message, rounds.len(), hero)?);
+            }
+            "end_kyoku" => {
+                if let Some(kyoku) = current.take() {
+                    rounds.push(kyoku.into_round()?);
+                }
+            }
+            "end_game" | "none" => {}
+            _ => current
+                .as_mut()
+                .ok_or(FormatError::Inconsistent {
+                    round: rounds.len(),
+                    reason: "a message outside a round",
+                })?
+                .apply(kind, &message)?,
+        }
+    }
+    if let Some(kyoku) = current {
+        rounds.push(kyoku.into_round()?);
+    }
+    Ok(rounds)
+}
+
+/// A round of the stream being turned into events.
+struct Kyoku {
+    index: usize,
+    table: Table,
+    wind: Direction,
+    /// The round of the wind, counted from 0.
+    number: usize,
+    honba: u8,
+    dealer: usize,
+    dealt: Hand,
+    dora: Vec<MahjongTile>,
+    ura_dora: Vec<MahjongTile>,
+    players: Vec<Holding>,
+    /// Whether the log shows each player's tiles.
+    known: Vec<bool>,
+    scores: Vec<i32>,
+    events: Vec<RoundEvent>,
+    /// The player who acted last.
+    last: usize,
+    /// The last tile given up by a player, which a ron would win on: a
+    /// discard, or the tile added to a kan.
+    offered: Option<(usize, MahjongTile)>,
+    ended: bool,
+}
+
+impl Kyoku {
+    fn new(message: &Value, index: usize, hero: usize) -> Result<Kyoku, FormatError> {
+        let tehais = message
+            .get("tehais")
+            .and_then(Value::as_array)
+            .ok_or(FormatError::Field("tehais"))?;
+        let num_players = match tehais.len() {
+            3 => NumPlayers::Three,
+            4 => NumPlayers::Four,
+            _ => return Err(FormatError::Field("tehais")),
+        };
+        if hero >= num_players.count() {
+            return Err(FormatError::Field("id"));
+        }
+        let table = Table { num_players, hero };
+        let mut players = Vec::new();
+        let mut known = Vec::new();
+        for tehai in tehais {
+            let tiles = maybe_tiles(tehai, "tehais")?;
+            known.push(tiles.iter().all(Option::is_some));
+            players.push(Holding {
+                concealed: tiles.into_iter().flatten().collect(),
+                ..Holding::default()
+            });
+        }
+        if !known[hero] {
+            return Err(FormatError::Inconsistent {
+                round: index,
+                reason: "the hero's hand is hidden",
+            });
+        }
+        let wind = message
+            .get("bakaze")
+            .and_then(Value::as_str)
+            .and_then(|w| match tile_named(w) {
+                Ok(MahjongTile::Honor(HonorTile::Wind(wind))) => Some(wind),
+                _ => None,
+            })
+            .ok_or(FormatError::Field("bakaze"))?;
+        let number = |name: &'static str| {
+            message
+                .get(name)
+                .and_then(Value::as_u64)
+                .ok_or(FormatError::Field(name))
+        };
+        Ok(Kyoku {
+            index,
+            table,
+            wind,
+            number: number("kyoku")?.saturating_sub(1) as usize,
+            honba: number("honba")? as u8,
+            dealer: actor_in(message, "oya", table)?,
+            dealt: players[hero].hand(None),
+            dora: vec![tile_field(message, "dora_marker")?],
+            ura_dora: Vec::new(),
+            players,
+            known,
+            scores: points(message.get("scores"), "scores", table)?,
+            events: Vec::new(),
+            last: 0,
+            offered: None,
+            ended: false,
+        })
+    }
+
+    fn inconsistent(&self, reason: &'static str) -> FormatError {
+        FormatError::Inconsistent {
+            round: self.index,
+            reason,
+        }
+    }
+
+    fn push(&mut self, player: usize, action: RoundAction) {
+        self.last = player;
+        self.events
+            .push(RoundEvent::new(self.table.seat(player), action));
+    }
+
+    /// Removes `tiles` from player `p`'s hand, if the log shows it.
+    fn take(&mut self, p: usize, tiles: &[MahjongTile]) -> Result<(), FormatError> {
+        if self.known[p] {
+            self.players[p].take_meld(tiles, None, self.index)?;
+        }
+        Ok(())
+    }
+
+    /// Applies a message inside the round of type `kind`.
+    fn apply(&mut self, kind: &str, message: &Value) -> Result<(), FormatError> {
+        // A double ron is announced as one `hora` per winner.
+        let another_ron = kind == "hora"
+            && matches!(
+                self.events.last(),
+                Some(RoundEvent {
+                    action: RoundAction::Ron(..),
+                    ..
+                })
+            );
+        if self.ended && !another_ron {
+            return Err(self.inconsistent("actions left after the round ended"));
+        }
+        let actor = || actor_in(message, "actor", self.table);
+        match kind {
+            "tsumo" => {
+                let p = actor()?;
+                let tile = maybe_tile(message.get("pai").ok_or(FormatError::Field("pai"))?, "pai")?;
+                let observed = match tile {
+                    Some(tile) if self.table.seat(p) == PlayerLocation::Hero => tile.into(),
+                    None if self.table.seat(p) == PlayerLocation::Hero => {
+                        return Err(self.inconsistent("the hero's draw is hidden"))
+                    }
+                    _ => ObservedTile::Hidden,
+                };
+                match tile {
+                    Some(tile) if self.known[p] => self.players[p].draw(tile),
+                    _ => self.players[p].drawn = None,
+                }
+                self.push(p, RoundAction::Draw(observed));
+            }
+            "dahai" => {
+                let p = actor()?;
+                let tile = tile_field(message, "pai")?;
+                self.players[p].drawn = None;
+                self.take(p, &[tile])?;
+                let tsumogiri = message
+                    .get("tsumogiri")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                self.events.push(
+                    RoundEvent::new(self.table.seat(p), RoundAction::Discard(tile))
+                        .with_tsumogiri(tsumogiri),
+                );
+                self.last = p;
+                self.offered = Some((p, tile));
+            }
+            "chi" | "pon" | "daiminkan" => self.call(kind, actor()?, message)?,
+            "ankan" | "kakan" => self.declare_kan(kind, actor()?, message)?,
+            "nukidora" => {
+                let p = actor()?;
+                self.players[p].drawn = None;
+                self.take(p, &[tile::NORTH])?;
+                self.push(p, RoundAction::Kita);
+            }
+            "dora" => self.dora.push(tile_field(message, "dora_marker")?),
+            "reach" => self.push(actor()?, RoundAction::Riichi),
+            "reach_accepted" => {
+                self.scores[actor()?] -= 1000;
+                self.update_scores(message)?;
+            }
+            "hora" => self.hora(message)?,
+            "ryukyoku" => self.ryukyoku(message)?,
+            _ => return Err(FormatError::UnknownCall(kind.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Replaces the scores with the message's `scores`, if it has them.
+    fn update_scores(&mut self, message: &Value) -> Result<(), FormatError> {
+        if message.get("scores").is_some() {
+            self.scores = points(message.get("scores"), "scores", self.table)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the message's `deltas` to the scores and returns them.
+    fn apply_deltas(&mut self, message: &Value) -> Result<Vec<i32>, FormatError> {
+        let deltas = match message.get("deltas") {
+            Some(_) => points(message.get("deltas"), "deltas", self.table)?,
+            None => vec![0; self.table.count()],
+        };
+        for (score, delta) in self.scores.iter_mut().zip(&deltas) {
+            *score += delta;
+        }
+        self.update_scores(message)?;
+        Ok(deltas)
+    }
+
+    /// Player `q` calls the last discard into a chii, pon or open kan.
+    fn call(&mut self, kind: &str, q: usize, message: &Value) -> Result<(), FormatError> {
+        let from = actor_in(message, "target", self.table)?;
+        let called = tile_field(message, "pai")?;
+        if self.offered != Some((from, called)) {
+            return Err(self.inconsistent("a call on a tile that wasn't just discarded"));
+        }
+        let consumed = tiles(message, "consumed")?;
+        let source = self.table.source(from, q);
+        let mut tiles = vec![called];
+        tiles.extend(&consumed);
+        let (meld, action) = match kind {
+            "chi" => {
+                let meld = ChiiMeld::from_called(called, tiles_array(&consumed, kind)?)?;
+                (Meld::Chii(meld.clone()), RoundAction::Chii(meld))
+            }
+            "pon" => {
+                let meld = PonMeld::new(tiles_array(&tiles, kind)?, called, source)?;
+                (Meld::Pon(meld.clone()), RoundAction::Pon(meld))
+            }
+            _ => {
+                let meld = OpenKanMeld::new(tiles_array(&tiles, kind)?, called, source)?;
+                (Meld::OpenKan(meld.clone()), RoundAction::OpenKan(meld))
+            }
+        };
+        self.take(q, &consumed)?;
+        self.players[q].melds.push(meld);
+        self.push(q, action);
+        Ok(())
+    }
+
+    /// Player `p` declares a closed kan, or adds `pai` to a pon.
+    fn declare_kan(&mut self, kind: &str, p: usize, message: &Value) -> Result<(), FormatError> {
+        self.players[p].drawn = None;
+        if kind == "ankan" {
+            let consumed = tiles(message, "consumed")?;
+            let meld = ClosedKanMeld::new(tiles_array(&consumed, kind)?)?;
+            self.take(p, &consumed)?;
+            self.players[p].melds.push(Meld::ClosedKan(meld.clone()));
+            self.push(p, RoundAction::ClosedKan(meld));
+            return Ok(());
+        }
+        let added = tile_field(message, "pai")?;
+        let pon = self.players[p]
+            .melds
+            .iter()
+            .position(|m| matches!(m, Meld::Pon(pon) if pon.pon_tile.eq_rank(&added)))
+            .ok_or_else(|| self.inconsistent("an added kan without a pon"))?;
+        let Meld::Pon(pon_meld) = self.players[p].melds[pon].clone() else {
+            unreachable!("found a pon");
+        };
+        let meld = AddedKanMeld::new(pon_meld, added)?;
+        self.take(p, &[added])?;
+        self.players[p].melds[pon] = Meld::AddedKan(meld.clone());
+        self.push(p, RoundAction::AddedKan(meld));
+        self.offered = Some((p, added));
+        Ok(())
+    }
+
+    /// Player `p`'s hand, with the winning tile if they won: followed
+    /// through the round when the log shows it, or else read from `shown`.
+    fn shown_hand(
+        &self,
+        p: usize,
+        shown: Option<&Value>,
+        name: &'static str,
+        winning: Option<(MahjongTile, WinType)>,
+    ) -> Result<Hand, FormatError> {
+        if self.known[p] {
+            let extra = match winning {
+                Some((tile, WinType::Ron)) => Some(tile),
+                _ => None,
+            };
+            return Ok(self.players[p].hand(extra));
+        }
+        let mut held = Holding {
+            concealed: tiles_in(shown.ok_or(FormatError::Field(name))?, name)?,
+            melds: self.players[p].melds.clone(),
+            drawn: None,
+        };
+        // Some servers list the winning tile with the hand, some don't.
+        let waiting = 13usize
+            .checked_sub(3 * held.melds.len())
+            .ok_or_else(|| self.inconsistent("a player has more than four melds"))?;
+        if let Some((tile, _)) = winning {
+            if held.concealed.len() == waiting {
+                held.concealed.push(tile);
+            }
+        }
+        if held.concealed.len() != waiting + winning.is_some() as usize {
+            return Err(FormatError::Field(name));
+        }
+        Ok(held.hand(None))
+    }
+
+    fn hora(&mut self, message: &Value) -> Result<(), FormatError> {
+        let who = actor_in(message, "actor", self.table)?;
+        let from = actor_in(message, "target", self.table)?;
+        let (win_type, winning_tile) = if who == from {
+            let tile = match (message.get("pai"), self.players[who].drawn) {
+                (Some(_), _) => tile_field(message, "pai")?,
+                (None, Some(drawn)) => drawn,
+                // A hidden draw is the last of the shown tiles, when they
+                // include it.
+                (None, None) => message
+                    .get("hora_tehais")
+                    .map(|shown| tiles_in(shown, "hora_tehais"))
+                    .transpose()?
+                    .filter(|shown| shown.len() % 3 == 2)
+                    .and_then(|shown| shown.last().copied())
+                    .ok_or_else(|| self.inconsistent("tsumo without a draw"))?,
+            };
+            (WinType::Tsumo, tile)
+        } else {
+            let (_, tile) = self
+                .offered
+                .filter(|&(p, _)| p == from)
+                .ok_or_else(|| self.inconsistent("ron without a discard"))?;
+            (WinType::Ron, tile)
+        };
+        let hand = self.shown_hand(
+            who,
+            message.get("hora_tehais"),
+            "hora_tehais",
+            Some((winning_tile, win_type)),
+        )?;
+        for name in ["uradora_markers", "ura_markers"] {
+            if self.ura_dora.is_empty() && message.get(name).is_some() {
+                self.ura_dora = tiles(message, name)?;
+            }
+        }
+        let deltas = self.apply_deltas(message)?;
+        let details = match message.get("yakus") {
+            Some(_) => {
+                Some(self.win_details(message, &deltas, who, from, win_type, winning_tile)?)
+            }
+            None => None,
+        };
+        let action = match win_type {
+            WinType::Tsumo => RoundAction::Tsumo(hand, details),
+            WinType::Ron => RoundAction::Ron(hand, details),
+        };
+        self.push(who, action);
+        self.ended = true;
+        Ok(())
+    }
+
+    fn win_details(
+        &self,
+        message: &Value,
+        deltas: &[i32],
+        who: usize,
+        from: usize,
+        win_type: WinType,
+        winning_tile: MahjongTile,
+    ) -> Result<WinDetails, FormatError> {
+        let mut yaku = Vec::new();
+        let mut dora = DoraCount::default();
+        for entry in message
+            .get("yakus")
+            .and_then(Value::as_array)
+            .ok_or(FormatError::Field("yakus"))?
+        {
+            let name = entry.get(0).and_then(Value::as_str);
+            let han = entry.get(1).and_then(Value::as_u64);
+            let (Some(name), Some(han)) = (name, han) else {
+                return Err(FormatError::Field("yakus"));
+            };
+            let han = han as u8;
+            match name {
+                "dora" => dora.dora += han,
+                "akadora" => dora.aka_dora += han,
+                "uradora" => dora.ura_dora += han,
+                "nukidora" => dora.nuki_dora += han,
+                _ => {
+                    let found = self
+                        .yaku_named(name, who)
+                        .ok_or_else(|| FormatError::UnknownYaku(name.to_string()))?;
+                    yaku.push((found, han));
+                }
+            }
+        }
+        let number = |name: &'static str| {
+            message
+                .get(name)
+                .and_then(Value::as_u64)
+                .ok_or(FormatError::Field(name))
+        };
+        let han = number("fan")? as u8;
+        let fu = number("fu")? as u8;
+        let dealer = who == self.dealer;
+        let limit = if han >= 13 {
+            Some(Limit::Yakuman(han / 13))
+        } else {
+            scoring::score(han, fu, dealer, win_type).limit
+        };
+
+        let payment = self
+            .table
+            .payment(deltas, who, from, self.dealer, win_type, limit);
+        Ok(WinDetails {
+            winning_tile,
+            yaku,
+            fu,
+            dora,
+            payment,
+        })
+    }
+
+    /// The yaku MJAI calls `name`, won by player `who`.
+    fn yaku_named(&self, name: &str, who: usize) -> Option<Yaku> {
+        match name {
+            "jikaze" => Some(Yaku::SeatWind(
+                self.table
+                    .seat(who)
+                    .seat_wind_in(self.table.seat(self.dealer), self.table.num_players),
+            )),
+            "bakaze" => Some(Yaku::RoundWind(self.wind)),
+            _ => YAKU_NAMES
+                .iter()
+                .find(|&&(n, _)| n == name)
+                .map(|&(_, yaku)| yaku),
+        }
+    }
+
+    fn ryukyoku(&mut self, message: &Value) -> Result<(), FormatError> {
+        let reason = message.get("reason").and_then(Value::as_str);
+        let abort = match reason {
+            None | Some("fanpai") | Some("nagashimangan") => None,
+            Some("kyushukyuhai") => Some(AbortiveDraw::KyuushuKyuuhai),
+            Some("sufonrenda") => Some(AbortiveDraw::SuufonRenda),
+            Some("suchareach") => Some(AbortiveDraw::SuuchaRiichi),
+            Some("sukaikan") => Some(AbortiveDraw::Suukaikan),
+            Some("sanchaho") => Some(AbortiveDraw::SanchaHou),
+            Some(reason) => return Err(FormatError::UnknownResult(reason.to_string())),
+        };
+        let discarder = self.offered.map_or(self.last, |(p, _)| p);
+        if let Some(abort) = abort {
+            let subject = match abort {
+                AbortiveDraw::KyuushuKyuuhai => match message.get("actor") {
+                    Some(_) => actor_in(message, "actor", self.table)?,
+                    None => self.last,
+                },
+                _ => discarder,
+            };
+            self.push(subject, RoundAction::AbortiveDraw(abort));
+        } else {
+            let tenpais = message.get("tenpais").and_then(Value::as_array);
+            let tehais = message.get("tehais").and_then(Value::as_array);
+            let n = self.table.count();
+            let mut hands = Vec::new();
+            for p in (0..n).map(|i| (self.dealer + i) % n) {
+                let tenpai = match tenpais {
+                    Some(tenpais) => tenpais.get(p).and_then(Value::as_bool) == Some(true),
+                    None => self.known[p] && self.players[p].hand(None).is_tenpai(),
+                };
+                if tenpai {
+                    let shown = tehais.and_then(|tehais| tehais.get(p));
+                    let hand = self.shown_hand(p, shown, "tehais", None)?;
+                    hands.push((self.table.seat(p), hand));
+                }
+            }
+            self.push(discarder, RoundAction::Exhaustive(hands));
+        }
+        self.apply_deltas(message)?;
+        self.ended = true;
+        Ok(())
+    }
+
+    fn into_round(self) -> Result<Round, FormatError> {
+        if !self.ended {
+            return Err(self.inconsistent("the round has no result"));
+        }
+        let mut config = RoundConfigBuilder::new()
+            .num_players(self.table.num_players)
+            .wind(self.wind)
+            .number(nth_number(self.number))
+            .repeat(self.honba)
+            .dealer(self.table.seat(self.dealer))
+            .hand(self.dealt)
+            .result(self.table.by_seat(&self.scores));
+        for tile in self.dora {
+            config = config.dora_indicator(tile);
+        }
+        for tile in self.ura_dora {
+            config = config.ura_dora_indicator(tile);
+        }
+        Ok(Round {
+            config: config.build()?,
+            events: self.events,
+            annotation: None,
+        })
+    }
+}
+
+impl Round {
+    /// The four-player round as MJAI messages, from `start_kyoku` to
+    /// `end_kyoku`, to feed to MJAI-compatible engines. Written one per line
+    /// after a `start_game` giving the hero's number as `id`, they can be
+    /// read back with [`parse_events`].
+    ///
+    /// Players are numbered as though the deal had moved round the table
+    /// from player 0, so the dealer of East 3 is player 2. Tiles the round
+    /// doesn't show, such as the other players' hands and draws, are `?`.
+    /// The starting scores are worked back from the result, or are 25000
+    /// each without one. Each kan dora is revealed right after its kan.
+    pub fn to_mjai_events(&self) -> Vec<Value> {
+        self.to_mjai_events_in(NumPlayers::Four)
+    }
+
+    /// As [`Round::to_mjai_events`], at a table of `num_players`. In a
+    /// three-player round a tsumo is paid as [`RuleSet::default`] says.
+    pub fn to_mjai_events_in(&self, num_players: NumPlayers) -> Vec<Value> {
+        Export::new(self, num_players).messages()
+    }
+}
+
+/// A round being written as MJAI messages.
+struct Export<'a> {
+    round: &'a Round,
+    num_players: NumPlayers,
+    /// The seat of each player, by MJAI number.
+    seats: Vec<PlayerLocation>,
+}
+
+impl<'a> Export<'a> {
+    fn new(round: &'a Round, num_players: NumPlayers) -> Export<'a> {
+        let all = num_players.seats();
+        let n = all.len();
+        let dealer = all
+            .iter()
+            .position(|&s| s == round.config.dealer)
+            .unwrap_or(0);
+        let first = (round.config.number.get() as usize - 1) % n;
+        let seats = (0..n).map(|a| all[(dealer + a + n - first) % n]).collect();
+        Export {
+            round,
+            num_players,
+            seats,
+        }
+    }
+
+    fn actor(&self, seat: PlayerLocation) -> usize {
+        self.seats
+            .iter()
+            .position(|&s| s == seat)
+            .expect("every seat has a player")
+    }
+
+    /// The player a call by `caller` from `source` took its tile from.
+    fn source(&self, caller: PlayerLocation, source: PlayerLocation) -> usize {
+        self.seats
+            .iter()
+            .position(|&s| s.seen_from_in(caller, self.num_players) == source)
+            .expect("every seat has a player")
+    }
+
+    /// One value per player, from values by seat.
+    fn by_actor<T: Copy>(&self, values: &[(PlayerLocation, T)], default: T) -> Vec<T> {
+        self.seats
+            .iter()
+            .map(|seat| {
+                values
+                    .iter()
+                    .find(|(s, _)| s == seat)
+                    .map_or(default, |&(_, v)| v)
+            })
+            .collect()
+    }
+
+    fn messages(&self) -> Vec<Value> {
+        let config = &self.round.config;
+        let events = &self.round.events;
+        let n = self.seats.len();
+        let hidden = || vec![json!("?"); 13];
+        let mut out = Vec::new();
+        let mut deltas = Vec::new();
+        let mut dora = config.dora.iter().skip(1);
+        let mut offered = None;
+        let mut riichi = None;
+        let mut deposits = 0;
+        let mut body = Vec::new();
+        for (i, event) in events.iter().enumerate() {
+            let actor = self.actor(event.subject);
+            let message = match &event.action {
+                RoundAction::Draw(tile) => json!({
+                    "type": "tsumo",
+                    "actor": actor,
+                    "pai": tile.known().map_or("?".to_string(), tile_name),
+                }),
+                RoundAction::Discard(tile) => {
+                    offered = Some((actor, *tile));
+                    json!({
+                        "type": "dahai",
+                        "actor": actor,
+                        "pai": tile_name(*tile),
+                        "tsumogiri": event.tsumogiri.unwrap_or(false),
+                    })
+                }
+                RoundAction::Chii(meld) => self.call(
+                    "chi",
+                    actor,
+                    event.subject,
+                    meld.source,
+                    meld.chii_tile,
+                    &meld.tiles,
+                ),
+                RoundAction::Pon(meld) => self.call(
+                    "pon",
+                    actor,
+                    event.subject,
+                    meld.source,
+                    meld.pon_tile,
+                    &meld.tiles,
+                ),
+                RoundAction::OpenKan(meld) => self.call(
+                    "daiminkan",
+                    actor,
+                    event.subject,
+                    meld.source,
+                    meld.kan_tile,
+                    &meld.tiles,
+                ),
+                RoundAction::ClosedKan(meld) => json!({
+                    "type": "ankan",
+                    "actor": actor,
+                    "consumed": tile_names(&meld.tiles),
+                }),
+                RoundAction::AddedKan(meld) => {
+                    offered = Some((actor, meld.added_tile));
+                    json!({
+                        "type": "kakan",
+                        "actor": actor,
+                        "pai": tile_name(meld.added_tile),
+                        "consumed": tile_names(&meld.pon.tiles),
+                    })
+                }
+                RoundAction::Kita => json!({"type": "nukidora", "actor": actor, "pai": "N"}),
+                RoundAction::Riichi => {
+                    riichi = Some(actor);
+                    json!({"type": "reach", "actor": actor})
+                }
+                RoundAction::Tsumo(hand, details) | RoundAction::Ron(hand, details) => {
+                    let (target, tile) = match (&event.action, offered) {
+                        (RoundAction::Ron(..), Some((target, tile))) => (target, Some(tile)),
+                        _ => (actor, details.as_ref().map(|d| d.winning_tile)),
+                    };
+                    let mut message = json!({
+                        "type": "hora",
+                        "actor": actor,
+                        "target": target,
+                        "hora_tehais": tile_names(&hand.concealed),
+                    });
+                    if let Some(tile) = tile {
+                        message["pai"] = json!(tile_name(tile));
+                    }
+                    if !config.ura_dora.is_empty() {
+                        message["uradora_markers"] = json!(tile_names(&config.ura_dora));
+                    }
+                    if let Some(details) = details {
+                        let discarder = (target != actor).then(|| self.seats[target]);
+                        let d = self.win_deltas(details, event.subject, discarder);
+                        if deltas.is_empty() {
+                            deposits = details.payment.deposits as i32;
+                        }
+                        add_deltas(&mut deltas, &d);
+                        let mut yakus: Vec<Value> = details
+                            .yaku
+                            .iter()
+                            .map(|&(yaku, han)| json!([yaku_name(yaku), han]))
+                            .collect();
+                        for (name, han) in [
+                            ("dora", details.dora.dora),
+                            ("akadora", details.dora.aka_dora),
+                            ("uradora", details.dora.ura_dora),
+                            ("nukidora", details.dora.nuki_dora),
+                        ] {
+                            if han > 0 {
+                                yakus.push(json!([name, han]));
+                            }
+                        }
+                        message["yakus"] = json!(yakus);
+                        message["fu"] = json!(details.fu);
+                        message["fan"] = json!(details.han());
+                        message["deltas"] = json!(d);
+                    }
+                    message
+                }
+                RoundAction::Exhaustive(hands) => {
+                    let tenpai: Vec<PlayerLocation> = hands
+                        .iter()
+                        .filter(|(_, hand)| hand.is_tenpai())
+                        .map(|&(seat, _)| seat)
+                        .collect();
+                    let d = self.by_actor(&scoring::noten_payments(&tenpai, self.num_players), 0);
+                    add_deltas(&mut deltas, &d);
+                    let tehais: Vec<Value> = self
+                        .seats
+                        .iter()
+                        .map(|seat| match hands.iter().find(|(s, _)| s == seat) {
+                            Some((_, hand)) => json!(tile_names(&hand.concealed)),
+                            None => json!(hidden()),
+                        })
+                        .collect();
+                    let tenpais: Vec<bool> =
+                        self.seats.iter().map(|s| tenpai.contains(s)).collect();
+                    json!({
+                        "type": "ryukyoku",
+                        "reason": "fanpai",
+                        "tehais": tehais,
+                        "tenpais": tenpais,
+                        "deltas": d,
+                    })
+                }
+                RoundAction::AbortiveDraw(abort) => {
+                    let reason = match abort {
+                        AbortiveDraw::KyuushuKyuuhai => "kyushukyuhai",
+                        AbortiveDraw::SuufonRenda => "sufonrenda",
+                        AbortiveDraw::SuuchaRiichi => "suchareach",
+                        AbortiveDraw::Suukaikan => "sukaikan",
+                        AbortiveDraw::SanchaHou => "sanchaho",
+                    };
+                    let mut message = json!({
+                        "type": "ryukyoku",
+                        "reason": reason,
+                        "deltas": vec![0; n],
+                    });
+                    if *abort == AbortiveDraw::KyuushuKyuuhai {
+                        message["actor"] = json!(actor);
+                    }
+                    message
+                }
+            };
+            body.push(message);
+            let kan = matches!(
+                event.action,
+                RoundAction::OpenKan(_) | RoundAction::ClosedKan(_) | RoundAction::AddedKan(_)
+            );
+            if kan {
+                if let Some(&tile) = dora.next() {
+                    body.push(json!({"type": "dora", "dora_marker": tile_name(tile)}));
+                }
+            }
+            // A declaration stands once its discard isn't won on.
+            if let (Some(p), RoundAction::Discard(_)) = (riichi, &event.action) {
+                let won_on = matches!(
+                    events.get(i + 1),
+                    Some(RoundEvent {
+                        action: RoundAction::Ron(..),
+                        ..
+                    })
+                );
+                if p == actor && !won_on {
+                    body.push(json!({"type": "reach_accepted", "actor": p}));
+                }
+                riichi = None;
+            }
+        }
+
+        let standing = riichi_deposits(events);
+        let scores: Vec<i32> = match &config.result {
+            Some(result) => {
+                let result = self.by_actor(result, 0);
+                (0..n)
+                    .map(|p| {
+                        let paid = standing.iter().filter(|&&s| s == self.seats[p]).count() as i32;
+                        result[p] - deltas.get(p).copied().unwrap_or(0) + 1000 * paid
+                    })
+                    .collect()
+            }
+            None => vec![25000; n],
+        };
+        let kyotaku = (deposits / 1000 - standing.len() as i32).max(0);
+        let hero = self.actor(PlayerLocation::Hero);
+        let tehais: Vec<Value> = (0..n)
+            .map(|p| {
+                if p == hero {
+                    json!(tile_names(&config.initial_hero_hand_state.concealed))
+                } else {
+                    json!(hidden())
+                }
+            })
+            .collect();
+        out.push(json!({
+            "type": "start_kyoku",
+            "bakaze": tile_name(MahjongTile::Honor(HonorTile::Wind(config.wind))),
+            "kyoku": config.number.get(),
+            "honba": config.repeat,
+            "kyotaku": kyotaku,
+            "oya": self.actor(config.dealer),
+            "dora_marker": config.dora.first().map_or("?".to_string(), |&t| tile_name(t)),
+            "scores": scores,
+            "tehais": tehais,
+        }));
+        out.append(&mut body);
+        out.push(json!({"type": "end_kyoku"}));
+        out
+    }
+
+    fn call(
+        &self,
+        kind: &str,
+        actor: usize,
+        caller: PlayerLocation,
+        source: PlayerLocation,
+        called: MahjongTile,
+        tiles: &[MahjongTile],
+    ) -> Value {
+        let mut consumed = tiles.to_vec();
+        if let Some(pos) = consumed.iter().position(|&t| t == called) {
+            consumed.remove(pos);
+        }
+        json!({
+            "type": kind,
+            "actor": actor,
+            "target": self.source(caller, source),
+            "pai": tile_name(called),
+            "consumed": tile_names(&consumed),
+        })
+    }
+
+    /// Each player's point change from a win, by MJAI number.
+    fn win_deltas(
+        &self,
+        details: &WinDetails,
+        winner: PlayerLocation,
+        discarder: Option<PlayerLocation>,
+    ) -> Vec<i32> {
+        let dealer = self.round.config.dealer;
+        let payment = &details.payment;
+        match self.num_players {
+            NumPlayers::Four => self.by_actor(&payment.deltas(winner, dealer, discarder), 0),
+            NumPlayers::Three => {
+                let rule = RuleSet::default().sanma_tsumo;
+                self.by_actor(
+                    &payment.three_player_deltas(winner, dealer, discarder, rule),
+                    0,
+                )
+            }
+        }
+    }
+}
+
+fn add_deltas(total: &mut Vec<i32>, deltas: &[i32]) {
+    total.resize(deltas.len(), 0);
+    for (t, d) in total.iter_mut().zip(deltas) {
+        *t += d;
+    }
+}
+
+/// MJAI's names for the yaku other than the seat and round winds. The
+/// double yakuman that MJAI doesn't name come after the yakuman they
+/// improve on, and are written with the same name.
+const YAKU_NAMES: [(&str, Yaku); 43] = [
+    ("menzenchin_tsumoho", Yaku::MenzenTsumo),
+    ("reach", Yaku::Riichi),
+    ("double_reach", Yaku::DoubleRiichi),
+    ("ippatsu", Yaku::Ippatsu),
+    ("chankan", Yaku::Chankan),
+    ("rinshankaiho", Yaku::RinshanKaihou),
+    ("haiteiraoyue", Yaku::HaiteiRaoyue),
+    ("hoteiraoyui", Yaku::HouteiRaoyui),
+    ("pinfu", Yaku::Pinfu),
+    ("tanyaochu", Yaku::Tanyao),
+    ("ipeko", Yaku::Iipeikou),
+    ("haku", Yaku::Dragon(DragonColor::White)),
+    ("hatsu", Yaku::Dragon(DragonColor::Green)),
+    ("chun", Yaku::Dragon(DragonColor::Red)),
+    ("chitoitsu", Yaku::Chiitoitsu),
+    ("honchantaiyao", Yaku::Chanta),
+    ("ikkitsukan", Yaku::Ittsuu),
+    ("sanshokudojun", Yaku::SanshokuDoujun),
+    ("sanshokudoko", Yaku::SanshokuDoukou),
+    ("sankantsu", Yaku::Sankantsu),
+    ("toitoiho", Yaku::Toitoi),
+    ("sananko", Yaku::Sanankou),
+    ("shosangen", Yaku::Shousangen),
+    ("honroto", Yaku::Honroutou),
+    ("ryanpeko", Yaku::Ryanpeikou),
+    ("junchantaiyao", Yaku::Junchan),
+    ("honiso", Yaku::Honitsu),
+    ("chiniso", Yaku::Chinitsu),
+    ("tenho", Yaku::Yakuman(Yakuman::Tenhou)),
+    ("chiho", Yaku::Yakuman(Yakuman::Chiihou)),
+    ("kokushimuso", Yaku::Yakuman(Yakuman::KokushiMusou)),
+    ("kokushimuso", Yaku::Yakuman(Yakuman::KokushiMusouJuusanmen)),
+    ("daisangen", Yaku::Yakuman(Yakuman::Daisangen)),
+    ("suanko", Yaku::Yakuman(Yakuman::Suuankou)),
+    ("suanko", Yaku::Yakuman(Yakuman::SuuankouTanki)),
+    ("tsuiso", Yaku::Yakuman(Yakuman::Tsuuiisou)),
+    ("ryuiso", Yaku::Yakuman(Yakuman::Ryuuiisou)),
+    ("chinroto", Yaku::Yakuman(Yakuman::Chinroutou)),
+    ("churenpoton", Yaku::Yakuman(Yakuman::ChuurenPoutou)),
+    ("churenpoton", Yaku::Yakuman(Yakuman::JunseiChuurenPoutou)),
+    ("sukantsu", Yaku::Yakuman(Yakuman::Suukantsu)),
+    ("daisushi", Yaku::Yakuman(Yakuman::Daisuushii)),
+    ("shosushi", Yaku::Yakuman(Yakuman::Shousuushii)),
+];
+
+/// The tile MJAI calls `name`, e.g. `"5mr"` or `"P"`.
+fn tile_named(name: &str) -> Result<MahjongTile, FormatError> {
+    let shorthand = match name {
+        "E" => "1z",
+        "S" => "2z",
+        "W" => "3z",
+        "N" => "4z",
+        "P" => "5z",
+        "F" => "6z",
+        "C" => "7z",
+        "5mr" => "0m",
+        "5pr" => "0p",
+        "5sr" => "0s",
+        // Honors and red fives have names of their own.
+        _ if name.ends_with('z') || name.starts_with('0') => "",
+        _ => name,
+    };
+    tile::build(shorthand).map_err(|_| FormatError::UnknownTile(name.to_string()))
+}
+
+/// The MJAI name of `tile`; the inverse of [`tile_named`].
+fn tile_name(tile: MahjongTile) -> String {
+    match tile {
+        MahjongTile::Honor(honor) => {
+            let name = match honor {
+                HonorTile::Wind(Direction::East) => "E",
+                HonorTile::Wind(Direction::South) => "S",
+                HonorTile::Wind(Direction::West) => "W",
+                HonorTile::Wind(Direction::North) => "N",
+                HonorTile::Dragon(DragonColor::White) => "P",
+                HonorTile::Dragon(DragonColor::Green) => "F",
+                HonorTile::Dragon(DragonColor::Red) => "C",
+            };
+            name.to_string()
+        }
+        _ if tile.is_red() => format!("5{}r", &tile.to_string()[1..]),
+        _ => tile.to_string(),
+    }
+}
+
+fn tile_names(tiles: &[MahjongTile]) -> Vec<String> {
+    tiles.iter().map(|&t| tile_name(t)).collect()
+}
+
+/// The name MJAI gives `yaku`.
+fn yaku_name(yaku: Yaku) -> &'static str {
+    match yaku {
+        Yaku::SeatWind(_) => "jikaze",
+        Yaku::RoundWind(_) => "bakaze",
+        _ => YAKU_NAMES
+            .iter()
+            .find(|&&(_, y)| y == yaku)
+            .map_or("", |&(name, _)| name),
+    }
+}
+
+/// A tile, or `None` for one the log hides as `?`.
+fn maybe_tile(tile: &Value, name: &'static str) -> Result<Option<MahjongTile>, FormatError> {
+    match tile.as_str().ok_or(FormatError::Field(name))? {
+        "?" => Ok(None),
+        tile => tile_named(tile).map(Some),
+    }
+}
+
+fn tile_field(message: &Value, name: &'static str) -> Result<MahjongTile, FormatError> {
+    let tile = message
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or(FormatError::Field(name))?;
+    tile_named(tile)
+}
+
+/// A list of tiles, some of which may be hidden.
+fn maybe_tiles(list: &Value, name: &'static str) -> Result<Vec<Option<MahjongTile>>, FormatError> {
+    list.as_array()
+        .ok_or(FormatError::Field(name))?
+        .iter()
+        .map(|tile| maybe_tile(tile, name))
+        .collect()
+}
+
+fn tiles_in(list: &Value, name: &'static str) -> Result<Vec<MahjongTile>, FormatError> {
+    maybe_tiles(list, name)?
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or(FormatError::Field(name))
+}
+
+fn tiles(message: &Value, name: &'static str) -> Result<Vec<MahjongTile>, FormatError> {
+    tiles_in(message.get(name).ok_or(FormatError::Field(name))?, name)
+}
+
+/// The player numbered by the field `name`.
+fn actor_in(message: &Value, name: &'static str, table: Table) -> Result<usize, FormatError> {
+    message
+        .get(name)
+        .and_then(Value::as_u64)
+        .map(|p| p as usize)
+        .filter(|&p| p < table.count())
+        .ok_or(FormatError::Field(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{PlayerLocation::*, RoundNumber};
+    use crate::Error;
+
+    /// South 3 with two repeats, seen by player 1's bot. Player 3's riichi
+    /// is accepted; player 0's declaring discard is ronned by the hero
+    /// before theirs is.
+    const LOG: &str = r#"
+{"type":"start_game","id":1,"names":["A","B","C","D"]}
+{"type":"start_kyoku","bakaze":"S","kyoku":3,"honba":2,"kyotaku":1,"oya":2,"dora_marker":"7m","scores":[20000,30000,25000,24000],"tehais":[H,["1m","2m","3m","4p","5p","6p","7p","8p","9p","3s","4s","6s","6s"],H,H]}
+{"type":"tsumo","actor":2,"pai":"?"}
+{"type":"dahai","actor":2,"pai":"N","tsumogiri":true}
+{"type":"tsumo","actor":3,"pai":"?"}
+{"type":"reach","actor":3}
+{"type":"dahai","actor":3,"pai":"9s","tsumogiri":false}
+{"type":"reach_accepted","actor":3}
+{"type":"tsumo","actor":0,"pai":"?"}
+{"type":"reach","actor":0}
+{"type":"dahai","actor":0,"pai":"5s","tsumogiri":true}
+{"type":"hora","actor":1,"target":0,"pai":"5s","yakus":[["pinfu",1]],"fu":30,"fan":1,"deltas":[-1600,3600,0,0]}
+{"type":"end_kyoku"}
+{"type":"end_game"}
+"#;
+
+    /// A hand the bot can't see.
+    const H: &str = r#"["?","?","?","?","?","?","?","?","?","?","?","?","?"]"#;
+
+    fn log() -> String {
+        LOG.replace('H', H)
+    }
+
+    fn t(s: &str) -> MahjongTile {
+        tile::build(s).unwrap()
+    }
+
+    /// `rounds` written as MJAI messages, after a `start_game` for the
+    /// hero. The dealer of South 3 is player 2, which leaves the hero
+    /// player 1.
+    fn exported(rounds: &[Round]) -> String {
+        std::iter::once(json!({"type": "start_game", "id": 1}))
+            .chain(rounds.iter().flat_map(Round::to_mjai_events))
+            .map(|m| m.to_string() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn starts_the_round_from_start_kyoku() {
+        let rounds = parse_events(log().as_bytes()).unwrap();
+        let config = &rounds[0].config;
+        assert_eq!(
+            (config.wind, config.number, config.repeat, config.dealer),
+            (Direction::South, RoundNumber::Three, 2, Right)
+        );
+        assert_eq!(config.dora, vec![t("7m")]);
+        assert_eq!(
+            config.initial_hero_hand_state,
+            Hand::parse("123m456789p3466s").unwrap()
+        );
+        assert_eq!(
+            (rounds[0].events[0].subject, &rounds[0].events[0].action),
+            (Right, &RoundAction::Draw(ObservedTile::Hidden))
+        );
+        // Without `start_game` the rounds are player 0's, whose hand this
+        // log hides.
+        let unseen = log().replacen(
+            r#"{"type":"start_game","id":1,"names":["A","B","C","D"]}"#,
+            "",
+            1,
+        );
+        assert_eq!(
+            parse_events(unseen.as_bytes()),
+            Err(Error::Format(FormatError::Inconsistent {
+                round: 0,
+                reason: "the hero's hand is hidden"
+            }))
+        );
+        let nobody = log().replace(r#""id":1"#, r#""id":7"#);
+        assert_eq!(
+            parse_events(nobody.as_bytes()),
+            Err(Error::Format(FormatError::Field("id")))
+        );
+    }
+
+    #[test]
+    fn takes_riichi_deposits_when_accepted() {
+        let rounds = parse_events(log().as_bytes()).unwrap();
+        let round = &rounds[0];
+        let RoundAction::Ron(_, Some(details)) = &round.events.last().unwrap().action else {
+            panic!("expected a ron, got {:?}", round.events.last());
+        };
+        // The deposit left from before and player 3's, but not the
+        // discarder's.
+        assert_eq!(
+            (details.payment.from_discarder, details.payment.deposits),
+            (1600, 2000)
+        );
+        assert_eq!(
+            round.config.result.as_ref().unwrap(),
+            &vec![
+                (Hero, 33600),
+                (Right, 25000),
+                (Across, 23000),
+                (Left, 18400)
+            ]
+        );
+        assert_eq!(round.validate(), vec![]);
+        let with_scores = log().replace(
+            r#"{"type":"reach_accepted","actor":3}"#,
+            r#"{"type":"reach_accepted","actor":3,"deltas":[0,0,0,-1000],"scores":[20000,30000,25000,23000]}"#,
+        );
+        assert_eq!(parse_events(with_scores.as_bytes()).unwrap(), rounds);
+        let unaccepted = log().replace(r#"{"type":"reach_accepted","actor":3}"#, "");
+        let result = parse_events(unaccepted.as_bytes()).unwrap()[0]
+            .config
+            .result
+            .clone();
+        assert_eq!(result.unwrap()[2], (Across, 24000));
+    }
+
+    #[test]
+    fn exports_rounds_that_read_back() {
+        let rounds = parse_events(log().as_bytes()).unwrap();
+        let messages = rounds[0].to_mjai_events();
+        let accepted: Vec<usize> = (0..messages.len())
+            .filter(|&i| messages[i]["type"] == "reach_accepted")
+            .collect();
+        assert_eq!(accepted, vec![6]);
+        assert_eq!(parse_events(exported(&rounds).as_bytes()).unwrap(), rounds);
+        // The dealer wins on a draw the bot doesn't see, without the score
+        // details, so only the hand shows the winning tile.
+        let hidden_tsumo = log().replace(
+            r#"{"type":"hora","actor":1,"target":0,"pai":"5s","yakus":[["pinfu",1]],"fu":30,"fan":1,"deltas":[-1600,3600,0,0]}"#,
+            r#"{"type":"reach_accepted","actor":0}
+{"type":"tsumo","actor":1,"pai":"E"}
+{"type":"dahai","actor":1,"pai":"E","tsumogiri":true}
+{"type":"tsumo","actor":2,"pai":"?"}
+{"type":"hora","actor":2,"target":2,"hora_tehais":["2m","3m","4m","5m","6m","7m","2p","3p","4p","5s","6s","9m","9m","7s"],"deltas":[-1200,-1200,6600,-1200]}"#,
+        );
+        let rounds = parse_events(hidden_tsumo.as_bytes()).unwrap();
+        assert_eq!(
+            rounds[0].events.last().map(|e| &e.action),
+            Some(&RoundAction::Tsumo(
+                Hand::parse("23456799m234p567s").unwrap(),
+                None
+            ))
+        );
+        assert_eq!(parse_events(exported(&rounds).as_bytes()).unwrap(), rounds);
+    }
+
+    #[test]
+    fn reports_malformed_messages() {
+        let bad_json = log().replace(r#"{"type":"end_game"}"#, "{");
+        assert!(
+            matches!(parse_events(bad_json.as_bytes()), Err(Error::Format(FormatError::Json(e))) if e.starts_with("line 15:"))
+        );
+        let hidden_draw = log().replace(
+            r#"{"type":"tsumo","actor":0,"pai":"?"}"#,
+            r#"{"type":"tsumo","actor":0,"pai":"?"}
+{"type":"tsumo","actor":1,"pai":"?"}"#,
+        );
+        assert_eq!(
+            parse_events(hidden_draw.as_bytes()),
+            Err(Error::Format(FormatError::Inconsistent {
+                round: 0,
+                reason: "the hero's draw is hidden"
+            }))
+        );
+        let bad_wind = log().replace(r#""bakaze":"S""#, r#""bakaze":"P""#);
+        assert_eq!(
+            parse_events(bad_wind.as_bytes()),
+            Err(Error::Format(FormatError::Field("bakaze")))
+        );
+        let unfinished = log().replace(r#"{"type":"hora""#, r#"{"type":"none""#);
+        assert_eq!(
+            parse_events(unfinished.as_bytes()),
+            Err(Error::Format(FormatError::Inconsistent {
+                round: 0,
+                reason: "the round has no result"
+            }))
+        );
+    }
+}
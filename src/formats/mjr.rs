@@ -0,0 +1,2140 @@
+//! MJR, this crate's own plain-text replay format, modelled on chess's PGN.
+//!
+//! A game starts with a header of tags, one per line, each a name and its
+//! values in square brackets. Free text is quoted, with `\"` and `\\` for
+//! a quote or backslash; everything else is a bare word. The header always
+//! gives `Event`, `Site`, `Date`, `Players` and `Rules`, then the table
+//! settings, then `Result` and the other optional tags when they are known:
+//!
+//! ```text
+//! [Event "Riichi Open 2024"]
+//! [Site "Tenhou"]
+//! [Date "2024-05-01T20:00:00+09:00"]
+//! [Players "Alice" "Bob" "Carol" "Dave"]
+//! [Ratings - 1834 - -]
+//! [Rules "Tenhou"]
+//! [Seats 4]
+//! [Length one-round]
+//! [RedFives 3]
+//! [TimeControl 5 20]
+//! [Result Hero=27900 Right=25000 Across=22100 Left=25000]
+//! ```
+//!
+//! Players are listed by seat, hero first, with `-` for what a log doesn't
+//! record. `Rules` names a preset, followed by `key=value` words for the
+//! rules that differ from it.
+//!
+//! Each round follows with tags of its own, then one event per line: the
+//! player's seat and the move, followed by `key=value` words for what the
+//! log recorded about it. Tiles are in MPSZ shorthand; melds name their
+//! called tile, the seat it came from and their tiles, both those in hands
+//! and as moves:
+//!
+//! ```text
+//! [Round East 1]
+//! [Dealer Hero]
+//! [Dora 9s]
+//! [Deal 123m456p789s1144z]
+//! Hero draw 6m
+//! Hero discard 6m tsumogiri=yes
+//! Right draw ?
+//! Right discard 1z
+//! Hero pon 1z from Right 111z
+//! Hero discard 1m ?!
+//! ( {Keeps the run}
+//!   Hero discard 4z
+//! )
+//! Across draw ?
+//! Across discard 4m elapsed=1.5s
+//! Hero ron 234m456p789s44z + pon 1z from Right 111z winning-tile=4m fu=30 yaku=seat-wind-east:1,round-wind-east:1 win-type=ron dealer=yes from-discarder=2900
+//! ```
+//!
+//! Annotations are the tag symbols, then the comment in braces. Alternative
+//! lines are in parentheses after the event they replace, as PGN
+//! variations are. Tiles and melds are written in the order they are
+//! stored, so the text holds everything the [`Game`] does, and
+//! [`Game::from_mjr_str`] reads it back unchanged. [`ReplayReader`] reads
+//! many games one after another from one source.
+
+use std::fmt::{self, Write};
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+use crate::annotation::{Annotation, Tag};
+use crate::error::Error;
+use crate::game::{
+    AbortiveDraw, EarlyEnd, Game, GameConfig, Length, NumPlayers, PlayerLocation, Round,
+    RoundAction, RoundConfig, RoundEvent, RoundNumber, Seating, Variation,
+};
+use crate::hand::{
+    AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, Meld, MeldError, OpenKanMeld, PonMeld,
+};
+use crate::rules::{AbortiveDraws, Kuikae, RuleSet, SanmaTsumo, Tobi, UmaOka};
+use crate::scoring::{DoraCount, Limit, Payment, WinDetails, WinType};
+use crate::tile::{
+    self, Direction, DragonColor, MahjongTile, ObservedTile, RedFive, Tile, TileParseError,
+};
+use crate::wall::WallSetup;
+use crate::yaku::{Yaku, Yakuman};
+
+impl Game {
+    /// The game in the MJR format. The same game always gives the same
+    /// text.
+    pub fn to_mjr_string(&self) -> String {
+        let mut out = String::new();
+        write_header(&mut out, self);
+        if let Some(annotation) = &self.annotation {
+            let _ = writeln!(out, "{}", annotation_text(annotation));
+        }
+        for round in &self.rounds {
+            out.push('\n');
+            write_round(&mut out, round);
+        }
+        out
+    }
+
+    /// Reads a game in the MJR format, as written by
+    /// [`Game::to_mjr_string`].
+    ///
+    /// Words may be separated by any whitespace, and `;` starts a comment
+    /// that runs to the end of its line. Tags may come in any order; those
+    /// left out keep the values of [`GameConfig::default`], or for a
+    /// round, no honba, the hero as dealer and nothing else known.
+    pub fn from_mjr_str(text: &str) -> crate::Result<Game> {
+        Ok(read_game(text)?)
+    }
+}
+
+fn read_game(text: &str) -> Result<Game, MjrError> {
+    let (tokens, end) = tokenize(text)?;
+    Parser {
+        tokens,
+        next: 0,
+        end,
+    }
+    .game()
+}
+
+fn write_header(out: &mut String, game: &Game) {
+    let config = &game.config;
+    tag(out, "Event", [quoted(&config.event)]);
+    tag(out, "Site", [quoted(&config.site)]);
+    tag(
+        out,
+        "Date",
+        [quoted(
+            &config.date.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+        )],
+    );
+    tag(
+        out,
+        "Players",
+        config.players.iter().map(|p| quoted(&p.name)),
+    );
+    let players = &config.players;
+    let profiles = [
+        (
+            "PlayerIds",
+            players.each_ref().map(|p| p.id.as_deref().map(quoted)),
+        ),
+        (
+            "Ranks",
+            players.each_ref().map(|p| p.rank.as_deref().map(quoted)),
+        ),
+        (
+            "Ratings",
+            players.each_ref().map(|p| p.rating.map(|r| r.to_string())),
+        ),
+    ];
+    for (name, values) in profiles {
+        if values.iter().any(Option::is_some) {
+            tag(
+                out,
+                name,
+                values.map(|v| v.unwrap_or_else(|| "-".to_string())),
+            );
+        }
+    }
+    tag(out, "Rules", rules_values(&config.rules));
+    let seats = match config.num_players {
+        NumPlayers::Three => "3",
+        NumPlayers::Four => "4",
+    };
+    tag(out, "Seats", [seats.to_string()]);
+    let length = match config.length {
+        Length::OneRound => "one-round",
+        Length::Tonpuu => "tonpuu",
+        Length::Hanchan => "hanchan",
+    };
+    tag(out, "Length", [length.to_string()]);
+    let red_fives = match config.red_five {
+        RedFive::Zero => "0",
+        RedFive::Three => "3",
+        RedFive::Four => "4",
+    };
+    tag(out, "RedFives", [red_fives.to_string()]);
+    tag(
+        out,
+        "TimeControl",
+        [seconds(config.thinking_time), seconds(config.time_bank)],
+    );
+    match config.seating {
+        Seating::Unknown => {}
+        Seating::Fixed => tag(out, "Seating", ["fixed".to_string()]),
+        Seating::Random => tag(out, "Seating", ["random".to_string()]),
+        Seating::Dice([a, b]) => tag(
+            out,
+            "Seating",
+            ["dice".to_string(), a.to_string(), b.to_string()],
+        ),
+    }
+    if let Some(dealer) = config.first_dealer {
+        tag(out, "FirstDealer", [dealer.to_string()]);
+    }
+    if let Some(result) = &config.result {
+        tag(out, "Result", scores(result));
+    }
+    match &config.early_end {
+        None => {}
+        Some(EarlyEnd::Tobi(seats)) => tag(
+            out,
+            "EarlyEnd",
+            std::iter::once("tobi".to_string()).chain(seats.iter().map(|s| s.to_string())),
+        ),
+        Some(EarlyEnd::AgariYame) => tag(out, "EarlyEnd", ["agari-yame".to_string()]),
+        Some(EarlyEnd::TenpaiYame) => tag(out, "EarlyEnd", ["tenpai-yame".to_string()]),
+    }
+}
+
+fn write_round(out: &mut String, round: &Round) {
+    let config = &round.config;
+    tag(
+        out,
+        "Round",
+        [config.wind.to_string(), config.number.get().to_string()],
+    );
+    if config.repeat > 0 {
+        tag(out, "Honba", [config.repeat.to_string()]);
+    }
+    tag(out, "Dealer", [config.dealer.to_string()]);
+    tag(out, "Dora", config.dora.iter().map(|t| t.to_string()));
+    if !config.ura_dora.is_empty() {
+        tag(
+            out,
+            "UraDora",
+            config.ura_dora.iter().map(|t| t.to_string()),
+        );
+    }
+    tag(out, "Deal", [hand_text(&config.initial_hero_hand_state)]);
+    if let Some(result) = &config.result {
+        tag(out, "Scores", scores(result));
+    }
+    if !config.wall.is_unknown() {
+        tag(out, "Wall", wall_values(&config.wall));
+    }
+    if let Some(annotation) = &round.annotation {
+        let _ = writeln!(out, "{}", annotation_text(annotation));
+    }
+    write_events(out, &round.events, 0);
+}
+
+fn write_events(out: &mut String, events: &[RoundEvent], depth: usize) {
+    let indent = "  ".repeat(depth);
+    for event in events {
+        let _ = write!(
+            out,
+            "{}{} {}",
+            indent,
+            event.subject,
+            move_text(&event.action)
+        );
+        if let Some(elapsed) = event.elapsed {
+            let _ = write!(out, " elapsed={}s", seconds(elapsed));
+        }
+        if let Some(time) = event.time {
+            let _ = write!(
+                out,
+                " time={}",
+                time.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+            );
+        }
+        if let Some(tsumogiri) = event.tsumogiri {
+            let _ = write!(out, " tsumogiri={}", yes_no(tsumogiri));
+        }
+        if let Some(annotation) = &event.annotation {
+            let _ = write!(out, " {}", annotation_text(annotation));
+        }
+        out.push('\n');
+        for variation in &event.variations {
+            write_variation(out, variation, depth);
+        }
+    }
+}
+
+fn write_variation(out: &mut String, variation: &Variation, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let _ = write!(out, "{}(", indent);
+    if let Some(annotation) = &variation.annotation {
+        let _ = write!(out, " {}", annotation_text(annotation));
+    }
+    out.push('\n');
+    write_events(out, &variation.events, depth + 1);
+    let _ = writeln!(out, "{})", indent);
+}
+
+/// The move of an event, without its subject.
+fn move_text(action: &RoundAction) -> String {
+    match action {
+        RoundAction::Draw(tile) => format!("draw {}", tile),
+        RoundAction::Discard(tile) => format!("discard {}", tile),
+        RoundAction::Chii(m) => meld_text(&Meld::Chii(m.clone())),
+        RoundAction::Pon(m) => meld_text(&Meld::Pon(m.clone())),
+        RoundAction::OpenKan(m) => meld_text(&Meld::OpenKan(m.clone())),
+        RoundAction::ClosedKan(m) => meld_text(&Meld::ClosedKan(m.clone())),
+        RoundAction::AddedKan(m) => meld_text(&Meld::AddedKan(m.clone())),
+        RoundAction::Riichi => "riichi".to_string(),
+        RoundAction::Kita => "kita".to_string(),
+        RoundAction::Tsumo(hand, details) => win_text("tsumo", hand, details.as_ref()),
+        RoundAction::Ron(hand, details) => win_text("ron", hand, details.as_ref()),
+        RoundAction::Exhaustive(hands) => {
+            let mut text = "exhaustive".to_string();
+            for (seat, hand) in hands {
+                let _ = write!(text, " tenpai {} {}", seat, hand_text(hand));
+            }
+            text
+        }
+        RoundAction::AbortiveDraw(kind) => format!("abort {}", abort_name(*kind)),
+    }
+}
+
+fn win_text(name: &str, hand: &Hand, details: Option<&WinDetails>) -> String {
+    let mut text = format!("{} {}", name, hand_text(hand));
+    let Some(details) = details else {
+        return text;
+    };
+    let _ = write!(
+        text,
+        " winning-tile={} fu={}",
+        details.winning_tile, details.fu
+    );
+    if !details.yaku.is_empty() {
+        let yaku: Vec<String> = details
+            .yaku
+            .iter()
+            .map(|&(yaku, han)| format!("{}:{}", yaku_name(yaku), han))
+            .collect();
+        let _ = write!(text, " yaku={}", yaku.join(","));
+    }
+    let payment = &details.payment;
+    let win_type = match payment.win_type {
+        WinType::Ron => "ron",
+        WinType::Tsumo => "tsumo",
+    };
+    let counts = [
+        ("dora", details.dora.dora as u32),
+        ("ura-dora", details.dora.ura_dora as u32),
+        ("aka-dora", details.dora.aka_dora as u32),
+        ("nuki-dora", details.dora.nuki_dora as u32),
+    ];
+    write_counts(&mut text, &counts);
+    let _ = write!(text, " win-type={}", win_type);
+    if payment.dealer {
+        text.push_str(" dealer=yes");
+    }
+    let counts = [
+        ("from-discarder", payment.from_discarder),
+        ("from-dealer", payment.from_dealer),
+        ("from-non-dealer", payment.from_non_dealer),
+        ("deposits", payment.deposits),
+    ];
+    write_counts(&mut text, &counts);
+    if let Some(limit) = payment.limit {
+        let _ = write!(text, " limit={}", limit_name(limit));
+    }
+    text
+}
+
+/// Writes each count as `key=value`, leaving out those that are zero.
+fn write_counts(text: &mut String, counts: &[(&str, u32)]) {
+    for &(key, count) in counts.iter().filter(|&&(_, count)| count > 0) {
+        let _ = write!(text, " {}={}", key, count);
+    }
+}
+
+/// A hand as its concealed tiles, or `-` if there are none, followed by
+/// its melds.
+fn hand_text(hand: &Hand) -> String {
+    let mut text = shorthand(&hand.concealed);
+    for meld in &hand.melds {
+        let _ = write!(text, " + {}", meld_text(meld));
+    }
+    text
+}
+
+/// A meld as it is called or declared: `chii 4p from Left 345p`, `closed-kan
+/// 1111z`, or `added-kan 0m to 5m from Right 555m` for the tile added to a
+/// pon.
+fn meld_text(meld: &Meld) -> String {
+    match meld {
+        Meld::Chii(m) => format!("chii {}", called(m.chii_tile, m.source, &m.tiles)),
+        Meld::Pon(m) => format!("pon {}", called(m.pon_tile, m.source, &m.tiles)),
+        Meld::OpenKan(m) => format!("kan {}", called(m.kan_tile, m.source, &m.tiles)),
+        Meld::ClosedKan(m) => format!("closed-kan {}", shorthand(&m.tiles)),
+        Meld::AddedKan(m) => format!(
+            "added-kan {} to {}",
+            m.added_tile,
+            called(m.pon.pon_tile, m.pon.source, &m.pon.tiles)
+        ),
+    }
+}
+
+fn called(tile: MahjongTile, source: PlayerLocation, tiles: &[MahjongTile]) -> String {
+    format!("{} from {} {}", tile, source, shorthand(tiles))
+}
+
+/// MPSZ shorthand for `tiles` in the order given, or `-` for none: runs of
+/// one suit share the suit letter, as in `31m2p5m`.
+fn shorthand(tiles: &[MahjongTile]) -> String {
+    if tiles.is_empty() {
+        return "-".to_string();
+    }
+    let mut text = String::new();
+    for (i, tile) in tiles.iter().enumerate() {
+        let notation = tile.to_string();
+        text.push_str(&notation[..1]);
+        if tiles
+            .get(i + 1)
+            .is_none_or(|next| next.suit() != tile.suit())
+        {
+            text.push(tile.suit());
+        }
+    }
+    text
+}
+
+fn tag<I: IntoIterator<Item = String>>(out: &mut String, name: &str, values: I) {
+    out.push('[');
+    out.push_str(name);
+    for value in values {
+        out.push(' ');
+        out.push_str(&value);
+    }
+    out.push_str("]\n");
+}
+
+fn quoted(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escaped)
+}
+
+/// Tag symbols, then the comment in braces. The braces are left out of an
+/// annotation with tags but no comment.
+fn annotation_text(annotation: &Annotation) -> String {
+    let mut words: Vec<String> = annotation
+        .tags
+        .iter()
+        .map(|tag| tag.symbol().to_string())
+        .collect();
+    if !annotation.comment.is_empty() || words.is_empty() {
+        let escaped = annotation.comment.replace('\\', "\\\\").replace('}', "\\}");
+        words.push(format!("{{{}}}", escaped));
+    }
+    words.join(" ")
+}
+
+fn scores(result: &[(PlayerLocation, i32)]) -> Vec<String> {
+    result
+        .iter()
+        .map(|(seat, points)| format!("{}={}", seat, points))
+        .collect()
+}
+
+fn wall_values(wall: &WallSetup) -> Vec<String> {
+    let mut values = Vec::new();
+    if let Some(seed) = wall.seed {
+        values.push(format!("seed={}", seed));
+    }
+    if let Some([a, b]) = wall.dice {
+        values.push(format!("dice={},{}", a, b));
+    }
+    if let Some(position) = wall.break_position {
+        values.push(format!("break={}", position));
+    }
+    values
+}
+
+/// Whole seconds, or seconds with as many decimals as they need.
+fn seconds(duration: Duration) -> String {
+    let nanos = duration.subsec_nanos();
+    if nanos == 0 {
+        return duration.as_secs().to_string();
+    }
+    let fraction = format!("{:09}", nanos);
+    format!("{}.{}", duration.as_secs(), fraction.trim_end_matches('0'))
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// The presets a `Rules` tag can name, looked for in this order.
+fn presets() -> [(&'static str, RuleSet); 4] {
+    [
+        ("Default", RuleSet::default()),
+        ("Tenhou", RuleSet::tenhou()),
+        ("Mahjong Soul", RuleSet::mahjong_soul()),
+        ("M.League", RuleSet::mleague()),
+    ]
+}
+
+/// The `Rules` tag: the first preset that matches, or the default rules
+/// followed by each rule that differs from them.
+fn rules_values(rules: &RuleSet) -> Vec<String> {
+    if let Some((name, _)) = presets().iter().find(|(_, preset)| preset == rules) {
+        return vec![quoted(name)];
+    }
+    let defaults = rule_settings(&RuleSet::default());
+    let mut values = vec![quoted("Default")];
+    for ((key, value), (_, default)) in rule_settings(rules).into_iter().zip(defaults) {
+        if value != default {
+            values.push(format!("{}={}", key, value));
+        }
+    }
+    values
+}
+
+/// Every rule as a `Rules` tag key and its value.
+fn rule_settings(rules: &RuleSet) -> Vec<(&'static str, String)> {
+    let kuikae = match rules.kuikae {
+        Kuikae::Allowed => "allowed",
+        Kuikae::SameTile => "same-tile",
+        Kuikae::SameTileAndSuji => "same-tile-and-suji",
+    };
+    let sanma_tsumo = match rules.sanma_tsumo {
+        SanmaTsumo::Loss => "loss",
+        SanmaTsumo::Split => "split",
+    };
+    let aborts: Vec<&str> = ABORTS
+        .iter()
+        .filter(|&&kind| rules.abortive_draws.allows(kind))
+        .map(|&kind| abort_name(kind))
+        .collect();
+    let aborts = if aborts.is_empty() {
+        "none".to_string()
+    } else {
+        aborts.join(",")
+    };
+    let extension_target = rules
+        .extension_target
+        .map_or("none".to_string(), |target| target.to_string());
+    let tobi = match rules.tobi {
+        Tobi::Never => "never",
+        Tobi::BelowZero => "below-zero",
+        Tobi::AtZero => "at-zero",
+    };
+    let uma_oka = &rules.uma_oka;
+    let uma_oka = format!(
+        "{},{},{}",
+        uma_oka.starting_points,
+        uma_oka.return_points,
+        uma_oka.uma.map(|u| u.to_string()).join(",")
+    );
+    vec![
+        ("kuitan", yes_no(rules.kuitan).to_string()),
+        ("atozuke", yes_no(rules.atozuke).to_string()),
+        ("kiriage-mangan", yes_no(rules.kiriage_mangan).to_string()),
+        ("double-yakuman", yes_no(rules.double_yakuman).to_string()),
+        ("stack-yakuman", yes_no(rules.stack_yakuman).to_string()),
+        ("nagashi-mangan", yes_no(rules.nagashi_mangan).to_string()),
+        ("kuikae", kuikae.to_string()),
+        (
+            "return-deposits-on-abort",
+            yes_no(rules.return_deposits_on_abort).to_string(),
+        ),
+        ("sanma-tsumo", sanma_tsumo.to_string()),
+        ("abortive-draws", aborts),
+        ("multiple-ron", yes_no(rules.multiple_ron).to_string()),
+        ("extension-target", extension_target),
+        ("tobi", tobi.to_string()),
+        ("agari-yame", yes_no(rules.agari_yame).to_string()),
+        ("tenpai-yame", yes_no(rules.tenpai_yame).to_string()),
+        ("uma-oka", uma_oka),
+    ]
+}
+
+const ABORTS: [AbortiveDraw; 5] = [
+    AbortiveDraw::KyuushuKyuuhai,
+    AbortiveDraw::SuufonRenda,
+    AbortiveDraw::SuuchaRiichi,
+    AbortiveDraw::Suukaikan,
+    AbortiveDraw::SanchaHou,
+];
+
+fn abort_name(kind: AbortiveDraw) -> &'static str {
+    match kind {
+        AbortiveDraw::KyuushuKyuuhai => "kyuushu-kyuuhai",
+        AbortiveDraw::SuufonRenda => "suufon-renda",
+        AbortiveDraw::SuuchaRiichi => "suucha-riichi",
+        AbortiveDraw::Suukaikan => "suukaikan",
+        AbortiveDraw::SanchaHou => "sancha-hou",
+    }
+}
+
+fn limit_name(limit: Limit) -> String {
+    match limit {
+        Limit::Mangan => "mangan".to_string(),
+        Limit::Haneman => "haneman".to_string(),
+        Limit::Baiman => "baiman".to_string(),
+        Limit::Sanbaiman => "sanbaiman".to_string(),
+        Limit::Yakuman(n) => format!("yakuman-{}", n),
+    }
+}
+
+fn yaku_name(yaku: Yaku) -> &'static str {
+    YAKU_NAMES
+        .iter()
+        .find(|&&(_, y)| y == yaku)
+        .map(|&(name, _)| name)
+        .expect("every yaku has a name")
+}
+
+/// The name MJR gives each yaku.
+const YAKU_NAMES: [(&str, Yaku); 51] = [
+    ("menzen-tsumo", Yaku::MenzenTsumo),
+    ("riichi", Yaku::Riichi),
+    ("double-riichi", Yaku::DoubleRiichi),
+    ("ippatsu", Yaku::Ippatsu),
+    ("chankan", Yaku::Chankan),
+    ("rinshan-kaihou", Yaku::RinshanKaihou),
+    ("haitei-raoyue", Yaku::HaiteiRaoyue),
+    ("houtei-raoyui", Yaku::HouteiRaoyui),
+    ("pinfu", Yaku::Pinfu),
+    ("tanyao", Yaku::Tanyao),
+    ("iipeikou", Yaku::Iipeikou),
+    ("seat-wind-east", Yaku::SeatWind(Direction::East)),
+    ("seat-wind-south", Yaku::SeatWind(Direction::South)),
+    ("seat-wind-west", Yaku::SeatWind(Direction::West)),
+    ("seat-wind-north", Yaku::SeatWind(Direction::North)),
+    ("round-wind-east", Yaku::RoundWind(Direction::East)),
+    ("round-wind-south", Yaku::RoundWind(Direction::South)),
+    ("round-wind-west", Yaku::RoundWind(Direction::West)),
+    ("round-wind-north", Yaku::RoundWind(Direction::North)),
+    ("dragon-white", Yaku::Dragon(DragonColor::White)),
+    ("dragon-green", Yaku::Dragon(DragonColor::Green)),
+    ("dragon-red", Yaku::Dragon(DragonColor::Red)),
+    ("chiitoitsu", Yaku::Chiitoitsu),
+    ("chanta", Yaku::Chanta),
+    ("ittsuu", Yaku::Ittsuu),
+    ("sanshoku-doujun", Yaku::SanshokuDoujun),
+    ("sanshoku-doukou", Yaku::SanshokuDoukou),
+    ("sankantsu", Yaku::Sankantsu),
+    ("toitoi", Yaku::Toitoi),
+    ("sanankou", Yaku::Sanankou),
+    ("shousangen", Yaku::Shousangen),
+    ("honroutou", Yaku::Honroutou),
+    ("ryanpeikou", Yaku::Ryanpeikou),
+    ("junchan", Yaku::Junchan),
+    ("honitsu", Yaku::Honitsu),
+    ("chinitsu", Yaku::Chinitsu),
+    ("kokushi-musou", Yaku::Yakuman(Yakuman::KokushiMusou)),
+    (
+        "kokushi-musou-juusanmen",
+        Yaku::Yakuman(Yakuman::KokushiMusouJuusanmen),
+    ),
+    ("suuankou", Yaku::Yakuman(Yakuman::Suuankou)),
+    ("suuankou-tanki", Yaku::Yakuman(Yakuman::SuuankouTanki)),
+    ("daisangen", Yaku::Yakuman(Yakuman::Daisangen)),
+    ("shousuushii", Yaku::Yakuman(Yakuman::Shousuushii)),
+    ("daisuushii", Yaku::Yakuman(Yakuman::Daisuushii)),
+    ("tsuuiisou", Yaku::Yakuman(Yakuman::Tsuuiisou)),
+    ("chinroutou", Yaku::Yakuman(Yakuman::Chinroutou)),
+    ("ryuuiisou", Yaku::Yakuman(Yakuman::Ryuuiisou)),
+    ("chuuren-poutou", Yaku::Yakuman(Yakuman::ChuurenPoutou)),
+    (
+        "junsei-chuuren-poutou",
+        Yaku::Yakuman(Yakuman::JunseiChuurenPoutou),
+    ),
+    ("suukantsu", Yaku::Yakuman(Yakuman::Suukantsu)),
+    ("tenhou", Yaku::Yakuman(Yakuman::Tenhou)),
+    ("chiihou", Yaku::Yakuman(Yakuman::Chiihou)),
+];
+
+/// Why MJR text could not be read, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MjrError {
+    /// The line of the text at fault, counted from 1.
+    pub line: usize,
+    /// The column of the text at fault in characters, counted from 1.
+    pub column: usize,
+    pub kind: MjrErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MjrErrorKind {
+    /// The text stops in the middle of a tag, event or variation.
+    UnexpectedEnd,
+    /// A quoted string or a comment in braces is never closed.
+    Unclosed(&'static str),
+    /// The text couldn't be read; the I/O error's message.
+    Io(String),
+    /// Something other than what the format allows here: what it allows,
+    /// and what was found.
+    Expected {
+        expected: &'static str,
+        found: String,
+    },
+    /// A tag with a name the format doesn't define.
+    UnknownTag(String),
+    /// A header tag given twice in one game.
+    DuplicateTag(String),
+    /// A `key=value` word with a key that means nothing where it is.
+    UnknownKey(String),
+    /// A score breakdown without this key.
+    MissingKey(&'static str),
+    /// A tag with the wrong number of values.
+    ValueCount { expected: usize, found: usize },
+    /// A word that should be tiles and isn't.
+    Tile(TileParseError),
+    /// A meld whose tiles don't fit together.
+    InvalidMeld(MeldError),
+}
+
+impl fmt::Display for MjrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            MjrErrorKind::UnexpectedEnd => write!(f, "unexpected end of text"),
+            MjrErrorKind::Unclosed(what) => write!(f, "unclosed {}", what),
+            MjrErrorKind::Io(message) => write!(f, "could not read the text: {}", message),
+            MjrErrorKind::Expected { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            MjrErrorKind::UnknownTag(name) => write!(f, "unknown tag {}", name),
+            MjrErrorKind::DuplicateTag(name) => write!(f, "duplicate tag {}", name),
+            MjrErrorKind::UnknownKey(key) => write!(f, "unknown key {}", key),
+            MjrErrorKind::MissingKey(key) => write!(f, "missing key {}", key),
+            MjrErrorKind::ValueCount { expected, found } => {
+                write!(f, "expected {} values, found {}", expected, found)
+            }
+            MjrErrorKind::Tile(e) => e.fmt(f),
+            MjrErrorKind::InvalidMeld(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MjrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            MjrErrorKind::Tile(e) => Some(e),
+            MjrErrorKind::InvalidMeld(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// One of `[`, `]`, `(` and `)`.
+    Punct(char),
+    Quoted(String),
+    /// The comment of an annotation, without its braces.
+    Comment(String),
+    Word(String),
+}
+
+/// A token and where it starts.
+#[derive(Debug, Clone)]
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+impl Spanned {
+    fn error(&self, kind: MjrErrorKind) -> MjrError {
+        MjrError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    /// An error saying that `expected` should have been here instead.
+    fn expected(&self, expected: &'static str) -> MjrError {
+        let found = match &self.token {
+            Token::Punct(c) => format!("`{}`", c),
+            Token::Quoted(text) => format!("{:?}", text),
+            Token::Comment(_) => "a comment".to_string(),
+            Token::Word(word) => format!("`{}`", word),
+        };
+        self.error(MjrErrorKind::Expected { expected, found })
+    }
+
+    /// The text of a word or quoted string.
+    fn text(&self) -> Option<&str> {
+        match &self.token {
+            Token::Word(text) | Token::Quoted(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.token == Token::Word(word.to_string())
+    }
+
+    /// The word, or `None` for a missing value: a bare `-`.
+    fn optional(&self) -> Option<&str> {
+        match &self.token {
+            Token::Word(word) if word == "-" => None,
+            _ => self.text(),
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(&self, expected: &'static str) -> Result<T, MjrError> {
+        self.text()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| self.expected(expected))
+    }
+
+    /// The word split at its first `=`.
+    fn pair(&self) -> Option<(&str, &str)> {
+        match &self.token {
+            Token::Word(word) => word.split_once('='),
+            _ => None,
+        }
+    }
+
+    /// The value of a `key=value` word, as a token of its own one column
+    /// after the `=`.
+    fn value(&self) -> Spanned {
+        let (key, value) = self.pair().unwrap_or_default();
+        Spanned {
+            token: Token::Word(value.to_string()),
+            line: self.line,
+            column: self.column + key.chars().count() + 1,
+        }
+    }
+}
+
+/// Reads the games of MJR text one at a time, for collections of logs too
+/// large to hold in memory.
+///
+/// The games may simply be concatenated. A new game starts at an `Event`
+/// tag, at a header tag the game being read already has, and at any header
+/// tag that follows a round or a blank line. Only the game being read is
+/// kept, and errors give their position in the whole text.
+pub struct ReplayReader<R> {
+    lines: Lines<BufReader<R>>,
+    /// How many lines have been read.
+    line: usize,
+    /// The line that starts the next game, already read.
+    pending: Option<String>,
+    split: Split,
+}
+
+impl<R: Read> ReplayReader<R> {
+    pub fn new(reader: R) -> ReplayReader<R> {
+        ReplayReader {
+            lines: BufReader::new(reader).lines(),
+            line: 0,
+            pending: None,
+            split: Split::default(),
+        }
+    }
+
+    /// The rounds of every game in turn, for analyses that don't need the
+    /// game headers.
+    pub fn rounds(self) -> Rounds<R> {
+        Rounds {
+            games: self,
+            rounds: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for ReplayReader<R> {
+    type Item = crate::Result<Game>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = String::new();
+        let mut first = self.line + 1;
+        if let Some(line) = self.pending.take() {
+            first -= 1;
+            self.split.scan(&line);
+            text.push_str(&line);
+            text.push('\n');
+        }
+        loop {
+            let line = match self.lines.next() {
+                None => break,
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    return Some(Err(Error::Mjr(MjrError {
+                        line: self.line + 1,
+                        column: 1,
+                        kind: MjrErrorKind::Io(e.to_string()),
+                    })))
+                }
+            };
+            self.line += 1;
+            if self.split.starts_game(&line) {
+                self.pending = Some(line);
+                break;
+            }
+            self.split.scan(&line);
+            text.push_str(&line);
+            text.push('\n');
+        }
+        if !std::mem::take(&mut self.split).content {
+            return None;
+        }
+        Some(read_game(&text).map_err(|e| {
+            Error::Mjr(MjrError {
+                line: e.line + first - 1,
+                ..e
+            })
+        }))
+    }
+}
+
+/// The rounds of the games a [`ReplayReader`] reads, from
+/// [`ReplayReader::rounds`].
+pub struct Rounds<R> {
+    games: ReplayReader<R>,
+    /// The rounds left of the game read last.
+    rounds: std::vec::IntoIter<Round>,
+}
+
+impl<R: Read> Iterator for Rounds<R> {
+    type Item = crate::Result<Round>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(round) = self.rounds.next() {
+                return Some(Ok(round));
+            }
+            match self.games.next()? {
+                Ok(game) => self.rounds = game.rounds.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// What the lines of the game being read have shown, to tell where the
+/// next game starts.
+#[derive(Debug, Default)]
+struct Split {
+    /// Whether anything but whitespace and `;` comments was read.
+    content: bool,
+    /// Whether a `Round` tag was read.
+    rounds: bool,
+    /// The header tags read.
+    header: Vec<String>,
+    /// Whether the last line was blank and came after some content.
+    blank: bool,
+    /// Whether the text read ends inside a quoted string or a comment in
+    /// braces, and so the next line can't start a tag.
+    inside: Option<char>,
+}
+
+/// The tags that belong to a round rather than the game header.
+const ROUND_TAGS: [&str; 8] = [
+    "Round", "Honba", "Dealer", "Dora", "UraDora", "Deal", "Scores", "Wall",
+];
+
+impl Split {
+    /// Whether `line` is a header tag starting another game.
+    fn starts_game(&self, line: &str) -> bool {
+        match self.tag_name(line) {
+            Some(name) if !ROUND_TAGS.contains(&name) => {
+                let repeated = name == "Event" || self.header.iter().any(|tag| tag == name);
+                self.content && (repeated || self.rounds || self.blank)
+            }
+            _ => false,
+        }
+    }
+
+    /// The name of the tag `line` starts with, if it starts one.
+    fn tag_name<'a>(&self, line: &'a str) -> Option<&'a str> {
+        if self.inside.is_some() {
+            return None;
+        }
+        let tag = line.trim_start().strip_prefix('[')?.trim_start();
+        tag.split(|c: char| c.is_whitespace() || c == ']').next()
+    }
+
+    /// Takes in a line of the game being read.
+    fn scan(&mut self, line: &str) {
+        match self.tag_name(line) {
+            Some("Round") => self.rounds = true,
+            Some(name) if !ROUND_TAGS.contains(&name) => self.header.push(name.to_string()),
+            _ => {}
+        }
+        let mut blank = self.inside.is_none();
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            match (self.inside, c) {
+                (Some(_), '\\') => {
+                    chars.next();
+                }
+                (Some(close), c) if c == close => self.inside = None,
+                (Some(_), _) => {}
+                (None, ';') => break,
+                (None, c) if c.is_whitespace() => {}
+                (None, c) => {
+                    blank = false;
+                    self.content = true;
+                    match c {
+                        '"' => self.inside = Some('"'),
+                        '{' => self.inside = Some('}'),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self.blank = blank && self.content;
+    }
+}
+
+/// Walks MJR text a character at a time, counting lines and columns.
+struct Scanner<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else if c.is_some() {
+            self.column += 1;
+        }
+        c
+    }
+
+    /// A token at the current position.
+    fn here(&self, token: Token) -> Spanned {
+        Spanned {
+            token,
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+/// Splits MJR text into tokens, dropping whitespace and `;` comments, and
+/// returns them with a token marking the end of the text.
+fn tokenize(text: &str) -> Result<(Vec<Spanned>, Spanned), MjrError> {
+    let mut scanner = Scanner {
+        chars: text.chars().peekable(),
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+    while let Some(c) = scanner.peek() {
+        let mut start = scanner.here(Token::Word(String::new()));
+        start.token = match c {
+            c if c.is_whitespace() => {
+                scanner.bump();
+                continue;
+            }
+            ';' => {
+                while scanner.peek().is_some_and(|c| c != '\n') {
+                    scanner.bump();
+                }
+                continue;
+            }
+            '[' | ']' | '(' | ')' => {
+                scanner.bump();
+                Token::Punct(c)
+            }
+            '"' | '{' => {
+                scanner.bump();
+                let (close, what) = if c == '"' {
+                    ('"', "string")
+                } else {
+                    ('}', "comment")
+                };
+                let mut text = String::new();
+                loop {
+                    match scanner.bump() {
+                        None => return Err(start.error(MjrErrorKind::Unclosed(what))),
+                        Some(end) if end == close => break,
+                        Some('\\') => text.extend(scanner.bump()),
+                        Some(other) => text.push(other),
+                    }
+                }
+                if c == '"' {
+                    Token::Quoted(text)
+                } else {
+                    Token::Comment(text)
+                }
+            }
+            '}' => return Err(start.expected("a word")),
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = scanner.peek() {
+                    if c.is_whitespace() || "[](){}\";".contains(c) {
+                        break;
+                    }
+                    word.extend(scanner.bump());
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(start);
+    }
+    let end = scanner.here(Token::Word(String::new()));
+    Ok((tokens, end))
+}
+
+/// Reads a game from its tokens.
+struct Parser {
+    tokens: Vec<Spanned>,
+    next: usize,
+    /// Where the text ends, for errors about what is missing.
+    end: Spanned,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Spanned> {
+        self.tokens.get(self.next)
+    }
+
+    fn peek_word(&self, word: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_word(word))
+    }
+
+    fn bump(&mut self) -> Result<Spanned, MjrError> {
+        let token = self
+            .tokens
+            .get(self.next)
+            .cloned()
+            .ok_or_else(|| self.end.error(MjrErrorKind::UnexpectedEnd))?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    /// An error saying that `expected` should come next.
+    fn expected(&self, expected: &'static str) -> MjrError {
+        match self.peek() {
+            Some(token) => token.expected(expected),
+            None => self.end.error(MjrErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn punct(&mut self, c: char) -> Result<(), MjrError> {
+        let expected = match c {
+            '[' => "`[`",
+            ']' => "`]`",
+            '(' => "`(`",
+            _ => "`)`",
+        };
+        match self.peek() {
+            Some(token) if token.token == Token::Punct(c) => {
+                self.next += 1;
+                Ok(())
+            }
+            _ => Err(self.expected(expected)),
+        }
+    }
+
+    fn word(&mut self, expected: &'static str) -> Result<Spanned, MjrError> {
+        match self.peek() {
+            Some(Spanned {
+                token: Token::Word(_),
+                ..
+            }) => self.bump(),
+            _ => Err(self.expected(expected)),
+        }
+    }
+
+    /// Whether a tag comes next, and if `name` is given, that tag.
+    fn at_tag(&self, name: Option<&str>) -> bool {
+        let open = self.peek().is_some_and(|t| t.token == Token::Punct('['));
+        let named = match name {
+            Some(name) => self
+                .tokens
+                .get(self.next + 1)
+                .is_some_and(|t| t.is_word(name)),
+            None => true,
+        };
+        open && named
+    }
+
+    /// The values of a tag up to its closing `]`.
+    fn tag_values(&mut self) -> Result<Vec<Spanned>, MjrError> {
+        let mut values = Vec::new();
+        loop {
+            let token = self.bump()?;
+            match token.token {
+                Token::Punct(']') => return Ok(values),
+                Token::Word(_) | Token::Quoted(_) => values.push(token),
+                _ => return Err(token.expected("a tag value or `]`")),
+            }
+        }
+    }
+
+    fn game(&mut self) -> Result<Game, MjrError> {
+        let mut config = GameConfig::default();
+        let mut seen = Vec::new();
+        while self.at_tag(None) && !self.at_tag(Some("Round")) {
+            self.punct('[')?;
+            let name = self.word("a tag name")?;
+            let tag = name.text().unwrap_or_default().to_string();
+            if seen.contains(&tag) {
+                return Err(name.error(MjrErrorKind::DuplicateTag(tag)));
+            }
+            seen.push(tag);
+            let values = self.tag_values()?;
+            header_tag(&mut config, &name, &values)?;
+        }
+        let annotation = self.annotation()?;
+        let mut rounds = Vec::new();
+        while self.at_tag(Some("Round")) {
+            rounds.push(self.round()?);
+        }
+        if self.peek().is_some() {
+            return Err(self.expected("a round"));
+        }
+        Ok(Game {
+            config,
+            rounds,
+            annotation,
+        })
+    }
+
+    fn round(&mut self) -> Result<Round, MjrError> {
+        self.punct('[')?;
+        self.bump()?;
+        let wind = direction(&self.word("a wind")?)?;
+        let number = self.word("a round number")?;
+        let number = match number.parse::<u8>("a round number from 1 to 4")? {
+            1 => RoundNumber::One,
+            2 => RoundNumber::Two,
+            3 => RoundNumber::Three,
+            4 => RoundNumber::Four,
+            _ => return Err(number.expected("a round number from 1 to 4")),
+        };
+        self.punct(']')?;
+        let mut config = RoundConfig {
+            wind,
+            number,
+            repeat: 0,
+            dealer: PlayerLocation::Hero,
+            dora: Vec::new(),
+            ura_dora: Vec::new(),
+            initial_hero_hand_state: Hand::default(),
+            result: None,
+            wall: WallSetup::default(),
+        };
+        while self.at_tag(None) && !self.at_tag(Some("Round")) {
+            self.punct('[')?;
+            let name = self.word("a tag name")?;
+            if name.is_word("Deal") {
+                config.initial_hero_hand_state = self.hand()?;
+                self.punct(']')?;
+                continue;
+            }
+            let values = self.tag_values()?;
+            round_tag(&mut config, &name, &values)?;
+        }
+        let annotation = self.annotation()?;
+        let events = self.events()?;
+        if self.peek().is_some() && !self.at_tag(Some("Round")) {
+            return Err(self.expected("an event"));
+        }
+        Ok(Round {
+            config,
+            events,
+            annotation,
+        })
+    }
+
+    /// Events up to the next tag, the end of a variation or the end of the
+    /// text.
+    fn events(&mut self) -> Result<Vec<RoundEvent>, MjrError> {
+        let mut events = Vec::new();
+        while let Some(Token::Word(word)) = self.peek().map(|t| &t.token) {
+            if !is_seat(word) {
+                break;
+            }
+            events.push(self.event()?);
+        }
+        Ok(events)
+    }
+
+    fn event(&mut self) -> Result<RoundEvent, MjrError> {
+        let subject = seat(&self.bump()?)?;
+        let verb = self.word("a move")?;
+        let name = verb.text().unwrap_or_default();
+        let action = match name {
+            "draw" => {
+                let tile = self.word("a tile or `?`")?;
+                let observed = match tile.text() {
+                    Some("?") => ObservedTile::Hidden,
+                    _ => ObservedTile::Known(one_tile(&tile)?),
+                };
+                RoundAction::Draw(observed)
+            }
+            "discard" => RoundAction::Discard(one_tile(&self.word("a tile")?)?),
+            "chii" | "pon" | "kan" | "closed-kan" | "added-kan" => match self.meld_after(&verb)? {
+                Meld::Chii(m) => RoundAction::Chii(m),
+                Meld::Pon(m) => RoundAction::Pon(m),
+                Meld::OpenKan(m) => RoundAction::OpenKan(m),
+                Meld::ClosedKan(m) => RoundAction::ClosedKan(m),
+                Meld::AddedKan(m) => RoundAction::AddedKan(m),
+            },
+            "riichi" => RoundAction::Riichi,
+            "kita" => RoundAction::Kita,
+            "tsumo" => RoundAction::Tsumo(self.hand()?, None),
+            "ron" => RoundAction::Ron(self.hand()?, None),
+            "exhaustive" => {
+                let mut hands = Vec::new();
+                while self.peek_word("tenpai") {
+                    self.bump()?;
+                    let player = seat(&self.word("a seat")?)?;
+                    hands.push((player, self.hand()?));
+                }
+                RoundAction::Exhaustive(hands)
+            }
+            "abort" => {
+                let kind = self.word("an abortive draw")?;
+                let kind = ABORTS
+                    .into_iter()
+                    .find(|&k| kind.is_word(abort_name(k)))
+                    .ok_or_else(|| kind.expected("an abortive draw"))?;
+                RoundAction::AbortiveDraw(kind)
+            }
+            _ => return Err(verb.expected("a move")),
+        };
+        let mut event = RoundEvent::new(subject, action);
+        let mut details = Vec::new();
+        while self.peek().and_then(Spanned::pair).is_some() {
+            let word = self.bump()?;
+            let value = word.value();
+            match word.pair().unwrap_or_default().0 {
+                "elapsed" => {
+                    let text = value.text().unwrap_or_default();
+                    let seconds = text.strip_suffix('s').map(|s| Spanned {
+                        token: Token::Word(s.to_string()),
+                        ..value.clone()
+                    });
+                    let seconds = seconds.ok_or_else(|| value.expected("a time in seconds"))?;
+                    event.elapsed = Some(duration(&seconds)?);
+                }
+                "time" => event.time = Some(date(&value)?),
+                "tsumogiri" => event.tsumogiri = Some(yes_or_no(&value)?),
+                _ => details.push(word),
+            }
+        }
+        if !details.is_empty() {
+            match &mut event.action {
+                RoundAction::Tsumo(_, slot) | RoundAction::Ron(_, slot) => {
+                    *slot = Some(win_details(&verb, &details)?);
+                }
+                _ => {
+                    let key = details[0].pair().unwrap_or_default().0.to_string();
+                    return Err(details[0].error(MjrErrorKind::UnknownKey(key)));
+                }
+            }
+        }
+        event.annotation = self.annotation()?;
+        while self.peek().is_some_and(|t| t.token == Token::Punct('(')) {
+            self.bump()?;
+            let annotation = self.annotation()?;
+            let events = self.events()?;
+            self.punct(')')?;
+            event.variations.push(Variation { events, annotation });
+        }
+        Ok(event)
+    }
+
+    /// Tag symbols and a comment, if any come next.
+    fn annotation(&mut self) -> Result<Option<Annotation>, MjrError> {
+        let mut annotation = None;
+        while let Some(tag) = self
+            .peek()
+            .and_then(Spanned::text)
+            .and_then(|text| TAGS.into_iter().find(|tag| tag.symbol() == text))
+        {
+            self.bump()?;
+            annotation
+                .get_or_insert_with(Annotation::default)
+                .tags
+                .push(tag);
+        }
+        if let Some(Token::Comment(comment)) = self.peek().map(|t| &t.token) {
+            let comment = comment.clone();
+            self.bump()?;
+            annotation.get_or_insert_with(Annotation::default).comment = comment;
+        }
+        Ok(annotation)
+    }
+
+    /// A hand: its concealed tiles then `+` and each meld.
+    fn hand(&mut self) -> Result<Hand, MjrError> {
+        let concealed = self.word("a hand")?;
+        let concealed = match concealed.optional() {
+            None => Vec::new(),
+            Some(_) => tiles(&concealed)?,
+        };
+        let mut melds = Vec::new();
+        while self.peek_word("+") {
+            self.bump()?;
+            let kind = self.word("a meld")?;
+            melds.push(self.meld_after(&kind)?);
+        }
+        Ok(Hand { concealed, melds })
+    }
+
+    /// The rest of a meld after the word naming its `kind`.
+    fn meld_after(&mut self, kind: &Spanned) -> Result<Meld, MjrError> {
+        let meld = match kind.text().unwrap_or_default() {
+            "chii" => {
+                let (tile, source, tiles) = self.called(kind)?;
+                Meld::Chii(ChiiMeld {
+                    tiles,
+                    chii_tile: tile,
+                    source,
+                })
+            }
+            "pon" => {
+                let (tile, source, tiles) = self.called(kind)?;
+                Meld::Pon(PonMeld {
+                    tiles,
+                    pon_tile: tile,
+                    source,
+                })
+            }
+            "kan" => {
+                let (tile, source, tiles) = self.called(kind)?;
+                Meld::OpenKan(OpenKanMeld {
+                    tiles,
+                    kan_tile: tile,
+                    source,
+                })
+            }
+            "closed-kan" => Meld::ClosedKan(ClosedKanMeld {
+                tiles: tile_array(&self.word("the kan's tiles")?)?,
+            }),
+            "added-kan" => {
+                let added_tile = one_tile(&self.word("the added tile")?)?;
+                let to = self.word("`to`")?;
+                if !to.is_word("to") {
+                    return Err(to.expected("`to`"));
+                }
+                let (tile, source, tiles) = self.called(kind)?;
+                Meld::AddedKan(AddedKanMeld {
+                    pon: PonMeld {
+                        tiles,
+                        pon_tile: tile,
+                        source,
+                    },
+                    added_tile,
+                })
+            }
+            _ => return Err(kind.expected("a meld")),
+        };
+        meld.validate()
+            .map_err(|e| kind.error(MjrErrorKind::InvalidMeld(e)))?;
+        Ok(meld)
+    }
+
+    /// The called tile, its source and the meld's tiles of a call.
+    fn called<const N: usize>(
+        &mut self,
+        kind: &Spanned,
+    ) -> Result<(MahjongTile, PlayerLocation, [MahjongTile; N]), MjrError> {
+        let tile = one_tile(&self.word("the called tile")?)?;
+        let from = self.word("`from`")?;
+        if !from.is_word("from") {
+            return Err(from.expected("`from`"));
+        }
+        let source = seat(&self.word("a seat")?)?;
+        let tiles = self.word("the meld's tiles")?;
+        let tiles = tile_array(&tiles).map_err(|e| match e.kind {
+            MjrErrorKind::ValueCount { .. } => kind.expected("a meld of three or four tiles"),
+            _ => e,
+        })?;
+        Ok((tile, source, tiles))
+    }
+}
+
+fn header_tag(config: &mut GameConfig, name: &Spanned, values: &[Spanned]) -> Result<(), MjrError> {
+    let tag = name.text().unwrap_or_default();
+    let single = || match values {
+        [value] => Ok(value),
+        _ => Err(value_count(name, 1, values.len())),
+    };
+    match tag {
+        "Event" => config.event = single()?.text().unwrap_or_default().to_string(),
+        "Site" => config.site = single()?.text().unwrap_or_default().to_string(),
+        "Date" => config.date = date(single()?)?,
+        "Players" => {
+            let names = per_seat(name, values)?;
+            for (player, value) in config.players.iter_mut().zip(names) {
+                player.name = value.text().unwrap_or_default().to_string();
+            }
+        }
+        "PlayerIds" | "Ranks" | "Ratings" => {
+            let values = per_seat(name, values)?;
+            for (player, value) in config.players.iter_mut().zip(values) {
+                let text = value.optional().map(str::to_string);
+                match tag {
+                    "PlayerIds" => player.id = text,
+                    "Ranks" => player.rank = text,
+                    _ if text.is_none() => player.rating = None,
+                    _ => player.rating = Some(value.parse("a rating")?),
+                }
+            }
+        }
+        "Rules" => config.rules = rules(name, values)?,
+        "Seats" => {
+            let value = single()?;
+            config.num_players = match value.text() {
+                Some("3") => NumPlayers::Three,
+                Some("4") => NumPlayers::Four,
+                _ => return Err(value.expected("3 or 4")),
+            };
+        }
+        "Length" => {
+            let value = single()?;
+            config.length = match value.text() {
+                Some("one-round") => Length::OneRound,
+                Some("tonpuu") => Length::Tonpuu,
+                Some("hanchan") => Length::Hanchan,
+                _ => return Err(value.expected("one-round, tonpuu or hanchan")),
+            };
+        }
+        "RedFives" => {
+            let value = single()?;
+            config.red_five = match value.text() {
+                Some("0") => RedFive::Zero,
+                Some("3") => RedFive::Three,
+                Some("4") => RedFive::Four,
+                _ => return Err(value.expected("0, 3 or 4")),
+            };
+        }
+        "TimeControl" => match values {
+            [thinking, bank] => {
+                config.thinking_time = duration(thinking)?;
+                config.time_bank = duration(bank)?;
+            }
+            _ => return Err(value_count(name, 2, values.len())),
+        },
+        "Seating" => {
+            config.seating = match values {
+                [kind] if kind.is_word("fixed") => Seating::Fixed,
+                [kind] if kind.is_word("random") => Seating::Random,
+                [kind, a, b] if kind.is_word("dice") => {
+                    Seating::Dice([a.parse("a die")?, b.parse("a die")?])
+                }
+                [kind, ..] => return Err(kind.expected("fixed, random or dice")),
+                [] => return Err(value_count(name, 1, 0)),
+            }
+        }
+        "FirstDealer" => config.first_dealer = Some(seat(single()?)?),
+        "Result" => config.result = Some(scores_in(values)?),
+        "EarlyEnd" => {
+            config.early_end = Some(match values {
+                [kind, seats @ ..] if kind.is_word("tobi") => {
+                    EarlyEnd::Tobi(seats.iter().map(seat).collect::<Result<_, _>>()?)
+                }
+                [kind] if kind.is_word("agari-yame") => EarlyEnd::AgariYame,
+                [kind] if kind.is_word("tenpai-yame") => EarlyEnd::TenpaiYame,
+                [kind, ..] => return Err(kind.expected("tobi, agari-yame or tenpai-yame")),
+                [] => return Err(value_count(name, 1, 0)),
+            })
+        }
+        _ => return Err(name.error(MjrErrorKind::UnknownTag(tag.to_string()))),
+    }
+    Ok(())
+}
+
+fn round_tag(config: &mut RoundConfig, name: &Spanned, values: &[Spanned]) -> Result<(), MjrError> {
+    let tag = name.text().unwrap_or_default();
+    let single = || match values {
+        [value] => Ok(value),
+        _ => Err(value_count(name, 1, values.len())),
+    };
+    match tag {
+        "Honba" => config.repeat = single()?.parse("a number of honba")?,
+        "Dealer" => config.dealer = seat(single()?)?,
+        "Dora" | "UraDora" => {
+            let mut indicators = Vec::new();
+            for value in values {
+                indicators.extend(tiles(value)?);
+            }
+            if tag == "Dora" {
+                config.dora = indicators;
+            } else {
+                config.ura_dora = indicators;
+            }
+        }
+        "Scores" => config.result = Some(scores_in(values)?),
+        "Wall" => {
+            for value in values {
+                let setting = value.value();
+                match value.pair().map(|(key, _)| key) {
+                    Some("seed") => config.wall.seed = Some(setting.parse("a seed")?),
+                    Some("dice") => {
+                        let dice = setting.text().unwrap_or_default().split_once(',');
+                        let dice = dice.and_then(|(a, b)| Some([a.parse().ok()?, b.parse().ok()?]));
+                        config.wall.dice = Some(dice.ok_or_else(|| setting.expected("two dice"))?);
+                    }
+                    Some("break") => {
+                        config.wall.break_position = Some(setting.parse("a wall position")?)
+                    }
+                    Some(key) => return Err(value.error(MjrErrorKind::UnknownKey(key.to_string()))),
+                    None => return Err(value.expected("a `key=value` setting")),
+                }
+            }
+        }
+        _ => return Err(name.error(MjrErrorKind::UnknownTag(tag.to_string()))),
+    }
+    Ok(())
+}
+
+fn value_count(name: &Spanned, expected: usize, found: usize) -> MjrError {
+    name.error(MjrErrorKind::ValueCount { expected, found })
+}
+
+/// The four values of a tag listing every seat.
+fn per_seat<'a>(name: &Spanned, values: &'a [Spanned]) -> Result<&'a [Spanned], MjrError> {
+    match values.len() {
+        4 => Ok(values),
+        n => Err(value_count(name, 4, n)),
+    }
+}
+
+/// `Seat=points` words.
+fn scores_in(values: &[Spanned]) -> Result<Vec<(PlayerLocation, i32)>, MjrError> {
+    values
+        .iter()
+        .map(|value| {
+            let (player, _) = value
+                .pair()
+                .ok_or_else(|| value.expected("`Seat=points`"))?;
+            let player = Spanned {
+                token: Token::Word(player.to_string()),
+                ..value.clone()
+            };
+            Ok((seat(&player)?, value.value().parse("points")?))
+        })
+        .collect()
+}
+
+fn rules(name: &Spanned, values: &[Spanned]) -> Result<RuleSet, MjrError> {
+    let Some((preset, changes)) = values.split_first() else {
+        return Err(value_count(name, 1, 0));
+    };
+    let mut rules = presets()
+        .into_iter()
+        .find(|(name, _)| preset.text() == Some(name))
+        .map(|(_, rules)| rules)
+        .ok_or_else(|| preset.expected("a preset"))?;
+    for change in changes {
+        let (key, _) = change
+            .pair()
+            .ok_or_else(|| change.expected("`rule=value`"))?;
+        let value = change.value();
+        let text = value.text().unwrap_or_default();
+        let choice = |choices: &[&str], expected: &'static str| {
+            choices
+                .iter()
+                .position(|&c| c == text)
+                .ok_or_else(|| value.expected(expected))
+        };
+        match key {
+            "kuitan" => rules.kuitan = yes_or_no(&value)?,
+            "atozuke" => rules.atozuke = yes_or_no(&value)?,
+            "kiriage-mangan" => rules.kiriage_mangan = yes_or_no(&value)?,
+            "double-yakuman" => rules.double_yakuman = yes_or_no(&value)?,
+            "stack-yakuman" => rules.stack_yakuman = yes_or_no(&value)?,
+            "nagashi-mangan" => rules.nagashi_mangan = yes_or_no(&value)?,
+            "kuikae" => {
+                let kinds = [Kuikae::Allowed, Kuikae::SameTile, Kuikae::SameTileAndSuji];
+                let names = ["allowed", "same-tile", "same-tile-and-suji"];
+                rules.kuikae = kinds[choice(&names, "a kuikae rule")?];
+            }
+            "return-deposits-on-abort" => rules.return_deposits_on_abort = yes_or_no(&value)?,
+            "sanma-tsumo" => {
+                let kinds = [SanmaTsumo::Loss, SanmaTsumo::Split];
+                rules.sanma_tsumo = kinds[choice(&["loss", "split"], "loss or split")?];
+            }
+            "abortive-draws" => {
+                let mut draws = AbortiveDraws::none();
+                for name in text.split(',').filter(|&name| name != "none") {
+                    let kind = ABORTS
+                        .into_iter()
+                        .find(|&k| abort_name(k) == name)
+                        .ok_or_else(|| value.expected("abortive draws"))?;
+                    match kind {
+                        AbortiveDraw::KyuushuKyuuhai => draws.kyuushu_kyuuhai = true,
+                        AbortiveDraw::SuufonRenda => draws.suufon_renda = true,
+                        AbortiveDraw::SuuchaRiichi => draws.suucha_riichi = true,
+                        AbortiveDraw::Suukaikan => draws.suukaikan = true,
+                        AbortiveDraw::SanchaHou => draws.sancha_hou = true,
+                    }
+                }
+                rules.abortive_draws = draws;
+            }
+            "multiple-ron" => rules.multiple_ron = yes_or_no(&value)?,
+            "extension-target" if text == "none" => rules.extension_target = None,
+            "extension-target" => rules.extension_target = Some(value.parse("points")?),
+            "tobi" => {
+                let kinds = [Tobi::Never, Tobi::BelowZero, Tobi::AtZero];
+                let names = ["never", "below-zero", "at-zero"];
+                rules.tobi = kinds[choice(&names, "a tobi rule")?];
+            }
+            "agari-yame" => rules.agari_yame = yes_or_no(&value)?,
+            "tenpai-yame" => rules.tenpai_yame = yes_or_no(&value)?,
+            "uma-oka" => {
+                let numbers: Option<Vec<i32>> = text.split(',').map(|n| n.parse().ok()).collect();
+                match numbers.as_deref() {
+                    Some(&[starting_points, return_points, a, b, c, d]) => {
+                        rules.uma_oka = UmaOka {
+                            starting_points,
+                            return_points,
+                            uma: [a, b, c, d],
+                        }
+                    }
+                    _ => return Err(value.expected("six numbers")),
+                }
+            }
+            _ => return Err(change.error(MjrErrorKind::UnknownKey(key.to_string()))),
+        }
+    }
+    Ok(rules)
+}
+
+/// The score breakdown from the `key=value` words after a win.
+fn win_details(verb: &Spanned, words: &[Spanned]) -> Result<WinDetails, MjrError> {
+    let mut winning_tile = None;
+    let mut fu = None;
+    let mut win_type = None;
+    let mut yaku = Vec::new();
+    let mut dora = DoraCount::default();
+    let mut payment = Payment {
+        win_type: WinType::Ron,
+        dealer: false,
+        from_discarder: 0,
+        from_dealer: 0,
+        from_non_dealer: 0,
+        deposits: 0,
+        limit: None,
+    };
+    for word in words {
+        let value = word.value();
+        let text = value.text().unwrap_or_default();
+        match word.pair().unwrap_or_default().0 {
+            "winning-tile" => winning_tile = Some(one_tile(&value)?),
+            "fu" => fu = Some(value.parse("fu")?),
+            "yaku" => {
+                for entry in text.split(',') {
+                    let found = entry.split_once(':').and_then(|(name, han)| {
+                        let &(_, yaku) = YAKU_NAMES.iter().find(|&&(n, _)| n == name)?;
+                        Some((yaku, han.parse().ok()?))
+                    });
+                    yaku.push(found.ok_or_else(|| value.expected("`yaku:han` entries"))?);
+                }
+            }
+            "dora" => dora.dora = value.parse("a dora count")?,
+            "ura-dora" => dora.ura_dora = value.parse("a dora count")?,
+            "aka-dora" => dora.aka_dora = value.parse("a dora count")?,
+            "nuki-dora" => dora.nuki_dora = value.parse("a dora count")?,
+            "win-type" => {
+                win_type = Some(match text {
+                    "ron" => WinType::Ron,
+                    "tsumo" => WinType::Tsumo,
+                    _ => return Err(value.expected("ron or tsumo")),
+                })
+            }
+            "dealer" => payment.dealer = yes_or_no(&value)?,
+            "from-discarder" => payment.from_discarder = value.parse("points")?,
+            "from-dealer" => payment.from_dealer = value.parse("points")?,
+            "from-non-dealer" => payment.from_non_dealer = value.parse("points")?,
+            "deposits" => payment.deposits = value.parse("points")?,
+            "limit" => {
+                let limit = [
+                    ("mangan", Limit::Mangan),
+                    ("haneman", Limit::Haneman),
+                    ("baiman", Limit::Baiman),
+                    ("sanbaiman", Limit::Sanbaiman),
+                ]
+                .into_iter()
+                .find(|&(name, _)| name == text)
+                .map(|(_, limit)| limit)
+                .or_else(|| Some(Limit::Yakuman(text.strip_prefix("yakuman-")?.parse().ok()?)));
+                payment.limit = Some(limit.ok_or_else(|| value.expected("a limit"))?);
+            }
+            key => return Err(word.error(MjrErrorKind::UnknownKey(key.to_string()))),
+        }
+    }
+    let missing = |key| verb.error(MjrErrorKind::MissingKey(key));
+    payment.win_type = win_type.ok_or_else(|| missing("win-type"))?;
+    Ok(WinDetails {
+        winning_tile: winning_tile.ok_or_else(|| missing("winning-tile"))?,
+        yaku,
+        fu: fu.ok_or_else(|| missing("fu"))?,
+        dora,
+        payment,
+    })
+}
+
+fn is_seat(text: &str) -> bool {
+    PlayerLocation::ALL
+        .iter()
+        .any(|seat| seat.to_string() == text)
+}
+
+fn seat(token: &Spanned) -> Result<PlayerLocation, MjrError> {
+    PlayerLocation::ALL
+        .into_iter()
+        .find(|seat| token.text() == Some(&seat.to_string()))
+        .ok_or_else(|| token.expected("a seat"))
+}
+
+fn direction(token: &Spanned) -> Result<Direction, MjrError> {
+    [
+        Direction::East,
+        Direction::South,
+        Direction::West,
+        Direction::North,
+    ]
+    .into_iter()
+    .find(|wind| token.text() == Some(&wind.to_string()))
+    .ok_or_else(|| token.expected("a wind"))
+}
+
+fn tiles(token: &Spanned) -> Result<Vec<MahjongTile>, MjrError> {
+    tile::parse_tiles(token.text().unwrap_or_default())
+        .map_err(|e| token.error(MjrErrorKind::Tile(e)))
+}
+
+fn one_tile(token: &Spanned) -> Result<MahjongTile, MjrError> {
+    tile::build(token.text().unwrap_or_default()).map_err(|e| token.error(MjrErrorKind::Tile(e)))
+}
+
+fn tile_array<const N: usize>(token: &Spanned) -> Result<[MahjongTile; N], MjrError> {
+    let tiles = tiles(token)?;
+    let found = tiles.len();
+    tiles
+        .try_into()
+        .map_err(|_| token.error(MjrErrorKind::ValueCount { expected: N, found }))
+}
+
+fn yes_or_no(token: &Spanned) -> Result<bool, MjrError> {
+    match token.text() {
+        Some("yes") => Ok(true),
+        Some("no") => Ok(false),
+        _ => Err(token.expected("yes or no")),
+    }
+}
+
+fn date(token: &Spanned) -> Result<DateTime<Local>, MjrError> {
+    DateTime::parse_from_rfc3339(token.text().unwrap_or_default())
+        .map(|date| date.with_timezone(&Local))
+        .map_err(|_| token.expected("an RFC 3339 date"))
+}
+
+/// The inverse of [`seconds`].
+fn duration(token: &Spanned) -> Result<Duration, MjrError> {
+    let text = token.text().unwrap_or_default();
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let digits = fraction.len() <= 9 && fraction.bytes().all(|b| b.is_ascii_digit());
+    let secs = whole.parse().ok().filter(|_| digits);
+    let nanos = format!("{:0<9}", fraction).parse().ok();
+    match (secs, nanos) {
+        (Some(secs), Some(nanos)) => Ok(Duration::new(secs, nanos)),
+        _ => Err(token.expected("a time in seconds")),
+    }
+}
+
+const TAGS: [Tag; 7] = [
+    Tag::Brilliant,
+    Tag::Good,
+    Tag::Interesting,
+    Tag::Dubious,
+    Tag::Mistake,
+    Tag::Blunder,
+    Tag::Forced,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotation::Tag;
+    use crate::game::PlayerLocation::*;
+    use crate::game::{GameConfigBuilder, RoundConfigBuilder};
+    use crate::hand::PonMeld;
+    use crate::scoring::{DoraCount, Payment};
+    use crate::tile;
+
+    fn t(s: &str) -> MahjongTile {
+        tile::build(s).unwrap()
+    }
+
+    /// The dealer's double East, won on the third go-around.
+    fn game() -> Game {
+        let result = vec![
+            (Hero, 27900),
+            (Right, 25000),
+            (Across, 22100),
+            (Left, 25000),
+        ];
+        let mut config = GameConfigBuilder::new()
+            .event("Riichi Open 2024")
+            .site("Tenhou")
+            .players(["Alice", "Bob", "Carol", "Dave"])
+            .one_round()
+            .rules(RuleSet::tenhou())
+            .result(result.clone())
+            .build()
+            .unwrap();
+        config.players[1].rating = Some(1834);
+        let round = RoundConfigBuilder::new()
+            .dora_indicator(t("9s"))
+            .hand(Hand::parse("123m456p789s1144z").unwrap())
+            .result(result)
+            .build()
+            .unwrap();
+        let pon = PonMeld::new([t("1z"); 3], t("1z"), Right).unwrap();
+        let details = WinDetails {
+            winning_tile: t("4m"),
+            yaku: vec![
+                (Yaku::SeatWind(Direction::East), 1),
+                (Yaku::RoundWind(Direction::East), 1),
+            ],
+            fu: 30,
+            dora: DoraCount::default(),
+            payment: Payment {
+                win_type: WinType::Ron,
+                dealer: true,
+                from_discarder: 2900,
+                from_dealer: 0,
+                from_non_dealer: 0,
+                deposits: 0,
+                limit: None,
+            },
+        };
+        let variation = Variation {
+            events: vec![RoundEvent::new(Hero, RoundAction::Discard(t("4z")))],
+            annotation: Some(Annotation::new("Keeps the run")),
+        };
+        let events = vec![
+            RoundEvent::new(Hero, RoundAction::Draw(t("6m").into())),
+            RoundEvent::new(Hero, RoundAction::Discard(t("6m"))).with_tsumogiri(true),
+            RoundEvent::new(Right, RoundAction::Draw(tile::ObservedTile::Hidden)),
+            RoundEvent::new(Right, RoundAction::Discard(t("1z"))),
+            RoundEvent::new(Hero, RoundAction::Pon(pon.clone())),
+            RoundEvent::new(Hero, RoundAction::Discard(t("1m")))
+                .with_annotation(Annotation::default().with_tag(Tag::Dubious))
+                .with_variation(variation),
+            RoundEvent::new(Across, RoundAction::Draw(tile::ObservedTile::Hidden)),
+            RoundEvent::new(Across, RoundAction::Discard(t("4m")))
+                .with_elapsed(Duration::from_millis(1500)),
+            RoundEvent::new(
+                Hero,
+                RoundAction::Ron(
+                    Hand::new(
+                        tile::parse_tiles("234m456p789s44z").unwrap(),
+                        vec![Meld::Pon(pon)],
+                    )
+                    .unwrap(),
+                    Some(details),
+                ),
+            ),
+        ];
+        Game {
+            config,
+            rounds: vec![Round {
+                config: round,
+                events,
+                annotation: None,
+            }],
+            annotation: None,
+        }
+    }
+
+    #[test]
+    fn writes_header_and_move_text() {
+        let game = game();
+        let date = game
+            .config
+            .date
+            .to_rfc3339_opts(SecondsFormat::AutoSi, false);
+        let expected = format!(
+            r#"[Event "Riichi Open 2024"]
+[Site "Tenhou"]
+[Date "{}"]
+[Players "Alice" "Bob" "Carol" "Dave"]
+[Ratings - 1834 - -]
+[Rules "Tenhou"]
+[Seats 4]
+[Length one-round]
+[RedFives 3]
+[TimeControl 5 20]
+[Result Hero=27900 Right=25000 Across=22100 Left=25000]
+
+[Round East 1]
+[Dealer Hero]
+[Dora 9s]
+[Deal 123m456p789s1144z]
+[Scores Hero=27900 Right=25000 Across=22100 Left=25000]
+Hero draw 6m
+Hero discard 6m tsumogiri=yes
+Right draw ?
+Right discard 1z
+Hero pon 1z from Right 111z
+Hero discard 1m ?!
+( {{Keeps the run}}
+  Hero discard 4z
+)
+Across draw ?
+Across discard 4m elapsed=1.5s
+Hero ron 234m456p789s44z + pon 1z from Right 111z winning-tile=4m fu=30 yaku=seat-wind-east:1,round-wind-east:1 win-type=ron dealer=yes from-discarder=2900
+"#,
+            date
+        );
+        assert_eq!(game.to_mjr_string(), expected);
+
+        let mut custom = game.config.rules.clone();
+        custom.kiriage_mangan = true;
+        custom.abortive_draws.suukaikan = false;
+        assert_eq!(
+            rules_values(&custom),
+            [
+                "\"Default\"",
+                "kiriage-mangan=yes",
+                "double-yakuman=no",
+                "abortive-draws=kyuushu-kyuuhai,suufon-renda,suucha-riichi,sancha-hou",
+                "uma-oka=25000,30000,20,10,-10,-20",
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let game = game();
+        assert_eq!(Game::from_mjr_str(&game.to_mjr_string()), Ok(game.clone()));
+
+        let mut custom = game.clone();
+        custom.config.rules.kiriage_mangan = true;
+        custom.config.rules.abortive_draws.suukaikan = false;
+        assert_eq!(
+            Game::from_mjr_str(&custom.to_mjr_string()),
+            Ok(custom.clone())
+        );
+
+        let spaced = game
+            .to_mjr_string()
+            .replace("\n[Round", "\n; the only hand\n\n[Round")
+            .replace(" from ", "   from\t");
+        assert_eq!(Game::from_mjr_str(&spaced), Ok(game));
+    }
+
+    #[test]
+    fn reads_concatenated_games_one_at_a_time() {
+        let first = game();
+        let mut second = game();
+        second.config.event = "Riichi Open 2025".to_string();
+        second.annotation = Some(Annotation {
+            comment: "Replayed from\n[Event \"the club\"]".to_string(),
+            tags: vec![],
+        });
+        let logs = format!(
+            "{}; the next log\n{}",
+            first.to_mjr_string(),
+            second.to_mjr_string()
+        );
+        let third = "[Event \"Riichi Open 2026\"]\n[Venue \"Club\"]\n";
+        let all = format!("{}{}", logs, third);
+
+        let mut reader = ReplayReader::new(all.as_bytes());
+        assert_eq!(reader.next(), Some(Ok(first)));
+        assert_eq!(reader.next(), Some(Ok(second)));
+        let Some(Err(Error::Mjr(error))) = reader.next() else {
+            panic!("the third game should fail to read");
+        };
+        assert_eq!((error.line, error.column), (logs.lines().count() + 2, 2));
+        assert_eq!(reader.next(), None);
+
+        assert_eq!(ReplayReader::new(logs.as_bytes()).rounds().count(), 2);
+    }
+
+    #[test]
+    fn splits_games_without_rounds() {
+        let mut first = game();
+        first.rounds.clear();
+        let mut second = first.clone();
+        second.config.event = "Riichi Open 2025".to_string();
+        let logs = format!("{}{}", first.to_mjr_string(), second.to_mjr_string());
+        let games: Vec<_> = ReplayReader::new(logs.as_bytes()).collect();
+        assert_eq!(games, [Ok(first), Ok(second)]);
+
+        let sites: Vec<_> = ReplayReader::new("[Site \"A\"]\n[Site \"B\"]\n".as_bytes())
+            .map(|game| game.unwrap().config.site)
+            .collect();
+        assert_eq!(sites, ["A", "B"]);
+        assert_eq!(
+            Game::from_mjr_str("[Site \"A\"] [Site \"B\"]"),
+            Err(Error::Mjr(MjrError {
+                line: 1,
+                column: 13,
+                kind: MjrErrorKind::DuplicateTag("Site".to_string()),
+            }))
+        );
+    }
+
+    #[test]
+    fn reports_where_the_text_is_wrong() {
+        let error = |text: &str| {
+            let e = read_game(text).unwrap_err();
+            (e.line, e.column, e.kind)
+        };
+        assert_eq!(
+            error("[Event \"Open\"]\n[Venue \"Club\"]"),
+            (2, 2, MjrErrorKind::UnknownTag("Venue".to_string()))
+        );
+        assert_eq!(
+            error("[Round East 1]\n[Deal 1234m]\nHero discard 1x"),
+            (3, 14, MjrErrorKind::Tile(TileParseError::InvalidSuit('x')))
+        );
+        assert_eq!(
+            error("[Round East 1]\nHero draw 1m elapsed=soon"),
+            (
+                2,
+                22,
+                MjrErrorKind::Expected {
+                    expected: "a time in seconds",
+                    found: "`soon`".to_string(),
+                }
+            )
+        );
+        assert_eq!(
+            error("[Round East 1]\nHero tsumo 11m fu=30"),
+            (2, 6, MjrErrorKind::MissingKey("win-type"))
+        );
+        assert_eq!(
+            error("{unfinished"),
+            (1, 1, MjrErrorKind::Unclosed("comment"))
+        );
+        assert_eq!(error("[Round East"), (1, 12, MjrErrorKind::UnexpectedEnd));
+    }
+}
@@ -0,0 +1,201 @@
+//! Bookkeeping shared by the importers of logs that record the whole
+//! table.
+
+use serde_json::Value;
+
+use crate::game::{NumPlayers, PlayerLocation, RoundAction, RoundEvent, RoundNumber};
+use crate::hand::{Hand, Meld};
+use crate::scoring::{Limit, Payment, WinType};
+use crate::tile::{self, Direction, MahjongTile};
+
+use super::FormatError;
+
+/// The players at the table, numbered from the first East as most logs
+/// do, and the one the game is seen from.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Table {
+    pub(super) num_players: NumPlayers,
+    pub(super) hero: usize,
+}
+
+impl Table {
+    pub(super) fn count(self) -> usize {
+        self.num_players.count()
+    }
+
+    pub(super) fn seat(self, player: usize) -> PlayerLocation {
+        let n = self.count();
+        self.num_players.seats()[(player + n - self.hero) % n]
+    }
+
+    /// Where `from` sits as seen by `by`.
+    pub(super) fn source(self, from: usize, by: usize) -> PlayerLocation {
+        self.seat(from)
+            .seen_from_in(self.seat(by), self.num_players)
+    }
+
+    /// One value per player, in [`NumPlayers::seats`] order.
+    pub(super) fn by_seat<T: Clone>(self, values: &[T]) -> Vec<(PlayerLocation, T)> {
+        let mut seated: Vec<_> = (0..self.count())
+            .map(|p| (self.seat(p), values[p].clone()))
+            .collect();
+        seated.sort_by_key(|&(seat, _)| seat.index());
+        seated
+    }
+
+    /// The payment of a win by `who` read from the point changes it made,
+    /// with `from` the discarder of a ron. Whatever the winner gained
+    /// beyond what was paid is counted as deposits.
+    pub(super) fn payment(
+        self,
+        deltas: &[i32],
+        who: usize,
+        from: usize,
+        dealer: usize,
+        win_type: WinType,
+        limit: Option<Limit>,
+    ) -> Payment {
+        let paid = |p: usize| (-deltas[p]).max(0) as u32;
+        let mut payment = Payment {
+            win_type,
+            dealer: who == dealer,
+            from_discarder: 0,
+            from_dealer: 0,
+            from_non_dealer: 0,
+            deposits: 0,
+            limit,
+        };
+        let collected = match win_type {
+            WinType::Ron => {
+                payment.from_discarder = paid(from);
+                paid(from)
+            }
+            WinType::Tsumo => {
+                let others = (0..self.count()).filter(|&p| p != who);
+                if who != dealer {
+                    payment.from_dealer = paid(dealer);
+                }
+                if let Some(p) = others.clone().find(|&p| p != dealer) {
+                    payment.from_non_dealer = paid(p);
+                }
+                others.map(paid).sum()
+            }
+        };
+        payment.deposits = (deltas[who].max(0) as u32).saturating_sub(collected);
+        payment
+    }
+}
+
+/// Points listed one per player, such as the scores a round starts with or
+/// the changes it makes, from the field `name`.
+pub(super) fn points(
+    json: Option<&Value>,
+    name: &'static str,
+    table: Table,
+) -> Result<Vec<i32>, FormatError> {
+    let points = json
+        .and_then(Value::as_array)
+        .ok_or(FormatError::Field(name))?;
+    (0..table.count())
+        .map(|p| points.get(p).and_then(Value::as_i64).map(|x| x as i32))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(FormatError::Field(name))
+}
+
+/// The tiles one player holds while a round is followed.
+#[derive(Debug, Default)]
+pub(super) struct Holding {
+    pub(super) concealed: Vec<MahjongTile>,
+    pub(super) melds: Vec<Meld>,
+    /// The tile drawn last, until the player gives up a tile.
+    pub(super) drawn: Option<MahjongTile>,
+}
+
+impl Holding {
+    pub(super) fn draw(&mut self, tile: MahjongTile) {
+        self.concealed.push(tile);
+        self.drawn = Some(tile);
+    }
+
+    pub(super) fn take(&mut self, tile: MahjongTile, round: usize) -> Result<(), FormatError> {
+        let pos =
+            self.concealed
+                .iter()
+                .position(|t| *t == tile)
+                .ok_or(FormatError::Inconsistent {
+                    round,
+                    reason: "a tile was played that the player doesn't hold",
+                })?;
+        self.concealed.remove(pos);
+        Ok(())
+    }
+
+    /// Removes the tiles of `meld` that came from the hand.
+    pub(super) fn take_meld(
+        &mut self,
+        meld: &[MahjongTile],
+        called: Option<MahjongTile>,
+        round: usize,
+    ) -> Result<(), FormatError> {
+        let mut called = called;
+        for &tile in meld {
+            if called == Some(tile) {
+                called = None;
+            } else {
+                self.take(tile, round)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn hand(&self, extra: Option<MahjongTile>) -> Hand {
+        let mut concealed = self.concealed.clone();
+        concealed.extend(extra);
+        tile::sort_hand(&mut concealed);
+        Hand {
+            concealed,
+            melds: self.melds.clone(),
+        }
+    }
+}
+
+/// The tiles of a meld of `N` tiles, named `call` if it has the wrong
+/// number.
+pub(super) fn tiles_array<const N: usize>(
+    tiles: &[MahjongTile],
+    call: &str,
+) -> Result<[MahjongTile; N], FormatError> {
+    tiles
+        .try_into()
+        .map_err(|_| FormatError::UnknownCall(call.to_string()))
+}
+
+/// Who paid a riichi deposit in `events`: everyone who declared, except a
+/// player whose declaring discard was won on.
+pub(super) fn riichi_deposits(events: &[RoundEvent]) -> Vec<PlayerLocation> {
+    events
+        .iter()
+        .enumerate()
+        .filter(|(i, e)| {
+            e.action == RoundAction::Riichi
+                && !matches!(
+                    events.get(i + 2),
+                    Some(RoundEvent {
+                        action: RoundAction::Ron(..),
+                        ..
+                    })
+                )
+        })
+        .map(|(_, e)| e.subject)
+        .collect()
+}
+
+pub(super) fn nth_wind(n: usize) -> Direction {
+    (0..n).fold(Direction::East, |wind, _| wind.next())
+}
+
+pub(super) fn nth_number(n: usize) -> RoundNumber {
+    (0..n).fold(RoundNumber::One, |number, _| {
+        number.next().unwrap_or(RoundNumber::Four)
+    })
+}
@@ -0,0 +1,955 @@
+//! The tenhou.net/6 JSON log format, as shown by tenhou's viewer and saved
+//! by most log downloaders.
+//!
+//! Each round of the `log` array lists every player's dealt hand, draws and
+//! discards in separate columns, with tiles numbered `11`–`19` (manzu),
+//! `21`–`29` (pinzu), `31`–`39` (souzu), `41`–`47` (honors) and `51`–`53`
+//! (red fives). Calls and kans are strings such as `"p454545"`, where the
+//! letter marks the called tile and its position the player it came from.
+//!
+//! A log records the whole table, but a [`Game`] is seen from one seat: the
+//! other players' draws are hidden and only the hero's dealt hand is kept.
+//! Every hand is still followed, so wins and tenpai hands are revealed in
+//! full.
+
+use std::collections::VecDeque;
+
+use chrono::{FixedOffset, Local, NaiveDate, TimeZone};
+use serde_json::Value;
+
+use super::table::{nth_number, nth_wind, points, riichi_deposits, tiles_array, Holding, Table};
+use super::FormatError;
+use crate::game::{
+    AbortiveDraw, Game, GameConfigBuilder, Length, NumPlayers, PlayerLocation, Round, RoundAction,
+    RoundConfigBuilder, RoundEvent,
+};
+use crate::hand::{AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, Meld, OpenKanMeld, PonMeld};
+use crate::rules::RuleSet;
+use crate::scoring::{DoraCount, Limit, WinDetails, WinType};
+use crate::tile::{self, Direction, DragonColor, MahjongTile, RedFive};
+use crate::yaku::{Yaku, Yakuman};
+
+/// Written in a discard column for the tile just drawn (tsumogiri).
+const TSUMOGIRI: u64 = 60;
+
+/// Converts a tenhou.net/6 JSON log, seen from the first East player.
+pub fn parse(json: &str) -> crate::Result<Game> {
+    parse_as(json, 0)
+}
+
+/// As [`parse`], seen from player `hero` in tenhou's order: 0 for the
+/// first East, 1 for the first South and so on.
+pub fn parse_as(json: &str, hero: usize) -> crate::Result<Game> {
+    Ok(import(json, hero)?)
+}
+
+fn import(json: &str, hero: usize) -> Result<Game, FormatError> {
+    let log: Value = serde_json::from_str(json).map_err(|e| FormatError::Json(e.to_string()))?;
+    let disp = log
+        .pointer("/rule/disp")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let num_players = if disp.contains('三') {
+        NumPlayers::Three
+    } else {
+        NumPlayers::Four
+    };
+    if hero >= num_players.count() {
+        return Err(FormatError::NoSuchPlayer(hero));
+    }
+    let table = Table { num_players, hero };
+    let red_five = red_fives(&log)?;
+    let rounds = log
+        .get("log")
+        .and_then(Value::as_array)
+        .ok_or(FormatError::Field("log"))?;
+    let mut starts = Vec::new();
+    for round in rounds {
+        starts.push(points(round.get(1), "scores", table)?);
+    }
+    let rounds = rounds
+        .iter()
+        .enumerate()
+        .map(|(i, round)| RoundLog::new(round, i, table, red_five)?.parse(starts.get(i + 1)))
+        .collect::<Result<Vec<Round>, FormatError>>()?;
+
+    let names = log
+        .get("name")
+        .and_then(Value::as_array)
+        .ok_or(FormatError::Field("name"))?;
+    let names = (0..table.count())
+        .map(|p| names.get(p).and_then(Value::as_str).unwrap_or(""))
+        .collect::<Vec<_>>();
+    let mut config = GameConfigBuilder::new()
+        .site("tenhou.net")
+        .num_players(num_players)
+        .players(table.by_seat(&names).into_iter().map(|(_, name)| name))
+        .length(if disp.contains('東') {
+            Length::Tonpuu
+        } else {
+            Length::Hanchan
+        })
+        .red_fives(red_five)
+        .rules(RuleSet {
+            kuitan: disp.is_empty() || disp.contains('喰'),
+            ..RuleSet::tenhou()
+        })
+        .first_dealer(table.seat(0));
+    if let Some(title) = log.pointer("/title/0").and_then(Value::as_str) {
+        config = config.event(title);
+    }
+    if let Some(date) = log.get("ref").and_then(Value::as_str).and_then(ref_date) {
+        config = config.date(date);
+    }
+    if let Some(sc) = log.get("sc").and_then(Value::as_array) {
+        // Final points in hundreds, each followed by the adjusted score.
+        let points = (0..table.count())
+            .map(|p| {
+                sc.get(2 * p)
+                    .and_then(Value::as_f64)
+                    .map(|x| (x * 100.0) as i32)
+            })
+            .collect::<Option<Vec<i32>>>()
+            .ok_or(FormatError::Field("sc"))?;
+        config = config.result(table.by_seat(&points));
+    }
+    Ok(Game {
+        config: config.build()?,
+        rounds,
+        annotation: None,
+    })
+}
+
+/// One entry of a draw or discard column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    Tile(u64),
+    Action(String),
+}
+
+/// What one player holds and has still to do in a round.
+#[derive(Debug, Default)]
+struct PlayerLog {
+    held: Holding,
+    draws: VecDeque<Entry>,
+    discards: VecDeque<Entry>,
+}
+
+/// Whose action comes next while following a round.
+#[derive(Debug, Clone, Copy)]
+enum Next {
+    Draw(usize),
+    Discard(usize),
+}
+
+/// A round of the log being turned into events.
+struct RoundLog<'a> {
+    json: &'a [Value],
+    index: usize,
+    table: Table,
+    red_five: RedFive,
+    dealer: usize,
+    start: Vec<i32>,
+    players: Vec<PlayerLog>,
+    events: Vec<RoundEvent>,
+    /// The last tile given up by a player, which a ron would win on: a
+    /// discard, or the tile added to a kan.
+    offered: Option<(usize, MahjongTile)>,
+}
+
+impl<'a> RoundLog<'a> {
+    fn new(
+        json: &'a Value,
+        index: usize,
+        table: Table,
+        red_five: RedFive,
+    ) -> Result<RoundLog<'a>, FormatError> {
+        let json = json
+            .as_array()
+            .map(Vec::as_slice)
+            .ok_or(FormatError::Field("log"))?;
+        let dealer = json
+            .first()
+            .and_then(|header| header.get(0))
+            .and_then(Value::as_u64)
+            .ok_or(FormatError::Field("round"))? as usize
+            % 4;
+        let start = points(json.get(1), "scores", table)?;
+        let mut players = Vec::new();
+        for p in 0..table.count() {
+            let column = |offset: usize, name: &'static str| -> Result<Vec<Entry>, FormatError> {
+                json.get(4 + 3 * p + offset)
+                    .and_then(Value::as_array)
+                    .ok_or(FormatError::Field(name))?
+                    .iter()
+                    .map(|entry| match entry {
+                        Value::Number(n) => n.as_u64().map(Entry::Tile),
+                        Value::String(s) => Some(Entry::Action(s.clone())),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<Entry>>>()
+                    .ok_or(FormatError::Field(name))
+            };
+            let concealed = column(0, "haipai")?
+                .into_iter()
+                .map(|entry| match entry {
+                    Entry::Tile(code) => tile_of(code, red_five),
+                    Entry::Action(s) => Err(FormatError::UnknownTile(s)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            players.push(PlayerLog {
+                held: Holding {
+                    concealed,
+                    ..Holding::default()
+                },
+                draws: column(1, "draws")?.into(),
+                discards: column(2, "discards")?.into(),
+            });
+        }
+        Ok(RoundLog {
+            json,
+            index,
+            table,
+            red_five,
+            dealer,
+            start,
+            players,
+            events: Vec::new(),
+            offered: None,
+        })
+    }
+
+    fn inconsistent(&self, reason: &'static str) -> FormatError {
+        FormatError::Inconsistent {
+            round: self.index,
+            reason,
+        }
+    }
+
+    fn push(&mut self, player: usize, action: RoundAction) {
+        self.events
+            .push(RoundEvent::new(self.table.seat(player), action));
+    }
+
+    /// Follows the round to its end. `next_start` are the scores the next
+    /// round starts with, if there is one.
+    fn parse(mut self, next_start: Option<&Vec<i32>>) -> Result<Round, FormatError> {
+        let json = self.json;
+        let header = json.first().and_then(Value::as_array);
+        let header = |i: usize| header.and_then(|h| h.get(i)).and_then(Value::as_u64);
+        let kyoku = header(0).ok_or(FormatError::Field("round"))? as usize;
+        let honba = header(1).ok_or(FormatError::Field("round"))? as u8;
+        let mut next = Next::Draw(self.dealer);
+        loop {
+            next = match next {
+                Next::Draw(p) => match self.players[p].draws.pop_front() {
+                    None => break,
+                    Some(Entry::Action(_)) => {
+                        return Err(self.inconsistent("a call where a draw was expected"))
+                    }
+                    Some(Entry::Tile(code)) => {
+                        let tile = tile_of(code, self.red_five)?;
+                        self.players[p].held.draw(tile);
+                        let observed = if self.table.seat(p) == PlayerLocation::Hero {
+                            tile.into()
+                        } else {
+                            tile::ObservedTile::Hidden
+                        };
+                        self.push(p, RoundAction::Draw(observed));
+                        Next::Discard(p)
+                    }
+                },
+                Next::Discard(p) => match self.players[p].discards.pop_front() {
+                    None => break,
+                    // An open kan's place in the discards; the player draws
+                    // from the dead wall instead.
+                    Some(Entry::Tile(0)) => Next::Draw(p),
+                    Some(Entry::Tile(code)) => self.discard(p, code)?,
+                    Some(Entry::Action(s)) if s.starts_with('r') => {
+                        let code = s[1..].parse().map_err(|_| FormatError::UnknownCall(s))?;
+                        self.push(p, RoundAction::Riichi);
+                        self.discard(p, code)?
+                    }
+                    Some(Entry::Action(s)) => {
+                        self.declare(p, &s)?;
+                        Next::Draw(p)
+                    }
+                },
+            };
+        }
+        let last = match next {
+            Next::Draw(p) | Next::Discard(p) => p,
+        };
+        if self
+            .players
+            .iter()
+            .any(|p| !p.draws.is_empty() || !p.discards.is_empty())
+        {
+            return Err(self.inconsistent("actions left after the round ended"));
+        }
+        let deltas = self.finish(last)?;
+
+        let result = match next_start {
+            Some(next) => self.table.by_seat(next),
+            None => {
+                let riichi = riichi_deposits(&self.events);
+                let points: Vec<i32> = (0..self.table.count())
+                    .map(|p| {
+                        let seat = self.table.seat(p);
+                        let deposits = riichi.iter().filter(|&&s| s == seat).count() as i32;
+                        self.start[p] + deltas[p] - 1000 * deposits
+                    })
+                    .collect();
+                self.table.by_seat(&points)
+            }
+        };
+
+        let hand = self.hand_dealt()?;
+        let mut config = RoundConfigBuilder::new()
+            .num_players(self.table.num_players)
+            .wind(nth_wind(kyoku / 4))
+            .number(nth_number(kyoku % 4))
+            .repeat(honba)
+            .dealer(self.table.seat(self.dealer))
+            .hand(hand)
+            .result(result);
+        for (i, name) in [(2, "dora"), (3, "ura dora")] {
+            let indicators = json
+                .get(i)
+                .and_then(Value::as_array)
+                .ok_or(FormatError::Field(name))?;
+            for code in indicators {
+                let tile = tile_of(
+                    code.as_u64().ok_or(FormatError::Field(name))?,
+                    self.red_five,
+                )?;
+                config = if i == 2 {
+                    config.dora_indicator(tile)
+                } else {
+                    config.ura_dora_indicator(tile)
+                };
+            }
+        }
+        Ok(Round {
+            config: config.build()?,
+            events: self.events,
+            annotation: None,
+        })
+    }
+
+    /// The hero's dealt hand, read again from the log.
+    fn hand_dealt(&self) -> Result<Hand, FormatError> {
+        let column = self
+            .json
+            .get(4 + 3 * self.table.hero)
+            .and_then(Value::as_array)
+            .ok_or(FormatError::Field("haipai"))?;
+        let mut concealed = column
+            .iter()
+            .map(|code| {
+                tile_of(
+                    code.as_u64().ok_or(FormatError::Field("haipai"))?,
+                    self.red_five,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        tile::sort_hand(&mut concealed);
+        Ok(Hand {
+            concealed,
+            melds: Vec::new(),
+        })
+    }
+
+    /// Discards the tile numbered `code` and works out who plays next.
+    fn discard(&mut self, p: usize, code: u64) -> Result<Next, FormatError> {
+        let drawn = self.players[p].held.drawn.take();
+        let tile = if code == TSUMOGIRI {
+            drawn.ok_or_else(|| self.inconsistent("tsumogiri without a draw"))?
+        } else {
+            tile_of(code, self.red_five)?
+        };
+        self.players[p].held.take(tile, self.index)?;
+        self.events.push(
+            RoundEvent::new(self.table.seat(p), RoundAction::Discard(tile))
+                .with_tsumogiri(code == TSUMOGIRI),
+        );
+        self.offered = Some((p, tile));
+        let n = self.table.count();
+        // Pon and kan take precedence over the next player's chii.
+        for q in (1..n).map(|i| (p + i) % n) {
+            let Some(Entry::Action(call)) = self.players[q].draws.front() else {
+                continue;
+            };
+            let call = call.clone();
+            let (letter, at, tiles) = split_call(&call, self.red_five)?;
+            let source = self.table.source(p, q);
+            let claims = tiles.get(at) == Some(&tile) && source_at(at) == source;
+            match letter {
+                'p' if claims => {
+                    let meld = PonMeld::new(tiles_array(&tiles, &call)?, tile, source)?;
+                    self.call(
+                        q,
+                        &tiles,
+                        tile,
+                        Meld::Pon(meld.clone()),
+                        RoundAction::Pon(meld),
+                    )?;
+                    return Ok(Next::Discard(q));
+                }
+                'm' if claims => {
+                    let meld = OpenKanMeld::new(tiles_array(&tiles, &call)?, tile, source)?;
+                    self.call(
+                        q,
+                        &tiles,
+                        tile,
+                        Meld::OpenKan(meld.clone()),
+                        RoundAction::OpenKan(meld),
+                    )?;
+                    return Ok(Next::Discard(q));
+                }
+                _ => {}
+            }
+        }
+        let q = (p + 1) % n;
+        if let Some(Entry::Action(call)) = self.players[q].draws.front() {
+            let call = call.clone();
+            let (letter, at, tiles) = split_call(&call, self.red_five)?;
+            if letter == 'c' && tiles.get(at) == Some(&tile) {
+                let mut rest = tiles.clone();
+                rest.remove(at);
+                let meld = ChiiMeld::from_called(tile, tiles_array(&rest, &call)?)?;
+                self.call(
+                    q,
+                    &tiles,
+                    tile,
+                    Meld::Chii(meld.clone()),
+                    RoundAction::Chii(meld),
+                )?;
+                return Ok(Next::Discard(q));
+            }
+        }
+        Ok(Next::Draw(q))
+    }
+
+    /// Player `q` calls `called` into `meld`, using the rest of `tiles`.
+    fn call(
+        &mut self,
+        q: usize,
+        tiles: &[MahjongTile],
+        called: MahjongTile,
+        meld: Meld,
+        action: RoundAction,
+    ) -> Result<(), FormatError> {
+        self.players[q].draws.pop_front();
+        self.players[q]
+            .held
+            .take_meld(tiles, Some(called), self.index)?;
+        self.players[q].held.melds.push(meld);
+        self.push(q, action);
+        Ok(())
+    }
+
+    /// Applies a closed kan, added kan or nuki dora from player `p`'s
+    /// discard column.
+    fn declare(&mut self, p: usize, action: &str) -> Result<(), FormatError> {
+        let (letter, at, tiles) = split_call(action, self.red_five)?;
+        let unknown = || FormatError::UnknownCall(action.to_string());
+        self.players[p].held.drawn = None;
+        match letter {
+            'a' => {
+                let meld = ClosedKanMeld::new(tiles_array(&tiles, action)?)?;
+                self.players[p].held.take_meld(&tiles, None, self.index)?;
+                self.players[p]
+                    .held
+                    .melds
+                    .push(Meld::ClosedKan(meld.clone()));
+                self.push(p, RoundAction::ClosedKan(meld));
+            }
+            'k' => {
+                let added = *tiles.get(at).ok_or_else(unknown)?;
+                let pon = self.players[p]
+                    .held
+                    .melds
+                    .iter()
+                    .position(|m| matches!(m, Meld::Pon(pon) if pon.pon_tile.eq_rank(&added)))
+                    .ok_or_else(|| self.inconsistent("an added kan without a pon"))?;
+                let Meld::Pon(pon_meld) = self.players[p].held.melds[pon].clone() else {
+                    unreachable!("found a pon");
+                };
+                let meld = AddedKanMeld::new(pon_meld, added)?;
+                self.players[p].held.take(added, self.index)?;
+                self.players[p].held.melds[pon] = Meld::AddedKan(meld.clone());
+                self.push(p, RoundAction::AddedKan(meld));
+                self.offered = Some((p, added));
+            }
+            'f' => {
+                self.players[p].held.take(tile::NORTH, self.index)?;
+                self.push(p, RoundAction::Kita);
+            }
+            _ => return Err(unknown()),
+        }
+        Ok(())
+    }
+
+    /// Adds the events ending the round from its result entry, where `last`
+    /// is the player who acted last. Returns each player's point change.
+    fn finish(&mut self, last: usize) -> Result<Vec<i32>, FormatError> {
+        let result = self
+            .json
+            .last()
+            .and_then(Value::as_array)
+            .ok_or(FormatError::Field("result"))?;
+        let kind = result
+            .first()
+            .and_then(Value::as_str)
+            .ok_or(FormatError::Field("result"))?;
+        let mut total = vec![0; self.table.count()];
+        let mut add = |deltas: &[i32]| {
+            for (t, d) in total.iter_mut().zip(deltas) {
+                *t += d;
+            }
+        };
+        let abort = match kind {
+            "九種九牌" => Some(AbortiveDraw::KyuushuKyuuhai),
+            "四風連打" => Some(AbortiveDraw::SuufonRenda),
+            "四家立直" => Some(AbortiveDraw::SuuchaRiichi),
+            "四槓散了" => Some(AbortiveDraw::Suukaikan),
+            "三家和了" => Some(AbortiveDraw::SanchaHou),
+            _ => None,
+        };
+        // Draws are announced after the last discard, except that nine
+        // terminals is declared instead of one.
+        let discarder = self.offered.map_or(last, |(p, _)| p);
+        if let Some(abort) = abort {
+            let subject = if abort == AbortiveDraw::KyuushuKyuuhai {
+                last
+            } else {
+                discarder
+            };
+            self.push(subject, RoundAction::AbortiveDraw(abort));
+        } else if kind == "和了" {
+            for pair in result[1..].chunks(2) {
+                let deltas = points(pair.first(), "deltas", self.table)?;
+                let info = pair
+                    .get(1)
+                    .and_then(Value::as_array)
+                    .ok_or(FormatError::Field("agari"))?;
+                let player = |i: usize| {
+                    info.get(i)
+                        .and_then(Value::as_u64)
+                        .map(|p| p as usize)
+                        .filter(|&p| p < self.table.count())
+                        .ok_or(FormatError::Field("agari"))
+                };
+                let (who, from) = (player(0)?, player(1)?);
+                let (win_type, winning_tile, hand) = if who == from {
+                    let drawn = self.players[who]
+                        .held
+                        .drawn
+                        .ok_or_else(|| self.inconsistent("tsumo without a draw"))?;
+                    (WinType::Tsumo, drawn, self.players[who].held.hand(None))
+                } else {
+                    let (_, tile) = self
+                        .offered
+                        .filter(|&(p, _)| p == from)
+                        .ok_or_else(|| self.inconsistent("ron without a discard"))?;
+                    (WinType::Ron, tile, self.players[who].held.hand(Some(tile)))
+                };
+                let details = self.win_details(info, &deltas, who, from, win_type, winning_tile)?;
+                let action = match win_type {
+                    WinType::Tsumo => RoundAction::Tsumo(hand, Some(details)),
+                    WinType::Ron => RoundAction::Ron(hand, Some(details)),
+                };
+                self.push(who, action);
+                add(&deltas);
+            }
+        } else if ["流局", "全員聴牌", "全員不聴", "流し満貫"].contains(&kind) {
+            for entry in &result[1..] {
+                add(&points(Some(entry), "deltas", self.table)?);
+            }
+            let n = self.table.count();
+            let hands = (0..n)
+                .map(|i| (self.dealer + i) % n)
+                .map(|p| (self.table.seat(p), self.players[p].held.hand(None)))
+                .filter(|(_, hand)| hand.is_tenpai())
+                .collect();
+            self.push(discarder, RoundAction::Exhaustive(hands));
+        } else {
+            return Err(FormatError::UnknownResult(kind.to_string()));
+        }
+        Ok(total)
+    }
+
+    fn win_details(
+        &self,
+        info: &[Value],
+        deltas: &[i32],
+        who: usize,
+        from: usize,
+        win_type: WinType,
+        winning_tile: MahjongTile,
+    ) -> Result<WinDetails, FormatError> {
+        let summary = info
+            .get(3)
+            .and_then(Value::as_str)
+            .ok_or(FormatError::Field("agari"))?;
+        let mut yaku = Vec::new();
+        let mut dora = DoraCount::default();
+        for entry in &info[4.min(info.len())..] {
+            let entry = entry.as_str().ok_or(FormatError::Field("yaku"))?;
+            let unknown = || FormatError::UnknownYaku(entry.to_string());
+            let (name, value) = entry.split_once('(').ok_or_else(unknown)?;
+            let value = value.trim_end_matches(')');
+            let han = if value == "役満" {
+                13
+            } else {
+                value
+                    .trim_end_matches('飜')
+                    .parse()
+                    .map_err(|_| unknown())?
+            };
+            match name {
+                "ドラ" => dora.dora += han,
+                "赤ドラ" => dora.aka_dora += han,
+                "裏ドラ" => dora.ura_dora += han,
+                "抜きドラ" => dora.nuki_dora += han,
+                _ => yaku.push((yaku_named(name).ok_or_else(unknown)?, han)),
+            }
+        }
+        let fu = summary
+            .split_once('符')
+            .and_then(|(fu, _)| fu.parse().ok())
+            .unwrap_or(0);
+        let yakuman = yaku
+            .iter()
+            .filter(|(y, _)| matches!(y, Yaku::Yakuman(_)))
+            .count();
+        let limit = [
+            ("役満", Limit::Yakuman(yakuman.max(1) as u8)),
+            ("三倍満", Limit::Sanbaiman),
+            ("倍満", Limit::Baiman),
+            ("跳満", Limit::Haneman),
+            ("満貫", Limit::Mangan),
+        ]
+        .into_iter()
+        .find(|(name, _)| summary.contains(name))
+        .map(|(_, limit)| limit);
+
+        let payment = self
+            .table
+            .payment(deltas, who, from, self.dealer, win_type, limit);
+        Ok(WinDetails {
+            winning_tile,
+            yaku,
+            fu,
+            dora,
+            payment,
+        })
+    }
+}
+
+/// The tile of tenhou's numeric `code`, in a game with `red_five`.
+fn tile_of(code: u64, red_five: RedFive) -> Result<MahjongTile, FormatError> {
+    let (rank, suit) = match code {
+        11..=19 => (code - 10, 'm'),
+        21..=29 => (code - 20, 'p'),
+        31..=39 => (code - 30, 's'),
+        41..=47 => (code - 40, 'z'),
+        51 => (0, 'm'),
+        52 => (0, 'p'),
+        53 => (0, 's'),
+        _ => return Err(FormatError::UnknownTile(code.to_string())),
+    };
+    tile::build_for(&format!("{}{}", rank, suit), red_five)
+        .map_err(|_| FormatError::UnknownTile(code.to_string()))
+}
+
+/// Splits a call string into its letter, the index of the tile after the
+/// letter, and the tiles.
+fn split_call(
+    call: &str,
+    red_five: RedFive,
+) -> Result<(char, usize, Vec<MahjongTile>), FormatError> {
+    let unknown = || FormatError::UnknownCall(call.to_string());
+    let mut letter = None;
+    let mut tiles = Vec::new();
+    let mut rest = call;
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_alphabetic() {
+            if letter.is_some() {
+                return Err(unknown());
+            }
+            letter = Some((c, tiles.len()));
+            rest = &rest[1..];
+        } else {
+            let code = rest.get(..2).ok_or_else(unknown)?;
+            tiles.push(tile_of(code.parse().map_err(|_| unknown())?, red_five)?);
+            rest = &rest[2..];
+        }
+    }
+    let (letter, at) = letter.ok_or_else(unknown)?;
+    Ok((letter, at, tiles))
+}
+
+/// Whom a call came from, by where its letter stands among the tiles:
+/// first for the left player, second for the player across and last for
+/// the right player.
+fn source_at(at: usize) -> PlayerLocation {
+    match at {
+        0 => PlayerLocation::Left,
+        1 => PlayerLocation::Across,
+        _ => PlayerLocation::Right,
+    }
+}
+
+/// The red fives from the `aka51`–`aka53` counts, or `aka` for one of
+/// each.
+fn red_fives(log: &Value) -> Result<RedFive, FormatError> {
+    let rule = |name: &str| {
+        log.get("rule")
+            .and_then(|r| r.get(name))
+            .and_then(Value::as_u64)
+    };
+    let all = rule("aka").unwrap_or(0);
+    let counts = ["aka51", "aka52", "aka53"].map(|name| rule(name).unwrap_or(all));
+    match counts {
+        [0, 0, 0] => Ok(RedFive::Zero),
+        [_, 2, _] if counts[0] <= 1 && counts[2] <= 1 => Ok(RedFive::Four),
+        _ if counts.iter().all(|&n| n <= 1) => Ok(RedFive::Three),
+        _ => Err(FormatError::Unsupported("red five counts")),
+    }
+}
+
+/// The start of the game from the `ref` log ID, whose first ten digits are
+/// the hour in Japan, e.g. `2024010112gm-...`.
+fn ref_date(id: &str) -> Option<chrono::DateTime<Local>> {
+    let digit = |range: std::ops::Range<usize>| id.get(range)?.parse::<u32>().ok();
+    let date = NaiveDate::from_ymd_opt(digit(0..4)? as i32, digit(4..6)?, digit(6..8)?)?
+        .and_hms_opt(digit(8..10)?, 0, 0)?;
+    let japan = FixedOffset::east_opt(9 * 3600)?;
+    Some(
+        japan
+            .from_local_datetime(&date)
+            .single()?
+            .with_timezone(&Local),
+    )
+}
+
+fn yaku_named(name: &str) -> Option<Yaku> {
+    let wind = |name: &str| match name {
+        "東" => Some(Direction::East),
+        "南" => Some(Direction::South),
+        "西" => Some(Direction::West),
+        "北" => Some(Direction::North),
+        _ => None,
+    };
+    if let Some(w) = name.strip_prefix("自風 ") {
+        return wind(w).map(Yaku::SeatWind);
+    }
+    if let Some(w) = name.strip_prefix("場風 ") {
+        return wind(w).map(Yaku::RoundWind);
+    }
+    let yaku = match name {
+        "門前清自摸和" => Yaku::MenzenTsumo,
+        "立直" => Yaku::Riichi,
+        "両立直" => Yaku::DoubleRiichi,
+        "一発" => Yaku::Ippatsu,
+        "槍槓" | "搶槓" => Yaku::Chankan,
+        "嶺上開花" => Yaku::RinshanKaihou,
+        "海底摸月" => Yaku::HaiteiRaoyue,
+        "河底撈魚" => Yaku::HouteiRaoyui,
+        "平和" => Yaku::Pinfu,
+        "断幺九" => Yaku::Tanyao,
+        "一盃口" => Yaku::Iipeikou,
+        "役牌 白" => Yaku::Dragon(DragonColor::White),
+        "役牌 發" => Yaku::Dragon(DragonColor::Green),
+        "役牌 中" => Yaku::Dragon(DragonColor::Red),
+        "七対子" => Yaku::Chiitoitsu,
+        "混全帯幺九" => Yaku::Chanta,
+        "一気通貫" => Yaku::Ittsuu,
+        "三色同順" => Yaku::SanshokuDoujun,
+        "三色同刻" => Yaku::SanshokuDoukou,
+        "三槓子" => Yaku::Sankantsu,
+        "対々和" => Yaku::Toitoi,
+        "三暗刻" => Yaku::Sanankou,
+        "小三元" => Yaku::Shousangen,
+        "混老頭" => Yaku::Honroutou,
+        "二盃口" => Yaku::Ryanpeikou,
+        "純全帯幺九" => Yaku::Junchan,
+        "混一色" => Yaku::Honitsu,
+        "清一色" => Yaku::Chinitsu,
+        "天和" => Yaku::Yakuman(Yakuman::Tenhou),
+        "地和" => Yaku::Yakuman(Yakuman::Chiihou),
+        "大三元" => Yaku::Yakuman(Yakuman::Daisangen),
+        "四暗刻" => Yaku::Yakuman(Yakuman::Suuankou),
+        "四暗刻単騎" => Yaku::Yakuman(Yakuman::SuuankouTanki),
+        "字一色" => Yaku::Yakuman(Yakuman::Tsuuiisou),
+        "緑一色" => Yaku::Yakuman(Yakuman::Ryuuiisou),
+        "清老頭" => Yaku::Yakuman(Yakuman::Chinroutou),
+        "九蓮宝燈" => Yaku::Yakuman(Yakuman::ChuurenPoutou),
+        "純正九蓮宝燈" => Yaku::Yakuman(Yakuman::JunseiChuurenPoutou),
+        "国士無双" => Yaku::Yakuman(Yakuman::KokushiMusou),
+        "国士無双１３面" => Yaku::Yakuman(Yakuman::KokushiMusouJuusanmen),
+        "大四喜" => Yaku::Yakuman(Yakuman::Daisuushii),
+        "小四喜" => Yaku::Yakuman(Yakuman::Shousuushii),
+        "四槓子" => Yaku::Yakuman(Yakuman::Suukantsu),
+        _ => return None,
+    };
+    Some(yaku)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerLocation::*;
+    use crate::tile::ObservedTile;
+    use crate::Error;
+
+    /// East 1 won by the dealer's riichi, then East 1 with a repeat
+    /// abandoned on nine terminals.
+    const LOG: &str = r#"{
+        "title": ["", ""],
+        "name": ["A", "B", "C", "D"],
+        "rule": {"disp": "般南喰赤", "aka": 1, "aka51": 1, "aka52": 1, "aka53": 1},
+        "ref": "2024010112gm-0009-0000-00000000",
+        "sc": [270, 17.0, 250, 5.0, 250, -5.0, 230, -17.0],
+        "log": [
+            [
+                [0, 0, 0], [25000, 25000, 25000, 25000], [29], [27],
+                [11, 12, 13, 24, 25, 26, 37, 38, 39, 41, 41, 43, 43], [42], ["r60"],
+                [14, 15, 16, 17, 18, 19, 21, 22, 23, 31, 32, 33, 44], [45], [60],
+                [14, 15, 16, 27, 28, 29, 34, 35, 36, 45, 45, 46, 47], ["p454545"], [46],
+                [17, 18, 19, 21, 22, 23, 31, 32, 33, 34, 35, 36, 47], [43], [60],
+                ["和了", [3000, 0, 0, -2000], [0, 3, 0, "40符1飜2000点", "立直(1飜)"]]
+            ],
+            [
+                [0, 1, 0], [27000, 25000, 25000, 23000], [11], [],
+                [11, 19, 21, 29, 31, 39, 41, 42, 43, 44, 45, 12, 13], [46], [],
+                [14, 15, 16, 17, 18, 19, 21, 22, 23, 31, 32, 33, 44], [], [],
+                [14, 15, 16, 27, 28, 29, 34, 35, 36, 45, 45, 46, 47], [], [],
+                [17, 18, 19, 21, 22, 23, 31, 32, 33, 34, 35, 36, 47], [], [],
+                ["九種九牌"]
+            ]
+        ]
+    }"#;
+
+    fn t(s: &str) -> MahjongTile {
+        tile::build(s).unwrap()
+    }
+
+    #[test]
+    fn imports_calls_wins_and_aborts() {
+        let game = parse(LOG).unwrap();
+        assert_eq!(game.config.player_name(Left), "D");
+        assert_eq!(game.config.result.as_ref().unwrap()[0], (Hero, 27000));
+        let first = &game.rounds[0];
+        assert_eq!(first.config.dora, vec![t("9p")]);
+        assert_eq!(
+            first.config.result.as_ref().unwrap(),
+            &vec![
+                (Hero, 27000),
+                (Right, 25000),
+                (Across, 25000),
+                (Left, 23000)
+            ]
+        );
+        let actions: Vec<(PlayerLocation, RoundAction)> = first.events[..9]
+            .iter()
+            .map(|e| (e.subject, e.action.clone()))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (Hero, RoundAction::Draw(t("2z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("2z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("5z"))),
+                (
+                    Across,
+                    RoundAction::Pon(PonMeld::new([t("5z"); 3], t("5z"), Left).unwrap())
+                ),
+                (Across, RoundAction::Discard(t("6z"))),
+                (Left, RoundAction::Draw(ObservedTile::Hidden)),
+                (Left, RoundAction::Discard(t("3z"))),
+            ]
+        );
+        assert_eq!(first.events[4].tsumogiri, Some(true));
+        assert_eq!(first.events[6].tsumogiri, Some(false));
+        let RoundAction::Ron(hand, Some(details)) = &first.events[9].action else {
+            panic!("expected a ron, got {:?}", first.events[9]);
+        };
+        assert_eq!(hand, &Hand::parse("123m456p789s11333z").unwrap());
+        assert_eq!(
+            (details.fu, details.yaku.clone()),
+            (40, vec![(Yaku::Riichi, 1)])
+        );
+        assert_eq!(
+            (details.payment.from_discarder, details.payment.deposits),
+            (2000, 1000)
+        );
+        assert_eq!(first.validate(), vec![]);
+        let second = &game.rounds[1];
+        assert_eq!(second.config.repeat, 1);
+        assert_eq!(
+            second.events.last().map(|e| &e.action),
+            Some(&RoundAction::AbortiveDraw(AbortiveDraw::KyuushuKyuuhai))
+        );
+        assert_eq!(second.validate(), vec![]);
+    }
+
+    #[test]
+    fn imports_from_another_seat() {
+        let game = parse_as(LOG, 2).unwrap();
+        assert_eq!(game.config.player_name(Hero), "C");
+        assert_eq!(game.config.first_dealer, Some(Across));
+        let first = &game.rounds[0];
+        assert_eq!(first.config.dealer, Across);
+        assert_eq!(
+            first.config.initial_hero_hand_state,
+            Hand::parse("456m789p456s5567z").unwrap()
+        );
+        assert_eq!(
+            first.events[0].action,
+            RoundAction::Draw(ObservedTile::Hidden)
+        );
+        assert_eq!(first.events[5].subject, Hero);
+        assert_eq!(first.validate(), vec![]);
+    }
+
+    #[test]
+    fn reports_malformed_logs() {
+        assert!(matches!(
+            parse("{"),
+            Err(Error::Format(FormatError::Json(_)))
+        ));
+        assert_eq!(parse("{}"), Err(Error::Format(FormatError::Field("log"))));
+        let sanma = r#"{"rule": {"disp": "三般南"}, "log": []}"#;
+        assert_eq!(
+            parse_as(sanma, 3),
+            Err(Error::Format(FormatError::NoSuchPlayer(3)))
+        );
+        let bad_tile = LOG.replace("[29], [27]", "[29], [99]");
+        assert_eq!(
+            parse(&bad_tile),
+            Err(Error::Format(FormatError::UnknownTile("99".to_string())))
+        );
+        let bad_yaku = LOG.replace("立直(1飜)", "人和(5飜)");
+        assert_eq!(
+            parse(&bad_yaku),
+            Err(Error::Format(FormatError::UnknownYaku(
+                "人和(5飜)".to_string()
+            )))
+        );
+        let red_without_reds = LOG
+            .replace(
+                r#""aka": 1, "aka51": 1, "aka52": 1, "aka53": 1"#,
+                r#""aka": 0"#,
+            )
+            .replace("[29], [27]", "[29], [52]");
+        assert_eq!(
+            parse(&red_without_reds),
+            Err(Error::Format(FormatError::UnknownTile("52".to_string())))
+        );
+    }
+}
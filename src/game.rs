@@ -0,0 +1,2292 @@
+//! Games, rounds and the events recorded inside a round.
+//!
+//! Seats are described relative to the player whose perspective the replay
+//! was recorded from (the *hero*), using [`PlayerLocation`].
+
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+
+use crate::annotation::Annotation;
+use crate::hand::{AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, HandError, OpenKanMeld, PonMeld};
+use crate::rules::RuleSet;
+use crate::scoring::WinDetails;
+use crate::tile::{self, Direction, MahjongTile, ObservedTile, RedFive};
+use crate::wall::WallSetup;
+
+/// A complete recorded game.
+///
+/// With the `serde` feature a game serializes as an object with a
+/// `schema_version` alongside its fields; see [`Game::SCHEMA_VERSION`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Game {
+    pub config: GameConfig,
+    pub rounds: Vec<Round>,
+    /// Commentary on the game as a whole.
+    pub annotation: Option<Annotation>,
+}
+
+impl Game {
+    /// The version of the serialized form of a game that this crate
+    /// writes.
+    ///
+    /// Field names, tile strings and event tags of a version don't change.
+    /// Later versions may only add optional fields, so every version up to
+    /// this one can still be read; files without a `schema_version` are
+    /// read as version 1. Anything newer is rejected.
+    pub const SCHEMA_VERSION: u32 = 1;
+}
+
+/// Metadata and settings for a whole game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    /// Name of the event or tournament the game was played in.
+    pub event: String,
+    /// Where the game was played, e.g. a club or an online platform.
+    pub site: String,
+    /// When the game started.
+    pub date: DateTime<Local>,
+    /// The players by [`PlayerLocation::index`], the hero first. The
+    /// empty seat of a three-player game keeps a default player with no
+    /// name.
+    pub players: [Player; 4],
+    pub num_players: NumPlayers,
+    pub length: Length,
+    pub red_five: RedFive,
+    /// Time allowed for each action before the time bank is used.
+    pub thinking_time: Duration,
+    /// Extra time each player may spend over the whole game.
+    pub time_bank: Duration,
+    /// Final scores, if the game has finished, one per seat in
+    /// [`NumPlayers::seats`] order.
+    pub result: Option<Vec<(PlayerLocation, i32)>>,
+    /// How the players' seats were decided.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Seating::is_unknown")
+    )]
+    pub seating: Seating,
+    /// The player who dealt first and started as East (chiicha).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub first_dealer: Option<PlayerLocation>,
+    /// Why the game finished before its scheduled last round, if it did.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub early_end: Option<EarlyEnd>,
+    /// The rules the game was played under.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: RuleSet,
+}
+
+/// How the players' seats, and so the first dealer, were decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Seating {
+    /// Not recorded.
+    #[default]
+    Unknown,
+    /// Set in advance, e.g. by a tournament schedule.
+    Fixed,
+    /// Drawn by the platform or the organiser's software.
+    Random,
+    /// Rolled and drawn at the table, with the dice of the roll that
+    /// picked the first dealer.
+    Dice([u8; 2]),
+}
+
+impl Seating {
+    pub fn is_unknown(&self) -> bool {
+        *self == Seating::Unknown
+    }
+}
+
+/// Why a game finished before its scheduled last round.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EarlyEnd {
+    /// These players were knocked out under [`RuleSet::tobi`], in
+    /// [`NumPlayers::seats`] order.
+    Tobi(Vec<PlayerLocation>),
+    /// The dealer won the last scheduled hand in first place and stopped
+    /// (agari-yame).
+    AgariYame,
+    /// The dealer was tenpai at an exhaustive draw on the last scheduled
+    /// hand in first place and stopped (tenpai-yame).
+    TenpaiYame,
+}
+
+/// A player's profile, as far as the log records it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Player {
+    pub name: String,
+    /// The player's account ID on the platform the game was played on.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<String>,
+    /// Rank or dan as the platform names it, e.g. "4-dan" or "Master 1".
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub rank: Option<String>,
+    /// Rating at the start of the game, rounded to a whole number as
+    /// platforms usually show it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub rating: Option<i32>,
+}
+
+impl Player {
+    /// A player known only by `name`.
+    pub fn new(name: impl Into<String>) -> Player {
+        Player {
+            name: name.into(),
+            ..Player::default()
+        }
+    }
+}
+
+impl From<String> for Player {
+    fn from(name: String) -> Player {
+        Player::new(name)
+    }
+}
+
+impl From<&str> for Player {
+    fn from(name: &str) -> Player {
+        Player::new(name)
+    }
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl GameConfig {
+    pub fn player(&self, seat: PlayerLocation) -> &Player {
+        &self.players[seat.index()]
+    }
+
+    /// The name of the player at `seat`.
+    pub fn player_name(&self, seat: PlayerLocation) -> &str {
+        &self.player(seat).name
+    }
+
+    /// The first occupied seat, in [`NumPlayers::seats`] order, of the
+    /// player called `name`.
+    pub fn seat_of(&self, name: &str) -> Option<PlayerLocation> {
+        self.num_players
+            .seats()
+            .iter()
+            .copied()
+            .find(|&seat| self.player_name(seat) == name)
+    }
+
+    /// The seat wind `seat` started the game with, if the first dealer is
+    /// known. Logs that list players by starting wind, East first, index
+    /// their players by this.
+    pub fn starting_wind(&self, seat: PlayerLocation) -> Option<Direction> {
+        let dealer = self.first_dealer?;
+        Some(seat.seat_wind_in(dealer, self.num_players))
+    }
+
+    /// The seat that started the game as `wind`, if the first dealer is
+    /// known; the inverse of [`GameConfig::starting_wind`].
+    pub fn seat_with_starting_wind(&self, wind: Direction) -> Option<PlayerLocation> {
+        PlayerLocation::from_seat_wind_in(wind, self.first_dealer?, self.num_players)
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            event: String::new(),
+            site: String::new(),
+            date: Local::now(),
+            players: Default::default(),
+            num_players: NumPlayers::Four,
+            length: Length::Hanchan,
+            red_five: RedFive::Three,
+            thinking_time: Duration::from_secs(5),
+            time_bank: Duration::from_secs(20),
+            result: None,
+            seating: Seating::Unknown,
+            first_dealer: None,
+            early_end: None,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+/// Builds a [`GameConfig`] from [`GameConfig::default`], checking it on
+/// [`GameConfigBuilder::build`].
+#[derive(Debug, Clone, Default)]
+pub struct GameConfigBuilder {
+    config: GameConfig,
+    players: Option<Vec<Player>>,
+}
+
+impl GameConfigBuilder {
+    pub fn new() -> GameConfigBuilder {
+        GameConfigBuilder::default()
+    }
+
+    pub fn event(mut self, event: impl Into<String>) -> GameConfigBuilder {
+        self.config.event = event.into();
+        self
+    }
+
+    pub fn site(mut self, site: impl Into<String>) -> GameConfigBuilder {
+        self.config.site = site.into();
+        self
+    }
+
+    pub fn date(mut self, date: DateTime<Local>) -> GameConfigBuilder {
+        self.config.date = date;
+        self
+    }
+
+    /// The players, or just their names, in [`NumPlayers::seats`] order:
+    /// hero, right, across and left, or hero, right and left in a
+    /// three-player game. There must be one per seat.
+    pub fn players<P: Into<Player>>(
+        mut self,
+        names: impl IntoIterator<Item = P>,
+    ) -> GameConfigBuilder {
+        self.players = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn num_players(mut self, num_players: NumPlayers) -> GameConfigBuilder {
+        self.config.num_players = num_players;
+        self
+    }
+
+    /// A three-player (sanma) game.
+    pub fn three_player(self) -> GameConfigBuilder {
+        self.num_players(NumPlayers::Three)
+    }
+
+    pub fn length(mut self, length: Length) -> GameConfigBuilder {
+        self.config.length = length;
+        self
+    }
+
+    pub fn one_round(self) -> GameConfigBuilder {
+        self.length(Length::OneRound)
+    }
+
+    pub fn tonpuu(self) -> GameConfigBuilder {
+        self.length(Length::Tonpuu)
+    }
+
+    pub fn hanchan(self) -> GameConfigBuilder {
+        self.length(Length::Hanchan)
+    }
+
+    pub fn red_fives(mut self, red_five: RedFive) -> GameConfigBuilder {
+        self.config.red_five = red_five;
+        self
+    }
+
+    pub fn thinking_time(mut self, thinking_time: Duration) -> GameConfigBuilder {
+        self.config.thinking_time = thinking_time;
+        self
+    }
+
+    pub fn time_bank(mut self, time_bank: Duration) -> GameConfigBuilder {
+        self.config.time_bank = time_bank;
+        self
+    }
+
+    /// Final scores, one per seat in [`NumPlayers::seats`] order.
+    pub fn result(mut self, result: Vec<(PlayerLocation, i32)>) -> GameConfigBuilder {
+        self.config.result = Some(result);
+        self
+    }
+
+    pub fn seating(mut self, seating: Seating) -> GameConfigBuilder {
+        self.config.seating = seating;
+        self
+    }
+
+    /// The player who dealt first. They must sit at an occupied seat.
+    pub fn first_dealer(mut self, dealer: PlayerLocation) -> GameConfigBuilder {
+        self.config.first_dealer = Some(dealer);
+        self
+    }
+
+    pub fn early_end(mut self, early_end: EarlyEnd) -> GameConfigBuilder {
+        self.config.early_end = Some(early_end);
+        self
+    }
+
+    pub fn rules(mut self, rules: RuleSet) -> GameConfigBuilder {
+        self.config.rules = rules;
+        self
+    }
+
+    pub fn build(self) -> Result<GameConfig, GameConfigError> {
+        let mut config = self.config;
+        if let Some(names) = self.players {
+            let seats = config.num_players.seats();
+            if names.len() != seats.len() {
+                return Err(GameConfigError::WrongPlayerCount {
+                    expected: seats.len(),
+                    found: names.len(),
+                });
+            }
+            for (seat, player) in seats.iter().zip(names) {
+                config.players[seat.index()] = player;
+            }
+        }
+        if let Some(result) = &config.result {
+            if !config.num_players.matches_result(result) {
+                return Err(GameConfigError::ResultSeats);
+            }
+        }
+        if let Some(dealer) = config.first_dealer {
+            if !config.num_players.seats().contains(&dealer) {
+                return Err(GameConfigError::EmptySeat(dealer));
+            }
+        }
+        if let Seating::Dice(dice) = config.seating {
+            if !dice.iter().all(|d| (1..=6).contains(d)) {
+                return Err(GameConfigError::InvalidDice(dice));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Why a [`GameConfigBuilder`] could not build a configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameConfigError {
+    /// The number of player names doesn't match the number of players.
+    WrongPlayerCount { expected: usize, found: usize },
+    /// The result doesn't list exactly the occupied seats in order.
+    ResultSeats,
+    /// The first dealer is the empty seat of a three-player game.
+    EmptySeat(PlayerLocation),
+    /// A die shows something other than 1 to 6.
+    InvalidDice([u8; 2]),
+}
+
+impl fmt::Display for GameConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameConfigError::WrongPlayerCount { expected, found } => {
+                write!(f, "expected {} player names, found {}", expected, found)
+            }
+            GameConfigError::ResultSeats => {
+                write!(f, "result does not have one score per seat")
+            }
+            GameConfigError::EmptySeat(seat) => write!(f, "nobody sits at {}", seat),
+            GameConfigError::InvalidDice([a, b]) => write!(f, "dice rolled {} and {}", a, b),
+        }
+    }
+}
+
+impl std::error::Error for GameConfigError {}
+
+/// Number of players at the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NumPlayers {
+    Three,
+    #[default]
+    Four,
+}
+
+impl NumPlayers {
+    pub fn count(self) -> usize {
+        self.seats().len()
+    }
+
+    /// The occupied seats in turn order from the hero. Three-player games
+    /// have no seat across from the hero.
+    pub fn seats(self) -> &'static [PlayerLocation] {
+        match self {
+            NumPlayers::Three => &[
+                PlayerLocation::Hero,
+                PlayerLocation::Right,
+                PlayerLocation::Left,
+            ],
+            NumPlayers::Four => &PlayerLocation::ALL,
+        }
+    }
+
+    /// Whether `result` holds one score per occupied seat, in
+    /// [`NumPlayers::seats`] order.
+    pub fn matches_result(self, result: &[(PlayerLocation, i32)]) -> bool {
+        result
+            .iter()
+            .map(|&(seat, _)| seat)
+            .eq(self.seats().iter().copied())
+    }
+}
+
+/// How many wind rounds a game is scheduled to last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Length {
+    /// A single hand.
+    OneRound,
+    /// East round only (tonpuusen).
+    Tonpuu,
+    /// East and South rounds (hanchan).
+    #[default]
+    Hanchan,
+}
+
+impl Length {
+    /// Whether `round` is the last hand scheduled (all-last, oorasu) at a
+    /// table of `num_players`: the last dealer's turn in the last wind.
+    /// Every hand is the last of a one-round game.
+    pub fn is_last_hand(self, round: &RoundConfig, num_players: NumPlayers) -> bool {
+        let last_wind = match self {
+            Length::OneRound => return true,
+            Length::Tonpuu => Direction::East,
+            Length::Hanchan => Direction::South,
+        };
+        round.wind == last_wind && round.number as usize + 1 == num_players.count()
+    }
+}
+
+/// A seat relative to the hero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayerLocation {
+    Hero,
+    /// The next player in turn order after the hero (shimocha).
+    Right,
+    /// Toimen.
+    Across,
+    /// The player who discards just before the hero (kamicha).
+    Left,
+}
+
+impl PlayerLocation {
+    /// All four seats in turn order, starting from the hero.
+    pub const ALL: [PlayerLocation; 4] = [
+        PlayerLocation::Hero,
+        PlayerLocation::Right,
+        PlayerLocation::Across,
+        PlayerLocation::Left,
+    ];
+
+    /// Position in [`PlayerLocation::ALL`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The player who takes their turn after this one in a four-player
+    /// game.
+    pub fn next(self) -> PlayerLocation {
+        self.next_in(NumPlayers::Four)
+    }
+
+    /// The player who takes their turn after this one, skipping the empty
+    /// seat in a three-player game.
+    pub fn next_in(self, num_players: NumPlayers) -> PlayerLocation {
+        let seats = num_players.seats();
+        let pos = seats.iter().position(|&s| s == self).unwrap_or(0);
+        seats[(pos + 1) % seats.len()]
+    }
+
+    /// Where this seat is as seen by the player at `viewer` in a
+    /// four-player game, e.g. the hero is `Across` as seen from across and
+    /// `Right` as seen from the left.
+    pub fn seen_from(self, viewer: PlayerLocation) -> PlayerLocation {
+        self.seen_from_in(viewer, NumPlayers::Four)
+    }
+
+    /// As [`PlayerLocation::seen_from`], at a table of `num_players`. In a
+    /// three-player game each player has only a right and a left.
+    pub fn seen_from_in(self, viewer: PlayerLocation, num_players: NumPlayers) -> PlayerLocation {
+        let seats = num_players.seats();
+        let pos = |p| seats.iter().position(|&s| s == p).unwrap_or(0);
+        seats[(pos(self) + seats.len() - pos(viewer)) % seats.len()]
+    }
+
+    /// This player's seat wind in a four-player round dealt by `dealer`.
+    pub fn seat_wind(self, dealer: PlayerLocation) -> Direction {
+        self.seat_wind_in(dealer, NumPlayers::Four)
+    }
+
+    /// As [`PlayerLocation::seat_wind`], at a table of `num_players`. Nobody
+    /// sits North in a three-player game.
+    pub fn seat_wind_in(self, dealer: PlayerLocation, num_players: NumPlayers) -> Direction {
+        let seats = num_players.seats();
+        let pos = |p| seats.iter().position(|&s| s == p).unwrap_or(0);
+        SEAT_WINDS[(pos(self) + seats.len() - pos(dealer)) % seats.len()]
+    }
+
+    /// The player whose seat wind is `wind` in a four-player round dealt by
+    /// `dealer`; the inverse of [`PlayerLocation::seat_wind`].
+    pub fn from_seat_wind(wind: Direction, dealer: PlayerLocation) -> PlayerLocation {
+        PlayerLocation::ALL[(dealer.index() + wind as usize) % 4]
+    }
+
+    /// As [`PlayerLocation::from_seat_wind`], at a table of `num_players`,
+    /// or `None` for North in a three-player game.
+    pub fn from_seat_wind_in(
+        wind: Direction,
+        dealer: PlayerLocation,
+        num_players: NumPlayers,
+    ) -> Option<PlayerLocation> {
+        let seats = num_players.seats();
+        let first = seats.iter().position(|&s| s == dealer)?;
+        let offset = wind as usize;
+        (offset < seats.len()).then(|| seats[(first + offset) % seats.len()])
+    }
+}
+
+/// Seat winds by position after the dealer in turn order.
+const SEAT_WINDS: [Direction; 4] = [
+    Direction::East,
+    Direction::South,
+    Direction::West,
+    Direction::North,
+];
+
+impl fmt::Display for PlayerLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PlayerLocation::Hero => "Hero",
+            PlayerLocation::Right => "Right",
+            PlayerLocation::Across => "Across",
+            PlayerLocation::Left => "Left",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A single hand of a game, from the deal to a win or draw.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Round {
+    pub config: RoundConfig,
+    pub events: Vec<RoundEvent>,
+    /// Commentary on the round as a whole.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub annotation: Option<Annotation>,
+}
+
+impl Game {
+    /// The same game as recorded from `new_hero`'s seat. Seats, names,
+    /// dealers and results are turned around to match, and everything only
+    /// the old hero could see is dropped: their draws become hidden and
+    /// each round's dealt hand is left empty, as the new hero's wasn't
+    /// recorded. Melds need no change, as their sources are already
+    /// relative to the caller.
+    ///
+    /// # Panics
+    ///
+    /// If `new_hero` is the empty seat of a three-player game.
+    pub fn rebase_perspective(&self, new_hero: PlayerLocation) -> Game {
+        let num_players = self.config.num_players;
+        assert!(
+            num_players.seats().contains(&new_hero),
+            "nobody sits at {}",
+            new_hero
+        );
+        let rebase = |seat: PlayerLocation| seat.seen_from_in(new_hero, num_players);
+        let rebase_result = |result: &Vec<(PlayerLocation, i32)>| {
+            let mut result: Vec<_> = result.iter().map(|&(p, s)| (rebase(p), s)).collect();
+            result.sort_by_key(|&(p, _)| p.index());
+            result
+        };
+        let mut config = self.config.clone();
+        for &seat in num_players.seats() {
+            config.players[rebase(seat).index()] = self.config.player(seat).clone();
+        }
+        config.result = self.config.result.as_ref().map(rebase_result);
+        config.first_dealer = self.config.first_dealer.map(rebase);
+        if let Some(EarlyEnd::Tobi(players)) = &mut config.early_end {
+            for player in players.iter_mut() {
+                *player = rebase(*player);
+            }
+            players.sort_by_key(|p| p.index());
+        }
+        let rounds = self
+            .rounds
+            .iter()
+            .map(|round| {
+                let events = rebase_events(&round.events, &rebase);
+                Round {
+                    config: RoundConfig {
+                        dealer: rebase(round.config.dealer),
+                        initial_hero_hand_state: Hand::default(),
+                        result: round.config.result.as_ref().map(rebase_result),
+                        ..round.config.clone()
+                    },
+                    events,
+                    annotation: round.annotation.clone(),
+                }
+            })
+            .collect();
+        Game {
+            config,
+            rounds,
+            annotation: self.annotation.clone(),
+        }
+    }
+
+    /// Whether `other` records the same game as `self`, possibly from
+    /// another seat: whether some [`Game::rebase_perspective`] of each
+    /// matches.
+    ///
+    /// Only the play is compared. Player profiles, commentary and
+    /// variations are ignored, as are the tiles only one hero could see.
+    pub fn equivalent(&self, other: &Game) -> bool {
+        let this = self.normalized(PlayerLocation::Hero);
+        other
+            .config
+            .num_players
+            .seats()
+            .iter()
+            .any(|&seat| other.normalized(seat) == this)
+    }
+
+    /// The game from `hero`'s seat without anything
+    /// [`Game::equivalent`] ignores.
+    fn normalized(&self, hero: PlayerLocation) -> Game {
+        let mut game = self.rebase_perspective(hero);
+        game.config.players = Default::default();
+        game.annotation = None;
+        for round in &mut game.rounds {
+            round.annotation = None;
+            for event in &mut round.events {
+                event.annotation = None;
+                event.variations.clear();
+            }
+        }
+        game
+    }
+}
+
+/// Events, and the variations within them, as seen by a new hero; see
+/// [`Game::rebase_perspective`].
+fn rebase_events(
+    events: &[RoundEvent],
+    rebase: &impl Fn(PlayerLocation) -> PlayerLocation,
+) -> Vec<RoundEvent> {
+    events
+        .iter()
+        .map(|event| {
+            let action = match &event.action {
+                RoundAction::Draw(_) => RoundAction::Draw(ObservedTile::Hidden),
+                RoundAction::Exhaustive(hands) => RoundAction::Exhaustive(
+                    hands.iter().map(|(p, h)| (rebase(*p), h.clone())).collect(),
+                ),
+                action => action.clone(),
+            };
+            let variations = event
+                .variations
+                .iter()
+                .map(|variation| Variation {
+                    events: rebase_events(&variation.events, rebase),
+                    annotation: variation.annotation.clone(),
+                })
+                .collect();
+            RoundEvent {
+                subject: rebase(event.subject),
+                action,
+                variations,
+                ..event.clone()
+            }
+        })
+        .collect()
+}
+
+impl Round {
+    /// How many Norths `player` has set aside as nuki dora so far. Each one
+    /// counts as a dora for that player's winning hand.
+    pub fn nuki_dora(&self, player: PlayerLocation) -> u8 {
+        self.events
+            .iter()
+            .filter(|e| e.subject == player && e.action == RoundAction::Kita)
+            .count() as u8
+    }
+
+    /// The players shown tenpai at an exhaustive draw, in the order they
+    /// were revealed, or `None` if the round didn't end in one. Revealed
+    /// hands that aren't actually tenpai are left out.
+    pub fn tenpai_players(&self) -> Option<Vec<PlayerLocation>> {
+        let Some(RoundEvent {
+            action: RoundAction::Exhaustive(hands),
+            ..
+        }) = self.events.last()
+        else {
+            return None;
+        };
+        let tenpai = hands
+            .iter()
+            .filter(|(_, hand)| hand.is_tenpai())
+            .map(|&(player, _)| player)
+            .collect();
+        Some(tenpai)
+    }
+
+    /// The players who score nagashi mangan, if the round ended in an
+    /// exhaustive draw and `rules` allow it.
+    ///
+    /// A player qualifies when they discarded at least once, every discard
+    /// was a terminal or honor, and no other player called any of them.
+    pub fn nagashi_mangan(&self, rules: &RuleSet) -> Vec<PlayerLocation> {
+        let exhaustive = matches!(
+            self.events.last(),
+            Some(RoundEvent {
+                action: RoundAction::Exhaustive(_),
+                ..
+            })
+        );
+        if !rules.nagashi_mangan || !exhaustive {
+            return vec![];
+        }
+        let mut qualifies: Vec<(PlayerLocation, bool)> = Vec::new();
+        for (i, event) in self.events.iter().enumerate() {
+            let RoundAction::Discard(tile) = event.action else {
+                continue;
+            };
+            let called = self.events.get(i + 1).is_some_and(|next| {
+                next.subject != event.subject
+                    && matches!(
+                        next.action,
+                        RoundAction::Chii(_) | RoundAction::Pon(_) | RoundAction::OpenKan(_)
+                    )
+            });
+            let ok = tile.is_yaochuu() && !called;
+            match qualifies.iter_mut().find(|(p, _)| *p == event.subject) {
+                Some((_, so_far)) => *so_far &= ok,
+                None => qualifies.push((event.subject, ok)),
+            }
+        }
+        let mut players: Vec<PlayerLocation> = qualifies
+            .into_iter()
+            .filter(|&(_, ok)| ok)
+            .map(|(p, _)| p)
+            .collect();
+        players.sort();
+        players
+    }
+
+    /// The turn each event belongs to, in order.
+    ///
+    /// A player's turn runs from their draw or call through their discard,
+    /// so an event is on turn *n* of its subject when they have made
+    /// *n* − 1 discards before it. Wins, draws and calls made on another
+    /// player's discard count towards the subject's next turn.
+    pub fn turns(&self) -> impl Iterator<Item = Turn> + '_ {
+        let mut discards = [0; 4];
+        self.events.iter().map(move |event| {
+            let count = &mut discards[event.subject.index()];
+            let turn = Turn {
+                player: event.subject,
+                number: *count + 1,
+            };
+            if let RoundAction::Discard(_) = event.action {
+                *count += 1;
+            }
+            turn
+        })
+    }
+
+    /// The turn of the event at `index`, if there is one.
+    pub fn turn_at(&self, index: usize) -> Option<Turn> {
+        self.turns().nth(index)
+    }
+
+    /// The index of the event that ends `turn`: the player's discard on
+    /// that turn, e.g. the hero's 8th discard for `Turn { player: Hero,
+    /// number: 8 }`.
+    pub fn discard_index(&self, turn: Turn) -> Option<usize> {
+        self.events
+            .iter()
+            .zip(self.turns())
+            .position(|(event, t)| t == turn && matches!(event.action, RoundAction::Discard(_)))
+    }
+
+    /// The index of the first event on turn `number` (counted from 1) of
+    /// any player, which in an uninterrupted round is the dealer's draw.
+    pub fn turn_start(&self, number: usize) -> Option<usize> {
+        self.turns().position(|turn| turn.number == number)
+    }
+}
+
+/// A moment in a round in human terms: one player's numbered turn, counted
+/// from 1. See [`Round::turns`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Turn {
+    pub player: PlayerLocation,
+    pub number: usize,
+}
+
+impl fmt::Display for Turn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, turn {}", self.player, self.number)
+    }
+}
+
+/// Position of a round within its wind, 1 to 4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoundNumber {
+    #[default]
+    One,
+    Two,
+    Three,
+    Four,
+}
+
+impl RoundNumber {
+    /// 1 to 4.
+    pub fn get(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The next round in the same wind, or `None` after the fourth.
+    pub fn next(self) -> Option<RoundNumber> {
+        match self {
+            RoundNumber::One => Some(RoundNumber::Two),
+            RoundNumber::Two => Some(RoundNumber::Three),
+            RoundNumber::Three => Some(RoundNumber::Four),
+            RoundNumber::Four => None,
+        }
+    }
+
+    /// As [`RoundNumber::next`], at a table of `num_players`: a wind has
+    /// one round per dealer, so only three in a three-player game.
+    pub fn next_in(self, num_players: NumPlayers) -> Option<RoundNumber> {
+        self.next()
+            .filter(|next| (*next as usize) < num_players.count())
+    }
+}
+
+/// Which hand of a game a round is, ordered as hands are played: by
+/// wind, then number, then repeat count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoundId {
+    pub wind: Direction,
+    pub number: RoundNumber,
+    /// Number of repeat counters (honba).
+    pub repeat: u8,
+}
+
+/// The usual name of the hand: `East 1`, or `South 3, 2 honba` once there
+/// are repeat counters.
+impl fmt::Display for RoundId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.wind, self.number.get())?;
+        if self.repeat > 0 {
+            write!(f, ", {} honba", self.repeat)?;
+        }
+        Ok(())
+    }
+}
+
+/// Settings and known state at the start of a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoundConfig {
+    /// The prevailing wind.
+    pub wind: Direction,
+    pub number: RoundNumber,
+    /// Number of repeat counters (honba).
+    pub repeat: u8,
+    /// The player sitting East for this round.
+    pub dealer: PlayerLocation,
+    /// Dora indicators revealed during the round, in order. See
+    /// [`RoundState::dora_indicators`] for those showing at a given point.
+    ///
+    /// [`RoundState::dora_indicators`]: crate::state::RoundState::dora_indicators
+    pub dora: Vec<MahjongTile>,
+    /// Ura dora indicators, if they were revealed.
+    pub ura_dora: Vec<MahjongTile>,
+    /// The hero's hand as dealt.
+    pub initial_hero_hand_state: Hand,
+    /// Scores after the round, if known, one per seat in
+    /// [`NumPlayers::seats`] order.
+    pub result: Option<Vec<(PlayerLocation, i32)>>,
+    /// How the wall was shuffled and broken, if recorded.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "WallSetup::is_unknown")
+    )]
+    pub wall: WallSetup,
+}
+
+impl RoundConfig {
+    pub fn id(&self) -> RoundId {
+        RoundId {
+            wind: self.wind,
+            number: self.number,
+            repeat: self.repeat,
+        }
+    }
+
+    /// The round's usual name, e.g. `South 3, 2 honba`; see [`RoundId`].
+    pub fn name(&self) -> String {
+        self.id().to_string()
+    }
+}
+
+/// Builds a [`RoundConfig`], checking it on [`RoundConfigBuilder::build`].
+///
+/// Unless told otherwise the round is East 1 with no repeats and the hero
+/// as dealer. The dealer can be given directly, through the hero's seat
+/// wind, or through the dealer of East 1; whichever are given must agree.
+#[derive(Debug, Clone, Default)]
+pub struct RoundConfigBuilder {
+    wind: Option<Direction>,
+    number: RoundNumber,
+    repeat: u8,
+    dealer: Option<PlayerLocation>,
+    hero_seat: Option<Direction>,
+    first_dealer: Option<PlayerLocation>,
+    dora: Vec<MahjongTile>,
+    ura_dora: Vec<MahjongTile>,
+    hand: Hand,
+    result: Option<Vec<(PlayerLocation, i32)>>,
+    num_players: NumPlayers,
+    wall: WallSetup,
+}
+
+impl RoundConfigBuilder {
+    pub fn new() -> RoundConfigBuilder {
+        RoundConfigBuilder::default()
+    }
+
+    /// The prevailing wind.
+    pub fn wind(mut self, wind: Direction) -> RoundConfigBuilder {
+        self.wind = Some(wind);
+        self
+    }
+
+    pub fn number(mut self, number: RoundNumber) -> RoundConfigBuilder {
+        self.number = number;
+        self
+    }
+
+    pub fn repeat(mut self, repeat: u8) -> RoundConfigBuilder {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn dealer(mut self, dealer: PlayerLocation) -> RoundConfigBuilder {
+        self.dealer = Some(dealer);
+        self
+    }
+
+    /// The hero's seat wind this round, e.g. `South` when the dealer sits
+    /// on the hero's left.
+    pub fn hero_seat(mut self, seat: Direction) -> RoundConfigBuilder {
+        self.hero_seat = Some(seat);
+        self
+    }
+
+    /// The dealer of East 1. The dealer moves one seat to the right each
+    /// round number, so with [`RoundConfigBuilder::number`] this fixes the
+    /// dealer.
+    pub fn first_dealer(mut self, dealer: PlayerLocation) -> RoundConfigBuilder {
+        self.first_dealer = Some(dealer);
+        self
+    }
+
+    /// Adds a dora indicator after those already added.
+    pub fn dora_indicator(mut self, tile: MahjongTile) -> RoundConfigBuilder {
+        self.dora.push(tile);
+        self
+    }
+
+    /// Adds an ura dora indicator after those already added.
+    pub fn ura_dora_indicator(mut self, tile: MahjongTile) -> RoundConfigBuilder {
+        self.ura_dora.push(tile);
+        self
+    }
+
+    /// The hero's hand as dealt.
+    pub fn hand(mut self, hand: Hand) -> RoundConfigBuilder {
+        self.hand = hand;
+        self
+    }
+
+    /// Scores after the round, one per seat in [`NumPlayers::seats`]
+    /// order.
+    pub fn result(mut self, result: Vec<(PlayerLocation, i32)>) -> RoundConfigBuilder {
+        self.result = Some(result);
+        self
+    }
+
+    /// The number of players, which fixes the seats the dealer moves
+    /// through and the result has. Four unless told otherwise.
+    pub fn num_players(mut self, num_players: NumPlayers) -> RoundConfigBuilder {
+        self.num_players = num_players;
+        self
+    }
+
+    /// The seed the wall was shuffled from.
+    pub fn wall_seed(mut self, seed: u64) -> RoundConfigBuilder {
+        self.wall.seed = Some(seed);
+        self
+    }
+
+    /// The two dice rolled to break the wall.
+    pub fn dice(mut self, dice: [u8; 2]) -> RoundConfigBuilder {
+        self.wall.dice = Some(dice);
+        self
+    }
+
+    /// Where drawing starts in the shuffled wall.
+    pub fn wall_break(mut self, position: usize) -> RoundConfigBuilder {
+        self.wall.break_position = Some(position);
+        self
+    }
+
+    pub fn build(self) -> Result<RoundConfig, RoundConfigError> {
+        if !self.hand.melds.is_empty() {
+            return Err(RoundConfigError::MeldsInDeal(self.hand.melds.len()));
+        }
+        if self.hand.concealed.len() != 13 {
+            return Err(RoundConfigError::WrongHandSize(self.hand.concealed.len()));
+        }
+        self.hand
+            .validate()
+            .map_err(RoundConfigError::InvalidHand)?;
+        if !(1..=5).contains(&self.dora.len()) {
+            return Err(RoundConfigError::DoraCount(self.dora.len()));
+        }
+        if self.ura_dora.len() > self.dora.len() {
+            return Err(RoundConfigError::UraDoraCount(self.ura_dora.len()));
+        }
+        let seats = self.num_players.seats();
+        let n = seats.len();
+        let from_first = match self.first_dealer {
+            Some(d) => match seats.iter().position(|&s| s == d) {
+                Some(first) => Some(seats[(first + self.number as usize) % n]),
+                None => return Err(RoundConfigError::SeatMismatch),
+            },
+            None => None,
+        };
+        let from_seat = match self.hero_seat {
+            Some(seat) if seat as usize >= n => return Err(RoundConfigError::SeatMismatch),
+            Some(seat) => Some(seats[(n - seat as usize) % n]),
+            None => None,
+        };
+        let mut dealers = [self.dealer, from_seat, from_first].into_iter().flatten();
+        let dealer = dealers.next().unwrap_or(PlayerLocation::Hero);
+        if dealers.any(|d| d != dealer) || !seats.contains(&dealer) {
+            return Err(RoundConfigError::SeatMismatch);
+        }
+        if let Some(result) = &self.result {
+            if !self.num_players.matches_result(result) {
+                return Err(RoundConfigError::ResultSeats);
+            }
+        }
+        if let Some(dice) = self.wall.dice {
+            if !dice.iter().all(|d| (1..=6).contains(d)) {
+                return Err(RoundConfigError::InvalidDice(dice));
+            }
+        }
+        Ok(RoundConfig {
+            wind: self.wind.unwrap_or(Direction::East),
+            number: self.number,
+            repeat: self.repeat,
+            dealer,
+            dora: self.dora,
+            ura_dora: self.ura_dora,
+            initial_hero_hand_state: self.hand,
+            result: self.result,
+            wall: self.wall,
+        })
+    }
+}
+
+/// Why a [`RoundConfigBuilder`] could not build a configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundConfigError {
+    /// The hero's hand holds this many tiles instead of 13.
+    WrongHandSize(usize),
+    /// The hero's hand holds this many melds, though a hand is dealt
+    /// without any.
+    MeldsInDeal(usize),
+    InvalidHand(HandError),
+    /// There must be between one and five dora indicators.
+    DoraCount(usize),
+    /// More ura dora indicators than dora indicators.
+    UraDoraCount(usize),
+    /// The dealer, the hero's seat wind and the dealer of East 1 disagree,
+    /// or one of them names a seat a three-player game doesn't have.
+    SeatMismatch,
+    /// The result doesn't list exactly the occupied seats in order.
+    ResultSeats,
+    /// A die shows something other than 1 to 6.
+    InvalidDice([u8; 2]),
+}
+
+impl fmt::Display for RoundConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoundConfigError::WrongHandSize(n) => {
+                write!(f, "dealt hand holds {} tiles, expected 13", n)
+            }
+            RoundConfigError::MeldsInDeal(n) => write!(f, "dealt hand holds {} melds", n),
+            RoundConfigError::InvalidHand(e) => e.fmt(f),
+            RoundConfigError::DoraCount(n) => write!(f, "{} dora indicators, expected 1 to 5", n),
+            RoundConfigError::UraDoraCount(n) => {
+                write!(f, "{} ura dora indicators is more than the dora", n)
+            }
+            RoundConfigError::SeatMismatch => {
+                write!(f, "dealer is inconsistent with the seats given")
+            }
+            RoundConfigError::ResultSeats => {
+                write!(f, "result does not have one score per seat")
+            }
+            RoundConfigError::InvalidDice([a, b]) => write!(f, "dice rolled {} and {}", a, b),
+        }
+    }
+}
+
+impl std::error::Error for RoundConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RoundConfigError::InvalidHand(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Something a player did during a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoundEvent {
+    /// The player performing the action.
+    pub subject: PlayerLocation,
+    pub action: RoundAction,
+    /// How long the player took over the action, if the log records it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub elapsed: Option<Duration>,
+    /// When the action happened, if the log records it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub time: Option<DateTime<Local>>,
+    /// For a discard, whether it was the tile just drawn (tsumogiri) rather
+    /// than one from the hand (tedashi), if the log records it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub tsumogiri: Option<bool>,
+    /// Commentary on the action.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub annotation: Option<Annotation>,
+    /// Lines that could have been played instead of this event.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub variations: Vec<Variation>,
+}
+
+/// An alternative line of play for study: events played instead of the
+/// event that owns the variation and everything after it, e.g. "what if
+/// the hero had discarded 5s here".
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variation {
+    pub events: Vec<RoundEvent>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub annotation: Option<Annotation>,
+}
+
+impl RoundEvent {
+    /// An event with no timing recorded.
+    pub fn new(subject: PlayerLocation, action: RoundAction) -> RoundEvent {
+        RoundEvent {
+            subject,
+            action,
+            elapsed: None,
+            time: None,
+            tsumogiri: None,
+            annotation: None,
+            variations: Vec::new(),
+        }
+    }
+
+    pub fn with_elapsed(mut self, elapsed: Duration) -> RoundEvent {
+        self.elapsed = Some(elapsed);
+        self
+    }
+
+    pub fn with_time(mut self, time: DateTime<Local>) -> RoundEvent {
+        self.time = Some(time);
+        self
+    }
+
+    /// Records whether a discard was the tile just drawn.
+    pub fn with_tsumogiri(mut self, tsumogiri: bool) -> RoundEvent {
+        self.tsumogiri = Some(tsumogiri);
+        self
+    }
+
+    pub fn with_annotation(mut self, annotation: Annotation) -> RoundEvent {
+        self.annotation = Some(annotation);
+        self
+    }
+
+    /// Adds a line that could have been played instead of this event.
+    pub fn with_variation(mut self, variation: Variation) -> RoundEvent {
+        self.variations.push(variation);
+        self
+    }
+}
+
+/// One line such as `Right discards 7p` or `Hero chii 4p from Left
+/// (3p4p5p)`. Timing is left out.
+impl fmt::Display for RoundEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.subject, self.action)
+    }
+}
+
+/// The kinds of action that can happen in a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoundAction {
+    /// Draw a tile from the wall. The tile is usually only known for the
+    /// hero.
+    Draw(ObservedTile),
+    Discard(MahjongTile),
+    Chii(ChiiMeld),
+    Pon(PonMeld),
+    OpenKan(OpenKanMeld),
+    ClosedKan(ClosedKanMeld),
+    AddedKan(AddedKanMeld),
+    /// Declare riichi. The declaring discard follows as its own event.
+    ///
+    /// Whether the declaration was legal can be checked with
+    /// [`Hand::can_riichi`].
+    #[cfg_attr(feature = "serde", serde(alias = "Richii"))]
+    Riichi,
+    /// Set aside a North as nuki dora in a three-player game. A replacement
+    /// draw from the dead wall follows as its own event.
+    Kita,
+    /// Win on a self-drawn tile, revealing the hand, with the score
+    /// breakdown if the log has one.
+    Tsumo(Hand, Option<WinDetails>),
+    /// Win on the previous discard, revealing the hand, with the score
+    /// breakdown if the log has one. In a double or triple ron each winner
+    /// has their own event, in turn order from the discarder.
+    Ron(Hand, Option<WinDetails>),
+    /// Exhaustive draw, with the hands revealed by tenpai players.
+    Exhaustive(Vec<(PlayerLocation, Hand)>),
+    /// The round is abandoned without a winner. Which kinds are played is
+    /// set by [`RuleSet::abortive_draws`].
+    AbortiveDraw(AbortiveDraw),
+}
+
+/// Ways a round can be abandoned before it is won or the wall runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AbortiveDraw {
+    /// A player shows nine or more different terminals and honors on their
+    /// first draw, before any call (kyuushu kyuuhai). The subject is that
+    /// player.
+    KyuushuKyuuhai,
+    /// All four players discard the same wind on the first go-around, with
+    /// no calls (suufon renda).
+    SuufonRenda,
+    /// All four players are in riichi once the fourth declaration's discard
+    /// passes (suucha riichi).
+    SuuchaRiichi,
+    /// The discard after a fourth kan passes, with the kans not all made by
+    /// one player (suukaikan).
+    Suukaikan,
+    /// Three players call ron on the same discard (sancha hou).
+    SanchaHou,
+}
+
+/// The action as it follows its subject, e.g. `discards 7p` or `chii 4p
+/// from Left (3p4p5p)`. Like the meld itself, the source of a call is seen
+/// from the caller.
+impl fmt::Display for RoundAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoundAction::Draw(ObservedTile::Known(tile)) => write!(f, "draws {}", tile),
+            RoundAction::Draw(ObservedTile::Hidden) => f.write_str("draws"),
+            RoundAction::Discard(tile) => write!(f, "discards {}", tile),
+            RoundAction::Chii(m) => {
+                write!(f, "chii {} from {} ", m.chii_tile, m.source)?;
+                write_tiles(f, &m.tiles)
+            }
+            RoundAction::Pon(m) => {
+                write!(f, "pon {} from {} ", m.pon_tile, m.source)?;
+                write_tiles(f, &m.tiles)
+            }
+            RoundAction::OpenKan(m) => {
+                write!(f, "kan {} from {} ", m.kan_tile, m.source)?;
+                write_tiles(f, &m.tiles)
+            }
+            RoundAction::ClosedKan(m) => {
+                f.write_str("closed kan ")?;
+                write_tiles(f, &m.tiles)
+            }
+            RoundAction::AddedKan(m) => {
+                write!(f, "added kan {} ", m.added_tile)?;
+                let mut tiles = m.pon.tiles.to_vec();
+                tiles.push(m.added_tile);
+                write_tiles(f, &tiles)
+            }
+            RoundAction::Riichi => f.write_str("riichi"),
+            RoundAction::Kita => f.write_str("kita"),
+            RoundAction::Tsumo(hand, _) => write_win(f, "tsumo", hand),
+            RoundAction::Ron(hand, _) => write_win(f, "ron", hand),
+            RoundAction::Exhaustive(hands) if hands.is_empty() => f.write_str("exhaustive draw"),
+            RoundAction::Exhaustive(hands) => {
+                f.write_str("exhaustive draw, tenpai:")?;
+                for (player, hand) in hands {
+                    write!(f, " {} ({})", player, hand)?;
+                }
+                Ok(())
+            }
+            RoundAction::AbortiveDraw(kind) => write!(f, "abortive draw ({})", kind),
+        }
+    }
+}
+
+/// The tiles of a meld in order, each in full: `(3p4p5p)`.
+fn write_tiles(f: &mut fmt::Formatter, tiles: &[MahjongTile]) -> fmt::Result {
+    let mut tiles = tiles.to_vec();
+    tile::sort_hand(&mut tiles);
+    f.write_str("(")?;
+    for tile in tiles {
+        write!(f, "{}", tile)?;
+    }
+    f.write_str(")")
+}
+
+/// A win, with the revealed hand if it was recorded.
+fn write_win(f: &mut fmt::Formatter, name: &str, hand: &Hand) -> fmt::Result {
+    f.write_str(name)?;
+    if hand.concealed.is_empty() && hand.melds.is_empty() {
+        return Ok(());
+    }
+    write!(f, " ({})", hand)
+}
+
+impl fmt::Display for AbortiveDraw {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            AbortiveDraw::KyuushuKyuuhai => "kyuushu kyuuhai",
+            AbortiveDraw::SuufonRenda => "suufon renda",
+            AbortiveDraw::SuuchaRiichi => "suucha riichi",
+            AbortiveDraw::Suukaikan => "suukaikan",
+            AbortiveDraw::SanchaHou => "sancha hou",
+        };
+        f.write_str(name)
+    }
+}
+
+impl RoundAction {
+    /// The old, misspelled name of [`RoundAction::Riichi`].
+    #[deprecated(note = "renamed to RoundAction::Riichi")]
+    #[allow(non_upper_case_globals)]
+    pub const Richii: RoundAction = RoundAction::Riichi;
+}
+
+/// Games serialize with their schema version, which is checked when they
+/// are read back.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{Annotation, Game, GameConfig, Round};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct GameRef<'a> {
+        schema_version: u32,
+        config: &'a GameConfig,
+        rounds: &'a [Round],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotation: &'a Option<Annotation>,
+    }
+
+    #[derive(Deserialize)]
+    struct GameFile {
+        #[serde(default = "first_version")]
+        schema_version: u32,
+        config: GameConfig,
+        rounds: Vec<Round>,
+        #[serde(default)]
+        annotation: Option<Annotation>,
+    }
+
+    fn first_version() -> u32 {
+        1
+    }
+
+    impl Serialize for Game {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            GameRef {
+                schema_version: Game::SCHEMA_VERSION,
+                config: &self.config,
+                rounds: &self.rounds,
+                annotation: &self.annotation,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Game {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let file = GameFile::deserialize(deserializer)?;
+            if file.schema_version > Game::SCHEMA_VERSION {
+                return Err(D::Error::custom(format_args!(
+                    "schema version {} is newer than {}",
+                    file.schema_version,
+                    Game::SCHEMA_VERSION
+                )));
+            }
+            Ok(Game {
+                config: file.config,
+                rounds: file.rounds,
+                annotation: file.annotation,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::{self, ObservedTile};
+
+    #[test]
+    fn game_config_builder() {
+        let config = GameConfigBuilder::new()
+            .event("Riichi Open 2024")
+            .players(["a", "b", "c", "d"])
+            .tonpuu()
+            .red_fives(RedFive::Four)
+            .time_bank(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        assert_eq!(config.event, "Riichi Open 2024");
+        assert_eq!(config.players.map(|p| p.name), ["a", "b", "c", "d"]);
+        assert_eq!(config.length, Length::Tonpuu);
+        assert_eq!(config.red_five, RedFive::Four);
+        assert_eq!(config.time_bank, Duration::from_secs(60));
+        assert_eq!(config.thinking_time, GameConfig::default().thinking_time);
+
+        let sanma = GameConfigBuilder::new()
+            .three_player()
+            .players(["a", "b", "c"])
+            .build()
+            .unwrap();
+        assert_eq!(
+            (
+                sanma.player_name(PlayerLocation::Across),
+                sanma.player_name(PlayerLocation::Left)
+            ),
+            ("", "c")
+        );
+        let ranked = Player {
+            rank: Some("4-dan".to_string()),
+            rating: Some(1834),
+            ..Player::new("c")
+        };
+        let profiles = GameConfigBuilder::new()
+            .three_player()
+            .players([Player::new("a"), Player::new("b"), ranked.clone()])
+            .build()
+            .unwrap();
+        assert_eq!(profiles.player(PlayerLocation::Left), &ranked);
+        assert_eq!(
+            GameConfigBuilder::new()
+                .three_player()
+                .players(["a", "b", "c", "d"])
+                .build(),
+            Err(GameConfigError::WrongPlayerCount {
+                expected: 3,
+                found: 4
+            })
+        );
+    }
+
+    #[test]
+    fn seats_by_name_and_starting_wind() {
+        let config = GameConfigBuilder::new()
+            .players(["a", "b", "c", "d"])
+            .seating(Seating::Dice([3, 4]))
+            .first_dealer(PlayerLocation::Across)
+            .build()
+            .unwrap();
+        assert_eq!(config.seat_of("b"), Some(PlayerLocation::Right));
+        assert_eq!(config.seat_of("e"), None);
+        assert_eq!(
+            config.starting_wind(PlayerLocation::Hero),
+            Some(Direction::West)
+        );
+        assert_eq!(
+            config.seat_with_starting_wind(Direction::South),
+            Some(PlayerLocation::Left)
+        );
+        assert_eq!(
+            GameConfig::default().starting_wind(PlayerLocation::Hero),
+            None
+        );
+
+        let sanma = GameConfigBuilder::new()
+            .three_player()
+            .players(["a", "b", "c"])
+            .first_dealer(PlayerLocation::Left)
+            .build()
+            .unwrap();
+        assert_eq!(
+            sanma.seat_with_starting_wind(Direction::South),
+            Some(PlayerLocation::Hero)
+        );
+        assert_eq!(sanma.seat_with_starting_wind(Direction::North), None);
+        assert_eq!(
+            GameConfigBuilder::new()
+                .three_player()
+                .first_dealer(PlayerLocation::Across)
+                .build(),
+            Err(GameConfigError::EmptySeat(PlayerLocation::Across))
+        );
+        assert_eq!(
+            GameConfigBuilder::new()
+                .seating(Seating::Dice([7, 1]))
+                .build(),
+            Err(GameConfigError::InvalidDice([7, 1]))
+        );
+
+        let game = Game {
+            config,
+            ..Game::default()
+        };
+        let rebased = game.rebase_perspective(PlayerLocation::Across);
+        assert_eq!(rebased.config.first_dealer, Some(PlayerLocation::Hero));
+        assert_eq!(rebased.config.seat_of("a"), Some(PlayerLocation::Across));
+    }
+
+    #[test]
+    fn round_names_and_order() {
+        let config = RoundConfigBuilder::new()
+            .wind(Direction::South)
+            .number(RoundNumber::Three)
+            .repeat(2)
+            .hand(Hand::parse("123m456p789s1234z").unwrap())
+            .dora_indicator(tile::EAST)
+            .build()
+            .unwrap();
+        assert_eq!(config.name(), "South 3, 2 honba");
+        let east1 = RoundId {
+            wind: Direction::East,
+            number: RoundNumber::One,
+            repeat: 0,
+        };
+        assert_eq!(east1.to_string(), "East 1");
+        let repeat = RoundId { repeat: 1, ..east1 };
+        let east2 = RoundId {
+            number: RoundNumber::Two,
+            ..east1
+        };
+        let mut ids = vec![config.id(), east2, repeat, east1];
+        ids.sort();
+        assert_eq!(ids, [east1, repeat, east2, config.id()]);
+
+        assert_eq!(RoundNumber::Three.next(), Some(RoundNumber::Four));
+        assert_eq!(RoundNumber::Four.next(), None);
+        assert_eq!(RoundNumber::Three.next_in(NumPlayers::Three), None);
+        assert_eq!(Direction::North.next(), Direction::East);
+    }
+
+    #[test]
+    fn round_config_builder() {
+        let hand = Hand::parse("123m456p789s1234z").unwrap();
+        let base = RoundConfigBuilder::new()
+            .hand(hand.clone())
+            .dora_indicator(tile::EAST);
+        let config = base.clone().build().unwrap();
+        assert_eq!(
+            (config.wind, config.number, config.repeat, config.dealer),
+            (Direction::East, RoundNumber::One, 0, PlayerLocation::Hero)
+        );
+        assert_eq!(config.dora, vec![tile::EAST]);
+        assert_eq!(config.initial_hero_hand_state, hand);
+
+        let south_three = base
+            .clone()
+            .wind(Direction::South)
+            .number(RoundNumber::Three)
+            .first_dealer(PlayerLocation::Right)
+            .hero_seat(Direction::South)
+            .build()
+            .unwrap();
+        assert_eq!(south_three.dealer, PlayerLocation::Left);
+        assert_eq!(
+            base.clone()
+                .dealer(PlayerLocation::Across)
+                .hero_seat(Direction::North)
+                .build(),
+            Err(RoundConfigError::SeatMismatch)
+        );
+        assert_eq!(
+            base.clone()
+                .hero_seat(Direction::North)
+                .build()
+                .map(|c| c.dealer),
+            Ok(PlayerLocation::Right)
+        );
+    }
+
+    #[test]
+    fn round_config_builder_errors() {
+        let hand = Hand::parse("123m456p789s1234z").unwrap();
+        assert_eq!(
+            RoundConfigBuilder::new().hand(hand.clone()).build(),
+            Err(RoundConfigError::DoraCount(0))
+        );
+        let mut six = RoundConfigBuilder::new().hand(hand.clone());
+        for _ in 0..6 {
+            six = six.dora_indicator(tile::EAST);
+        }
+        assert_eq!(six.build(), Err(RoundConfigError::DoraCount(6)));
+        assert_eq!(
+            RoundConfigBuilder::new()
+                .hand(hand)
+                .dora_indicator(tile::EAST)
+                .ura_dora_indicator(tile::EAST)
+                .ura_dora_indicator(tile::EAST)
+                .build(),
+            Err(RoundConfigError::UraDoraCount(2))
+        );
+        assert_eq!(
+            RoundConfigBuilder::new()
+                .hand(Hand::parse("123m456p789s11234z").unwrap())
+                .dora_indicator(tile::EAST)
+                .build(),
+            Err(RoundConfigError::WrongHandSize(14))
+        );
+        assert_eq!(
+            RoundConfigBuilder::new()
+                .hand(Hand::parse("123m456p789s1z + p111z(left)").unwrap())
+                .dora_indicator(tile::EAST)
+                .build(),
+            Err(RoundConfigError::MeldsInDeal(1))
+        );
+        assert_eq!(
+            RoundConfigBuilder::new()
+                .hand(Hand::parse("11111m456p789s12z").unwrap())
+                .dora_indicator(tile::EAST)
+                .build(),
+            Err(RoundConfigError::InvalidHand(HandError::TooManyCopies(
+                tile::MAN_1
+            )))
+        );
+        let rolled = |dice| {
+            RoundConfigBuilder::new()
+                .hand(Hand::parse("123m456p789s1234z").unwrap())
+                .dora_indicator(tile::EAST)
+                .wall_seed(7)
+                .dice(dice)
+                .build()
+        };
+        assert_eq!(rolled([0, 6]), Err(RoundConfigError::InvalidDice([0, 6])));
+        let wall = rolled([6, 6]).unwrap().wall;
+        assert_eq!((wall.seed, wall.dice), (Some(7), Some([6, 6])));
+    }
+
+    #[test]
+    fn round_config_builder_seats() {
+        use PlayerLocation::*;
+        let builder = || {
+            RoundConfigBuilder::new()
+                .hand(Hand::parse("19m19p19s1234567z").unwrap())
+                .dora_indicator(tile::EAST)
+        };
+        let three = || builder().num_players(NumPlayers::Three);
+        let sanma = three()
+            .number(RoundNumber::Two)
+            .first_dealer(Left)
+            .hero_seat(Direction::East)
+            .result(vec![(Hero, 30000), (Right, 40000), (Left, 35000)])
+            .build()
+            .unwrap();
+        assert_eq!(sanma.dealer, Hero);
+        let west = three().hero_seat(Direction::West).build().unwrap();
+        assert_eq!(west.dealer, Right);
+        for wrong in [
+            three().hero_seat(Direction::North),
+            three().dealer(Across),
+            three().first_dealer(Across),
+        ] {
+            assert_eq!(wrong.build(), Err(RoundConfigError::SeatMismatch));
+        }
+        assert_eq!(
+            three()
+                .result(vec![(Hero, 0), (Right, 0), (Across, 0), (Left, 0)])
+                .build(),
+            Err(RoundConfigError::ResultSeats)
+        );
+        let bust = builder()
+            .result(vec![
+                (Hero, -1200),
+                (Right, 51200),
+                (Across, 25000),
+                (Left, 25000),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(bust.result.unwrap()[0], (Hero, -1200));
+        assert_eq!(
+            GameConfigBuilder::new()
+                .three_player()
+                .result(vec![(Hero, 0), (Left, 0), (Right, 0)])
+                .build(),
+            Err(GameConfigError::ResultSeats)
+        );
+    }
+
+    #[test]
+    fn relative_seats() {
+        use PlayerLocation::*;
+        assert_eq!(Left.next(), Hero);
+        assert_eq!(Hero.next(), Right);
+        assert_eq!(Hero.seen_from(Across), Across);
+        assert_eq!(Hero.seen_from(Left), Right);
+        assert_eq!(Left.seen_from(Hero), Left);
+        assert_eq!(Across.seen_from(Right), Right);
+        for seat in PlayerLocation::ALL {
+            assert_eq!(seat.seen_from(seat), Hero);
+            assert_eq!(seat.seen_from(seat.next()), Left);
+        }
+        let three = NumPlayers::Three;
+        assert_eq!(Right.next_in(three), Left);
+        assert_eq!(Left.next_in(three), Hero);
+        assert_eq!(Right.seen_from_in(Left, three), Left);
+        assert_eq!(Hero.seen_from_in(Left, three), Right);
+        for &seat in three.seats() {
+            assert_eq!(seat.seen_from_in(seat, three), Hero);
+            assert_eq!(seat.seen_from_in(seat.next_in(three), three), Left);
+        }
+    }
+
+    #[test]
+    fn rebased_perspective() {
+        use PlayerLocation::*;
+        let t = |s| tile::build(s).unwrap();
+        let round = Round {
+            config: RoundConfigBuilder::new()
+                .dealer(Right)
+                .hand(Hand::parse("123m456p789s1234z").unwrap())
+                .dora_indicator(t("1m"))
+                .result(vec![
+                    (Hero, 24000),
+                    (Right, 26000),
+                    (Across, 25000),
+                    (Left, 25000),
+                ])
+                .build()
+                .unwrap(),
+            events: vec![
+                RoundEvent::new(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                RoundEvent::new(Right, RoundAction::Discard(t("7z"))),
+                RoundEvent::new(
+                    Across,
+                    RoundAction::Pon(PonMeld::new([t("7z"); 3], t("7z"), Left).unwrap()),
+                ),
+                RoundEvent::new(Across, RoundAction::Discard(t("1p"))),
+                RoundEvent::new(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                RoundEvent::new(Left, RoundAction::Discard(t("2p"))),
+                RoundEvent::new(Hero, RoundAction::Draw(t("5z").into())),
+                RoundEvent::new(Hero, RoundAction::Discard(t("5z"))),
+            ],
+            annotation: Some(Annotation::new("Right deals")),
+        };
+        let game = Game {
+            config: GameConfigBuilder::new()
+                .players(["a", "b", "c", "d"])
+                .result(vec![(Hero, 0), (Right, 1), (Across, 2), (Left, 3)])
+                .build()
+                .unwrap(),
+            rounds: vec![round],
+            annotation: None,
+        };
+        let across = game.rebase_perspective(Across);
+        assert_eq!(across.rounds[0].annotation, game.rounds[0].annotation);
+        assert_eq!(
+            PlayerLocation::ALL.map(|seat| across.config.player_name(seat)),
+            ["c", "d", "a", "b"]
+        );
+        assert_eq!(
+            across.config.result,
+            Some(vec![(Hero, 2), (Right, 3), (Across, 0), (Left, 1)])
+        );
+        let round = &across.rounds[0];
+        assert_eq!(round.config.dealer, Left);
+        assert_eq!(round.config.initial_hero_hand_state, Hand::default());
+        assert_eq!(round.config.result.as_ref().unwrap()[0], (Hero, 25000));
+        let subjects: Vec<_> = round.events.iter().map(|e| e.subject).collect();
+        assert_eq!(
+            subjects,
+            [Left, Left, Hero, Hero, Right, Right, Across, Across]
+        );
+        assert_eq!(
+            round.events[6].action,
+            RoundAction::Draw(ObservedTile::Hidden)
+        );
+        assert_eq!(round.events[2].action, game.rounds[0].events[2].action);
+        let errors: Vec<_> = round.validate().into_iter().map(|e| e.kind).collect();
+        assert_eq!(errors, [crate::validate::ValidationErrorKind::Unfinished]);
+        assert_eq!(game.rebase_perspective(Hero), {
+            let mut same = game.clone();
+            same.rounds[0].config.initial_hero_hand_state = Hand::default();
+            same.rounds[0].events[6].action = RoundAction::Draw(ObservedTile::Hidden);
+            same
+        });
+    }
+
+    #[test]
+    fn equivalent_from_any_seat() {
+        use PlayerLocation::*;
+        let t = |s| tile::build(s).unwrap();
+        let round = Round {
+            config: RoundConfigBuilder::new()
+                .dealer(Left)
+                .hand(Hand::parse("123m456p789s1234z").unwrap())
+                .dora_indicator(t("1m"))
+                .build()
+                .unwrap(),
+            events: vec![
+                RoundEvent::new(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                RoundEvent::new(Left, RoundAction::Discard(t("7z"))),
+                RoundEvent::new(Hero, RoundAction::Draw(t("5z").into())),
+                RoundEvent::new(Hero, RoundAction::Discard(t("5z"))),
+            ],
+            annotation: None,
+        };
+        let game = Game {
+            config: GameConfigBuilder::new()
+                .players(["me", "b", "c", "d"])
+                .build()
+                .unwrap(),
+            rounds: vec![round],
+            annotation: None,
+        };
+        let mut across = game.rebase_perspective(Across);
+        across.config.players[Across.index()] = Player::new("a");
+        across.annotation = Some(Annotation::new("imported"));
+        assert!(game.equivalent(&across));
+        assert!(across.equivalent(&game));
+        let mut other = across.clone();
+        other.rounds[0].events[1].action = RoundAction::Discard(t("6z"));
+        assert!(!game.equivalent(&other));
+    }
+
+    #[test]
+    fn seat_winds() {
+        use PlayerLocation::*;
+        assert_eq!(Hero.seat_wind(Hero), Direction::East);
+        assert_eq!(Hero.seat_wind(Right), Direction::North);
+        assert_eq!(Across.seat_wind(Right), Direction::South);
+        assert_eq!(PlayerLocation::from_seat_wind(Direction::West, Left), Right);
+        for dealer in PlayerLocation::ALL {
+            for seat in PlayerLocation::ALL {
+                let wind = seat.seat_wind(dealer);
+                assert_eq!(PlayerLocation::from_seat_wind(wind, dealer), seat);
+            }
+        }
+        let three = NumPlayers::Three;
+        assert_eq!(Left.seat_wind_in(Right, three), Direction::South);
+        assert_eq!(Hero.seat_wind_in(Right, three), Direction::West);
+        assert_eq!(
+            PlayerLocation::from_seat_wind_in(Direction::South, Left, three),
+            Some(Hero)
+        );
+        assert_eq!(
+            PlayerLocation::from_seat_wind_in(Direction::North, Hero, three),
+            None
+        );
+        assert_eq!(
+            PlayerLocation::from_seat_wind_in(Direction::East, Across, three),
+            None
+        );
+    }
+
+    #[test]
+    fn nuki_dora_counts_kita_per_player() {
+        let event = RoundEvent::new;
+        let round = Round {
+            config: RoundConfig {
+                wind: Direction::East,
+                number: RoundNumber::One,
+                repeat: 0,
+                dealer: PlayerLocation::Hero,
+                dora: vec![],
+                ura_dora: vec![],
+                initial_hero_hand_state: Hand::default(),
+                result: None,
+                wall: Default::default(),
+            },
+            events: vec![
+                event(PlayerLocation::Hero, RoundAction::Draw(tile::NORTH.into())),
+                event(PlayerLocation::Hero, RoundAction::Kita),
+                event(
+                    PlayerLocation::Hero,
+                    RoundAction::Draw(ObservedTile::Hidden),
+                ),
+                event(PlayerLocation::Hero, RoundAction::Kita),
+                event(PlayerLocation::Right, RoundAction::Kita),
+            ],
+            annotation: None,
+        };
+        assert_eq!(round.nuki_dora(PlayerLocation::Hero), 2);
+        assert_eq!(round.nuki_dora(PlayerLocation::Right), 1);
+        assert_eq!(round.nuki_dora(PlayerLocation::Left), 0);
+    }
+
+    #[test]
+    fn nagashi_mangan_needs_uncalled_terminal_discards() {
+        use PlayerLocation::*;
+        let t = |s| tile::build(s).unwrap();
+        let discard = |subject, s| RoundEvent::new(subject, RoundAction::Discard(t(s)));
+        let pon = PonMeld::new([t("1z"); 3], t("1z"), Right).unwrap();
+        let mut round = Round {
+            config: RoundConfig {
+                wind: Direction::East,
+                number: RoundNumber::One,
+                repeat: 0,
+                dealer: Hero,
+                dora: vec![],
+                ura_dora: vec![],
+                initial_hero_hand_state: Hand::default(),
+                result: None,
+                wall: Default::default(),
+            },
+            events: vec![
+                discard(Hero, "1m"),
+                discard(Right, "1z"),
+                RoundEvent::new(Across, RoundAction::Pon(pon)),
+                discard(Across, "9p"),
+                discard(Left, "5p"),
+                discard(Hero, "7z"),
+                RoundEvent::new(Hero, RoundAction::Exhaustive(vec![])),
+            ],
+            annotation: None,
+        };
+        let rules = RuleSet::default();
+        assert_eq!(round.nagashi_mangan(&rules), vec![Hero, Across]);
+        let off = RuleSet {
+            nagashi_mangan: false,
+            ..rules.clone()
+        };
+        assert!(round.nagashi_mangan(&off).is_empty());
+        assert_eq!(round.tenpai_players(), Some(vec![]));
+        round.events.pop();
+        assert!(round.nagashi_mangan(&rules).is_empty());
+        assert_eq!(round.tenpai_players(), None);
+    }
+
+    #[test]
+    fn events_map_to_turns() {
+        use PlayerLocation::*;
+        let t = |s| tile::build(s).unwrap();
+        let round = Round {
+            config: RoundConfigBuilder::new()
+                .hand(Hand::parse("123m456p789s1234z").unwrap())
+                .dora_indicator(t("1m"))
+                .build()
+                .unwrap(),
+            events: vec![
+                RoundEvent::new(Hero, RoundAction::Draw(t("1z").into())),
+                RoundEvent::new(Hero, RoundAction::Discard(t("1z"))),
+                RoundEvent::new(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                RoundEvent::new(Right, RoundAction::Discard(t("7z"))),
+                RoundEvent::new(
+                    Left,
+                    RoundAction::Pon(PonMeld::new([t("7z"); 3], t("7z"), Left).unwrap()),
+                ),
+                RoundEvent::new(Left, RoundAction::Discard(t("9m"))),
+                RoundEvent::new(Hero, RoundAction::Draw(t("2z").into())),
+                RoundEvent::new(Hero, RoundAction::Riichi),
+                RoundEvent::new(Hero, RoundAction::Discard(t("2z"))),
+            ],
+            annotation: None,
+        };
+        let turn = |player, number| Turn { player, number };
+        assert_eq!(round.turn_at(0), Some(turn(Hero, 1)));
+        assert_eq!(round.turn_at(4), Some(turn(Left, 1)));
+        assert_eq!(round.turn_at(7), Some(turn(Hero, 2)));
+        assert_eq!(round.turn_at(9), None);
+        assert_eq!(round.discard_index(turn(Hero, 2)), Some(8));
+        assert_eq!(round.discard_index(turn(Right, 1)), Some(3));
+        assert_eq!(round.discard_index(turn(Across, 1)), None);
+        assert_eq!(round.turn_start(1), Some(0));
+        assert_eq!(round.turn_start(2), Some(6));
+        assert_eq!(round.turn_start(3), None);
+        assert_eq!(turn(Hero, 8).to_string(), "Hero, turn 8");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn events_serialize_tiles_as_shorthand() {
+        use crate::tile::build;
+        use chrono::TimeZone;
+
+        let event = RoundEvent::new(
+            PlayerLocation::Right,
+            RoundAction::Discard(build("7p").unwrap()),
+        );
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"subject":"Right","action":{"Discard":"7p"}}"#);
+        assert_eq!(serde_json::from_str::<RoundEvent>(&json).unwrap(), event);
+        let timed = event
+            .with_elapsed(Duration::from_millis(1500))
+            .with_time(Local.timestamp_opt(1_700_000_000, 0).unwrap());
+        let json = serde_json::to_string(&timed).unwrap();
+        assert_eq!(serde_json::from_str::<RoundEvent>(&json).unwrap(), timed);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn games_serialize_with_their_schema_version() {
+        let game = Game {
+            annotation: Some(Annotation::new("league final")),
+            ..Game::default()
+        };
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["schema_version"], Game::SCHEMA_VERSION);
+        assert_eq!(json["rounds"], serde_json::json!([]));
+        assert_eq!(json["annotation"]["comment"], "league final");
+        assert_eq!(serde_json::from_value::<Game>(json.clone()).unwrap(), game);
+        let mut unversioned = json.clone();
+        unversioned
+            .as_object_mut()
+            .unwrap()
+            .remove("schema_version");
+        assert_eq!(serde_json::from_value::<Game>(unversioned).unwrap(), game);
+        let mut newer = json;
+        newer["schema_version"] = (Game::SCHEMA_VERSION + 1).into();
+        let err = serde_json::from_value::<Game>(newer).unwrap_err();
+        assert!(err.to_string().contains("schema version 2"));
+    }
+
+    /// The game saved in `tests/fixtures/game-v1.json`: the Hero deals,
+    /// declares riichi with a reviewed discard and rons the tile discarded
+    /// after a pon.
+    #[cfg(feature = "serde")]
+    fn saved_game() -> Game {
+        use chrono::TimeZone;
+
+        use crate::annotation::Tag;
+        use crate::hand::PonMeld;
+        use crate::scoring::{DoraCount, Payment, WinDetails, WinType};
+        use crate::yaku::Yaku;
+
+        let t = |s| tile::build(s).unwrap();
+        let config = GameConfigBuilder::new()
+            .event("Riichi Open 2024")
+            .site("Tenhou")
+            .date(Local.timestamp_opt(1_714_561_200, 0).unwrap())
+            .players(["Alice", "Bob", "Carol", "Dave"])
+            .one_round()
+            .rules(RuleSet::tenhou())
+            .result(vec![
+                (PlayerLocation::Hero, 32700),
+                (PlayerLocation::Right, 25000),
+                (PlayerLocation::Across, 17300),
+                (PlayerLocation::Left, 25000),
+            ])
+            .build()
+            .unwrap();
+        let pon = PonMeld::new([t("6z"); 3], t("6z"), PlayerLocation::Left).unwrap();
+        let hand = Hand::parse("234m406p789s11444z").unwrap();
+        let details = WinDetails {
+            winning_tile: t("4z"),
+            yaku: vec![(Yaku::Riichi, 1)],
+            fu: 40,
+            dora: DoraCount {
+                aka_dora: 1,
+                ura_dora: 1,
+                ..DoraCount::default()
+            },
+            payment: Payment {
+                win_type: WinType::Ron,
+                dealer: true,
+                from_discarder: 7700,
+                from_dealer: 0,
+                from_non_dealer: 0,
+                deposits: 1000,
+                limit: None,
+            },
+        };
+        let round = Round {
+            config: RoundConfigBuilder::new()
+                .dora_indicator(t("9s"))
+                .ura_dora_indicator(t("2m"))
+                .hand(Hand::parse("234m4069p789s114z").unwrap())
+                .build()
+                .unwrap(),
+            events: vec![
+                RoundEvent::new(PlayerLocation::Hero, RoundAction::Draw(t("4z").into())),
+                RoundEvent::new(PlayerLocation::Hero, RoundAction::Riichi),
+                RoundEvent::new(PlayerLocation::Hero, RoundAction::Discard(t("9p")))
+                    .with_tsumogiri(false)
+                    .with_elapsed(Duration::from_millis(2500))
+                    .with_annotation(Annotation {
+                        comment: "Riichi or dama?".to_string(),
+                        tags: vec![Tag::Dubious],
+                    }),
+                RoundEvent::new(
+                    PlayerLocation::Right,
+                    RoundAction::Draw(ObservedTile::Hidden),
+                ),
+                RoundEvent::new(PlayerLocation::Right, RoundAction::Discard(t("6z"))),
+                RoundEvent::new(PlayerLocation::Across, RoundAction::Pon(pon)),
+                RoundEvent::new(PlayerLocation::Across, RoundAction::Discard(t("4z"))),
+                RoundEvent::new(PlayerLocation::Hero, RoundAction::Ron(hand, Some(details))),
+            ],
+            annotation: None,
+        };
+        Game {
+            config,
+            rounds: vec![round],
+            annotation: Some(Annotation::new("league final")),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn reads_the_saved_version_1_file() {
+        let saved = include_str!("../tests/fixtures/game-v1.json");
+        let game: Game = serde_json::from_str(saved).unwrap();
+        assert_eq!(game, saved_game());
+        assert!(game.validate().is_empty(), "{:?}", game.validate());
+
+        // Written back, only the date's offset can differ, with the time zone.
+        let saved: serde_json::Value = serde_json::from_str(saved).unwrap();
+        let mut written = serde_json::to_value(&game).unwrap();
+        written["config"]["date"] = saved["config"]["date"].clone();
+        assert_eq!(written, saved);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn misspelled_riichi_still_deserializes() {
+        let action: RoundAction = serde_json::from_str(r#""Richii""#).unwrap();
+        assert_eq!(action, RoundAction::Riichi);
+        assert_eq!(serde_json::to_string(&action).unwrap(), r#""Riichi""#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn wins_serialize_with_their_details() {
+        use crate::scoring::{score, DoraCount, WinType};
+        use crate::yaku::Yaku;
+
+        let hand = Hand::parse("123m456p789s11z").unwrap();
+        let details = WinDetails {
+            winning_tile: tile::build("1z").unwrap(),
+            yaku: vec![(Yaku::Riichi, 1)],
+            fu: 40,
+            dora: DoraCount::default(),
+            payment: score(1, 40, false, WinType::Ron),
+        };
+        for action in [
+            RoundAction::Ron(hand.clone(), None),
+            RoundAction::Ron(hand.clone(), Some(details)),
+        ] {
+            let json = serde_json::to_string(&action).unwrap();
+            assert_eq!(serde_json::from_str::<RoundAction>(&json).unwrap(), action);
+        }
+    }
+
+    #[test]
+    fn events_display_as_lines() {
+        let t = |s: &str| tile::build(s).unwrap();
+        let line = |subject, action| RoundEvent::new(subject, action).to_string();
+        assert_eq!(
+            line(PlayerLocation::Right, RoundAction::Discard(t("7p"))),
+            "Right discards 7p"
+        );
+        assert_eq!(
+            line(PlayerLocation::Hero, RoundAction::Draw(t("0s").into())),
+            "Hero draws 0s"
+        );
+        assert_eq!(
+            line(
+                PlayerLocation::Left,
+                RoundAction::Draw(ObservedTile::Hidden)
+            ),
+            "Left draws"
+        );
+        let chii = ChiiMeld::from_called(t("4p"), [t("3p"), t("5p")]).unwrap();
+        assert_eq!(
+            line(PlayerLocation::Hero, RoundAction::Chii(chii)),
+            "Hero chii 4p from Left (3p4p5p)"
+        );
+        let kan = AddedKanMeld::new(
+            PonMeld::new([t("1z"); 3], t("1z"), PlayerLocation::Across).unwrap(),
+            t("1z"),
+        )
+        .unwrap();
+        assert_eq!(
+            line(PlayerLocation::Hero, RoundAction::AddedKan(kan)),
+            "Hero added kan 1z (1z1z1z1z)"
+        );
+        assert_eq!(
+            line(
+                PlayerLocation::Across,
+                RoundAction::Tsumo(Hand::default(), None)
+            ),
+            "Across tsumo"
+        );
+        assert_eq!(
+            line(
+                PlayerLocation::Hero,
+                RoundAction::Ron(Hand::parse("123m456p789s11z55z").unwrap(), None)
+            ),
+            "Hero ron (123m456p789s1155z)"
+        );
+        assert_eq!(
+            line(
+                PlayerLocation::Left,
+                RoundAction::AbortiveDraw(AbortiveDraw::KyuushuKyuuhai)
+            ),
+            "Left abortive draw (kyuushu kyuuhai)"
+        );
+    }
+}
@@ -0,0 +1,879 @@
+//! Hands: concealed tiles plus the melds a player has called or declared.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::PlayerLocation;
+use crate::tile::{self, MahjongTile, Tile, Tile34, TileParseError};
+
+mod calls;
+mod decompose;
+mod riichi;
+mod shanten;
+mod ukeire;
+mod waits;
+
+pub use decompose::{Group, GroupKind, HandDecomposition, WaitKind};
+pub use riichi::{RIICHI_DEPOSIT, RIICHI_MIN_WALL_TILES};
+pub use ukeire::DiscardOption;
+
+/// A player's hand.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hand {
+    /// Tiles held in hand, not part of any meld.
+    pub concealed: Vec<MahjongTile>,
+    /// Called and declared melds, in the order they were made.
+    pub melds: Vec<Meld>,
+}
+
+impl Hand {
+    /// A hand of `concealed` tiles and `melds`, checked with
+    /// [`Hand::validate`].
+    pub fn new(concealed: Vec<MahjongTile>, melds: Vec<Meld>) -> Result<Hand, HandError> {
+        let hand = Hand { concealed, melds };
+        hand.validate()?;
+        Ok(hand)
+    }
+
+    /// Checks that the hand could be held at a table: 13 or 14 tiles
+    /// counting each meld as three, no more than four copies of any tile
+    /// and only valid melds.
+    pub fn validate(&self) -> Result<(), HandError> {
+        let size = self.concealed.len() + 3 * self.melds.len();
+        if size != 13 && size != 14 {
+            return Err(HandError::WrongTileCount(size));
+        }
+        for meld in &self.melds {
+            meld.validate().map_err(HandError::InvalidMeld)?;
+        }
+        let counts = tile_counts(&self.tiles());
+        if let Some(i) = counts.iter().position(|&c| c > 4) {
+            let tile =
+                MahjongTile::try_from(Tile34(i as u8)).expect("index is below Tile34::COUNT");
+            return Err(HandError::TooManyCopies(tile));
+        }
+        Ok(())
+    }
+
+    /// Parses a hand written as concealed tiles followed by melds, separated
+    /// by `+`, e.g. `"234m567p11z + p555s(right) + ck9999m"`.
+    ///
+    /// Each meld is a kind prefix followed by its tiles in MPSZ shorthand:
+    /// `c` for chii, `p` for pon, `k` for an open kan, `ck` for a closed kan
+    /// and `ak` for an added kan. The first tile written is the called one;
+    /// for an added kan the last tile written is the one that was added.
+    /// Called melds may end with the source in parentheses, `(left)`,
+    /// `(across)` or `(right)`, which defaults to left. The concealed part
+    /// may be empty.
+    pub fn parse(s: &str) -> Result<Hand, HandParseError> {
+        let mut groups = s.split('+');
+        let concealed = tile::parse_tiles(groups.next().unwrap_or_default())?;
+        let melds = groups.map(parse_meld).collect::<Result<_, _>>()?;
+        Ok(Hand { concealed, melds })
+    }
+}
+
+impl Hand {
+    /// Every tile in the hand: the concealed tiles followed by the tiles of
+    /// each meld.
+    pub fn tiles(&self) -> Vec<MahjongTile> {
+        let mut tiles = self.concealed.clone();
+        for meld in &self.melds {
+            tiles.extend(meld.tiles());
+        }
+        tiles
+    }
+}
+
+/// Why a hand could not be held at a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandError {
+    /// The hand holds this many tiles, counting melds as three, instead of
+    /// 13 or 14.
+    WrongTileCount(usize),
+    /// More than four copies of this tile, counting red fives as fives.
+    TooManyCopies(MahjongTile),
+    InvalidMeld(MeldError),
+}
+
+impl fmt::Display for HandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandError::WrongTileCount(n) => {
+                write!(f, "hand holds {} tiles, expected 13 or 14", n)
+            }
+            HandError::TooManyCopies(tile) => write!(f, "more than four copies of {}", tile),
+            HandError::InvalidMeld(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for HandError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HandError::InvalidMeld(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Builds a [`Hand`] a tile or meld at a time, checking it on
+/// [`HandBuilder::build`].
+#[derive(Debug, Clone, Default)]
+pub struct HandBuilder {
+    concealed: Vec<MahjongTile>,
+    melds: Vec<Meld>,
+}
+
+impl HandBuilder {
+    pub fn new() -> HandBuilder {
+        HandBuilder::default()
+    }
+
+    /// Adds a concealed tile.
+    pub fn tile(mut self, tile: MahjongTile) -> HandBuilder {
+        self.concealed.push(tile);
+        self
+    }
+
+    /// Adds several concealed tiles.
+    pub fn tiles(mut self, tiles: impl IntoIterator<Item = MahjongTile>) -> HandBuilder {
+        self.concealed.extend(tiles);
+        self
+    }
+
+    /// Adds a meld after those already added.
+    pub fn meld(mut self, meld: Meld) -> HandBuilder {
+        self.melds.push(meld);
+        self
+    }
+
+    pub fn build(self) -> Result<Hand, HandError> {
+        Hand::new(self.concealed, self.melds)
+    }
+}
+
+/// Writes the hand in the notation read by [`Hand::parse`], with the
+/// concealed tiles sorted.
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&tile::format_tiles(&self.concealed))?;
+        for meld in &self.melds {
+            write!(f, " + {}", meld)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Hand {
+    type Err = HandParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Hand::parse(s)
+    }
+}
+
+/// How many of each [`Tile34`] kind `tiles` holds, counting red fives as
+/// plain fives.
+pub(crate) fn tile_counts(tiles: &[MahjongTile]) -> [u8; Tile34::COUNT] {
+    let mut counts = [0; Tile34::COUNT];
+    for tile in tiles {
+        counts[Tile34::from(tile).index()] += 1;
+    }
+    counts
+}
+
+fn parse_meld(group: &str) -> Result<Meld, HandParseError> {
+    let group = group.trim();
+    let (body, source) = match group.strip_suffix(')').and_then(|g| g.split_once('(')) {
+        Some((body, source)) => (body, Some(parse_source(source)?)),
+        None => (group, None),
+    };
+    let (kind, tiles) = ["ck", "ak", "c", "p", "k"]
+        .into_iter()
+        .find_map(|prefix| Some((prefix, body.strip_prefix(prefix)?)))
+        .ok_or_else(|| HandParseError::UnknownMeld(group.to_string()))?;
+    let mut tiles = tile::parse_tiles(tiles)?;
+    let expected = if kind.ends_with('k') { 4 } else { 3 };
+    if tiles.len() != expected {
+        return Err(HandParseError::WrongTileCount {
+            expected,
+            found: tiles.len(),
+        });
+    }
+    let called = tiles[0];
+    let source = match (kind, source) {
+        ("ck", Some(_)) => return Err(HandParseError::InvalidSource(group.to_string())),
+        ("c", Some(source)) if source != PlayerLocation::Left => {
+            return Err(HandParseError::InvalidSource(group.to_string()))
+        }
+        (_, source) => source.unwrap_or(PlayerLocation::Left),
+    };
+    let added = if kind == "ak" { tiles.pop() } else { None };
+    tile::sort_hand(&mut tiles);
+
+    let meld = match (kind, added) {
+        ("c", _) => Meld::Chii(ChiiMeld {
+            tiles: [tiles[0], tiles[1], tiles[2]],
+            chii_tile: called,
+            source,
+        }),
+        ("p", _) => Meld::Pon(PonMeld {
+            tiles: [tiles[0], tiles[1], tiles[2]],
+            pon_tile: called,
+            source,
+        }),
+        ("k", _) => Meld::OpenKan(OpenKanMeld {
+            tiles: [tiles[0], tiles[1], tiles[2], tiles[3]],
+            kan_tile: called,
+            source,
+        }),
+        ("ck", _) => Meld::ClosedKan(ClosedKanMeld {
+            tiles: [tiles[0], tiles[1], tiles[2], tiles[3]],
+        }),
+        (_, added) => Meld::AddedKan(AddedKanMeld {
+            pon: PonMeld {
+                tiles: [tiles[0], tiles[1], tiles[2]],
+                pon_tile: called,
+                source,
+            },
+            added_tile: added.expect("added kans keep their fourth tile"),
+        }),
+    };
+    meld.validate()
+        .map_err(|_| HandParseError::InvalidMeld(group.to_string()))?;
+    Ok(meld)
+}
+
+fn parse_source(s: &str) -> Result<PlayerLocation, HandParseError> {
+    match s.trim() {
+        "left" => Ok(PlayerLocation::Left),
+        "across" => Ok(PlayerLocation::Across),
+        "right" => Ok(PlayerLocation::Right),
+        other => Err(HandParseError::InvalidSource(other.to_string())),
+    }
+}
+
+/// Why a hand string could not be parsed by [`Hand::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandParseError {
+    /// The tiles themselves were malformed.
+    Tile(TileParseError),
+    /// A meld group did not start with a known kind prefix.
+    UnknownMeld(String),
+    /// A meld had the wrong number of tiles for its kind.
+    WrongTileCount { expected: usize, found: usize },
+    /// A meld's tiles do not form a sequence, triplet or quad as its kind
+    /// requires.
+    InvalidMeld(String),
+    /// A source was not `left`, `across` or `right`, or was not allowed for
+    /// the meld: chii is always from the left and closed kans have none.
+    InvalidSource(String),
+}
+
+impl fmt::Display for HandParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandParseError::Tile(e) => e.fmt(f),
+            HandParseError::UnknownMeld(s) => write!(f, "{:?} is not a known meld kind", s),
+            HandParseError::WrongTileCount { expected, found } => {
+                write!(f, "meld needs {} tiles, got {}", expected, found)
+            }
+            HandParseError::InvalidMeld(s) => write!(f, "{:?} is not a valid meld", s),
+            HandParseError::InvalidSource(s) => write!(f, "{:?} is not a valid meld source", s),
+        }
+    }
+}
+
+impl std::error::Error for HandParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HandParseError::Tile(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TileParseError> for HandParseError {
+    fn from(e: TileParseError) -> Self {
+        HandParseError::Tile(e)
+    }
+}
+
+/// Any meld that sits face up (or, for closed kans, face down) beside a hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Meld {
+    Chii(ChiiMeld),
+    Pon(PonMeld),
+    OpenKan(OpenKanMeld),
+    ClosedKan(ClosedKanMeld),
+    AddedKan(AddedKanMeld),
+}
+
+impl Meld {
+    /// The tiles making up the meld; for an added kan, the pon's tiles then
+    /// the added tile.
+    pub fn tiles(&self) -> Vec<MahjongTile> {
+        match self {
+            Meld::Chii(meld) => meld.tiles.to_vec(),
+            Meld::Pon(meld) => meld.tiles.to_vec(),
+            Meld::OpenKan(meld) => meld.tiles.to_vec(),
+            Meld::ClosedKan(meld) => meld.tiles.to_vec(),
+            Meld::AddedKan(meld) => {
+                let mut tiles = meld.pon.tiles.to_vec();
+                tiles.push(meld.added_tile);
+                tiles
+            }
+        }
+    }
+}
+
+impl Meld {
+    /// Checks that the meld could exist at a table: see the `new`
+    /// constructor of each meld type for the rules.
+    pub fn validate(&self) -> Result<(), MeldError> {
+        match self {
+            Meld::Chii(meld) => meld.validate(),
+            Meld::Pon(meld) => meld.validate(),
+            Meld::OpenKan(meld) => meld.validate(),
+            Meld::ClosedKan(meld) => meld.validate(),
+            Meld::AddedKan(meld) => meld.validate(),
+        }
+    }
+}
+
+impl ChiiMeld {
+    /// A chii of `tiles`, which must be three consecutive number tiles of
+    /// one suit including `chii_tile`. Chii is always called from the left.
+    pub fn new(tiles: [MahjongTile; 3], chii_tile: MahjongTile) -> Result<ChiiMeld, MeldError> {
+        let meld = ChiiMeld {
+            tiles,
+            chii_tile,
+            source: PlayerLocation::Left,
+        };
+        meld.validate()?;
+        Ok(meld)
+    }
+
+    /// A chii of `chii_tile` using the two `hand_tiles`, which need not be
+    /// in order. The meld's tiles are sorted and checked as for
+    /// [`ChiiMeld::new`].
+    pub fn from_called(
+        chii_tile: MahjongTile,
+        hand_tiles: [MahjongTile; 2],
+    ) -> Result<ChiiMeld, MeldError> {
+        let mut tiles = [chii_tile, hand_tiles[0], hand_tiles[1]];
+        tile::sort_hand(&mut tiles);
+        ChiiMeld::new(tiles, chii_tile)
+    }
+
+    /// Checks the rules enforced by [`ChiiMeld::new`].
+    pub fn validate(&self) -> Result<(), MeldError> {
+        let mut tiles = self.tiles;
+        tile::sort_hand(&mut tiles);
+        let consecutive = |a: &MahjongTile, b: &MahjongTile| a.succ().is_some_and(|t| t.eq_rank(b));
+        if !(consecutive(&tiles[0], &tiles[1]) && consecutive(&tiles[1], &tiles[2])) {
+            return Err(MeldError::NotASequence);
+        }
+        check_called(&self.tiles, self.chii_tile)?;
+        if self.source != PlayerLocation::Left {
+            return Err(MeldError::InvalidSource(self.source));
+        }
+        Ok(())
+    }
+}
+
+impl PonMeld {
+    /// A pon of three rank-equal `tiles`, including `pon_tile`, called from
+    /// another player.
+    pub fn new(
+        tiles: [MahjongTile; 3],
+        pon_tile: MahjongTile,
+        source: PlayerLocation,
+    ) -> Result<PonMeld, MeldError> {
+        let meld = PonMeld {
+            tiles,
+            pon_tile,
+            source,
+        };
+        meld.validate()?;
+        Ok(meld)
+    }
+
+    /// Checks the rules enforced by [`PonMeld::new`].
+    pub fn validate(&self) -> Result<(), MeldError> {
+        check_same_rank(&self.tiles)?;
+        check_called(&self.tiles, self.pon_tile)?;
+        check_other_player(self.source)
+    }
+}
+
+impl OpenKanMeld {
+    /// An open kan of four rank-equal `tiles`, including `kan_tile`, called
+    /// from another player.
+    pub fn new(
+        tiles: [MahjongTile; 4],
+        kan_tile: MahjongTile,
+        source: PlayerLocation,
+    ) -> Result<OpenKanMeld, MeldError> {
+        let meld = OpenKanMeld {
+            tiles,
+            kan_tile,
+            source,
+        };
+        meld.validate()?;
+        Ok(meld)
+    }
+
+    /// Checks the rules enforced by [`OpenKanMeld::new`].
+    pub fn validate(&self) -> Result<(), MeldError> {
+        check_same_rank(&self.tiles)?;
+        check_called(&self.tiles, self.kan_tile)?;
+        check_other_player(self.source)
+    }
+}
+
+impl ClosedKanMeld {
+    /// A closed kan of four rank-equal `tiles`.
+    pub fn new(tiles: [MahjongTile; 4]) -> Result<ClosedKanMeld, MeldError> {
+        let meld = ClosedKanMeld { tiles };
+        meld.validate()?;
+        Ok(meld)
+    }
+
+    /// Checks the rules enforced by [`ClosedKanMeld::new`].
+    pub fn validate(&self) -> Result<(), MeldError> {
+        check_same_rank(&self.tiles)
+    }
+}
+
+impl AddedKanMeld {
+    /// An added kan made by adding `added_tile` to a valid `pon` of the same
+    /// rank.
+    pub fn new(pon: PonMeld, added_tile: MahjongTile) -> Result<AddedKanMeld, MeldError> {
+        let meld = AddedKanMeld { pon, added_tile };
+        meld.validate()?;
+        Ok(meld)
+    }
+
+    /// Checks the rules enforced by [`AddedKanMeld::new`].
+    pub fn validate(&self) -> Result<(), MeldError> {
+        self.pon.validate()?;
+        if !self.added_tile.eq_rank(&self.pon.pon_tile) {
+            return Err(MeldError::MismatchedRanks);
+        }
+        Ok(())
+    }
+}
+
+fn check_same_rank(tiles: &[MahjongTile]) -> Result<(), MeldError> {
+    if tiles.iter().all(|t| t.eq_rank(&tiles[0])) {
+        Ok(())
+    } else {
+        Err(MeldError::MismatchedRanks)
+    }
+}
+
+fn check_called(tiles: &[MahjongTile], called: MahjongTile) -> Result<(), MeldError> {
+    if tiles.contains(&called) {
+        Ok(())
+    } else {
+        Err(MeldError::CalledTileMissing(called))
+    }
+}
+
+fn check_other_player(source: PlayerLocation) -> Result<(), MeldError> {
+    if source == PlayerLocation::Hero {
+        Err(MeldError::InvalidSource(source))
+    } else {
+        Ok(())
+    }
+}
+
+/// Why a meld could not exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeldError {
+    /// A chii's tiles are not three consecutive number tiles of one suit.
+    NotASequence,
+    /// A pon or kan mixes tiles of different ranks.
+    MismatchedRanks,
+    /// The called tile is not one of the meld's tiles.
+    CalledTileMissing(MahjongTile),
+    /// The tile was called from a player it can't come from: chii only
+    /// from the left, and nothing from the caller themself.
+    InvalidSource(PlayerLocation),
+}
+
+impl fmt::Display for MeldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeldError::NotASequence => write!(f, "chii tiles are not a sequence"),
+            MeldError::MismatchedRanks => write!(f, "meld tiles are not all the same tile"),
+            MeldError::CalledTileMissing(tile) => {
+                write!(f, "called tile {} is not in the meld", tile)
+            }
+            MeldError::InvalidSource(source) => {
+                write!(f, "meld cannot be called from {:?}", source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeldError {}
+
+impl fmt::Display for Meld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Meld::Chii(meld) => meld.fmt(f),
+            Meld::Pon(meld) => meld.fmt(f),
+            Meld::OpenKan(meld) => meld.fmt(f),
+            Meld::ClosedKan(meld) => meld.fmt(f),
+            Meld::AddedKan(meld) => meld.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for ChiiMeld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("c")?;
+        write_meld_tiles(f, &self.tiles, self.chii_tile, None)
+    }
+}
+
+impl fmt::Display for PonMeld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("p")?;
+        write_meld_tiles(f, &self.tiles, self.pon_tile, None)?;
+        write_source(f, self.source)
+    }
+}
+
+impl fmt::Display for OpenKanMeld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("k")?;
+        write_meld_tiles(f, &self.tiles, self.kan_tile, None)?;
+        write_source(f, self.source)
+    }
+}
+
+/// Writes the kan as a `ck` group, e.g. `ck9999m`, rather than the
+/// face-down `--X X--` form, so that a displayed [`Hand`] parses back with
+/// [`Hand::parse`].
+impl fmt::Display for ClosedKanMeld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ck")?;
+        write_meld_tiles(f, &self.tiles, self.tiles[0], None)
+    }
+}
+
+impl fmt::Display for AddedKanMeld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ak")?;
+        write_meld_tiles(f, &self.pon.tiles, self.pon.pon_tile, Some(self.added_tile))?;
+        write_source(f, self.pon.source)
+    }
+}
+
+/// Writes a meld's tiles as one shorthand group: the called tile first,
+/// then the others in order, then the tile added to a kan.
+fn write_meld_tiles(
+    f: &mut fmt::Formatter,
+    tiles: &[MahjongTile],
+    called: MahjongTile,
+    added: Option<MahjongTile>,
+) -> fmt::Result {
+    let mut rest = tiles.to_vec();
+    if let Some(pos) = rest.iter().position(|t| *t == called) {
+        rest.remove(pos);
+    }
+    tile::sort_hand(&mut rest);
+    for tile in std::iter::once(called).chain(rest).chain(added) {
+        let notation = tile.to_string();
+        f.write_str(&notation[..1])?;
+    }
+    write!(f, "{}", called.suit())
+}
+
+fn write_source(f: &mut fmt::Formatter, source: PlayerLocation) -> fmt::Result {
+    let name = match source {
+        PlayerLocation::Left => "left",
+        PlayerLocation::Across => "across",
+        PlayerLocation::Right => "right",
+        PlayerLocation::Hero => "hero",
+    };
+    write!(f, "({})", name)
+}
+
+/// A sequence called from the player on the left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChiiMeld {
+    /// All three tiles of the sequence, including the called tile.
+    pub tiles: [MahjongTile; 3],
+    /// The discarded tile that was called.
+    pub chii_tile: MahjongTile,
+    /// The player the tile was called from.
+    pub source: PlayerLocation,
+}
+
+/// A triplet called from another player's discard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PonMeld {
+    /// All three tiles of the triplet, including the called tile.
+    pub tiles: [MahjongTile; 3],
+    /// The discarded tile that was called.
+    pub pon_tile: MahjongTile,
+    /// The player the tile was called from.
+    pub source: PlayerLocation,
+}
+
+/// A quad formed by calling a discard onto a concealed triplet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenKanMeld {
+    /// All four tiles of the quad, including the called tile.
+    pub tiles: [MahjongTile; 4],
+    /// The discarded tile that was called.
+    pub kan_tile: MahjongTile,
+    /// The player the tile was called from.
+    pub source: PlayerLocation,
+}
+
+/// A quad declared from four concealed tiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClosedKanMeld {
+    pub tiles: [MahjongTile; 4],
+}
+
+/// A quad formed by adding the fourth tile to an existing pon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddedKanMeld {
+    /// The pon the tile was added to.
+    pub pon: PonMeld,
+    /// The tile that was added.
+    pub added_tile: MahjongTile,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::{build, parse_tiles};
+
+    #[test]
+    fn parse_concealed_and_melds() {
+        let hand = Hand::parse("234m567p11z + p555s(right) + ck9999m").unwrap();
+        assert_eq!(hand.concealed, parse_tiles("234m567p11z").unwrap());
+        let five = build("5s").unwrap();
+        assert_eq!(
+            hand.melds,
+            vec![
+                Meld::Pon(PonMeld {
+                    tiles: [five; 3],
+                    pon_tile: five,
+                    source: PlayerLocation::Right,
+                }),
+                Meld::ClosedKan(ClosedKanMeld {
+                    tiles: [build("9m").unwrap(); 4],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_called_tile_is_written_first() {
+        let hand: Hand = "+c435m+ak0555p(across)".parse().unwrap();
+        assert!(hand.concealed.is_empty());
+        let Meld::Chii(chii) = &hand.melds[0] else {
+            panic!("expected a chii");
+        };
+        assert_eq!(chii.chii_tile, build("4m").unwrap());
+        assert_eq!(chii.tiles.to_vec(), parse_tiles("345m").unwrap());
+        let Meld::AddedKan(kan) = &hand.melds[1] else {
+            panic!("expected an added kan");
+        };
+        assert_eq!(kan.pon.pon_tile, build("0p").unwrap());
+        assert_eq!(kan.pon.source, PlayerLocation::Across);
+        assert_eq!(kan.added_tile, build("5p").unwrap());
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        for notation in [
+            "234m567p11z + p555s(right) + ck9999m",
+            "11z + c435m + k5555p(across) + ak0555s(left)",
+            " + c768s + p777z(left) + p111z(right) + c312p",
+            "1112345678999m",
+            "",
+        ] {
+            let hand = Hand::parse(notation).unwrap();
+            assert_eq!(hand.to_string(), notation);
+            assert_eq!(Hand::parse(&hand.to_string()), Ok(hand));
+        }
+    }
+
+    #[test]
+    fn display_sorts_concealed_tiles() {
+        let hand = Hand::parse("5z321m + p505m").unwrap();
+        assert_eq!(hand.to_string(), "123m5z + p505m(left)");
+    }
+
+    #[test]
+    fn validate_melds() {
+        let t = |s| build(s).unwrap();
+        assert!(ChiiMeld::new([t("3p"), t("4p"), t("0p")], t("0p")).is_ok());
+        assert!(ChiiMeld::new([t("5p"), t("3p"), t("4p")], t("3p")).is_ok());
+        assert_eq!(
+            ChiiMeld::from_called(t("4p"), [t("0p"), t("3p")]),
+            Ok(ChiiMeld {
+                tiles: [t("3p"), t("4p"), t("0p")],
+                chii_tile: t("4p"),
+                source: PlayerLocation::Left,
+            })
+        );
+        assert_eq!(
+            ChiiMeld::from_called(t("4p"), [t("6p"), t("7p")]),
+            Err(MeldError::NotASequence)
+        );
+        assert_eq!(
+            ChiiMeld::new([t("1z"), t("2z"), t("3z")], t("1z")),
+            Err(MeldError::NotASequence)
+        );
+        assert_eq!(
+            ChiiMeld::new([t("8s"), t("9s"), t("1m")], t("1m")),
+            Err(MeldError::NotASequence)
+        );
+        assert_eq!(
+            ChiiMeld::new([t("3p"), t("4p"), t("5p")], t("0p")),
+            Err(MeldError::CalledTileMissing(t("0p")))
+        );
+        let chii = Meld::Chii(ChiiMeld {
+            tiles: [t("3p"), t("4p"), t("5p")],
+            chii_tile: t("3p"),
+            source: PlayerLocation::Across,
+        });
+        assert_eq!(
+            chii.validate(),
+            Err(MeldError::InvalidSource(PlayerLocation::Across))
+        );
+
+        assert!(PonMeld::new([t("5s"), t("0s"), t("5s")], t("0s"), PlayerLocation::Right).is_ok());
+        assert_eq!(
+            PonMeld::new([t("5s"), t("6s"), t("5s")], t("5s"), PlayerLocation::Right),
+            Err(MeldError::MismatchedRanks)
+        );
+        assert_eq!(
+            PonMeld::new([t("5s"); 3], t("5s"), PlayerLocation::Hero),
+            Err(MeldError::InvalidSource(PlayerLocation::Hero))
+        );
+        assert_eq!(
+            ClosedKanMeld::new([t("1z"), t("1z"), t("1z"), t("2z")]),
+            Err(MeldError::MismatchedRanks)
+        );
+        assert!(OpenKanMeld::new([t("9m"); 4], t("9m"), PlayerLocation::Left).is_ok());
+
+        let pon = PonMeld::new([t("7z"); 3], t("7z"), PlayerLocation::Across).unwrap();
+        assert!(AddedKanMeld::new(pon.clone(), t("7z")).is_ok());
+        assert_eq!(
+            AddedKanMeld::new(pon, t("6z")),
+            Err(MeldError::MismatchedRanks)
+        );
+    }
+
+    #[test]
+    fn checked_construction() {
+        let tiles = parse_tiles("123m456p789s1122z").unwrap();
+        assert!(Hand::new(tiles.clone(), vec![]).is_ok());
+        assert_eq!(
+            Hand::new(tiles[..12].to_vec(), vec![]),
+            Err(HandError::WrongTileCount(12))
+        );
+        let kan = ClosedKanMeld {
+            tiles: [build("1z").unwrap(); 4],
+        };
+        assert_eq!(
+            Hand::new(tiles[..10].to_vec(), vec![Meld::ClosedKan(kan)]),
+            Err(HandError::TooManyCopies(build("1z").unwrap()))
+        );
+        let bad = Meld::Pon(PonMeld {
+            tiles: [
+                build("1z").unwrap(),
+                build("2z").unwrap(),
+                build("1z").unwrap(),
+            ],
+            pon_tile: build("1z").unwrap(),
+            source: PlayerLocation::Right,
+        });
+        assert_eq!(
+            Hand::new(tiles[..10].to_vec(), vec![bad]),
+            Err(HandError::InvalidMeld(MeldError::MismatchedRanks))
+        );
+        assert_eq!(
+            Hand::new(parse_tiles("00555m123p456s11z").unwrap(), vec![]),
+            Err(HandError::TooManyCopies(build("5m").unwrap()))
+        );
+    }
+
+    #[test]
+    fn builder() {
+        let pon = Hand::parse("+p777z(across)").unwrap().melds.remove(0);
+        let hand = HandBuilder::new()
+            .tiles(parse_tiles("123m456p11z3s").unwrap())
+            .tile(build("4s").unwrap())
+            .meld(pon)
+            .build()
+            .unwrap();
+        assert_eq!(hand, Hand::parse("123m456p11z34s + p777z(across)").unwrap());
+        assert_eq!(
+            HandBuilder::new().build(),
+            Err(HandError::WrongTileCount(0))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Hand::parse("123x"),
+            Err(HandParseError::Tile(TileParseError::InvalidSuit('x')))
+        );
+        assert_eq!(
+            Hand::parse("1m + x123m"),
+            Err(HandParseError::UnknownMeld("x123m".to_string()))
+        );
+        assert_eq!(
+            Hand::parse("+p55s"),
+            Err(HandParseError::WrongTileCount {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Hand::parse("+c135m"),
+            Err(HandParseError::InvalidMeld("c135m".to_string()))
+        );
+        assert_eq!(
+            Hand::parse("+c891p"),
+            Err(HandParseError::InvalidMeld("c891p".to_string()))
+        );
+        assert_eq!(
+            Hand::parse("+c123z"),
+            Err(HandParseError::InvalidMeld("c123z".to_string()))
+        );
+        assert_eq!(
+            Hand::parse("+p556s"),
+            Err(HandParseError::InvalidMeld("p556s".to_string()))
+        );
+        assert_eq!(
+            Hand::parse("+c345m(right)"),
+            Err(HandParseError::InvalidSource("c345m(right)".to_string()))
+        );
+        assert_eq!(
+            Hand::parse("+p555m(behind)"),
+            Err(HandParseError::InvalidSource("behind".to_string()))
+        );
+    }
+}
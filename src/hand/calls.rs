@@ -0,0 +1,333 @@
+//! Which calls a hand can make.
+
+use super::{AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, Meld, OpenKanMeld, PonMeld};
+use crate::game::PlayerLocation;
+use crate::rules::{Kuikae, RuleSet};
+use crate::tile::{self, MahjongTile};
+
+impl Meld {
+    /// The tiles that may not be discarded straight after making this meld,
+    /// in [`Tile34`](crate::tile::Tile34) order. Only chii and pon restrict
+    /// the next discard.
+    pub fn forbidden_discards(&self, rules: &RuleSet) -> Vec<MahjongTile> {
+        let plain =
+            |t: MahjongTile| MahjongTile::try_from(t.rank_key()).expect("tile kinds convert back");
+        let (called, others) = match self {
+            Meld::Chii(chii) => (chii.chii_tile, &chii.tiles[..]),
+            Meld::Pon(pon) => (pon.pon_tile, &pon.tiles[..]),
+            _ => return vec![],
+        };
+        let mut forbidden = match rules.kuikae {
+            Kuikae::Allowed => return vec![],
+            Kuikae::SameTile | Kuikae::SameTileAndSuji => vec![plain(called)],
+        };
+        if let (Kuikae::SameTileAndSuji, Meld::Chii(_)) = (rules.kuikae, self) {
+            let lowest = others.iter().map(|t| t.rank_key()).min();
+            let highest = others.iter().map(|t| t.rank_key()).max();
+            let suji = if Some(called.rank_key()) == lowest {
+                // Called 4 onto 56: 7 is the other side.
+                called.succ().and_then(|t| t.succ()).and_then(|t| t.succ())
+            } else if Some(called.rank_key()) == highest {
+                called.pred().and_then(|t| t.pred()).and_then(|t| t.pred())
+            } else {
+                None
+            };
+            forbidden.extend(suji);
+        }
+        forbidden.sort_by_key(|t| t.rank_key());
+        forbidden
+    }
+
+    /// Whether discarding `tile` straight after this meld is kuikae.
+    pub fn forbids_discard(&self, tile: MahjongTile, rules: &RuleSet) -> bool {
+        self.forbidden_discards(rules)
+            .iter()
+            .any(|t| t.eq_rank(&tile))
+    }
+}
+
+impl Hand {
+    /// Every chii this hand could make on `tile` discarded by `from`.
+    ///
+    /// Chii is only possible on the left player's discard. A tile can fit
+    /// up to three sequences, and when the hand holds both a red and a plain
+    /// five each choice is listed separately.
+    pub fn chii_options(&self, tile: MahjongTile, from: PlayerLocation) -> Vec<ChiiMeld> {
+        if from != PlayerLocation::Left || !tile.is_number() {
+            return vec![];
+        }
+        let below = tile.pred();
+        let above = tile.succ();
+        let shapes = [
+            (below.and_then(|t| t.pred()), below),
+            (below, above),
+            (above, above.and_then(|t| t.succ())),
+        ];
+        let mut options = Vec::new();
+        for (a, b) in shapes {
+            let (Some(a), Some(b)) = (a, b) else {
+                continue;
+            };
+            for x in self.distinct_of_rank(a) {
+                for y in self.distinct_of_rank(b) {
+                    options
+                        .push(ChiiMeld::from_called(tile, [x, y]).expect("shapes are sequences"));
+                }
+            }
+        }
+        options
+    }
+
+    /// Every pon this hand could make on `tile` discarded by `from`, one for
+    /// each distinct pair of tiles it could use.
+    pub fn pon_options(&self, tile: MahjongTile, from: PlayerLocation) -> Vec<PonMeld> {
+        if from == PlayerLocation::Hero {
+            return vec![];
+        }
+        let matching = self.concealed_of_rank(tile);
+        let mut options = Vec::new();
+        for i in 0..matching.len() {
+            for j in i + 1..matching.len() {
+                let mut tiles = [matching[i], matching[j], tile];
+                tile::sort_hand(&mut tiles);
+                let pon = PonMeld {
+                    tiles,
+                    pon_tile: tile,
+                    source: from,
+                };
+                if !options.contains(&pon) {
+                    options.push(pon);
+                }
+            }
+        }
+        options
+    }
+
+    /// The open kan this hand could make on `tile` discarded by `from`, if
+    /// it holds the other three copies.
+    pub fn open_kan_option(&self, tile: MahjongTile, from: PlayerLocation) -> Option<OpenKanMeld> {
+        let matching = self.concealed_of_rank(tile);
+        if from == PlayerLocation::Hero || matching.len() < 3 {
+            return None;
+        }
+        let mut tiles = [matching[0], matching[1], matching[2], tile];
+        tile::sort_hand(&mut tiles);
+        Some(OpenKanMeld {
+            tiles,
+            kan_tile: tile,
+            source: from,
+        })
+    }
+
+    /// The closed kans this hand could declare on its own turn.
+    pub fn closed_kan_options(&self) -> Vec<ClosedKanMeld> {
+        MahjongTile::all_kinds()
+            .filter_map(|kind| {
+                let mut tiles: [MahjongTile; 4] = self.concealed_of_rank(kind).try_into().ok()?;
+                tile::sort_hand(&mut tiles);
+                Some(ClosedKanMeld { tiles })
+            })
+            .collect()
+    }
+
+    /// The added kans this hand could declare on its own turn, by adding a
+    /// concealed tile to one of its pons.
+    pub fn added_kan_options(&self) -> Vec<AddedKanMeld> {
+        self.melds
+            .iter()
+            .filter_map(|meld| match meld {
+                Meld::Pon(pon) => {
+                    let added = *self.concealed_of_rank(pon.pon_tile).first()?;
+                    Some(AddedKanMeld {
+                        pon: pon.clone(),
+                        added_tile: added,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether any chii is possible; see [`Hand::chii_options`].
+    pub fn can_chii(&self, tile: MahjongTile, from: PlayerLocation) -> bool {
+        !self.chii_options(tile, from).is_empty()
+    }
+
+    /// Whether any pon is possible; see [`Hand::pon_options`].
+    pub fn can_pon(&self, tile: MahjongTile, from: PlayerLocation) -> bool {
+        !self.pon_options(tile, from).is_empty()
+    }
+
+    /// Whether an open kan is possible; see [`Hand::open_kan_option`].
+    pub fn can_kan(&self, tile: MahjongTile, from: PlayerLocation) -> bool {
+        self.open_kan_option(tile, from).is_some()
+    }
+
+    /// Whether, after making `meld` from this hand's concealed tiles, any
+    /// discard would be allowed. Calls that leave only kuikae discards are
+    /// not allowed.
+    pub fn call_leaves_discard(&self, meld: &Meld, rules: &RuleSet) -> bool {
+        let mut rest = self.concealed.clone();
+        let mut called = match meld {
+            Meld::Chii(chii) => Some(chii.chii_tile),
+            Meld::Pon(pon) => Some(pon.pon_tile),
+            Meld::OpenKan(kan) => Some(kan.kan_tile),
+            Meld::ClosedKan(_) | Meld::AddedKan(_) => None,
+        };
+        for tile in meld.tiles() {
+            // The called tile comes from another player's discard.
+            if called == Some(tile) {
+                called = None;
+                continue;
+            }
+            if let Some(pos) = rest.iter().position(|t| *t == tile) {
+                rest.remove(pos);
+            }
+        }
+        rest.iter().any(|t| !meld.forbids_discard(*t, rules))
+    }
+
+    /// Whether `tile` completes the hand. Yaku and furiten are not checked.
+    pub fn can_ron(&self, tile: MahjongTile) -> bool {
+        if self.concealed.len() % 3 != 1 {
+            return false;
+        }
+        let mut hand = self.clone();
+        hand.concealed.push(tile);
+        hand.is_complete()
+    }
+
+    fn concealed_of_rank(&self, tile: MahjongTile) -> Vec<MahjongTile> {
+        let mut tiles: Vec<MahjongTile> = self
+            .concealed
+            .iter()
+            .copied()
+            .filter(|t| t.eq_rank(&tile))
+            .collect();
+        tile::sort_hand(&mut tiles);
+        tiles
+    }
+
+    fn distinct_of_rank(&self, tile: MahjongTile) -> Vec<MahjongTile> {
+        let mut tiles = self.concealed_of_rank(tile);
+        tiles.dedup();
+        tiles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::{build, parse_tiles};
+
+    fn hand(s: &str) -> Hand {
+        Hand::parse(s).unwrap()
+    }
+
+    fn t(s: &str) -> MahjongTile {
+        build(s).unwrap()
+    }
+
+    #[test]
+    fn chii_shapes() {
+        let options = hand("3p56p1z").chii_options(t("4p"), PlayerLocation::Left);
+        let shapes: Vec<String> = options
+            .iter()
+            .map(|m| tile::format_tiles(&m.tiles))
+            .collect();
+        assert_eq!(shapes, vec!["345p", "456p"]);
+        assert!(options.iter().all(|m| m.validate().is_ok()));
+        assert!(!hand("3p56p1z").can_chii(t("4p"), PlayerLocation::Across));
+        assert!(!hand("1z2z").can_chii(t("3z"), PlayerLocation::Left));
+        assert!(!hand("89m1p").can_chii(t("7p"), PlayerLocation::Left));
+    }
+
+    #[test]
+    fn chii_with_red_and_plain_fives() {
+        let options = hand("405s").chii_options(t("3s"), PlayerLocation::Left);
+        assert_eq!(options.len(), 2);
+        assert_eq!(options[0].tiles.to_vec(), parse_tiles("340s").unwrap());
+        assert_eq!(options[1].tiles.to_vec(), parse_tiles("345s").unwrap());
+    }
+
+    #[test]
+    fn pon_and_kan() {
+        let h = hand("505m1z");
+        let options = h.pon_options(t("5m"), PlayerLocation::Right);
+        assert_eq!(options.len(), 2);
+        assert!(h.can_pon(t("0m"), PlayerLocation::Across));
+        assert!(!h.can_pon(t("1z"), PlayerLocation::Across));
+        let kan = h.open_kan_option(t("5m"), PlayerLocation::Left).unwrap();
+        assert_eq!(kan.source, PlayerLocation::Left);
+        assert!(kan.validate().is_ok());
+        assert!(!hand("55m").can_kan(t("5m"), PlayerLocation::Left));
+    }
+
+    #[test]
+    fn own_turn_kans() {
+        let h = hand("11112z3s + p333s(left)");
+        assert_eq!(
+            h.closed_kan_options(),
+            vec![ClosedKanMeld {
+                tiles: [t("1z"); 4]
+            }]
+        );
+        let added = h.added_kan_options();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].added_tile, t("3s"));
+    }
+
+    #[test]
+    fn kuikae() {
+        let rules = RuleSet::default();
+        let chii = |tiles: &str, called: &str| {
+            let tiles = parse_tiles(tiles).unwrap();
+            Meld::Chii(ChiiMeld::new([tiles[0], tiles[1], tiles[2]], t(called)).unwrap())
+        };
+        let end = chii("456p", "4p");
+        assert_eq!(end.forbidden_discards(&rules), parse_tiles("47p").unwrap());
+        assert!(end.forbids_discard(t("7p"), &rules));
+        assert!(!end.forbids_discard(t("8p"), &rules));
+        let high = chii("789s", "9s");
+        assert_eq!(high.forbidden_discards(&rules), parse_tiles("69s").unwrap());
+        let middle = chii("340m", "4m");
+        assert_eq!(
+            middle.forbidden_discards(&rules),
+            parse_tiles("4m").unwrap()
+        );
+        let red = chii("067p", "0p");
+        assert_eq!(red.forbidden_discards(&rules), parse_tiles("58p").unwrap());
+        // No suji partner beyond the end of the suit.
+        let edge = chii("789m", "7m");
+        assert_eq!(edge.forbidden_discards(&rules), parse_tiles("7m").unwrap());
+
+        let same_tile = RuleSet {
+            kuikae: Kuikae::SameTile,
+            ..rules.clone()
+        };
+        assert_eq!(
+            end.forbidden_discards(&same_tile),
+            parse_tiles("4p").unwrap()
+        );
+        let allowed = RuleSet {
+            kuikae: Kuikae::Allowed,
+            ..rules.clone()
+        };
+        assert!(end.forbidden_discards(&allowed).is_empty());
+
+        let pon = Meld::Pon(PonMeld::new([t("5z"); 3], t("5z"), PlayerLocation::Right).unwrap());
+        assert_eq!(pon.forbidden_discards(&rules), vec![t("5z")]);
+        let h = hand("4567p + p111z + p222z + p333z");
+        assert!(!h.call_leaves_discard(&end, &rules));
+        assert!(h.call_leaves_discard(&end, &same_tile));
+    }
+
+    #[test]
+    fn ron() {
+        let h = hand("123m456p789s11z23s");
+        assert!(h.can_ron(t("1s")));
+        assert!(h.can_ron(t("4s")));
+        assert!(!h.can_ron(t("5s")));
+        assert!(!hand("123m456p789s11z234s").can_ron(t("1z")));
+    }
+}
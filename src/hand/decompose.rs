@@ -0,0 +1,372 @@
+//! Breaking complete hands into groups and a pair.
+
+use super::shanten::{seven_pairs_shanten, thirteen_orphans_shanten};
+use super::{tile_counts, Hand, Meld};
+use crate::tile::{MahjongTile, Tile34};
+
+/// One way of reading a complete hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandDecomposition {
+    /// Four groups and a pair. Groups from melds come first, in the order
+    /// the melds were made, followed by the concealed groups in
+    /// [`Tile34`] order.
+    Standard {
+        pair: MahjongTile,
+        groups: Vec<Group>,
+    },
+    /// Seven distinct pairs (chiitoitsu), in [`Tile34`] order.
+    SevenPairs(Vec<MahjongTile>),
+    /// One of each terminal and honor plus a pair of one of them (kokushi
+    /// musou).
+    ThirteenOrphans { pair: MahjongTile },
+}
+
+/// A sequence, triplet or quad within a decomposed hand.
+///
+/// Tiles are identified by rank, so red fives appear as plain fives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Group {
+    pub kind: GroupKind,
+    /// The group's lowest tile; for triplets and quads, its only kind.
+    pub tile: MahjongTile,
+    /// Whether the group was called from another player. Closed kans and
+    /// concealed groups are not open.
+    pub open: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GroupKind {
+    Sequence,
+    Triplet,
+    Quad,
+}
+
+/// The shape a hand was waiting on before its winning tile (machi).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WaitKind {
+    /// Two-sided, e.g. `23m` waiting on 1m or 4m.
+    Ryanmen,
+    /// Closed, e.g. `24m` waiting on 3m.
+    Kanchan,
+    /// Edge, `12` waiting on 3 or `89` waiting on 7.
+    Penchan,
+    /// Two pairs, one of which became a triplet.
+    Shanpon,
+    /// Pair wait on a single tile. Seven pairs and the thirteen orphans
+    /// always count as this.
+    Tanki,
+}
+
+impl HandDecomposition {
+    /// Every wait the hand could have had for `winning_tile` to complete
+    /// this reading, without duplicates.
+    ///
+    /// The same tile can often be read as finishing different parts of a
+    /// hand, e.g. 3m in `123m` is a penchan but in `345m` a ryanmen; fu and
+    /// pinfu should use whichever reading scores best. Groups from melds
+    /// are never considered. Returns an empty list if the winning tile is
+    /// not a concealed part of the reading.
+    pub fn wait_kinds(&self, winning_tile: MahjongTile) -> Vec<WaitKind> {
+        let (pair, groups) = match self {
+            HandDecomposition::Standard { pair, groups } => (*pair, &groups[..]),
+            HandDecomposition::SevenPairs(pairs) => {
+                return match pairs.iter().any(|p| p.eq_rank(&winning_tile)) {
+                    true => vec![WaitKind::Tanki],
+                    false => vec![],
+                };
+            }
+            HandDecomposition::ThirteenOrphans { .. } => {
+                return match winning_tile.is_yaochuu() {
+                    true => vec![WaitKind::Tanki],
+                    false => vec![],
+                };
+            }
+        };
+        let won = Tile34::from(winning_tile).index();
+        let mut kinds = Vec::new();
+        if pair.eq_rank(&winning_tile) {
+            kinds.push(WaitKind::Tanki);
+        }
+        for group in groups.iter().filter(|g| !g.open) {
+            let low = Tile34::from(group.tile).index();
+            let kind = match group.kind {
+                GroupKind::Triplet if low == won => WaitKind::Shanpon,
+                GroupKind::Sequence if low + 1 == won => WaitKind::Kanchan,
+                GroupKind::Sequence if low == won && low % 9 == 6 => WaitKind::Penchan,
+                GroupKind::Sequence if low + 2 == won && low % 9 == 0 => WaitKind::Penchan,
+                GroupKind::Sequence if low == won || low + 2 == won => WaitKind::Ryanmen,
+                _ => continue,
+            };
+            kinds.push(kind);
+        }
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    }
+}
+
+impl Group {
+    /// The group a meld forms.
+    pub fn from_meld(meld: &Meld) -> Group {
+        let (kind, tiles, open) = match meld {
+            Meld::Chii(m) => (GroupKind::Sequence, m.tiles.to_vec(), true),
+            Meld::Pon(m) => (GroupKind::Triplet, m.tiles.to_vec(), true),
+            Meld::OpenKan(m) => (GroupKind::Quad, m.tiles.to_vec(), true),
+            Meld::ClosedKan(m) => (GroupKind::Quad, m.tiles.to_vec(), false),
+            Meld::AddedKan(m) => (GroupKind::Quad, m.pon.tiles.to_vec(), true),
+        };
+        let lowest = tiles
+            .iter()
+            .map(Tile34::from)
+            .min()
+            .expect("melds have tiles");
+        Group {
+            kind,
+            tile: plain(lowest.index()),
+            open,
+        }
+    }
+}
+
+impl Hand {
+    /// Whether the hand is a complete winning shape: four groups and a
+    /// pair, seven pairs or the thirteen orphans. Yaku are not checked.
+    pub fn is_complete(&self) -> bool {
+        !self.decompose().is_empty()
+    }
+
+    /// Every way of reading the hand as a complete shape, or an empty list
+    /// if it is not complete.
+    ///
+    /// A hand may have several readings, e.g. `111222333m` is either three
+    /// triplets or three identical sequences, and scoring should consider
+    /// each of them. Seven pairs and the thirteen orphans are only possible
+    /// without melds.
+    pub fn decompose(&self) -> Vec<HandDecomposition> {
+        if self.concealed.len() % 3 != 2 {
+            return vec![];
+        }
+        let mut counts = tile_counts(&self.concealed);
+        let melds: Vec<Group> = self.melds.iter().map(Group::from_meld).collect();
+        let mut found = Vec::new();
+        for pair in 0..Tile34::COUNT {
+            if counts[pair] < 2 {
+                continue;
+            }
+            counts[pair] -= 2;
+            let mut readings = Vec::new();
+            concealed_groups(&mut counts, &mut melds.clone(), &mut readings);
+            counts[pair] += 2;
+            found.extend(
+                readings
+                    .into_iter()
+                    .map(|groups| HandDecomposition::Standard {
+                        pair: plain(pair),
+                        groups,
+                    }),
+            );
+        }
+        if self.melds.is_empty() {
+            if seven_pairs_shanten(&counts) == -1 {
+                let pairs = (0..Tile34::COUNT)
+                    .filter(|&i| counts[i] == 2)
+                    .map(plain)
+                    .collect();
+                found.push(HandDecomposition::SevenPairs(pairs));
+            }
+            if thirteen_orphans_shanten(&counts) == -1 {
+                let pair = (0..Tile34::COUNT)
+                    .find(|&i| counts[i] == 2)
+                    .expect("thirteen orphans has a pair");
+                found.push(HandDecomposition::ThirteenOrphans { pair: plain(pair) });
+            }
+        }
+        found
+    }
+}
+
+/// Splits all of `counts` into triplets and sequences, pushing each
+/// complete reading (appended to `groups`) onto `out`.
+fn concealed_groups(
+    counts: &mut [u8; Tile34::COUNT],
+    groups: &mut Vec<Group>,
+    out: &mut Vec<Vec<Group>>,
+) {
+    let Some(i) = counts.iter().position(|&c| c > 0) else {
+        out.push(groups.clone());
+        return;
+    };
+    let concealed = |kind| Group {
+        kind,
+        tile: plain(i),
+        open: false,
+    };
+    if counts[i] >= 3 {
+        counts[i] -= 3;
+        groups.push(concealed(GroupKind::Triplet));
+        concealed_groups(counts, groups, out);
+        groups.pop();
+        counts[i] += 3;
+    }
+    if i < 27 && i % 9 < 7 && counts[i + 1] > 0 && counts[i + 2] > 0 {
+        counts[i..i + 3].iter_mut().for_each(|c| *c -= 1);
+        groups.push(concealed(GroupKind::Sequence));
+        concealed_groups(counts, groups, out);
+        groups.pop();
+        counts[i..i + 3].iter_mut().for_each(|c| *c += 1);
+    }
+}
+
+/// The plain tile of a [`Tile34`] index.
+fn plain(index: usize) -> MahjongTile {
+    MahjongTile::try_from(Tile34(index as u8)).expect("index is below Tile34::COUNT")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::{self, build};
+
+    fn decompose(s: &str) -> Vec<HandDecomposition> {
+        Hand::parse(s).unwrap().decompose()
+    }
+
+    fn group(kind: GroupKind, tile: &str, open: bool) -> Group {
+        Group {
+            kind,
+            tile: build(tile).unwrap(),
+            open,
+        }
+    }
+
+    #[test]
+    fn single_standard_reading() {
+        assert_eq!(
+            decompose("123m406p789s11122z"),
+            vec![HandDecomposition::Standard {
+                pair: tile::SOUTH,
+                groups: vec![
+                    group(GroupKind::Sequence, "1m", false),
+                    group(GroupKind::Sequence, "4p", false),
+                    group(GroupKind::Sequence, "7s", false),
+                    group(GroupKind::Triplet, "1z", false),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn ambiguous_hands_have_every_reading() {
+        let sequences = vec![group(GroupKind::Sequence, "1m", false); 3];
+        let triplets = vec![
+            group(GroupKind::Triplet, "1m", false),
+            group(GroupKind::Triplet, "2m", false),
+            group(GroupKind::Triplet, "3m", false),
+        ];
+        let reading = |mut groups: Vec<Group>| {
+            groups.push(group(GroupKind::Sequence, "4p", false));
+            HandDecomposition::Standard {
+                pair: tile::EAST,
+                groups,
+            }
+        };
+        assert_eq!(
+            decompose("111222333m456p11z"),
+            vec![reading(triplets), reading(sequences)]
+        );
+    }
+
+    #[test]
+    fn seven_pairs_and_standard() {
+        let readings = decompose("223344m556677p11z");
+        assert_eq!(readings.len(), 2);
+        assert!(readings.contains(&HandDecomposition::SevenPairs(vec![
+            tile::MAN_2,
+            tile::MAN_3,
+            tile::MAN_4,
+            tile::PIN_5,
+            tile::PIN_6,
+            tile::PIN_7,
+            tile::EAST,
+        ])));
+    }
+
+    #[test]
+    fn seven_pairs_is_read_once() {
+        let readings = decompose("22334455667788m");
+        let pairs = readings
+            .iter()
+            .filter(|r| matches!(r, HandDecomposition::SevenPairs(_)))
+            .count();
+        assert_eq!(pairs, 1);
+        assert_eq!(readings.len(), 4);
+    }
+
+    #[test]
+    fn thirteen_orphans() {
+        assert_eq!(
+            decompose("19m19p19s12345677z"),
+            vec![HandDecomposition::ThirteenOrphans {
+                pair: tile::RED_DRAGON
+            }]
+        );
+        assert!(decompose("1119m19p19s12345z").is_empty());
+    }
+
+    #[test]
+    fn melds_come_first() {
+        let hand = Hand::parse("55z678s + ck1111p + c213m + p999s(across)").unwrap();
+        assert!(hand.is_complete());
+        assert_eq!(
+            hand.decompose(),
+            vec![HandDecomposition::Standard {
+                pair: tile::WHITE_DRAGON,
+                groups: vec![
+                    group(GroupKind::Quad, "1p", false),
+                    group(GroupKind::Sequence, "1m", true),
+                    group(GroupKind::Triplet, "9s", true),
+                    group(GroupKind::Sequence, "6s", false),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn wait_kinds() {
+        let waits = |hand: &str, tile: &str| -> Vec<Vec<WaitKind>> {
+            decompose(hand)
+                .iter()
+                .map(|r| r.wait_kinds(build(tile).unwrap()))
+                .collect()
+        };
+        use WaitKind::*;
+        assert_eq!(
+            waits("123m456p789s11z345m", "3m"),
+            vec![vec![Ryanmen, Penchan]]
+        );
+        assert_eq!(waits("123m456p789s11z345m", "4m"), vec![vec![Kanchan]]);
+        assert_eq!(waits("123m456p789s11z789m", "7m"), vec![vec![Penchan]]);
+        assert_eq!(waits("123m456p789s11z999m", "9m"), vec![vec![Shanpon]]);
+        assert_eq!(waits("123m456p789s222z11z", "1z"), vec![vec![Tanki]]);
+        // 111222333m reads either as triplets or as sequences.
+        assert_eq!(
+            waits("111222333m456p11z", "3m"),
+            vec![vec![Shanpon], vec![Penchan]]
+        );
+        assert_eq!(waits("22334455667788m", "2m")[0], vec![Tanki]);
+        assert_eq!(waits("19m19p19s12345677z", "1m"), vec![vec![Tanki]]);
+        // Called groups don't count.
+        let hand = Hand::parse("11z456p789s + c123m + p999m").unwrap();
+        let reading = &hand.decompose()[0];
+        assert!(reading.wait_kinds(tile::MAN_3).is_empty());
+        assert_eq!(reading.wait_kinds(tile::PIN_5), vec![Kanchan]);
+    }
+
+    #[test]
+    fn incomplete_hands() {
+        assert!(!Hand::parse("123m456p789s1122z").unwrap().is_complete());
+        assert!(!Hand::parse("123m456p789s11223z").unwrap().is_complete());
+        // Four copies are not two pairs.
+        assert!(!Hand::parse("1111335577m1122z").unwrap().is_complete());
+    }
+}
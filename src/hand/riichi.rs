@@ -0,0 +1,88 @@
+//! Whether a hand may declare riichi.
+
+use super::{Hand, Meld};
+use crate::tile::MahjongTile;
+
+/// Points a player must have to pay the riichi deposit.
+pub const RIICHI_DEPOSIT: i32 = 1000;
+
+/// Fewest tiles that must be left in the live wall to declare riichi, so
+/// the declarer is guaranteed another draw in a four-player game.
+pub const RIICHI_MIN_WALL_TILES: usize = 4;
+
+impl Hand {
+    /// Whether a player holding this hand, with `score` points and
+    /// `wall_tiles_left` tiles left in the live wall, may declare riichi.
+    ///
+    /// The hand must be closed (closed kans are allowed) and tenpai: for a
+    /// hand that has just drawn, at least one discard must leave it tenpai.
+    pub fn can_riichi(&self, score: i32, wall_tiles_left: usize) -> bool {
+        if !self.is_closed() || score < RIICHI_DEPOSIT || wall_tiles_left < RIICHI_MIN_WALL_TILES {
+            return false;
+        }
+        match self.concealed.len() % 3 {
+            1 => self.is_tenpai(),
+            2 => !self.riichi_discards().is_empty(),
+            _ => false,
+        }
+    }
+
+    /// The discards that leave a hand that has just drawn tenpai, in the
+    /// order they appear in the hand. A red five and a plain five are listed
+    /// separately.
+    pub fn riichi_discards(&self) -> Vec<MahjongTile> {
+        if self.concealed.len() % 3 != 2 {
+            return vec![];
+        }
+        let mut discards: Vec<MahjongTile> = Vec::new();
+        for (i, tile) in self.concealed.iter().enumerate() {
+            if discards.contains(tile) {
+                continue;
+            }
+            let mut rest = self.clone();
+            rest.concealed.remove(i);
+            if rest.is_tenpai() {
+                discards.push(*tile);
+            }
+        }
+        discards
+    }
+
+    /// Whether the hand has made no calls. Closed kans keep a hand closed.
+    pub fn is_closed(&self) -> bool {
+        self.melds.iter().all(|m| matches!(m, Meld::ClosedKan(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::parse_tiles;
+
+    fn hand(s: &str) -> Hand {
+        Hand::parse(s).unwrap()
+    }
+
+    #[test]
+    fn riichi_requirements() {
+        let tenpai = hand("123m456p789s11z23s");
+        assert!(tenpai.can_riichi(25000, 70));
+        assert!(tenpai.can_riichi(1000, 4));
+        assert!(!tenpai.can_riichi(999, 70));
+        assert!(!tenpai.can_riichi(25000, 3));
+        assert!(hand("123m456p789s1z234s").can_riichi(25000, 70));
+        assert!(!hand("123m456p789s12z34s").can_riichi(25000, 70));
+        assert!(!hand("123m789s11z23s + p444p").can_riichi(25000, 70));
+        assert!(hand("123m789s11z23s + ck4444p").can_riichi(25000, 70));
+    }
+
+    #[test]
+    fn discards_that_keep_tenpai() {
+        let drawn = hand("123m456p789s11z23s9m");
+        assert_eq!(drawn.riichi_discards(), parse_tiles("9m").unwrap());
+        assert!(drawn.can_riichi(25000, 70));
+        let drawn = hand("123m456p789s1z2344s");
+        assert_eq!(drawn.riichi_discards(), parse_tiles("1z4s").unwrap());
+        assert!(hand("123m456p789s11z23s").riichi_discards().is_empty());
+    }
+}
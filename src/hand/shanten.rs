@@ -0,0 +1,185 @@
+//! Shanten: how many tiles a hand is away from tenpai.
+
+use super::{tile_counts, Hand};
+use crate::tile::Tile34;
+
+/// Indices of the 13 terminal and honor kinds, in [`Tile34`] order.
+const YAOCHUU: [usize; 13] = [0, 8, 9, 17, 18, 26, 27, 28, 29, 30, 31, 32, 33];
+
+impl Hand {
+    /// The hand's shanten number: 0 when tenpai, -1 when the hand is already
+    /// complete, and otherwise the number of tile exchanges needed to reach
+    /// tenpai.
+    ///
+    /// This is the minimum over standard hands, seven pairs and the thirteen
+    /// orphans. Each meld counts as a finished group, so a hand with calls
+    /// only needs its concealed tiles to supply the remaining groups. Seven
+    /// pairs and the thirteen orphans are only considered for fully
+    /// concealed hands.
+    pub fn shanten(&self) -> i8 {
+        shanten_of(&tile_counts(&self.concealed), self.melds.len() as i8)
+    }
+}
+
+/// Shanten of concealed tiles given as counts, alongside `melds` melds.
+pub(crate) fn shanten_of(counts: &[u8; Tile34::COUNT], melds: i8) -> i8 {
+    let standard = standard_shanten(counts, melds);
+    if melds > 0 {
+        return standard;
+    }
+    standard
+        .min(seven_pairs_shanten(counts))
+        .min(thirteen_orphans_shanten(counts))
+}
+
+/// Shanten for four groups and a pair, given `melds` groups already made.
+pub(crate) fn standard_shanten(counts: &[u8; Tile34::COUNT], melds: i8) -> i8 {
+    let mut counts = *counts;
+    let mut best = 8;
+    search(&mut counts, 0, melds, 0, false, &mut best);
+    best
+}
+
+/// Depth-first search over ways of splitting the tiles into groups,
+/// partial groups and a pair, keeping the best shanten seen in `best`.
+fn search(
+    counts: &mut [u8; Tile34::COUNT],
+    start: usize,
+    groups: i8,
+    partials: i8,
+    pair: bool,
+    best: &mut i8,
+) {
+    let Some(i) = (start..Tile34::COUNT).find(|&i| counts[i] > 0) else {
+        let partials = partials.min(4 - groups);
+        *best = (*best).min(8 - 2 * groups - partials - pair as i8);
+        return;
+    };
+    let sequence_start = i < 27 && i % 9 < 7;
+
+    if counts[i] >= 3 {
+        counts[i] -= 3;
+        search(counts, i, groups + 1, partials, pair, best);
+        counts[i] += 3;
+    }
+    if sequence_start && counts[i + 1] > 0 && counts[i + 2] > 0 {
+        take(counts, &[i, i + 1, i + 2]);
+        search(counts, i, groups + 1, partials, pair, best);
+        give(counts, &[i, i + 1, i + 2]);
+    }
+    if counts[i] >= 2 {
+        counts[i] -= 2;
+        if !pair {
+            search(counts, i, groups, partials, true, best);
+        }
+        search(counts, i, groups, partials + 1, pair, best);
+        counts[i] += 2;
+    }
+    if i < 27 && i % 9 < 8 && counts[i + 1] > 0 {
+        take(counts, &[i, i + 1]);
+        search(counts, i, groups, partials + 1, pair, best);
+        give(counts, &[i, i + 1]);
+    }
+    if sequence_start && counts[i + 2] > 0 {
+        take(counts, &[i, i + 2]);
+        search(counts, i, groups, partials + 1, pair, best);
+        give(counts, &[i, i + 2]);
+    }
+    // Leave every remaining copy of this kind unused.
+    let isolated = counts[i];
+    counts[i] = 0;
+    search(counts, i + 1, groups, partials, pair, best);
+    counts[i] = isolated;
+}
+
+fn take(counts: &mut [u8; Tile34::COUNT], kinds: &[usize]) {
+    for &k in kinds {
+        counts[k] -= 1;
+    }
+}
+
+fn give(counts: &mut [u8; Tile34::COUNT], kinds: &[usize]) {
+    for &k in kinds {
+        counts[k] += 1;
+    }
+}
+
+/// Shanten for seven distinct pairs. Four copies of a kind only make one.
+pub(crate) fn seven_pairs_shanten(counts: &[u8; Tile34::COUNT]) -> i8 {
+    let pairs = counts.iter().filter(|&&c| c >= 2).count() as i8;
+    let kinds = counts.iter().filter(|&&c| c > 0).count() as i8;
+    6 - pairs + (7 - kinds).max(0)
+}
+
+/// Shanten for one of each terminal and honor plus a pair of any of them.
+pub(crate) fn thirteen_orphans_shanten(counts: &[u8; Tile34::COUNT]) -> i8 {
+    let kinds = YAOCHUU.iter().filter(|&&i| counts[i] > 0).count() as i8;
+    let pair = YAOCHUU.iter().any(|&i| counts[i] >= 2);
+    13 - kinds - pair as i8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::parse_tiles;
+
+    fn shanten(s: &str) -> i8 {
+        Hand::parse(s).unwrap().shanten()
+    }
+
+    #[test]
+    fn standard_hands() {
+        assert_eq!(shanten("123m456p789s1122z"), 0);
+        assert_eq!(shanten("123m456p789s11222z"), -1);
+        assert_eq!(shanten("123m456p789s1z234z"), 2);
+        assert_eq!(shanten("147m258p369s1234z"), 6);
+        let scattered = tile_counts(&parse_tiles("147m258p369s1234z").unwrap());
+        assert_eq!(standard_shanten(&scattered, 0), 8);
+        assert_eq!(shanten("13m46p79s1234567z"), 4);
+        assert_eq!(shanten("1112345678999m"), 0);
+        assert_eq!(shanten("12m45p78s11223344z"), 2);
+    }
+
+    #[test]
+    fn red_fives_count_by_rank() {
+        assert_eq!(shanten("340m456p789s1122z"), 0);
+        assert_eq!(shanten("055m456p789s1122z"), 0);
+    }
+
+    #[test]
+    fn melds_reduce_concealed_tiles() {
+        assert_eq!(shanten("1122z + c123m + p555p + k9999s(right)"), 0);
+        assert_eq!(shanten("1z + c123m + p555p + k9999s + ck7777z"), 0);
+        assert_eq!(shanten("12z + c123m + p555p + k9999s + ck7777z"), 0);
+        assert_eq!(shanten("11z + c123m + p555p + k9999s + ck7777z"), -1);
+        // Seven pairs needs a closed hand.
+        assert_eq!(shanten("1133557799m2z222s"), 0);
+        assert_eq!(shanten("1133557799m2z + p222s"), 2);
+    }
+
+    #[test]
+    fn seven_pairs() {
+        assert_eq!(shanten("1133557799m1122z"), -1);
+        assert_eq!(shanten("113355779m11223z"), 0);
+        // Four of a kind is only one pair.
+        assert_eq!(shanten("1111335577m1122z"), 1);
+    }
+
+    #[test]
+    fn thirteen_orphans() {
+        assert_eq!(shanten("19m19p19s1234567z"), 0);
+        assert_eq!(shanten("19m19p19s12345677z"), -1);
+        assert_eq!(shanten("19m19p11s1234566z"), 1);
+        assert_eq!(shanten("119m19p19s123456z"), 0);
+    }
+
+    #[test]
+    fn special_hand_edge_cases() {
+        // Three copies are one pair plus a spare tile.
+        assert_eq!(shanten("1133557799m222z"), 1);
+        // Only one pair helps the thirteen orphans.
+        assert_eq!(shanten("1119m19p19s12345z"), 1);
+        // Neither special hand is possible after a call.
+        assert_eq!(shanten("19m19p19s1234z + p777z"), 6);
+    }
+}
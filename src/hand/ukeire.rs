@@ -0,0 +1,158 @@
+//! Ukeire: the tiles that would improve a hand, how many are left, and
+//! which discard leaves the most of them.
+
+use super::shanten::shanten_of;
+use super::{tile_counts, Hand};
+use crate::tile::{MahjongTile, Tile34, TileCounts};
+
+/// The result of one possible discard, as ranked by
+/// [`Hand::best_discards`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscardOption {
+    pub discard: MahjongTile,
+    /// Shanten of the hand after the discard.
+    pub shanten: i8,
+    /// The hand's [`ukeire`](Hand::ukeire) after the discard.
+    pub ukeire: Vec<(MahjongTile, u8)>,
+}
+
+impl DiscardOption {
+    /// How many tiles in total would improve the hand.
+    pub fn acceptance(&self) -> u32 {
+        self.ukeire.iter().map(|&(_, n)| n as u32).sum()
+    }
+}
+
+impl Hand {
+    /// Every distinct discard from a hand that has just drawn, best first:
+    /// lowest resulting shanten, then most tiles accepted. Ties keep the
+    /// order the tiles appear in the hand.
+    ///
+    /// `visible` is as for [`Hand::ukeire`]. A red five and a plain five are
+    /// separate options. Returns an empty list unless the hand holds 14
+    /// tiles counting each meld as three.
+    pub fn best_discards(&self, visible: &TileCounts) -> Vec<DiscardOption> {
+        if self.concealed.len() % 3 != 2 {
+            return vec![];
+        }
+        let mut options: Vec<DiscardOption> = Vec::new();
+        for (i, &tile) in self.concealed.iter().enumerate() {
+            if options.iter().any(|o| o.discard == tile) {
+                continue;
+            }
+            let mut rest = self.clone();
+            rest.concealed.remove(i);
+            // The discarded tile is visible to everyone once it is gone.
+            let mut seen = visible.clone();
+            seen.add(tile);
+            options.push(DiscardOption {
+                discard: tile,
+                shanten: rest.shanten(),
+                ukeire: rest.ukeire(&seen),
+            });
+        }
+        options.sort_by_key(|o| (o.shanten, std::cmp::Reverse(o.acceptance())));
+        options
+    }
+
+    /// Every tile kind that would lower this hand's shanten if drawn, with
+    /// how many copies may still be drawn, in [`Tile34`] order.
+    ///
+    /// `visible` holds the tiles seen elsewhere, such as discards, dora
+    /// indicators and other players' melds. The hand's own tiles are counted
+    /// automatically and should not be included. Kinds that would help but
+    /// have no copies left are still listed, with a count of 0.
+    ///
+    /// Returns an empty list unless the hand is waiting to draw, i.e. holds
+    /// 13 tiles counting each meld as three.
+    pub fn ukeire(&self, visible: &TileCounts) -> Vec<(MahjongTile, u8)> {
+        if self.concealed.len() % 3 != 1 {
+            return vec![];
+        }
+        let mut counts = tile_counts(&self.concealed);
+        let melds = self.melds.len() as i8;
+        let shanten = shanten_of(&counts, melds);
+        let held: TileCounts = self.tiles().into_iter().collect();
+        MahjongTile::all_kinds()
+            .filter(|tile| {
+                let i = Tile34::from(tile).index();
+                counts[i] += 1;
+                let improves = shanten_of(&counts, melds) < shanten;
+                counts[i] -= 1;
+                improves
+            })
+            .map(|tile| {
+                let seen = held.get(tile) + visible.get(tile);
+                (tile, 4u8.saturating_sub(seen))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::{self, parse_tiles};
+
+    fn ukeire(hand: &str, visible: &str) -> Vec<(MahjongTile, u8)> {
+        let visible = parse_tiles(visible).unwrap().into_iter().collect();
+        Hand::parse(hand).unwrap().ukeire(&visible)
+    }
+
+    #[test]
+    fn counts_remaining_copies() {
+        assert_eq!(
+            ukeire("123m456p789s11z23s", ""),
+            vec![(tile::SOU_1, 4), (tile::SOU_4, 4)]
+        );
+        assert_eq!(
+            ukeire("123m456p789s11z23s", "1s44s1z"),
+            vec![(tile::SOU_1, 3), (tile::SOU_4, 2)]
+        );
+        // The hand's own copies count against what is left.
+        assert_eq!(
+            ukeire("123m456p789s11z34s", "2s"),
+            vec![(tile::SOU_2, 3), (tile::SOU_5, 4)]
+        );
+    }
+
+    #[test]
+    fn exhausted_kinds_are_listed_with_zero() {
+        assert_eq!(ukeire("123m456p789s1z234s", "111z"), vec![(tile::EAST, 0)]);
+    }
+
+    #[test]
+    fn red_fives_count_by_rank() {
+        assert_eq!(ukeire("123m456p789s11z46s", "0s"), vec![(tile::SOU_5, 3)]);
+    }
+
+    #[test]
+    fn best_discards_rank_by_shanten_then_acceptance() {
+        let hand = Hand::parse("123m456p789s11z235s").unwrap();
+        let options = hand.best_discards(&TileCounts::new());
+        let order: Vec<(MahjongTile, i8, u32)> = options
+            .iter()
+            .map(|o| (o.discard, o.shanten, o.acceptance()))
+            .collect();
+        // Discarding 5s keeps the 23s ryanmen (8 tiles) and 2s leaves a 35s
+        // kanchan (4); every other discard breaks tenpai.
+        assert_eq!(order[0], (tile::SOU_5, 0, 8));
+        assert_eq!(order[1], (tile::SOU_2, 0, 4));
+        assert_eq!(order[2], (tile::SOU_3, 1, 26));
+        assert!(order[2..].iter().all(|&(_, shanten, _)| shanten == 1));
+        assert_eq!(order.len(), 13);
+        assert!(Hand::parse("123m456p789s11z23s")
+            .unwrap()
+            .best_discards(&TileCounts::new())
+            .is_empty());
+    }
+
+    #[test]
+    fn open_hand_and_wrong_size() {
+        assert_eq!(
+            ukeire("2m + p555p + c789s + ck1111z + k9999m(across)", "2m"),
+            vec![(tile::MAN_2, 2)]
+        );
+        assert!(ukeire("123m456p789s11222z", "").is_empty());
+    }
+}
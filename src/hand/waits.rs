@@ -0,0 +1,103 @@
+//! Tenpai and waits (machi).
+
+use super::shanten::shanten_of;
+use super::{tile_counts, Hand};
+use crate::tile::{MahjongTile, Tile34};
+
+impl Hand {
+    /// The tiles that would complete this hand, in [`Tile34`] order, or an
+    /// empty list if the hand is not tenpai.
+    ///
+    /// Red fives are treated by rank, so a wait on five is returned once as
+    /// a plain five. A tile whose four copies are all already in the hand or
+    /// its melds can never arrive and is not a wait.
+    pub fn waits(&self) -> Vec<MahjongTile> {
+        if self.concealed.len() % 3 != 1 {
+            return vec![];
+        }
+        let mut counts = tile_counts(&self.concealed);
+        let held = tile_counts(&self.tiles());
+        let melds = self.melds.len() as i8;
+        MahjongTile::all_kinds()
+            .filter(|tile| {
+                let i = Tile34::from(tile).index();
+                if held[i] >= 4 {
+                    return false;
+                }
+                counts[i] += 1;
+                let complete = shanten_of(&counts, melds) == -1;
+                counts[i] -= 1;
+                complete
+            })
+            .collect()
+    }
+
+    /// Whether the hand is one tile away from complete.
+    pub fn is_tenpai(&self) -> bool {
+        !self.waits().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::parse_tiles;
+
+    fn waits(s: &str) -> Vec<MahjongTile> {
+        Hand::parse(s).unwrap().waits()
+    }
+
+    #[test]
+    fn standard_waits() {
+        assert_eq!(waits("123m456p789s1z234s"), parse_tiles("1z").unwrap());
+        assert_eq!(waits("123m456p789s11z23s"), parse_tiles("14s").unwrap());
+        assert_eq!(waits("123m456p789s11z13s"), parse_tiles("2s").unwrap());
+        assert_eq!(waits("123m456p789s1122z"), parse_tiles("12z").unwrap());
+        assert_eq!(waits("1112345678999m"), parse_tiles("123456789m").unwrap());
+        assert!(waits("123m456p789s1z235s").is_empty());
+    }
+
+    #[test]
+    fn red_fives_are_waited_on_by_rank() {
+        assert_eq!(waits("40m456p789s11222z"), parse_tiles("36m").unwrap());
+        assert_eq!(waits("123m406p789s1z055s"), parse_tiles("1z").unwrap());
+    }
+
+    #[test]
+    fn open_hands() {
+        assert_eq!(
+            waits("1z + c123m + p555p + k9999s + ck7777z"),
+            parse_tiles("1z").unwrap()
+        );
+        assert_eq!(
+            waits("2344m567s + p555p(right) + c789s"),
+            parse_tiles("14m").unwrap()
+        );
+    }
+
+    #[test]
+    fn special_hands() {
+        assert_eq!(waits("113355779m1122z"), parse_tiles("9m").unwrap());
+        assert_eq!(
+            waits("19m19p19s1234567z"),
+            parse_tiles("19m19p19s1234567z").unwrap()
+        );
+        assert_eq!(waits("19m19p19s1234566z"), parse_tiles("7z").unwrap());
+        // Seven pairs and standard waits are combined without duplicates.
+        assert_eq!(waits("1122334455667m"), parse_tiles("147m").unwrap());
+        assert!(waits("1133557799m222z").is_empty());
+    }
+
+    #[test]
+    fn all_copies_held_is_not_a_wait() {
+        // Waiting on the fifth 1z of a hand holding four.
+        assert!(waits("1z + ck1111z + c123m + c456m + c789m").is_empty());
+        assert!(!Hand::parse("123m456p789s1122z").unwrap().waits().is_empty());
+        assert!(Hand::parse("123m456p789s1122z").unwrap().is_tenpai());
+    }
+
+    #[test]
+    fn wrong_tile_count_has_no_waits() {
+        assert!(waits("123m456p789s11222z").is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+//! MajReplay: a data model for loading and replaying hands of Riichi Mahjong.
+//!
+//! The crate is organised around three layers:
+//!
+//! * [`tile`]: individual tiles and their MPSZ notation (`"3p"`, `"7z"`, ...).
+//! * [`hand`]: concealed tiles and called melds.
+//! * [`game`]: games, rounds and the events that happen inside a round.
+//!
+//! [`state`] replays a round's events one at a time, tracking hands,
+//! discards and the wall. [`validate`] uses it to check that recorded
+//! rounds follow the rules, and [`cursor`] to step and seek through a
+//! round in a replay viewer.
+//!
+//! [`yaku`] recognises scoring hands and [`scoring`] turns them into
+//! payments, under the table rules in [`rules`]; [`scoreboard`] keeps the
+//! running scores between rounds. [`session`] groups the games of one
+//! sitting. [`wall`] models the physical wall for simulators that generate
+//! games.
+//!
+//! Games, rounds and events can carry review commentary from
+//! [`annotation`].
+//!
+//! [`formats`] converts logs from other replay formats, and holds the
+//! crate's own MJR text format.
+//!
+//! Importers return the crate-wide [`Error`], and every module's own
+//! errors convert into it.
+
+pub mod annotation;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod cursor;
+pub mod error;
+pub mod formats;
+pub mod game;
+pub mod hand;
+pub mod rules;
+pub mod scoreboard;
+pub mod scoring;
+pub mod session;
+pub mod state;
+pub mod tile;
+pub mod validate;
+pub mod wall;
+pub mod yaku;
+
+pub use error::{Error, Result};
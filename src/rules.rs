@@ -0,0 +1,280 @@
+//! Table rules that vary between platforms and clubs.
+
+use crate::game::{AbortiveDraw, NumPlayers};
+
+/// Optional rules that change how hands are played and scored, and how a
+/// game's final scores are settled.
+///
+/// The default follows Tenhou and Mahjong Soul where they agree; the
+/// presets set up each platform or league in full.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleSet {
+    /// Allow all simples (tanyao) in an open hand (kuitan).
+    pub kuitan: bool,
+    /// Allow a win whose only yaku depends on the winning tile or on a call
+    /// made after reaching tenpai (atozuke).
+    pub atozuke: bool,
+    /// Round 4 han 30 fu and 3 han 60 fu up to mangan (kiriage mangan).
+    pub kiriage_mangan: bool,
+    /// Score the single-wait and pure forms of yakuman as double yakuman:
+    /// thirteen-wait kokushi, suuankou tanki, junsei chuuren poutou and
+    /// daisuushii.
+    pub double_yakuman: bool,
+    /// Add up every yakuman a hand holds instead of scoring only the most
+    /// valuable one.
+    pub stack_yakuman: bool,
+    /// Pay a mangan at an exhaustive draw to a player whose discards were
+    /// all terminals and honors and never called (nagashi mangan).
+    pub nagashi_mangan: bool,
+    /// Which discards are forbidden straight after a chii or pon.
+    pub kuikae: Kuikae,
+    /// Hand riichi deposits back to their players after an abortive draw
+    /// instead of leaving them on the table for the next winner.
+    pub return_deposits_on_abort: bool,
+    /// Who covers the missing player's share of a tsumo in three-player
+    /// games.
+    pub sanma_tsumo: SanmaTsumo,
+    /// Which abortive draws end a round.
+    pub abortive_draws: AbortiveDraws,
+    /// Pay every player who rons the same discard. Otherwise only the first
+    /// of them in turn order from the discarder wins (atamahane, head
+    /// bump).
+    pub multiple_ron: bool,
+    /// If nobody has this many points at the scheduled end of a game, play
+    /// on into the next wind until someone has, ending after that wind at
+    /// the latest (enchousen). `None` always ends the game on schedule.
+    pub extension_target: Option<i32>,
+    /// When a player's points end the game early (tobi).
+    pub tobi: Tobi,
+    /// Let a dealer in first place end the game by winning the last
+    /// scheduled hand instead of dealing again (agari-yame).
+    pub agari_yame: bool,
+    /// As `agari_yame`, for a dealer tenpai at an exhaustive draw on the
+    /// last scheduled hand (tenpai-yame).
+    pub tenpai_yame: bool,
+    /// Starting points, return points and uma.
+    pub uma_oka: UmaOka,
+}
+
+/// Which abortive draws are played. Tenhou and Mahjong Soul play all of
+/// them; many tournaments play none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AbortiveDraws {
+    pub kyuushu_kyuuhai: bool,
+    pub suufon_renda: bool,
+    pub suucha_riichi: bool,
+    pub suukaikan: bool,
+    /// Abort on a triple ron instead of paying every winner, or only the
+    /// first by head bump.
+    pub sancha_hou: bool,
+}
+
+impl Default for AbortiveDraws {
+    fn default() -> Self {
+        AbortiveDraws::all()
+    }
+}
+
+impl AbortiveDraws {
+    pub fn all() -> AbortiveDraws {
+        AbortiveDraws {
+            kyuushu_kyuuhai: true,
+            suufon_renda: true,
+            suucha_riichi: true,
+            suukaikan: true,
+            sancha_hou: true,
+        }
+    }
+
+    pub fn none() -> AbortiveDraws {
+        AbortiveDraws {
+            kyuushu_kyuuhai: false,
+            suufon_renda: false,
+            suucha_riichi: false,
+            suukaikan: false,
+            sancha_hou: false,
+        }
+    }
+
+    pub fn allows(&self, kind: AbortiveDraw) -> bool {
+        match kind {
+            AbortiveDraw::KyuushuKyuuhai => self.kyuushu_kyuuhai,
+            AbortiveDraw::SuufonRenda => self.suufon_renda,
+            AbortiveDraw::SuuchaRiichi => self.suucha_riichi,
+            AbortiveDraw::Suukaikan => self.suukaikan,
+            AbortiveDraw::SanchaHou => self.sancha_hou,
+        }
+    }
+}
+
+/// How a tsumo is paid in a three-player game, where one of the three
+/// payers of a four-player tsumo is missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SanmaTsumo {
+    /// The missing share is simply not paid (tsumo-zon), so a tsumo is
+    /// worth less than a ron.
+    #[default]
+    Loss,
+    /// The missing share is split between the two payers, rounded up to
+    /// the next 100 points each.
+    Split,
+}
+
+/// Which scores knock a player out and end the game at once (tobi,
+/// bankruptcy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tobi {
+    /// Play on whatever the scores, as in many tournaments.
+    Never,
+    /// End the game once a player's points are below zero.
+    #[default]
+    BelowZero,
+    /// End the game once a player has no points left, zero included.
+    AtZero,
+}
+
+impl Tobi {
+    /// Whether a player on `points` is knocked out.
+    pub fn is_bust(self, points: i32) -> bool {
+        match self {
+            Tobi::Never => false,
+            Tobi::BelowZero => points < 0,
+            Tobi::AtZero => points <= 0,
+        }
+    }
+}
+
+/// Swap-calling (kuikae) restrictions on the discard that follows a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Kuikae {
+    /// Any tile may be discarded.
+    Allowed,
+    /// The called tile's kind may not be discarded.
+    SameTile,
+    /// Neither the called tile's kind nor, after a chii on the end of a
+    /// sequence, the tile on the other end of the two tiles used.
+    #[default]
+    SameTileAndSuji,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            kuitan: true,
+            atozuke: true,
+            kiriage_mangan: false,
+            double_yakuman: true,
+            stack_yakuman: true,
+            nagashi_mangan: true,
+            kuikae: Kuikae::SameTileAndSuji,
+            return_deposits_on_abort: false,
+            sanma_tsumo: SanmaTsumo::Loss,
+            abortive_draws: AbortiveDraws::all(),
+            multiple_ron: true,
+            extension_target: Some(30000),
+            tobi: Tobi::BelowZero,
+            agari_yame: true,
+            tenpai_yame: false,
+            uma_oka: UmaOka::default(),
+        }
+    }
+}
+
+impl RuleSet {
+    /// Tenhou ranked games: single yakuman only for the double forms,
+    /// double ron with triple ron aborting, and +20/+10/-10/-20 uma.
+    pub fn tenhou() -> RuleSet {
+        RuleSet {
+            double_yakuman: false,
+            uma_oka: UmaOka::tenhou(),
+            ..RuleSet::default()
+        }
+    }
+
+    /// Mahjong Soul ranked games: double yakuman, double ron with triple
+    /// ron aborting, and +15/+5/-5/-15 uma.
+    pub fn mahjong_soul() -> RuleSet {
+        RuleSet::default()
+    }
+
+    /// M.League: kiriage mangan, head bump, no abortive draws or double
+    /// yakuman, no extension past South 4, tobi or agari-yame, and
+    /// +30/+10/-10/-30 uma.
+    pub fn mleague() -> RuleSet {
+        RuleSet {
+            kiriage_mangan: true,
+            double_yakuman: false,
+            abortive_draws: AbortiveDraws::none(),
+            multiple_ron: false,
+            extension_target: None,
+            tobi: Tobi::Never,
+            agari_yame: false,
+            uma_oka: UmaOka::mleague(),
+            ..RuleSet::default()
+        }
+    }
+}
+
+/// End-of-game adjustments to the raw scores: the placement bonus (uma)
+/// and the top prize (oka) funded by everyone starting below the return
+/// score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UmaOka {
+    /// Points each player starts with.
+    pub starting_points: i32,
+    /// Points subtracted from every final score (kaeshi). The difference
+    /// from the starting points, times four, goes to first place as oka.
+    pub return_points: i32,
+    /// Bonus for first to fourth place, in thousands of points. Three-player
+    /// games use the first three.
+    pub uma: [i32; 4],
+}
+
+impl Default for UmaOka {
+    /// 25000 points returned at 30000, with +15/+5/-5/-15 uma.
+    fn default() -> Self {
+        UmaOka {
+            starting_points: 25000,
+            return_points: 30000,
+            uma: [15, 5, -5, -15],
+        }
+    }
+}
+
+impl UmaOka {
+    /// Tenhou ranked games: 25000 returned at 30000, +20/+10/-10/-20.
+    pub fn tenhou() -> UmaOka {
+        UmaOka {
+            uma: [20, 10, -10, -20],
+            ..UmaOka::default()
+        }
+    }
+
+    /// M.League: 25000 returned at 30000, +30/+10/-10/-30.
+    pub fn mleague() -> UmaOka {
+        UmaOka {
+            uma: [30, 10, -10, -30],
+            ..UmaOka::default()
+        }
+    }
+
+    /// No uma or oka: raw scores are final.
+    pub fn none() -> UmaOka {
+        UmaOka {
+            starting_points: 25000,
+            return_points: 25000,
+            uma: [0; 4],
+        }
+    }
+
+    /// The oka paid to first place by a table of `num_players`.
+    pub fn oka(&self, num_players: NumPlayers) -> i32 {
+        num_players.count() as i32 * (self.return_points - self.starting_points)
+    }
+}
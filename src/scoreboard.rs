@@ -0,0 +1,771 @@
+//! Running scores across the rounds of a game, and the final standings.
+
+use crate::game::{
+    EarlyEnd, Game, Length, NumPlayers, PlayerLocation, Round, RoundAction, RoundConfig,
+    RoundConfigBuilder, RoundNumber,
+};
+use crate::hand::RIICHI_DEPOSIT;
+use crate::rules::{RuleSet, SanmaTsumo, UmaOka};
+use crate::scoring::{nagashi_mangan_payment, noten_payments, Payment};
+
+/// Points each player starts a standard game with.
+pub const STARTING_POINTS: i32 = 25000;
+
+/// Points each player starts a standard three-player game with.
+pub const THREE_PLAYER_STARTING_POINTS: i32 = 35000;
+
+/// Each player's points, plus the repeat counters and riichi deposits on
+/// the table, updated as rounds finish.
+///
+/// Record riichi with [`Scoreboard::declare_riichi`] as it happens, then
+/// end each round with exactly one of [`Scoreboard::record_win`],
+/// [`Scoreboard::record_rons`], [`Scoreboard::record_exhaustive_draw`],
+/// [`Scoreboard::record_draw`] or [`Scoreboard::record_abort`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoreboard {
+    /// Points by [`PlayerLocation::index`]. The empty seat of a
+    /// three-player game stays at its starting points.
+    scores: [i32; 4],
+    num_players: NumPlayers,
+    sanma_tsumo: SanmaTsumo,
+    honba: u8,
+    riichi_sticks: u8,
+    /// Who has paid a deposit in the current round.
+    declared: Vec<PlayerLocation>,
+    /// Why the dealer of the last finished round deals again, if they do.
+    renchan: Option<Renchan>,
+}
+
+/// Why a dealer deals again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Renchan {
+    Win,
+    Tenpai,
+    /// Any other draw.
+    Draw,
+}
+
+impl Default for Scoreboard {
+    fn default() -> Self {
+        Scoreboard::new(STARTING_POINTS)
+    }
+}
+
+impl Scoreboard {
+    /// A new four-player game in which every player starts with
+    /// `starting_points`.
+    pub fn new(starting_points: i32) -> Scoreboard {
+        Scoreboard {
+            scores: [starting_points; 4],
+            num_players: NumPlayers::Four,
+            sanma_tsumo: SanmaTsumo::default(),
+            honba: 0,
+            riichi_sticks: 0,
+            declared: Vec::new(),
+            renchan: None,
+        }
+    }
+
+    /// A new three-player game, paying tsumo as `rules` say.
+    pub fn three_player(starting_points: i32, rules: &RuleSet) -> Scoreboard {
+        Scoreboard {
+            num_players: NumPlayers::Three,
+            sanma_tsumo: rules.sanma_tsumo,
+            ..Scoreboard::new(starting_points)
+        }
+    }
+
+    pub fn num_players(&self) -> NumPlayers {
+        self.num_players
+    }
+
+    pub fn score(&self, player: PlayerLocation) -> i32 {
+        self.scores[player.index()]
+    }
+
+    /// Repeat counters for the next round.
+    pub fn honba(&self) -> u8 {
+        self.honba
+    }
+
+    /// Riichi deposits waiting on the table for the next winner.
+    pub fn riichi_sticks(&self) -> u8 {
+        self.riichi_sticks
+    }
+
+    /// `player` pays a riichi deposit to the table.
+    pub fn declare_riichi(&mut self, player: PlayerLocation) {
+        self.scores[player.index()] -= RIICHI_DEPOSIT;
+        self.riichi_sticks += 1;
+        self.declared.push(player);
+    }
+
+    /// Ends the round with a win. `payment` is the hand's value alone: the
+    /// current honba and every deposit on the table are added to it here.
+    /// The honba carries on, one higher, if the dealer won, and is reset
+    /// otherwise.
+    pub fn record_win(
+        &mut self,
+        winner: PlayerLocation,
+        dealer: PlayerLocation,
+        discarder: Option<PlayerLocation>,
+        payment: Payment,
+    ) {
+        let payment = payment
+            .with_honba(self.honba)
+            .with_riichi_sticks(self.riichi_sticks);
+        let deltas = match self.num_players {
+            NumPlayers::Three => {
+                payment.three_player_deltas(winner, dealer, discarder, self.sanma_tsumo)
+            }
+            NumPlayers::Four => payment.deltas(winner, dealer, discarder).to_vec(),
+        };
+        for (seat, delta) in deltas {
+            self.scores[seat.index()] += delta;
+        }
+        self.riichi_sticks = 0;
+        self.honba = if winner == dealer { self.honba + 1 } else { 0 };
+        self.renchan = (winner == dealer).then_some(Renchan::Win);
+        self.declared.clear();
+    }
+
+    /// Ends the round with one or more players ronning `discarder`, each
+    /// paid their own `payment` plus the honba. The deposits on the table
+    /// go to the first winner in turn order from the discarder. The honba
+    /// carries on if the dealer was among the winners.
+    pub fn record_rons(
+        &mut self,
+        dealer: PlayerLocation,
+        discarder: PlayerLocation,
+        wins: &[(PlayerLocation, Payment)],
+    ) {
+        let first = wins
+            .iter()
+            .map(|&(winner, _)| winner)
+            .min_by_key(|w| w.seen_from_in(discarder, self.num_players).index());
+        for &(winner, payment) in wins {
+            let mut payment = payment.with_honba(self.honba);
+            if Some(winner) == first {
+                payment = payment.with_riichi_sticks(self.riichi_sticks);
+            }
+            for (seat, delta) in payment.deltas(winner, dealer, Some(discarder)) {
+                self.scores[seat.index()] += delta;
+            }
+        }
+        let renchan = wins.iter().any(|&(winner, _)| winner == dealer);
+        self.riichi_sticks = 0;
+        self.honba = if renchan { self.honba + 1 } else { 0 };
+        self.renchan = renchan.then_some(Renchan::Win);
+        self.declared.clear();
+    }
+
+    /// Ends the round in an exhaustive draw with `tenpai` the tenpai
+    /// players, collecting the noten payments. Deposits stay on the table
+    /// and the honba goes up. The dealer deals again only if tenpai.
+    pub fn record_exhaustive_draw(&mut self, dealer: PlayerLocation, tenpai: &[PlayerLocation]) {
+        for (seat, delta) in noten_payments(tenpai, self.num_players) {
+            self.scores[seat.index()] += delta;
+        }
+        self.record_draw();
+        self.renchan = tenpai.contains(&dealer).then_some(Renchan::Tenpai);
+    }
+
+    /// Ends the round in a draw without noten payments. Deposits stay on
+    /// the table, the honba goes up and the dealer deals again.
+    pub fn record_draw(&mut self) {
+        self.honba += 1;
+        self.renchan = Some(Renchan::Draw);
+        self.declared.clear();
+    }
+
+    /// Ends the round in an abortive draw. The honba goes up, and this
+    /// round's deposits go back to their players if `rules` say so.
+    pub fn record_abort(&mut self, rules: &RuleSet) {
+        if rules.return_deposits_on_abort {
+            for player in &self.declared {
+                self.scores[player.index()] += RIICHI_DEPOSIT;
+                self.riichi_sticks -= 1;
+            }
+        }
+        self.record_draw();
+    }
+
+    /// The wind, number, repeat count and dealer of the round after
+    /// `current`, once it has been recorded here, ready for the dora and
+    /// hand to be added.
+    ///
+    /// The dealer deals again after winning, or after a draw as the
+    /// recording method describes; otherwise the deal passes to the right
+    /// and the round number goes up, moving on to the next wind after the
+    /// last dealer. Whether the game is over is not considered.
+    pub fn next_round_config(&self, current: &RoundConfig) -> RoundConfigBuilder {
+        let builder = RoundConfigBuilder::new()
+            .num_players(self.num_players)
+            .repeat(self.honba);
+        if self.renchan.is_some() {
+            return builder
+                .wind(current.wind)
+                .number(current.number)
+                .dealer(current.dealer);
+        }
+        let (wind, number) = match current.number.next_in(self.num_players) {
+            Some(next) => (current.wind, next),
+            None => (current.wind.next(), RoundNumber::One),
+        };
+        builder
+            .wind(wind)
+            .number(number)
+            .dealer(current.dealer.next_in(self.num_players))
+    }
+
+    /// Whether the scores end the game now under [`RuleSet::tobi`], and who
+    /// was knocked out, to be recorded in [`GameConfig::early_end`].
+    ///
+    /// [`GameConfig::early_end`]: crate::game::GameConfig::early_end
+    pub fn tobi(&self, rules: &RuleSet) -> Option<EarlyEnd> {
+        let busted: Vec<_> = self
+            .num_players
+            .seats()
+            .iter()
+            .copied()
+            .filter(|&seat| rules.tobi.is_bust(self.score(seat)))
+            .collect();
+        (!busted.is_empty()).then_some(EarlyEnd::Tobi(busted))
+    }
+
+    /// Whether the dealer of `current`, once it has been recorded here, may
+    /// end the game instead of dealing again, and how: agari-yame after a
+    /// win or tenpai-yame after a tenpai exhaustive draw, as `rules`
+    /// allow. `current` must be the last hand of a game of `length` and the
+    /// dealer alone in first place.
+    pub fn dealer_stop(
+        &self,
+        current: &RoundConfig,
+        length: Length,
+        rules: &RuleSet,
+    ) -> Option<EarlyEnd> {
+        let stop = match self.renchan? {
+            Renchan::Win if rules.agari_yame => EarlyEnd::AgariYame,
+            Renchan::Tenpai if rules.tenpai_yame => EarlyEnd::TenpaiYame,
+            _ => return None,
+        };
+        let dealer = self.score(current.dealer);
+        let top = self
+            .num_players
+            .seats()
+            .iter()
+            .all(|&seat| seat == current.dealer || self.score(seat) < dealer);
+        (top && length.is_last_hand(current, self.num_players)).then_some(stop)
+    }
+
+    /// The current scores in the form of [`RoundConfig::result`] and
+    /// [`GameConfig::result`], in [`NumPlayers::seats`] order.
+    ///
+    /// [`RoundConfig::result`]: crate::game::RoundConfig::result
+    /// [`GameConfig::result`]: crate::game::GameConfig::result
+    pub fn result(&self) -> Vec<(PlayerLocation, i32)> {
+        self.num_players
+            .seats()
+            .iter()
+            .map(|&seat| (seat, self.score(seat)))
+            .collect()
+    }
+}
+
+/// One player's place at the end of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Standing {
+    pub player: PlayerLocation,
+    /// 1 to the number of players.
+    pub place: u8,
+    /// Raw final points.
+    pub points: i32,
+    /// Points after uma and oka, e.g. 47000 for a final result usually
+    /// written as +47.0.
+    pub adjusted: i32,
+}
+
+impl Game {
+    /// Each player's final place and adjusted score under `uma_oka`, first
+    /// place first, or `None` if the game has no result.
+    ///
+    /// Players on equal points are ranked in seat order from the first
+    /// dealer, as is usual when uma is not split.
+    pub fn final_standings(&self, uma_oka: &UmaOka) -> Option<Vec<Standing>> {
+        let result = self.config.result.as_ref()?;
+        let num_players = self.config.num_players;
+        let first_dealer = self
+            .config
+            .first_dealer
+            .or_else(|| self.rounds.first().map(|r| r.config.dealer))
+            .unwrap_or(PlayerLocation::Hero);
+        let mut ranked = result.clone();
+        ranked.sort_by_key(|&(player, points)| {
+            (
+                std::cmp::Reverse(points),
+                player.seen_from_in(first_dealer, num_players),
+            )
+        });
+        let standings = ranked
+            .into_iter()
+            .zip(1..)
+            .map(|((player, points), place)| {
+                let mut adjusted =
+                    points - uma_oka.return_points + 1000 * uma_oka.uma[place as usize - 1];
+                if place == 1 {
+                    adjusted += uma_oka.oka(num_players);
+                }
+                Standing {
+                    player,
+                    place,
+                    points,
+                    adjusted,
+                }
+            })
+            .collect();
+        Some(standings)
+    }
+}
+
+/// The point changes caused by one event of a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreChange {
+    /// Index of the event in [`Round::events`].
+    pub event: usize,
+    /// The change in each player's score, in [`NumPlayers::seats`] order.
+    pub deltas: Vec<(PlayerLocation, i32)>,
+}
+
+impl Round {
+    /// The point changes in a four-player round under the default
+    /// [`RuleSet`], in event order.
+    pub fn score_deltas(&self) -> Vec<ScoreChange> {
+        self.score_deltas_with(&RuleSet::default(), NumPlayers::Four)
+    }
+
+    /// The point changes under `rules` at a table of `num_players`: a
+    /// riichi deposit once the declaring discard isn't ronned, each win
+    /// with a recorded score breakdown, and the noten payments or nagashi
+    /// mangan at an exhaustive draw. Chombo penalties aren't recorded as
+    /// events, so aren't included.
+    ///
+    /// A win pays what its breakdown says, honba and deposits included;
+    /// deposits are credited without being debited again, as they were
+    /// paid at the riichi.
+    pub fn score_deltas_with(&self, rules: &RuleSet, num_players: NumPlayers) -> Vec<ScoreChange> {
+        let dealer = self.config.dealer;
+        let seats = num_players.seats();
+        let single = |player: PlayerLocation, delta: i32| -> Vec<(PlayerLocation, i32)> {
+            seats
+                .iter()
+                .map(|&seat| (seat, if seat == player { delta } else { 0 }))
+                .collect()
+        };
+        let win = |winner, discarder, payment: &Payment| match num_players {
+            NumPlayers::Three => {
+                payment.three_player_deltas(winner, dealer, discarder, rules.sanma_tsumo)
+            }
+            NumPlayers::Four => payment.deltas(winner, dealer, discarder).to_vec(),
+        };
+        let mut changes = Vec::new();
+        let mut discarder = None;
+        for (i, event) in self.events.iter().enumerate() {
+            let deltas = match &event.action {
+                RoundAction::Discard(_) | RoundAction::AddedKan(_) => {
+                    discarder = Some(event.subject);
+                    let declared = self.events[..i]
+                        .iter()
+                        .rev()
+                        .find(|e| e.subject == event.subject)
+                        .is_some_and(|e| e.action == RoundAction::Riichi);
+                    let ronned = self
+                        .events
+                        .get(i + 1)
+                        .is_some_and(|e| matches!(e.action, RoundAction::Ron(..)));
+                    if !declared || ronned {
+                        continue;
+                    }
+                    single(event.subject, -RIICHI_DEPOSIT)
+                }
+                RoundAction::Tsumo(_, Some(details)) => win(event.subject, None, &details.payment),
+                RoundAction::Ron(_, Some(details)) => {
+                    win(event.subject, discarder, &details.payment)
+                }
+                RoundAction::Exhaustive(_) => {
+                    let nagashi = self.nagashi_mangan(rules);
+                    let mut deltas = if nagashi.is_empty() {
+                        noten_payments(&self.tenpai_players().unwrap_or_default(), num_players)
+                    } else {
+                        single(dealer, 0)
+                    };
+                    for &player in &nagashi {
+                        let payment = nagashi_mangan_payment(player == dealer);
+                        for (seat, delta) in win(player, None, &payment) {
+                            if let Some((_, total)) = deltas.iter_mut().find(|(s, _)| *s == seat) {
+                                *total += delta;
+                            }
+                        }
+                    }
+                    deltas
+                }
+                _ => continue,
+            };
+            changes.push(ScoreChange { event: i, deltas });
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerLocation::*;
+    use crate::game::{RoundEvent, RoundNumber};
+    use crate::hand::Hand;
+    use crate::scoring::{score, DoraCount, WinDetails, WinType};
+    use crate::tile::Direction;
+    use crate::tile::ObservedTile;
+    use crate::yaku::Yaku;
+
+    #[test]
+    fn wins_collect_honba_and_deposits() {
+        let mut board = Scoreboard::default();
+        board.declare_riichi(Hero);
+        board.record_draw();
+        assert_eq!((board.honba(), board.riichi_sticks()), (1, 1));
+        board.declare_riichi(Across);
+        // 3900 from the dealer, plus 300 for the honba and both deposits.
+        board.record_win(Right, Right, Some(Across), score(2, 40, true, WinType::Ron));
+        assert_eq!(
+            board.result(),
+            vec![
+                (Hero, 24000),
+                (Right, 25000 + 3900 + 300 + 2000),
+                (Across, 24000 - 3900 - 300),
+                (Left, 25000),
+            ]
+        );
+        assert_eq!((board.honba(), board.riichi_sticks()), (2, 0));
+        board.record_win(Left, Right, None, score(1, 30, false, WinType::Tsumo));
+        assert_eq!(board.score(Right), 31200 - 500 - 200);
+        assert_eq!(board.score(Hero), 24000 - 300 - 200);
+        assert_eq!(board.honba(), 0);
+    }
+
+    #[test]
+    fn three_player_scores() {
+        let rules = RuleSet {
+            sanma_tsumo: SanmaTsumo::Split,
+            ..RuleSet::default()
+        };
+        let mut board = Scoreboard::three_player(THREE_PLAYER_STARTING_POINTS, &rules);
+        board.record_win(Left, Hero, None, score(3, 30, false, WinType::Tsumo));
+        // 1000/2000 plus half of the missing 1000 from each payer.
+        assert_eq!(
+            board.result(),
+            vec![(Hero, 32500), (Right, 33500), (Left, 39000)]
+        );
+    }
+
+    #[test]
+    fn aborts_can_return_deposits() {
+        let mut board = Scoreboard::new(30000);
+        board.declare_riichi(Left);
+        board.record_draw();
+        board.declare_riichi(Hero);
+        let mut kept = board.clone();
+        kept.record_abort(&RuleSet::default());
+        assert_eq!((kept.score(Hero), kept.riichi_sticks()), (29000, 2));
+        let rules = RuleSet {
+            return_deposits_on_abort: true,
+            ..RuleSet::default()
+        };
+        board.record_abort(&rules);
+        assert_eq!(board.score(Hero), 30000);
+        assert_eq!(board.score(Left), 29000);
+        assert_eq!((board.honba(), board.riichi_sticks()), (2, 1));
+    }
+
+    fn finished_game(result: [(PlayerLocation, i32); 4]) -> Game {
+        let mut game = Game::default();
+        game.config.result = Some(result.to_vec());
+        game
+    }
+
+    #[test]
+    fn standings_apply_uma_and_oka() {
+        let game = finished_game([(Hero, 20000), (Right, 42000), (Across, 31000), (Left, 7000)]);
+        let standings = game.final_standings(&UmaOka::default()).unwrap();
+        let summary: Vec<(PlayerLocation, u8, i32)> = standings
+            .iter()
+            .map(|s| (s.player, s.place, s.adjusted))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Right, 1, 12000 + 15000 + 20000),
+                (Across, 2, 1000 + 5000),
+                (Hero, 3, -10000 - 5000),
+                (Left, 4, -23000 - 15000),
+            ]
+        );
+        assert_eq!(standings.iter().map(|s| s.adjusted).sum::<i32>(), 0);
+        let mleague = game.final_standings(&UmaOka::mleague()).unwrap();
+        assert_eq!(mleague[0].adjusted, 12000 + 30000 + 20000);
+        let raw = game.final_standings(&UmaOka::none()).unwrap();
+        assert_eq!(raw[3].adjusted, 7000 - 25000);
+        assert_eq!(Game::default().final_standings(&UmaOka::tenhou()), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_first_dealer_side() {
+        let mut game = finished_game([
+            (Hero, 25000),
+            (Right, 25000),
+            (Across, 25000),
+            (Left, 25000),
+        ]);
+        let order = |game: &Game| -> Vec<PlayerLocation> {
+            game.final_standings(&UmaOka::default())
+                .unwrap()
+                .iter()
+                .map(|s| s.player)
+                .collect()
+        };
+        assert_eq!(order(&game), vec![Hero, Right, Across, Left]);
+        game.rounds.push(Round {
+            config: RoundConfig {
+                wind: Direction::East,
+                number: RoundNumber::One,
+                repeat: 0,
+                dealer: Across,
+                dora: vec![],
+                ura_dora: vec![],
+                initial_hero_hand_state: Hand::default(),
+                result: None,
+                wall: Default::default(),
+            },
+            events: vec![],
+            annotation: None,
+        });
+        assert_eq!(order(&game), vec![Across, Left, Hero, Right]);
+    }
+
+    #[test]
+    fn negative_scores_in_results() {
+        let mut board = Scoreboard::new(1000);
+        board.record_win(Hero, Right, Some(Left), score(5, 30, false, WinType::Ron));
+        assert_eq!(board.score(Left), -7000);
+        assert_eq!(board.result()[3], (Left, -7000));
+    }
+
+    #[test]
+    fn double_ron_pays_both_winners() {
+        let mut board = Scoreboard::default();
+        board.declare_riichi(Left);
+        board.record_draw();
+        board.record_rons(
+            Hero,
+            Right,
+            &[
+                (Left, score(1, 30, false, WinType::Ron)),
+                (Hero, score(2, 30, true, WinType::Ron)),
+            ],
+        );
+        // Honba to both winners, and the deposit to Left, who comes before
+        // Hero in turn order from Right.
+        assert_eq!(
+            board.result(),
+            vec![
+                (Hero, 25000 + 2900 + 300),
+                (Right, 25000 - 1000 - 2900 - 600),
+                (Across, 25000),
+                (Left, 24000 + 1000 + 300 + 1000),
+            ]
+        );
+        assert_eq!((board.honba(), board.riichi_sticks()), (2, 0));
+    }
+
+    #[test]
+    fn exhaustive_draws_pay_noten() {
+        let mut board = Scoreboard::default();
+        board.declare_riichi(Right);
+        board.record_exhaustive_draw(Hero, &[Right, Across, Left]);
+        assert_eq!(
+            board.result(),
+            vec![
+                (Hero, 22000),
+                (Right, 25000),
+                (Across, 26000),
+                (Left, 26000)
+            ]
+        );
+        assert_eq!((board.honba(), board.riichi_sticks()), (1, 1));
+    }
+
+    #[test]
+    fn next_round_follows_the_dealer() {
+        let next = |board: &Scoreboard, current: &RoundConfig| -> RoundConfig {
+            board
+                .next_round_config(current)
+                .hand(Hand::parse("123m456p789s1234z").unwrap())
+                .dora_indicator(crate::tile::EAST)
+                .build()
+                .unwrap()
+        };
+        let summary = |c: &RoundConfig| (c.wind, c.number, c.repeat, c.dealer);
+        let mut board = Scoreboard::default();
+        let east4 = RoundConfigBuilder::new()
+            .number(RoundNumber::Four)
+            .dealer(Left)
+            .hand(Hand::parse("123m456p789s1234z").unwrap())
+            .dora_indicator(crate::tile::EAST)
+            .build()
+            .unwrap();
+        board.record_win(Left, Left, None, score(1, 30, true, WinType::Tsumo));
+        let again = next(&board, &east4);
+        assert_eq!(
+            summary(&again),
+            (Direction::East, RoundNumber::Four, 1, Left)
+        );
+        board.record_exhaustive_draw(Left, &[Hero]);
+        let south1 = next(&board, &again);
+        assert_eq!(
+            summary(&south1),
+            (Direction::South, RoundNumber::One, 2, Hero)
+        );
+        board.record_win(Right, Hero, Some(Across), score(1, 30, false, WinType::Ron));
+        assert_eq!(
+            summary(&next(&board, &south1)),
+            (Direction::South, RoundNumber::Two, 0, Right)
+        );
+
+        let mut sanma = Scoreboard::three_player(THREE_PLAYER_STARTING_POINTS, &RuleSet::default());
+        let east3 = RoundConfigBuilder::new()
+            .num_players(NumPlayers::Three)
+            .number(RoundNumber::Three)
+            .dealer(Left)
+            .hand(Hand::parse("19m19p19s1234567z").unwrap())
+            .dora_indicator(crate::tile::EAST)
+            .build()
+            .unwrap();
+        sanma.record_win(Hero, Left, Some(Left), score(1, 30, false, WinType::Ron));
+        assert_eq!(
+            summary(&next(&sanma, &east3)),
+            (Direction::South, RoundNumber::One, 0, Hero)
+        );
+    }
+
+    #[test]
+    fn tobi_ends_the_game() {
+        let mut board = Scoreboard::new(3000);
+        board.record_win(Right, Right, Some(Across), score(1, 30, true, WinType::Ron));
+        assert_eq!(board.tobi(&RuleSet::default()), None);
+        board.record_win(Right, Right, Some(Across), score(2, 40, true, WinType::Ron));
+        assert_eq!(board.score(Across), 1500 - 3900 - 300);
+        assert_eq!(
+            board.tobi(&RuleSet::default()),
+            Some(EarlyEnd::Tobi(vec![Across]))
+        );
+        assert_eq!(board.tobi(&RuleSet::mleague()), None);
+    }
+
+    #[test]
+    fn dealer_stops_in_first_place_on_the_last_hand() {
+        let east4 = RoundConfigBuilder::new()
+            .number(RoundNumber::Four)
+            .dealer(Left)
+            .hand(Hand::parse("123m456p789s1234z").unwrap())
+            .dora_indicator(crate::tile::EAST)
+            .build()
+            .unwrap();
+        let rules = RuleSet::default();
+        let mut board = Scoreboard::default();
+        board.record_win(Left, Left, None, score(1, 30, true, WinType::Tsumo));
+        assert_eq!(
+            board.dealer_stop(&east4, Length::Tonpuu, &rules),
+            Some(EarlyEnd::AgariYame)
+        );
+        assert_eq!(board.dealer_stop(&east4, Length::Hanchan, &rules), None);
+        assert_eq!(
+            board.dealer_stop(&east4, Length::Tonpuu, &RuleSet::mleague()),
+            None
+        );
+
+        board.record_exhaustive_draw(Left, &[Left]);
+        assert_eq!(board.dealer_stop(&east4, Length::Tonpuu, &rules), None);
+        let tenpai_yame = RuleSet {
+            tenpai_yame: true,
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            board.dealer_stop(&east4, Length::Tonpuu, &tenpai_yame),
+            Some(EarlyEnd::TenpaiYame)
+        );
+
+        let mut behind = Scoreboard::default();
+        behind.record_win(Hero, Left, Some(Right), score(3, 30, false, WinType::Ron));
+        behind.record_win(Left, Left, Some(Right), score(1, 30, true, WinType::Ron));
+        assert_eq!(behind.dealer_stop(&east4, Length::Tonpuu, &rules), None);
+    }
+
+    #[test]
+    fn score_deltas_by_event() {
+        let events = |actions: Vec<(PlayerLocation, RoundAction)>| {
+            actions
+                .into_iter()
+                .map(|(subject, action)| RoundEvent::new(subject, action))
+                .collect()
+        };
+        let t = |s: &str| crate::tile::build(s).unwrap();
+        let config = RoundConfigBuilder::new()
+            .hand(Hand::parse("123m456p789s1135z").unwrap())
+            .dora_indicator(crate::tile::EAST)
+            .build()
+            .unwrap();
+        let payment = score(2, 40, true, WinType::Ron).with_riichi_sticks(1);
+        let details = WinDetails {
+            winning_tile: t("1z"),
+            yaku: vec![(Yaku::Riichi, 1), (Yaku::Ippatsu, 1)],
+            fu: 40,
+            dora: DoraCount::default(),
+            payment,
+        };
+        let mut round = Round {
+            config,
+            events: events(vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("3z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Riichi),
+                (Right, RoundAction::Discard(t("1z"))),
+                (Hero, RoundAction::Ron(Hand::default(), Some(details))),
+            ]),
+            annotation: None,
+        };
+        let summary = |round: &Round| -> Vec<(usize, Vec<i32>)> {
+            round
+                .score_deltas()
+                .into_iter()
+                .map(|c| (c.event, c.deltas.into_iter().map(|(_, d)| d).collect()))
+                .collect()
+        };
+        assert_eq!(
+            summary(&round),
+            vec![(2, vec![-1000, 0, 0, 0]), (6, vec![4900, -3900, 0, 0])]
+        );
+
+        let tenpai = Hand::parse("123m456p789s1122z").unwrap();
+        round.events = events(vec![(Hero, RoundAction::Exhaustive(vec![(Hero, tenpai)]))]);
+        assert_eq!(summary(&round), vec![(0, vec![3000, -1000, -1000, -1000])]);
+        round.events = events(vec![
+            (Hero, RoundAction::Draw(t("5m").into())),
+            (Hero, RoundAction::Discard(t("5m"))),
+            (Right, RoundAction::Draw(ObservedTile::Hidden)),
+            (Right, RoundAction::Discard(t("1p"))),
+            (Right, RoundAction::Exhaustive(vec![])),
+        ]);
+        assert_eq!(summary(&round), vec![(4, vec![-4000, 8000, -2000, -2000])]);
+    }
+}
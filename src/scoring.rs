@@ -0,0 +1,598 @@
+//! Turning han and fu into points.
+//!
+//! Amounts follow the standard Japanese table: a hand's base points are
+//! `fu * 2^(han + 2)`, capped at mangan, and each payment is a multiple of
+//! the base rounded up to the next 100.
+
+use crate::game::{NumPlayers, PlayerLocation, RoundConfig};
+use crate::hand::Hand;
+use crate::rules::{RuleSet, SanmaTsumo};
+use crate::tile::{self, MahjongTile};
+use crate::yaku::Yaku;
+
+/// How a hand was won.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WinType {
+    /// On another player's discard; the discarder pays everything.
+    Ron,
+    /// On a self-drawn tile; every other player pays a share.
+    Tsumo,
+}
+
+/// The named scoring tiers that replace the han and fu calculation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Limit {
+    /// 5 han, or fewer han whose base points reach 2000.
+    Mangan,
+    /// 6–7 han.
+    Haneman,
+    /// 8–10 han.
+    Baiman,
+    /// 11–12 han.
+    Sanbaiman,
+    /// 13 han or more, or a yakuman hand, counting how many yakuman the hand
+    /// is worth.
+    Yakuman(u8),
+}
+
+impl Limit {
+    /// The base points of the tier.
+    pub fn base_points(&self) -> u32 {
+        match self {
+            Limit::Mangan => 2000,
+            Limit::Haneman => 3000,
+            Limit::Baiman => 4000,
+            Limit::Sanbaiman => 6000,
+            Limit::Yakuman(n) => 8000 * *n as u32,
+        }
+    }
+}
+
+/// What each player pays the winner of a hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Payment {
+    pub win_type: WinType,
+    /// Whether the winner is the dealer.
+    pub dealer: bool,
+    /// Paid by the discarder on ron; 0 on tsumo.
+    pub from_discarder: u32,
+    /// Paid by the dealer when a non-dealer wins by tsumo; 0 otherwise.
+    pub from_dealer: u32,
+    /// Paid by each non-dealer, other than the winner, on tsumo; 0 on ron.
+    pub from_non_dealer: u32,
+    /// Riichi deposits collected from the table, in points.
+    pub deposits: u32,
+    /// The tier the hand reached, if any.
+    pub limit: Option<Limit>,
+}
+
+impl Payment {
+    /// Adds `honba` repeat counters: 300 points each, paid by the discarder
+    /// on ron or split 100 per player on tsumo.
+    pub fn with_honba(mut self, honba: u8) -> Payment {
+        let honba = honba as u32;
+        match self.win_type {
+            WinType::Ron => self.from_discarder += 300 * honba,
+            WinType::Tsumo => {
+                if !self.dealer {
+                    self.from_dealer += 100 * honba;
+                }
+                self.from_non_dealer += 100 * honba;
+            }
+        }
+        self
+    }
+
+    /// Adds `sticks` riichi deposits of 1000 points each for the winner.
+    pub fn with_riichi_sticks(mut self, sticks: u8) -> Payment {
+        self.deposits += 1000 * sticks as u32;
+        self
+    }
+
+    /// The points the winner gains in a four-player game, including
+    /// deposits.
+    pub fn total(&self) -> u32 {
+        let paid = match (self.win_type, self.dealer) {
+            (WinType::Ron, _) => self.from_discarder,
+            (WinType::Tsumo, true) => 3 * self.from_non_dealer,
+            (WinType::Tsumo, false) => self.from_dealer + 2 * self.from_non_dealer,
+        };
+        paid + self.deposits
+    }
+
+    /// The change in each player's score in a four-player game, in
+    /// Hero, Right, Across, Left order. `discarder` is only used on ron.
+    ///
+    /// Deposits are credited to the winner without being debited from
+    /// anyone, as they were paid when riichi was declared.
+    pub fn deltas(
+        &self,
+        winner: PlayerLocation,
+        dealer: PlayerLocation,
+        discarder: Option<PlayerLocation>,
+    ) -> [(PlayerLocation, i32); 4] {
+        let seats = [
+            PlayerLocation::Hero,
+            PlayerLocation::Right,
+            PlayerLocation::Across,
+            PlayerLocation::Left,
+        ];
+        seats.map(|seat| {
+            let paid = if seat == winner {
+                0
+            } else {
+                match self.win_type {
+                    WinType::Ron if Some(seat) == discarder => self.from_discarder,
+                    WinType::Ron => 0,
+                    WinType::Tsumo if seat == dealer => self.from_dealer,
+                    WinType::Tsumo => self.from_non_dealer,
+                }
+            };
+            let delta = if seat == winner {
+                self.total() as i32
+            } else {
+                -(paid as i32)
+            };
+            (seat, delta)
+        })
+    }
+
+    /// The change in each player's score in a three-player game, in
+    /// [`NumPlayers::seats`] order. Ron is paid as in a four-player game;
+    /// on tsumo the share of the missing player is handled as `rule` says.
+    pub fn three_player_deltas(
+        &self,
+        winner: PlayerLocation,
+        dealer: PlayerLocation,
+        discarder: Option<PlayerLocation>,
+        rule: SanmaTsumo,
+    ) -> Vec<(PlayerLocation, i32)> {
+        let extra = match (self.win_type, rule) {
+            (WinType::Tsumo, SanmaTsumo::Split) => self.from_non_dealer.div_ceil(200) * 100,
+            _ => 0,
+        };
+        let mut deltas: Vec<(PlayerLocation, i32)> = NumPlayers::Three
+            .seats()
+            .iter()
+            .map(|&seat| {
+                let paid = match self.win_type {
+                    _ if seat == winner => 0,
+                    WinType::Ron if Some(seat) == discarder => self.from_discarder,
+                    WinType::Ron => 0,
+                    WinType::Tsumo if seat == dealer => self.from_dealer + extra,
+                    WinType::Tsumo => self.from_non_dealer + extra,
+                };
+                (seat, -(paid as i32))
+            })
+            .collect();
+        let won = self.deposits as i32 - deltas.iter().map(|&(_, d)| d).sum::<i32>();
+        for (seat, delta) in &mut deltas {
+            if *seat == winner {
+                *delta = won;
+            }
+        }
+        deltas
+    }
+}
+
+/// Scores a hand of `han` han and `fu` fu.
+///
+/// Fu are rounded up to the next 10, except for the 25 fu of seven pairs.
+/// 13 han or more scores as a counted (kazoe) yakuman. Honba and riichi
+/// deposits are added with [`Payment::with_honba`] and
+/// [`Payment::with_riichi_sticks`].
+pub fn score(han: u8, fu: u8, is_dealer: bool, win_type: WinType) -> Payment {
+    let limit = match han {
+        0..=4 => None,
+        5 => Some(Limit::Mangan),
+        6 | 7 => Some(Limit::Haneman),
+        8..=10 => Some(Limit::Baiman),
+        11 | 12 => Some(Limit::Sanbaiman),
+        _ => Some(Limit::Yakuman(1)),
+    };
+    let fu = if fu == 25 {
+        25
+    } else {
+        (fu as u32).div_ceil(10) * 10
+    };
+    match limit {
+        Some(limit) => limit_payment(limit, is_dealer, win_type),
+        None => match fu * 2u32.pow(han as u32 + 2) {
+            base if base >= 2000 => limit_payment(Limit::Mangan, is_dealer, win_type),
+            base => payment(base, None, is_dealer, win_type),
+        },
+    }
+}
+
+/// Scores a hand like [`score`], rounding 4 han 30 fu and 3 han 60 fu up to
+/// mangan if `rules` play kiriage mangan.
+pub fn score_with(han: u8, fu: u8, is_dealer: bool, win_type: WinType, rules: &RuleSet) -> Payment {
+    let fu_rounded = (fu as u32).div_ceil(10) * 10;
+    match (han, fu_rounded) {
+        (4, 30) | (3, 60) if rules.kiriage_mangan => {
+            limit_payment(Limit::Mangan, is_dealer, win_type)
+        }
+        _ => score(han, fu, is_dealer, win_type),
+    }
+}
+
+/// Scores a hand worth `multiple` yakuman, e.g. 2 for a double yakuman.
+pub fn yakuman_score(multiple: u8, is_dealer: bool, win_type: WinType) -> Payment {
+    limit_payment(Limit::Yakuman(multiple), is_dealer, win_type)
+}
+
+/// The payment for nagashi mangan: a mangan paid as if won by tsumo.
+/// Honba and riichi deposits are not collected.
+pub fn nagashi_mangan_payment(is_dealer: bool) -> Payment {
+    limit_payment(Limit::Mangan, is_dealer, WinType::Tsumo)
+}
+
+/// Points the noten players pay in total to the tenpai players at an
+/// exhaustive draw.
+pub const NOTEN_PAYMENT: i32 = 3000;
+
+/// The noten payments at an exhaustive draw where `tenpai` are the only
+/// tenpai players, as score changes in [`NumPlayers::seats`] order: the
+/// noten players share [`NOTEN_PAYMENT`] between them and the tenpai
+/// players share it out, e.g. 1000 from each to a lone tenpai player, or
+/// 1500 each from two noten players to two tenpai players. Nothing changes
+/// hands if everyone or nobody is tenpai.
+pub fn noten_payments(
+    tenpai: &[PlayerLocation],
+    num_players: NumPlayers,
+) -> Vec<(PlayerLocation, i32)> {
+    let seats = num_players.seats();
+    let winners = seats.iter().filter(|s| tenpai.contains(s)).count() as i32;
+    let losers = seats.len() as i32 - winners;
+    seats
+        .iter()
+        .map(|&seat| {
+            let delta = match (winners, losers) {
+                (0, _) | (_, 0) => 0,
+                _ if tenpai.contains(&seat) => NOTEN_PAYMENT / winners,
+                _ => -NOTEN_PAYMENT / losers,
+            };
+            (seat, delta)
+        })
+        .collect()
+}
+
+fn limit_payment(limit: Limit, is_dealer: bool, win_type: WinType) -> Payment {
+    payment(limit.base_points(), Some(limit), is_dealer, win_type)
+}
+
+fn payment(base: u32, limit: Option<Limit>, dealer: bool, win_type: WinType) -> Payment {
+    let round = |points: u32| points.div_ceil(100) * 100;
+    let (from_discarder, from_dealer, from_non_dealer) = match (win_type, dealer) {
+        (WinType::Ron, true) => (round(6 * base), 0, 0),
+        (WinType::Ron, false) => (round(4 * base), 0, 0),
+        (WinType::Tsumo, true) => (0, 0, round(2 * base)),
+        (WinType::Tsumo, false) => (0, round(2 * base), round(base)),
+    };
+    Payment {
+        win_type,
+        dealer,
+        from_discarder,
+        from_dealer,
+        from_non_dealer,
+        deposits: 0,
+        limit,
+    }
+}
+
+/// The score breakdown of a win, as recorded by the platform that hosted
+/// it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WinDetails {
+    /// The tile that completed the hand.
+    pub winning_tile: MahjongTile,
+    /// Each yaku with the han it was worth. Yakuman are listed at 13 han
+    /// per multiple.
+    pub yaku: Vec<(Yaku, u8)>,
+    /// 0 for limit hands whose fu were not recorded.
+    pub fu: u8,
+    pub dora: DoraCount,
+    /// What was paid, including honba and riichi deposits.
+    pub payment: Payment,
+}
+
+impl WinDetails {
+    /// The hand's total han: every yaku plus every dora.
+    pub fn han(&self) -> u8 {
+        self.yaku.iter().map(|&(_, han)| han).sum::<u8>() + self.dora.total()
+    }
+}
+
+/// The dora in a winning hand, by kind. Each counts one han.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DoraCount {
+    /// Tiles indicated by the dora indicators.
+    pub dora: u8,
+    /// Tiles indicated by the ura dora indicators; only counted after
+    /// riichi.
+    pub ura_dora: u8,
+    /// Red fives.
+    pub aka_dora: u8,
+    /// Norths set aside in a three-player game.
+    pub nuki_dora: u8,
+}
+
+impl DoraCount {
+    /// All dora combined.
+    pub fn total(&self) -> u8 {
+        self.dora + self.ura_dora + self.aka_dora + self.nuki_dora
+    }
+}
+
+/// Counts the dora in a four-player winning hand, including its melds.
+///
+/// Each indicator in `config.dora` counts every copy of the tile it
+/// indicates, so a tile indicated twice counts twice. Ura dora only count
+/// when `riichi` is set.
+pub fn count_dora(hand: &Hand, config: &RoundConfig, riichi: bool) -> DoraCount {
+    dora_in(hand, config, riichi, NumPlayers::Four, 0)
+}
+
+/// Counts the dora in a three-player winning hand whose owner has set
+/// aside `nuki` Norths.
+///
+/// Indicators follow the three-player dora order, where 1m indicates 9m.
+/// Each set-aside North counts as a nuki dora, and again as a dora for each
+/// indicator pointing at North.
+pub fn count_dora_three_player(
+    hand: &Hand,
+    config: &RoundConfig,
+    riichi: bool,
+    nuki: u8,
+) -> DoraCount {
+    dora_in(hand, config, riichi, NumPlayers::Three, nuki)
+}
+
+fn dora_in(
+    hand: &Hand,
+    config: &RoundConfig,
+    riichi: bool,
+    num_players: NumPlayers,
+    nuki: u8,
+) -> DoraCount {
+    let mut tiles = hand.tiles();
+    tiles.extend(std::iter::repeat_n(tile::NORTH, nuki as usize));
+    let count = |indicators: &[MahjongTile]| {
+        indicators
+            .iter()
+            .map(|indicator| {
+                let dora = indicator.dora_from_indicator(num_players);
+                tiles.iter().filter(|t| t.eq_rank(&dora)).count() as u8
+            })
+            .sum()
+    };
+    DoraCount {
+        dora: count(&config.dora),
+        ura_dora: if riichi { count(&config.ura_dora) } else { 0 },
+        aka_dora: hand.tiles().iter().filter(|t| t.is_red()).count() as u8,
+        nuki_dora: nuki,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::RoundNumber;
+    use crate::tile::{parse_tiles, Direction};
+
+    fn config(dora: &str, ura_dora: &str) -> RoundConfig {
+        RoundConfig {
+            wind: Direction::East,
+            number: RoundNumber::One,
+            repeat: 0,
+            dealer: PlayerLocation::Hero,
+            dora: parse_tiles(dora).unwrap(),
+            ura_dora: parse_tiles(ura_dora).unwrap(),
+            initial_hero_hand_state: Hand::default(),
+            result: None,
+            wall: Default::default(),
+        }
+    }
+
+    #[test]
+    fn dora_in_hand_and_melds() {
+        let hand = Hand::parse("234m067p11z + p999s + c345m").unwrap();
+        let count = count_dora(&hand, &config("8s3m", "4z"), false);
+        assert_eq!(
+            count,
+            DoraCount {
+                dora: 5,
+                ura_dora: 0,
+                aka_dora: 1,
+                nuki_dora: 0,
+            }
+        );
+        assert_eq!(count.total(), 6);
+        // 4z indicates 1z; ura dora only count after riichi.
+        assert_eq!(count_dora(&hand, &config("8s3m", "4z"), true).ura_dora, 2);
+    }
+
+    #[test]
+    fn repeated_indicators_count_twice() {
+        let hand = Hand::parse("45p + k5555s").unwrap();
+        let count = count_dora(&hand, &config("4p4p", ""), false);
+        assert_eq!((count.dora, count.aka_dora), (2, 0));
+        let count = count_dora(&hand, &config("4s4s", "3p"), true);
+        assert_eq!((count.dora, count.ura_dora), (8, 1));
+    }
+
+    #[test]
+    fn three_player_dora() {
+        let hand = Hand::parse("99m123p456s11z44z").unwrap();
+        assert_eq!(count_dora(&hand, &config("1m", ""), false).dora, 0);
+        let count = count_dora_three_player(&hand, &config("1m3z", ""), false, 2);
+        assert_eq!(count.dora, 6);
+        assert_eq!(count.nuki_dora, 2);
+        assert_eq!(count.total(), 8);
+    }
+
+    fn ron(han: u8, fu: u8, dealer: bool) -> u32 {
+        score(han, fu, dealer, WinType::Ron).from_discarder
+    }
+
+    fn tsumo(han: u8, fu: u8, dealer: bool) -> (u32, u32) {
+        let payment = score(han, fu, dealer, WinType::Tsumo);
+        (payment.from_dealer, payment.from_non_dealer)
+    }
+
+    #[test]
+    fn table_values() {
+        assert_eq!(ron(1, 30, false), 1000);
+        assert_eq!(ron(1, 30, true), 1500);
+        assert_eq!(ron(2, 25, false), 1600);
+        assert_eq!(ron(3, 40, false), 5200);
+        assert_eq!(ron(4, 30, false), 7700);
+        assert_eq!(ron(4, 30, true), 11600);
+        assert_eq!(tsumo(1, 30, false), (500, 300));
+        assert_eq!(tsumo(2, 20, false), (700, 400));
+        assert_eq!(tsumo(1, 30, true), (0, 500));
+        assert_eq!(tsumo(3, 70, true), (0, 4000));
+    }
+
+    #[test]
+    fn fu_round_up() {
+        assert_eq!(ron(1, 32, false), ron(1, 40, false));
+        assert_eq!(ron(3, 22, false), ron(3, 30, false));
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(score(4, 40, false, WinType::Ron).limit, Some(Limit::Mangan));
+        assert_eq!(ron(4, 40, false), 8000);
+        assert_eq!(ron(5, 30, false), 8000);
+        assert_eq!(ron(6, 30, false), 12000);
+        assert_eq!(ron(8, 30, true), 24000);
+        assert_eq!(ron(11, 30, false), 24000);
+        assert_eq!(tsumo(7, 30, false), (6000, 3000));
+        let kazoe = score(13, 30, false, WinType::Ron);
+        assert_eq!(kazoe.limit, Some(Limit::Yakuman(1)));
+        assert_eq!(kazoe.from_discarder, 32000);
+        assert_eq!(ron(40, 30, false), 32000);
+        assert_eq!(ron(u8::MAX, 110, true), 48000);
+        assert_eq!(
+            yakuman_score(2, true, WinType::Tsumo).from_non_dealer,
+            32000
+        );
+        assert_eq!(score(1, 30, false, WinType::Ron).limit, None);
+    }
+
+    #[test]
+    fn nagashi_mangan_pays_a_tsumo_mangan() {
+        let payment = nagashi_mangan_payment(false);
+        assert_eq!((payment.from_dealer, payment.from_non_dealer), (4000, 2000));
+        assert_eq!(nagashi_mangan_payment(true).total(), 12000);
+    }
+
+    #[test]
+    fn honba_and_deposits() {
+        let payment = score(1, 30, false, WinType::Ron)
+            .with_honba(2)
+            .with_riichi_sticks(1);
+        assert_eq!(payment.from_discarder, 1600);
+        assert_eq!(payment.total(), 2600);
+        let payment = score(1, 30, false, WinType::Tsumo).with_honba(1);
+        assert_eq!((payment.from_dealer, payment.from_non_dealer), (600, 400));
+        assert_eq!(payment.total(), 1400);
+        let payment = score(1, 30, true, WinType::Tsumo).with_honba(1);
+        assert_eq!(payment.total(), 1800);
+    }
+
+    #[test]
+    fn deltas_by_seat() {
+        use PlayerLocation::*;
+        let payment = score(2, 30, false, WinType::Tsumo).with_riichi_sticks(1);
+        assert_eq!(
+            payment.deltas(Hero, Across, None),
+            [(Hero, 3000), (Right, -500), (Across, -1000), (Left, -500)]
+        );
+        let payment = score(2, 30, true, WinType::Ron);
+        assert_eq!(
+            payment.deltas(Left, Left, Some(Right)),
+            [(Hero, 0), (Right, -2900), (Across, 0), (Left, 2900)]
+        );
+    }
+
+    #[test]
+    fn three_player_tsumo_loss_or_split() {
+        use PlayerLocation::*;
+        let payment = score(2, 30, false, WinType::Tsumo);
+        assert_eq!(
+            payment.three_player_deltas(Hero, Right, None, SanmaTsumo::Loss),
+            vec![(Hero, 1500), (Right, -1000), (Left, -500)]
+        );
+        // The missing 500 is split as 250 each, rounded up.
+        assert_eq!(
+            payment
+                .with_riichi_sticks(1)
+                .three_player_deltas(Hero, Right, None, SanmaTsumo::Split),
+            vec![(Hero, 3100), (Right, -1300), (Left, -800)]
+        );
+        let payment = score(2, 30, true, WinType::Ron);
+        assert_eq!(
+            payment.three_player_deltas(Left, Left, Some(Right), SanmaTsumo::Split),
+            vec![(Hero, 0), (Right, -2900), (Left, 2900)]
+        );
+    }
+
+    #[test]
+    fn noten_payments_share_3000() {
+        use PlayerLocation::*;
+        assert_eq!(
+            noten_payments(&[Across], NumPlayers::Four),
+            vec![(Hero, -1000), (Right, -1000), (Across, 3000), (Left, -1000)]
+        );
+        assert_eq!(
+            noten_payments(&[Left, Hero], NumPlayers::Four),
+            vec![(Hero, 1500), (Right, -1500), (Across, -1500), (Left, 1500)]
+        );
+        assert_eq!(
+            noten_payments(&[Hero, Right, Left], NumPlayers::Three),
+            vec![(Hero, 0), (Right, 0), (Left, 0)]
+        );
+        assert_eq!(
+            noten_payments(&[Right], NumPlayers::Three),
+            vec![(Hero, -1500), (Right, 3000), (Left, -1500)]
+        );
+    }
+
+    #[test]
+    fn win_details_total_han() {
+        let details = WinDetails {
+            winning_tile: tile::build("5p").unwrap(),
+            yaku: vec![(Yaku::Riichi, 1), (Yaku::Pinfu, 1), (Yaku::Iipeikou, 1)],
+            fu: 30,
+            dora: DoraCount {
+                dora: 1,
+                aka_dora: 1,
+                ..DoraCount::default()
+            },
+            payment: score(5, 30, false, WinType::Ron),
+        };
+        assert_eq!(details.han(), 5);
+    }
+
+    #[test]
+    fn kiriage_mangan_rounds_up() {
+        let kiriage = RuleSet::mleague();
+        let plain = RuleSet::default();
+        let ron = |han, fu, rules| score_with(han, fu, false, WinType::Ron, rules).total();
+        assert_eq!(ron(4, 30, &plain), 7700);
+        assert_eq!(ron(4, 30, &kiriage), 8000);
+        assert_eq!(ron(3, 60, &kiriage), 8000);
+        assert_eq!(ron(3, 50, &kiriage), 6400);
+        assert_eq!(
+            score_with(4, 30, false, WinType::Ron, &kiriage).limit,
+            Some(Limit::Mangan)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Sets of games played by the same table, such as a league night.
+
+use crate::annotation::Annotation;
+use crate::game::{Game, Player};
+use crate::rules::UmaOka;
+
+/// Several games played one after another by the same players.
+///
+/// Players are told apart by name across games, so each game's seats are
+/// matched to the shared profiles in [`Session::players`] by
+/// [`Player::name`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Session {
+    /// Name of the league, tournament or night the games were part of.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "String::is_empty")
+    )]
+    pub event: String,
+    /// The profile of everyone who played, in the order they first sat
+    /// down.
+    pub players: Vec<Player>,
+    /// The games in the order they were played.
+    pub games: Vec<Game>,
+    /// Commentary on the session as a whole.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub annotation: Option<Annotation>,
+}
+
+/// One player's results over a [`Session`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStanding {
+    pub name: String,
+    /// The player's place in each finished game they played, in order.
+    pub places: Vec<u8>,
+    /// Sum of the adjusted scores, see
+    /// [`Standing::adjusted`](crate::scoreboard::Standing::adjusted).
+    pub total: i32,
+}
+
+impl Session {
+    /// Adds `game` as the latest game, and a profile for anyone in it who
+    /// hasn't played yet.
+    pub fn push_game(&mut self, game: Game) {
+        for &seat in game.config.num_players.seats() {
+            let player = game.config.player(seat);
+            if self.player(&player.name).is_none() {
+                self.players.push(player.clone());
+            }
+        }
+        self.games.push(game);
+    }
+
+    /// The shared profile of the player called `name`.
+    pub fn player(&self, name: &str) -> Option<&Player> {
+        self.players.iter().find(|p| p.name == name)
+    }
+
+    /// Everyone's places and total adjusted score over the finished games,
+    /// best total first. Players on equal totals stay in
+    /// [`Session::players`] order, and players without a finished game are
+    /// left out.
+    pub fn standings(&self, uma_oka: &UmaOka) -> Vec<SessionStanding> {
+        let mut standings: Vec<SessionStanding> = Vec::new();
+        for game in &self.games {
+            let Some(result) = game.final_standings(uma_oka) else {
+                continue;
+            };
+            for standing in result {
+                let name = &game.config.player(standing.player).name;
+                let index = match standings.iter().position(|s| &s.name == name) {
+                    Some(index) => index,
+                    None => {
+                        standings.push(SessionStanding {
+                            name: name.clone(),
+                            places: Vec::new(),
+                            total: 0,
+                        });
+                        standings.len() - 1
+                    }
+                };
+                standings[index].places.push(standing.place);
+                standings[index].total += standing.adjusted;
+            }
+        }
+        let order = |name: &str| self.players.iter().position(|p| p.name == name);
+        standings.sort_by_key(|s| (std::cmp::Reverse(s.total), order(&s.name)));
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameConfigBuilder;
+    use crate::game::PlayerLocation::*;
+
+    fn game(names: [&str; 4], result: [i32; 4]) -> Game {
+        Game {
+            config: GameConfigBuilder::new()
+                .players(names)
+                .result(vec![
+                    (Hero, result[0]),
+                    (Right, result[1]),
+                    (Across, result[2]),
+                    (Left, result[3]),
+                ])
+                .build()
+                .unwrap(),
+            rounds: vec![],
+            annotation: None,
+        }
+    }
+
+    #[test]
+    fn standings_add_up_over_games() {
+        let mut session = Session::default();
+        session.push_game(game(["a", "b", "c", "d"], [40000, 30000, 20000, 10000]));
+        session.push_game(game(["d", "c", "b", "e"], [40000, 30000, 20000, 10000]));
+        let mut unfinished = game(["a", "b", "c", "d"], [0; 4]);
+        unfinished.config.result = None;
+        session.push_game(unfinished);
+        let names: Vec<&str> = session.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+        assert_eq!(session.player("e"), Some(&Player::new("e")));
+        let summary: Vec<(String, Vec<u8>, i32)> = session
+            .standings(&UmaOka::none())
+            .into_iter()
+            .map(|s| (s.name, s.places, s.total))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a".to_string(), vec![1], 15000),
+                ("b".to_string(), vec![2, 3], 0),
+                ("c".to_string(), vec![3, 2], 0),
+                ("d".to_string(), vec![4, 1], 0),
+                ("e".to_string(), vec![4], -15000),
+            ]
+        );
+    }
+}
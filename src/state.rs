@@ -0,0 +1,1466 @@
+//! Replaying a round event by event.
+//!
+//! [`RoundState`] follows a [`Round`](crate::game::Round) from the deal,
+//! applying each [`RoundEvent`] in turn and rejecting events that could not
+//! have happened at that point. The hero's hand is tracked exactly; other
+//! players' concealed tiles are only counted, except where a call reveals
+//! them.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::game::{
+    AbortiveDraw, NumPlayers, PlayerLocation, Round, RoundAction, RoundConfig, RoundEvent,
+};
+use crate::hand::{Hand, Meld};
+use crate::rules::RuleSet;
+use crate::tile::{self, MahjongTile, ObservedTile};
+
+/// Tiles in the live wall after the deal in a four-player game: 136 less
+/// the 52 dealt and the 14 of the dead wall. Replacement draws after a kan
+/// also come out of this count, as the dead wall is topped up from the end
+/// of the live wall.
+pub const LIVE_WALL_TILES: usize = 70;
+
+/// Tiles in the live wall after the deal in a three-player game: 108 less
+/// 39 dealt and the dead wall.
+pub const THREE_PLAYER_LIVE_WALL_TILES: usize = 55;
+
+/// Tiles dealt to each player.
+const DEALT_TILES: usize = 13;
+
+/// What is known about one player partway through a round.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlayerState {
+    /// Concealed tiles whose identity is known: all of the hero's, and
+    /// none of anyone else's.
+    pub known: Vec<MahjongTile>,
+    /// How many concealed tiles are not known.
+    pub hidden: usize,
+    /// Called and declared melds, in the order they were made.
+    pub melds: Vec<Meld>,
+    /// The discard pond (kawa): every tile discarded, in order, including
+    /// those called away. See [`PlayerState::visible_pond`] for what is
+    /// still on the table.
+    pub discards: Vec<PondTile>,
+    /// Whether the player has declared riichi.
+    pub riichi: bool,
+    /// The tile the player has just drawn, kept through a riichi
+    /// declaration until their next action.
+    pub drawn: Option<ObservedTile>,
+    /// Whether the player's ippatsu window is open: from the riichi
+    /// discard until their next discard, unless someone calls first.
+    pub ippatsu: bool,
+    /// Norths set aside as nuki dora.
+    pub nuki: u8,
+    /// Total time taken over actions whose time was recorded.
+    pub elapsed: Duration,
+    /// Time bank left, if tracked with [`RoundState::with_time_bank`].
+    pub time_bank: Duration,
+}
+
+/// One discard in a player's pond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PondTile {
+    pub tile: MahjongTile,
+    /// The player who called the tile away into a meld, if anyone did.
+    pub called_by: Option<PlayerLocation>,
+    /// Whether this was the discard that declared riichi, shown sideways.
+    pub riichi: bool,
+    /// Whether the tile was the one just drawn (tsumogiri) rather than one
+    /// from the hand (tedashi), if the log records it or the state can
+    /// tell.
+    pub tsumogiri: Option<bool>,
+}
+
+impl PondTile {
+    /// Whether the tile was called away and is no longer in the pond.
+    pub fn is_called(&self) -> bool {
+        self.called_by.is_some()
+    }
+}
+
+impl PlayerState {
+    /// The discards still lying in the pond, without those called away.
+    pub fn visible_pond(&self) -> impl Iterator<Item = &PondTile> {
+        self.discards.iter().filter(|d| !d.is_called())
+    }
+
+    /// Where the riichi declaration tile is in [`PlayerState::discards`],
+    /// once it has been discarded.
+    pub fn riichi_discard(&self) -> Option<usize> {
+        self.discards.iter().position(|d| d.riichi)
+    }
+
+    /// Whether the player has declared riichi but not yet made the
+    /// declaring discard.
+    pub fn riichi_pending(&self) -> bool {
+        self.riichi && self.riichi_discard().is_none()
+    }
+
+    /// Number of concealed tiles, known or not.
+    pub fn concealed_count(&self) -> usize {
+        self.known.len() + self.hidden
+    }
+
+    /// The player's hand, if all of its concealed tiles are known.
+    pub fn hand(&self) -> Option<Hand> {
+        if self.hidden > 0 {
+            return None;
+        }
+        let mut concealed = self.known.clone();
+        tile::sort_hand(&mut concealed);
+        Some(Hand {
+            concealed,
+            melds: self.melds.clone(),
+        })
+    }
+
+    fn draw(&mut self, tile: ObservedTile) {
+        match tile {
+            ObservedTile::Known(tile) => self.known.push(tile),
+            ObservedTile::Hidden => self.hidden += 1,
+        }
+    }
+
+    /// Removes `tile` from the concealed tiles, spending an unknown tile if
+    /// it isn't among the known ones.
+    fn take(&mut self, tile: MahjongTile) -> Result<(), StateError> {
+        if let Some(pos) = self.known.iter().position(|t| *t == tile) {
+            self.known.remove(pos);
+        } else if self.hidden > 0 {
+            self.hidden -= 1;
+        } else {
+            return Err(StateError::TileNotInHand(tile));
+        }
+        Ok(())
+    }
+
+    /// Removes the tiles of `meld` that came from the hand, i.e. all of them
+    /// except one copy of `called`.
+    fn take_meld(&mut self, meld: &Meld, called: Option<MahjongTile>) -> Result<(), StateError> {
+        let mut called = called;
+        for tile in meld.tiles() {
+            if called == Some(tile) {
+                called = None;
+                continue;
+            }
+            self.take(tile)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a round is waiting for next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// `0` draws next, either in turn or as the replacement after a kan or
+    /// kita.
+    Draw(PlayerLocation),
+    /// `0` has drawn or called and must discard, unless they declare a kan,
+    /// riichi, kita or tsumo first.
+    Discard(PlayerLocation),
+    /// `by` has just discarded `tile`. Another player may call it;
+    /// otherwise the next player draws.
+    Discarded {
+        by: PlayerLocation,
+        tile: MahjongTile,
+    },
+    /// `by` has just added `tile` to a pon. Another player may rob it with
+    /// a ron (chankan); otherwise `by` draws a replacement.
+    Kan {
+        by: PlayerLocation,
+        tile: MahjongTile,
+    },
+    /// The round has ended in a win or draw.
+    Finished,
+}
+
+/// Who wins when several players call ron on the same tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RonOutcome {
+    /// These players win, in turn order from the discarder.
+    Wins(Vec<PlayerLocation>),
+    /// Three players called and the round is abandoned (sancha hou).
+    SanchaHou,
+}
+
+/// The state of a round after some of its events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundState {
+    /// By [`PlayerLocation::index`]. The empty seat of a three-player game
+    /// never acts.
+    players: [PlayerState; 4],
+    num_players: NumPlayers,
+    wall: usize,
+    phase: Phase,
+    /// The discarder and winners so far of a ron, which further players
+    /// may join.
+    ron: Option<(PlayerLocation, Vec<PlayerLocation>)>,
+    /// Time allowed per action before the time bank is used, if banks are
+    /// tracked.
+    thinking_time: Option<Duration>,
+    /// Whether the next draw comes from the dead wall, after a kan or kita.
+    replacement_due: bool,
+    /// Whether the last event was a draw from the dead wall.
+    rinshan: bool,
+    /// Whether the ron in progress robs an added kan.
+    chankan: bool,
+    /// Every dora indicator of the round, from [`RoundConfig::dora`].
+    dora: Vec<MahjongTile>,
+    /// How many of `dora` have been turned over.
+    dora_revealed: usize,
+    /// Kan dora from open and added kans, turned over after the kanner's
+    /// discard or the next kan.
+    dora_pending: usize,
+}
+
+impl RoundState {
+    /// The state straight after the deal described by `config` in a
+    /// four-player game, with the dealer to draw.
+    pub fn new(config: &RoundConfig) -> RoundState {
+        RoundState::with_players(config, NumPlayers::Four)
+    }
+
+    /// As [`RoundState::new`], at a table of `num_players`. Three-player
+    /// games have a shorter wall, no chii and allow kita.
+    ///
+    /// An empty dealt hand means the hero's tiles weren't recorded, and
+    /// they are tracked as hidden like everyone else's.
+    pub fn with_players(config: &RoundConfig, num_players: NumPlayers) -> RoundState {
+        let mut players: [PlayerState; 4] = Default::default();
+        for &seat in num_players.seats() {
+            let player = &mut players[seat.index()];
+            let hero_hand = &config.initial_hero_hand_state.concealed;
+            if seat == PlayerLocation::Hero && !hero_hand.is_empty() {
+                player.known = hero_hand.clone();
+            } else {
+                player.hidden = DEALT_TILES;
+            }
+        }
+        let wall = match num_players {
+            NumPlayers::Three => THREE_PLAYER_LIVE_WALL_TILES,
+            NumPlayers::Four => LIVE_WALL_TILES,
+        };
+        RoundState {
+            players,
+            num_players,
+            wall,
+            phase: Phase::Draw(config.dealer),
+            ron: None,
+            thinking_time: None,
+            replacement_due: false,
+            rinshan: false,
+            chankan: false,
+            dora: config.dora.clone(),
+            dora_revealed: 1,
+            dora_pending: 0,
+        }
+    }
+
+    /// Tracks each player's time bank, starting from `banks` by
+    /// [`PlayerLocation::index`]. Recorded time over `thinking_time` on an
+    /// action comes out of the bank, which can't go below zero. Take the
+    /// banks left at the end of a round into the next one, as
+    /// [`GameConfig::time_bank`] lasts the whole game.
+    ///
+    /// [`GameConfig::time_bank`]: crate::game::GameConfig::time_bank
+    pub fn with_time_bank(mut self, thinking_time: Duration, banks: [Duration; 4]) -> RoundState {
+        self.thinking_time = Some(thinking_time);
+        for (player, bank) in self.players.iter_mut().zip(banks) {
+            player.time_bank = bank;
+        }
+        self
+    }
+
+    pub fn num_players(&self) -> NumPlayers {
+        self.num_players
+    }
+
+    pub fn player(&self, player: PlayerLocation) -> &PlayerState {
+        &self.players[player.index()]
+    }
+
+    /// Tiles left to draw from the live wall.
+    pub fn wall_remaining(&self) -> usize {
+        self.wall
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// The player expected to act next, if the round hasn't finished. After
+    /// a discard this is the next player to draw, although another player
+    /// may call instead.
+    pub fn turn(&self) -> Option<PlayerLocation> {
+        match self.phase {
+            Phase::Draw(player) | Phase::Discard(player) | Phase::Kan { by: player, .. } => {
+                Some(player)
+            }
+            Phase::Discarded { by, .. } => Some(by.next_in(self.num_players)),
+            Phase::Finished => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.phase == Phase::Finished
+    }
+
+    /// Whether the next draw is the replacement from the dead wall after a
+    /// kan or kita.
+    pub fn replacement_due(&self) -> bool {
+        self.replacement_due
+    }
+
+    /// Whether the last event was a replacement draw, so that a tsumo now
+    /// wins by rinshan kaihou.
+    pub fn is_rinshan(&self) -> bool {
+        self.rinshan
+    }
+
+    /// Whether a ron now robs a kan (chankan): straight after an added kan,
+    /// or joining a ron that did.
+    pub fn is_chankan(&self) -> bool {
+        matches!(self.phase, Phase::Kan { .. }) || self.chankan
+    }
+
+    /// Whether a win by `player` now would be ippatsu.
+    pub fn is_ippatsu(&self, player: PlayerLocation) -> bool {
+        self.player(player).ippatsu
+    }
+
+    /// The dora indicators turned over so far: the first from the deal,
+    /// then one per kan. A closed kan's is turned over at once; an open or
+    /// added kan's after the kanner's discard, or at the next kan if that
+    /// comes first.
+    pub fn dora_indicators(&self) -> &[MahjongTile] {
+        &self.dora[..self.dora_revealed.min(self.dora.len())]
+    }
+
+    /// Everyone who has called ron on the final discard, in turn order from
+    /// the discarder.
+    pub fn ron_winners(&self) -> &[PlayerLocation] {
+        self.ron.as_ref().map_or(&[], |(_, winners)| winners)
+    }
+
+    /// What happens if `claimants` call ron on the tile now on offer, along
+    /// with anyone who already has. Under head bump (no
+    /// [`RuleSet::multiple_ron`]) only the nearest in turn order from the
+    /// discarder wins; otherwise they all do, unless three call and
+    /// [`sancha_hou`](crate::rules::AbortiveDraws::sancha_hou) abandons
+    /// the round. The discarder and empty seats are left out, and nobody
+    /// wins if no tile is on offer.
+    pub fn resolve_rons(&self, claimants: &[PlayerLocation], rules: &RuleSet) -> RonOutcome {
+        let (by, mut callers) = match (&self.ron, self.phase) {
+            (Some((by, winners)), _) => (*by, winners.clone()),
+            (None, Phase::Discarded { by, .. } | Phase::Kan { by, .. }) => (by, Vec::new()),
+            _ => return RonOutcome::Wins(Vec::new()),
+        };
+        let seats = self.num_players.seats();
+        for &claimant in claimants {
+            if claimant != by && seats.contains(&claimant) && !callers.contains(&claimant) {
+                callers.push(claimant);
+            }
+        }
+        callers.sort_by_key(|p| p.seen_from_in(by, self.num_players).index());
+        if !rules.multiple_ron {
+            callers.truncate(1);
+        } else if rules.abortive_draws.sancha_hou && callers.len() >= 3 {
+            return RonOutcome::SanchaHou;
+        }
+        RonOutcome::Wins(callers)
+    }
+
+    /// Applies the next event of the round. On error the state is left as
+    /// it was.
+    pub fn apply(&mut self, event: &RoundEvent) -> Result<(), StateError> {
+        let subject = event.subject;
+        if !self.num_players.seats().contains(&subject) {
+            return Err(StateError::EmptySeat(subject));
+        }
+        let next = |p: PlayerLocation| p.next_in(self.num_players);
+        let mut player = self.player(subject).clone();
+        if player.riichi_pending() && !matches!(event.action, RoundAction::Discard(_)) {
+            return Err(StateError::RiichiWithoutDiscard);
+        }
+        let phase = match &event.action {
+            RoundAction::Draw(tile) => {
+                match self.phase {
+                    Phase::Draw(p) | Phase::Kan { by: p, .. } if p == subject => {}
+                    Phase::Discarded { by, .. } if next(by) == subject => {}
+                    _ => return Err(self.out_of_turn(subject)),
+                }
+                if self.wall == 0 {
+                    return Err(StateError::WallEmpty);
+                }
+                player.draw(*tile);
+                Phase::Discard(subject)
+            }
+            RoundAction::Discard(tile) => {
+                self.expect_discard_phase(subject)?;
+                let derived = match player.drawn {
+                    // After a call.
+                    None => Some(false),
+                    Some(ObservedTile::Hidden) => None,
+                    Some(ObservedTile::Known(drawn)) if drawn != *tile => Some(false),
+                    // Another copy in hand could have been the one discarded.
+                    Some(ObservedTile::Known(_)) => {
+                        let copies = player.known.iter().filter(|t| *t == tile).count();
+                        (copies == 1).then_some(true)
+                    }
+                };
+                if let (Some(recorded), Some(derived)) = (event.tsumogiri, derived) {
+                    if recorded != derived {
+                        return Err(StateError::TsumogiriMismatch(recorded));
+                    }
+                }
+                player.take(*tile)?;
+                player.ippatsu = player.riichi_pending();
+                player.discards.push(PondTile {
+                    tile: *tile,
+                    called_by: None,
+                    riichi: player.riichi_pending(),
+                    tsumogiri: event.tsumogiri.or(derived),
+                });
+                Phase::Discarded {
+                    by: subject,
+                    tile: *tile,
+                }
+            }
+            RoundAction::Chii(meld) => {
+                if self.num_players == NumPlayers::Three {
+                    return Err(StateError::ChiiInThreePlayer);
+                }
+                if let Phase::Discarded { by, .. } = self.phase {
+                    if next(by) != subject {
+                        return Err(StateError::OutOfTurn(subject));
+                    }
+                }
+                self.check_call(subject, &player, meld.chii_tile, meld.source)?;
+                let meld = Meld::Chii(meld.clone());
+                player.take_meld(&meld, Some(meld_called(&meld)))?;
+                player.melds.push(meld);
+                Phase::Discard(subject)
+            }
+            RoundAction::Pon(meld) => {
+                self.check_call(subject, &player, meld.pon_tile, meld.source)?;
+                let meld = Meld::Pon(meld.clone());
+                player.take_meld(&meld, Some(meld_called(&meld)))?;
+                player.melds.push(meld);
+                Phase::Discard(subject)
+            }
+            RoundAction::OpenKan(meld) => {
+                self.check_call(subject, &player, meld.kan_tile, meld.source)?;
+                let meld = Meld::OpenKan(meld.clone());
+                player.take_meld(&meld, Some(meld_called(&meld)))?;
+                player.melds.push(meld);
+                Phase::Draw(subject)
+            }
+            RoundAction::ClosedKan(meld) => {
+                self.expect_discard_phase(subject)?;
+                let meld = Meld::ClosedKan(meld.clone());
+                player.take_meld(&meld, None)?;
+                player.melds.push(meld);
+                Phase::Draw(subject)
+            }
+            RoundAction::AddedKan(meld) => {
+                self.expect_discard_phase(subject)?;
+                let pon = player
+                    .melds
+                    .iter()
+                    .position(|m| matches!(m, Meld::Pon(p) if *p == meld.pon))
+                    .ok_or(StateError::NoSuchPon)?;
+                player.take(meld.added_tile)?;
+                player.melds[pon] = Meld::AddedKan(meld.clone());
+                Phase::Kan {
+                    by: subject,
+                    tile: meld.added_tile,
+                }
+            }
+            RoundAction::Riichi => {
+                self.expect_discard_phase(subject)?;
+                let closed = player.melds.iter().all(|m| matches!(m, Meld::ClosedKan(_)));
+                if player.riichi || !closed {
+                    return Err(StateError::InvalidRiichi);
+                }
+                player.riichi = true;
+                Phase::Discard(subject)
+            }
+            RoundAction::Kita => {
+                if self.num_players == NumPlayers::Four {
+                    return Err(StateError::KitaInFourPlayer);
+                }
+                self.expect_discard_phase(subject)?;
+                player.take(tile::NORTH)?;
+                player.nuki += 1;
+                Phase::Draw(subject)
+            }
+            RoundAction::Tsumo(..) => {
+                self.expect_discard_phase(subject)?;
+                Phase::Finished
+            }
+            RoundAction::Ron(..) => {
+                self.check_ron(subject)?;
+                Phase::Finished
+            }
+            RoundAction::Exhaustive(_) => match self.phase {
+                Phase::Discarded { .. } if self.wall == 0 => Phase::Finished,
+                Phase::Discarded { .. } => return Err(StateError::WallNotEmpty(self.wall)),
+                _ => return Err(self.out_of_turn(subject)),
+            },
+            RoundAction::AbortiveDraw(kind) => {
+                self.check_abort(subject, &player, *kind)?;
+                Phase::Finished
+            }
+        };
+        match &event.action {
+            RoundAction::Draw(_) => self.wall -= 1,
+            RoundAction::Ron(..) => match (&mut self.ron, self.phase) {
+                (Some((_, winners)), _) => winners.push(subject),
+                (None, Phase::Discarded { by, .. }) => self.ron = Some((by, vec![subject])),
+                (None, Phase::Kan { by, tile }) => {
+                    // The robbed kan doesn't complete: the added tile is
+                    // the winning tile and the pon stays a pon.
+                    for meld in &mut self.players[by.index()].melds {
+                        if let Meld::AddedKan(kan) = meld {
+                            if kan.added_tile == tile {
+                                *meld = Meld::Pon(kan.pon.clone());
+                            }
+                        }
+                    }
+                    self.ron = Some((by, vec![subject]));
+                    self.chankan = true;
+                }
+                (None, _) => unreachable!("checked by check_ron"),
+            },
+            RoundAction::Chii(_) | RoundAction::Pon(_) | RoundAction::OpenKan(_) => {
+                if let Phase::Discarded { by, .. } = self.phase {
+                    if let Some(called) = self.players[by.index()].discards.last_mut() {
+                        called.called_by = Some(subject);
+                    }
+                }
+            }
+            _ => {}
+        }
+        match event.action {
+            RoundAction::Draw(tile) => player.drawn = Some(tile),
+            RoundAction::Riichi => {}
+            _ => player.drawn = None,
+        }
+        if let Some(elapsed) = event.elapsed {
+            player.elapsed += elapsed;
+            if let Some(allowed) = self.thinking_time {
+                let over = elapsed.saturating_sub(allowed);
+                player.time_bank = player.time_bank.saturating_sub(over);
+            }
+        }
+        self.rinshan = self.replacement_due && matches!(event.action, RoundAction::Draw(_));
+        self.replacement_due = matches!(
+            event.action,
+            RoundAction::OpenKan(_)
+                | RoundAction::ClosedKan(_)
+                | RoundAction::AddedKan(_)
+                | RoundAction::Kita
+        );
+        // Any call breaks every ippatsu window. An added kan only does once
+        // it stands, since a player robbing it may still win ippatsu.
+        let robbable = matches!(self.phase, Phase::Kan { .. });
+        let breaks_ippatsu = match event.action {
+            RoundAction::Chii(_)
+            | RoundAction::Pon(_)
+            | RoundAction::OpenKan(_)
+            | RoundAction::ClosedKan(_)
+            | RoundAction::Kita => true,
+            RoundAction::Draw(_) => robbable,
+            _ => false,
+        };
+        match event.action {
+            RoundAction::Discard(_) => self.reveal_pending_dora(),
+            RoundAction::ClosedKan(_) => {
+                self.reveal_pending_dora();
+                self.dora_revealed += 1;
+            }
+            RoundAction::OpenKan(_) | RoundAction::AddedKan(_) => {
+                self.reveal_pending_dora();
+                self.dora_pending += 1;
+            }
+            _ => {}
+        }
+        self.players[subject.index()] = player;
+        if breaks_ippatsu {
+            for player in &mut self.players {
+                player.ippatsu = false;
+            }
+        }
+        self.phase = phase;
+        Ok(())
+    }
+
+    fn reveal_pending_dora(&mut self) {
+        self.dora_revealed += self.dora_pending;
+        self.dora_pending = 0;
+    }
+
+    fn out_of_turn(&self, subject: PlayerLocation) -> StateError {
+        match self.phase {
+            Phase::Finished => StateError::RoundOver,
+            _ => StateError::OutOfTurn(subject),
+        }
+    }
+
+    fn expect_discard_phase(&self, subject: PlayerLocation) -> Result<(), StateError> {
+        match self.phase {
+            Phase::Discard(p) if p == subject => Ok(()),
+            _ => Err(self.out_of_turn(subject)),
+        }
+    }
+
+    /// Checks that `subject` may call `called` from `source` (as seen by
+    /// them) now.
+    fn check_call(
+        &self,
+        subject: PlayerLocation,
+        player: &PlayerState,
+        called: MahjongTile,
+        source: PlayerLocation,
+    ) -> Result<(), StateError> {
+        let Phase::Discarded { by, tile } = self.phase else {
+            return Err(self.out_of_turn(subject));
+        };
+        if by == subject {
+            return Err(StateError::OutOfTurn(subject));
+        }
+        if called != tile {
+            return Err(StateError::NotLastDiscard(called));
+        }
+        if source != by.seen_from_in(subject, self.num_players) {
+            return Err(StateError::WrongSource(source));
+        }
+        if player.riichi {
+            return Err(StateError::CallInRiichi);
+        }
+        if self.wall == 0 {
+            return Err(StateError::WallEmpty);
+        }
+        Ok(())
+    }
+
+    /// Checks that `subject` may ron now: on the last discard or added kan
+    /// tile, or joining an earlier ron on it from later in turn order.
+    fn check_ron(&self, subject: PlayerLocation) -> Result<(), StateError> {
+        match (&self.ron, self.phase) {
+            (None, Phase::Discarded { by, .. } | Phase::Kan { by, .. }) if by != subject => Ok(()),
+            (Some((by, winners)), _) => {
+                let order = |p: PlayerLocation| p.seen_from_in(*by, self.num_players).index();
+                let last = *winners.last().expect("a ron has a winner");
+                if subject == *by || winners.contains(&subject) {
+                    Err(StateError::RoundOver)
+                } else if order(subject) < order(last) {
+                    Err(StateError::OutOfTurn(subject))
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Err(self.out_of_turn(subject)),
+        }
+    }
+
+    /// Checks that the round can be abandoned as `kind` now, as far as the
+    /// known tiles show. `player` is the subject's state.
+    fn check_abort(
+        &self,
+        subject: PlayerLocation,
+        player: &PlayerState,
+        kind: AbortiveDraw,
+    ) -> Result<(), StateError> {
+        let seated = || self.num_players.seats().iter().map(|s| self.player(*s));
+        let uninterrupted = seated().all(|p| p.melds.is_empty() && p.nuki == 0);
+        let discarded = matches!(self.phase, Phase::Discarded { .. });
+        let four = self.num_players == NumPlayers::Four;
+        let met = match kind {
+            AbortiveDraw::KyuushuKyuuhai => {
+                self.expect_discard_phase(subject)?;
+                let mut kinds: Vec<_> = player
+                    .known
+                    .iter()
+                    .filter(|t| t.is_yaochuu())
+                    .map(|t| t.rank_key())
+                    .collect();
+                kinds.sort();
+                kinds.dedup();
+                uninterrupted && player.discards.is_empty() && kinds.len() + player.hidden >= 9
+            }
+            AbortiveDraw::SuufonRenda => {
+                let first = self.player(PlayerLocation::Hero).discards.first();
+                let wind = first
+                    .map(|d| d.tile)
+                    .filter(|t| [tile::EAST, tile::SOUTH, tile::WEST, tile::NORTH].contains(t));
+                discarded
+                    && four
+                    && uninterrupted
+                    && wind.is_some()
+                    && seated().all(|p| {
+                        p.discards.len() == 1 && p.discards.first().map(|d| d.tile) == wind
+                    })
+            }
+            AbortiveDraw::SuuchaRiichi => discarded && four && seated().all(|p| p.riichi),
+            AbortiveDraw::Suukaikan => {
+                let kans = |p: &PlayerState| {
+                    p.melds
+                        .iter()
+                        .filter(|m| !matches!(m, Meld::Chii(_) | Meld::Pon(_)))
+                        .count()
+                };
+                let total: usize = seated().map(kans).sum();
+                discarded && total == 4 && seated().all(|p| kans(p) < 4)
+            }
+            AbortiveDraw::SanchaHou => {
+                if !discarded {
+                    return Err(self.out_of_turn(subject));
+                }
+                four
+            }
+        };
+        if !met {
+            if self.phase == Phase::Finished {
+                return Err(StateError::RoundOver);
+            }
+            return Err(StateError::AbortNotPossible(kind));
+        }
+        Ok(())
+    }
+}
+
+impl Round {
+    /// The hero's hand after the first `index` events of a four-player
+    /// round, or after all of them if there are fewer.
+    pub fn hero_hand_at(&self, index: usize) -> Result<Hand, StateError> {
+        self.hero_hand_at_in(index, NumPlayers::Four)
+    }
+
+    /// As [`Round::hero_hand_at`], at a table of `num_players`. The events
+    /// are replayed to get there, so any that couldn't have happened are
+    /// reported.
+    pub fn hero_hand_at_in(
+        &self,
+        index: usize,
+        num_players: NumPlayers,
+    ) -> Result<Hand, StateError> {
+        let mut state = RoundState::with_players(&self.config, num_players);
+        for event in self.events.iter().take(index) {
+            state.apply(event)?;
+        }
+        state
+            .player(PlayerLocation::Hero)
+            .hand()
+            .ok_or(StateError::HeroHandUnknown)
+    }
+}
+
+/// The tile a called meld took from another player.
+fn meld_called(meld: &Meld) -> MahjongTile {
+    match meld {
+        Meld::Chii(m) => m.chii_tile,
+        Meld::Pon(m) => m.pon_tile,
+        Meld::OpenKan(m) => m.kan_tile,
+        Meld::ClosedKan(_) | Meld::AddedKan(_) => unreachable!("not a called meld"),
+    }
+}
+
+/// Why an event could not be applied to a [`RoundState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// This player could not act at this point in the round.
+    OutOfTurn(PlayerLocation),
+    /// A call names a different tile from the one just discarded.
+    NotLastDiscard(MahjongTile),
+    /// The player does not hold this tile.
+    TileNotInHand(MahjongTile),
+    /// A meld claims to be called from this seat, as seen by the caller,
+    /// but the discard came from elsewhere.
+    WrongSource(PlayerLocation),
+    /// An added kan extends a pon the player has not made.
+    NoSuchPon,
+    /// A draw or call after the live wall ran out.
+    WallEmpty,
+    /// An exhaustive draw with this many tiles still in the live wall.
+    WallNotEmpty(usize),
+    /// Riichi declared again, or with an open hand.
+    InvalidRiichi,
+    /// Anything but a discard straight after declaring riichi.
+    RiichiWithoutDiscard,
+    /// A chii, pon or open kan by a player in riichi.
+    CallInRiichi,
+    /// An event after the round ended.
+    RoundOver,
+    /// An event by the empty seat of a three-player game.
+    EmptySeat(PlayerLocation),
+    /// Chii is not allowed in three-player games.
+    ChiiInThreePlayer,
+    /// Kita is only declared in three-player games.
+    KitaInFourPlayer,
+    /// An abortive draw whose conditions are not met, e.g. suufon renda
+    /// with different winds discarded.
+    AbortNotPossible(AbortiveDraw),
+    /// The hero's hand was asked for, but their dealt hand wasn't recorded.
+    HeroHandUnknown,
+    /// A discard recorded as tsumogiri (`true`) or tedashi (`false`) when
+    /// the drawn tile shows otherwise.
+    TsumogiriMismatch(bool),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::OutOfTurn(player) => write!(f, "{} cannot act now", player),
+            StateError::NotLastDiscard(tile) => {
+                write!(f, "called {} but it was not the last discard", tile)
+            }
+            StateError::TileNotInHand(tile) => write!(f, "{} is not in hand", tile),
+            StateError::WrongSource(source) => {
+                write!(
+                    f,
+                    "meld called from {} but the discard was not theirs",
+                    source
+                )
+            }
+            StateError::NoSuchPon => write!(f, "added kan without a matching pon"),
+            StateError::WallEmpty => write!(f, "the wall is empty"),
+            StateError::WallNotEmpty(n) => {
+                write!(f, "exhaustive draw with {} tiles left in the wall", n)
+            }
+            StateError::InvalidRiichi => write!(f, "riichi cannot be declared"),
+            StateError::RiichiWithoutDiscard => {
+                write!(f, "riichi must be followed by a discard")
+            }
+            StateError::CallInRiichi => write!(f, "players in riichi cannot call"),
+            StateError::RoundOver => write!(f, "the round is already over"),
+            StateError::EmptySeat(seat) => write!(f, "nobody sits at {}", seat),
+            StateError::ChiiInThreePlayer => write!(f, "chii is not allowed with three players"),
+            StateError::KitaInFourPlayer => write!(f, "kita is only declared with three players"),
+            StateError::AbortNotPossible(kind) => {
+                write!(f, "the round cannot be aborted by {}", kind)
+            }
+            StateError::HeroHandUnknown => write!(f, "the hero's dealt hand was not recorded"),
+            StateError::TsumogiriMismatch(true) => {
+                write!(f, "the discard is not the tile just drawn")
+            }
+            StateError::TsumogiriMismatch(false) => {
+                write!(f, "the discard can only be the tile just drawn")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerLocation::*;
+    use crate::game::RoundNumber;
+    use crate::hand::{AddedKanMeld, ChiiMeld, ClosedKanMeld, OpenKanMeld, PonMeld};
+    use crate::tile::{build, Direction};
+
+    fn t(s: &str) -> MahjongTile {
+        build(s).unwrap()
+    }
+
+    fn config(dealer: PlayerLocation, hand: &str) -> RoundConfig {
+        RoundConfig {
+            wind: Direction::East,
+            number: RoundNumber::One,
+            repeat: 0,
+            dealer,
+            dora: vec![t("1m")],
+            ura_dora: vec![],
+            initial_hero_hand_state: Hand::parse(hand).unwrap(),
+            result: None,
+            wall: Default::default(),
+        }
+    }
+
+    fn event(subject: PlayerLocation, action: RoundAction) -> RoundEvent {
+        RoundEvent::new(subject, action)
+    }
+
+    fn apply_all(state: &mut RoundState, events: &[RoundEvent]) {
+        for e in events {
+            state
+                .apply(e)
+                .unwrap_or_else(|err| panic!("{:?}: {}", e, err));
+        }
+    }
+
+    #[test]
+    fn tracks_hands_discards_and_wall() {
+        let mut state = RoundState::new(&config(Hero, "123m456p789s1234z"));
+        assert_eq!(state.turn(), Some(Hero));
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("3z").into())),
+                event(Hero, RoundAction::Discard(t("1z"))),
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Right, RoundAction::Discard(t("3z"))),
+                event(
+                    Hero,
+                    RoundAction::Pon(PonMeld::new([t("3z"); 3], t("3z"), Right).unwrap()),
+                ),
+                event(Hero, RoundAction::Discard(t("2z"))),
+            ],
+        );
+        assert_eq!(state.wall_remaining(), LIVE_WALL_TILES - 2);
+        assert_eq!(state.turn(), Some(Right));
+        let pond = |p: &PlayerState| -> Vec<(MahjongTile, Option<PlayerLocation>)> {
+            p.discards.iter().map(|d| (d.tile, d.called_by)).collect()
+        };
+        let hero = state.player(Hero);
+        assert_eq!(pond(hero), vec![(t("1z"), None), (t("2z"), None)]);
+        assert_eq!(
+            hero.hand(),
+            Some(Hand::parse("123m456p789s4z + p333z(right)").unwrap())
+        );
+        let right = state.player(Right);
+        assert_eq!((right.hidden, right.hand()), (13, None));
+        assert_eq!(pond(right), vec![(t("3z"), Some(Hero))]);
+        assert_eq!(right.visible_pond().count(), 0);
+    }
+
+    #[test]
+    fn calls_check_turn_source_and_tiles() {
+        let mut state = RoundState::new(&config(Left, "123m456p789s1234z"));
+        apply_all(
+            &mut state,
+            &[
+                event(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Left, RoundAction::Discard(t("7s"))),
+            ],
+        );
+        let chii = ChiiMeld::from_called(t("7s"), [t("8s"), t("9s")]).unwrap();
+        let before = state.clone();
+        assert_eq!(
+            state.apply(&event(Right, RoundAction::Chii(chii.clone()))),
+            Err(StateError::OutOfTurn(Right))
+        );
+        let missing = ChiiMeld::from_called(t("7s"), [t("5s"), t("6s")]).unwrap();
+        assert_eq!(
+            state.apply(&event(Hero, RoundAction::Chii(missing))),
+            Err(StateError::TileNotInHand(t("5s")))
+        );
+        assert_eq!(state, before);
+        let pon = PonMeld::new([t("7s"); 3], t("7s"), Left).unwrap();
+        assert_eq!(
+            state.apply(&event(Across, RoundAction::Pon(pon.clone()))),
+            Err(StateError::WrongSource(Left))
+        );
+        assert_eq!(
+            state.apply(&event(Left, RoundAction::Pon(pon))),
+            Err(StateError::OutOfTurn(Left))
+        );
+        state.apply(&event(Hero, RoundAction::Chii(chii))).unwrap();
+        assert_eq!(state.phase(), Phase::Discard(Hero));
+        assert_eq!(state.player(Hero).known.len(), 11);
+    }
+
+    #[test]
+    fn out_of_turn_riichi_and_round_over() {
+        let mut state = RoundState::new(&config(Hero, "123m456p789s1234z"));
+        assert_eq!(
+            state.apply(&event(Right, RoundAction::Draw(ObservedTile::Hidden))),
+            Err(StateError::OutOfTurn(Right))
+        );
+        state
+            .apply(&event(Hero, RoundAction::Draw(t("5z").into())))
+            .unwrap();
+        assert_eq!(
+            state.apply(&event(Hero, RoundAction::Discard(t("6z")))),
+            Err(StateError::TileNotInHand(t("6z")))
+        );
+        state.apply(&event(Hero, RoundAction::Riichi)).unwrap();
+        assert!(state.player(Hero).riichi_pending());
+        assert_eq!(
+            state.apply(&event(Hero, RoundAction::Riichi)),
+            Err(StateError::RiichiWithoutDiscard)
+        );
+        assert_eq!(
+            state.apply(&event(Hero, RoundAction::Tsumo(Hand::default(), None))),
+            Err(StateError::RiichiWithoutDiscard)
+        );
+        state
+            .apply(&event(Hero, RoundAction::Discard(t("5z"))))
+            .unwrap();
+        assert!(state.player(Hero).riichi);
+        let riichi: Vec<bool> = state
+            .player(Hero)
+            .discards
+            .iter()
+            .map(|d| d.riichi)
+            .collect();
+        assert_eq!(riichi, [true]);
+        state
+            .apply(&event(Across, RoundAction::Ron(Hand::default(), None)))
+            .unwrap();
+        assert!(state.is_finished());
+        assert_eq!(state.turn(), None);
+        assert_eq!(
+            state.apply(&event(Right, RoundAction::Draw(ObservedTile::Hidden))),
+            Err(StateError::RoundOver)
+        );
+    }
+
+    #[test]
+    fn kans_take_replacement_draws() {
+        let mut state = RoundState::new(&config(Hero, "1111m456p789s123z"));
+        let kan = ClosedKanMeld::new([t("1m"); 4]).unwrap();
+        let pon = PonMeld::new([t("2z"); 3], t("2z"), Left).unwrap();
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("5z").into())),
+                event(Hero, RoundAction::ClosedKan(kan)),
+            ],
+        );
+        assert!(state.replacement_due() && !state.is_rinshan());
+        apply_all(
+            &mut state,
+            &[event(Hero, RoundAction::Draw(t("2z").into()))],
+        );
+        assert!(!state.replacement_due() && state.is_rinshan());
+        assert_eq!(state.wall_remaining(), LIVE_WALL_TILES - 2);
+        let added = AddedKanMeld::new(pon, t("2z")).unwrap();
+        assert_eq!(
+            state.apply(&event(Hero, RoundAction::AddedKan(added))),
+            Err(StateError::NoSuchPon)
+        );
+        // A closed kan keeps the hand closed.
+        assert_eq!(state.apply(&event(Hero, RoundAction::Riichi)), Ok(()));
+        assert!(!state.is_rinshan());
+        assert_eq!(state.player(Hero).concealed_count(), 11);
+    }
+
+    #[test]
+    fn three_player_rounds() {
+        let config = config(Left, "123m456p789s1234z");
+        let mut state = RoundState::with_players(&config, NumPlayers::Three);
+        assert_eq!(state.wall_remaining(), THREE_PLAYER_LIVE_WALL_TILES);
+        apply_all(
+            &mut state,
+            &[
+                event(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Left, RoundAction::Kita),
+                event(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Left, RoundAction::Discard(t("7s"))),
+            ],
+        );
+        assert_eq!(state.player(Left).nuki, 1);
+        assert_eq!(state.turn(), Some(Hero));
+        let chii = ChiiMeld::from_called(t("7s"), [t("8s"), t("9s")]).unwrap();
+        assert_eq!(
+            state.apply(&event(Hero, RoundAction::Chii(chii))),
+            Err(StateError::ChiiInThreePlayer)
+        );
+        assert_eq!(
+            state.apply(&event(Across, RoundAction::Ron(Hand::default(), None))),
+            Err(StateError::EmptySeat(Across))
+        );
+        // The discarder is on the right as seen from the left.
+        let pon = PonMeld::new([t("7s"); 3], t("7s"), Right).unwrap();
+        state.apply(&event(Right, RoundAction::Pon(pon))).unwrap();
+        state
+            .apply(&event(Right, RoundAction::Discard(t("1m"))))
+            .unwrap();
+        assert_eq!(state.turn(), Some(Left));
+
+        let mut four = RoundState::new(&config);
+        four.apply(&event(Left, RoundAction::Draw(ObservedTile::Hidden)))
+            .unwrap();
+        assert_eq!(
+            four.apply(&event(Left, RoundAction::Kita)),
+            Err(StateError::KitaInFourPlayer)
+        );
+    }
+
+    #[test]
+    fn the_wall_runs_out() {
+        let mut state = RoundState::new(&config(Right, "123m456p789s1234z"));
+        let mut events = Vec::new();
+        let mut seat = Right;
+        for _ in 0..LIVE_WALL_TILES {
+            events.push(event(seat, RoundAction::Draw(ObservedTile::Hidden)));
+            events.push(event(seat, RoundAction::Discard(t("9m"))));
+            seat = seat.next();
+        }
+        let (last, events) = events.split_last().unwrap();
+        apply_all(&mut state, events);
+        assert_eq!(
+            state
+                .clone()
+                .apply(&event(seat, RoundAction::Exhaustive(vec![]))),
+            Err(StateError::OutOfTurn(seat))
+        );
+        state.apply(last).unwrap();
+        assert_eq!(state.wall_remaining(), 0);
+        assert_eq!(
+            state.apply(&event(seat, RoundAction::Draw(ObservedTile::Hidden))),
+            Err(StateError::WallEmpty)
+        );
+        let pon = PonMeld::new([t("9m"); 3], t("9m"), Left).unwrap();
+        assert_eq!(
+            state.apply(&event(seat, RoundAction::Pon(pon))),
+            Err(StateError::WallEmpty)
+        );
+        state
+            .apply(&event(seat, RoundAction::Exhaustive(vec![])))
+            .unwrap();
+        assert!(state.is_finished());
+    }
+
+    #[test]
+    fn abortive_draws_check_their_conditions() {
+        let abort = |seat, kind| event(seat, RoundAction::AbortiveDraw(kind));
+        let mut state = RoundState::new(&config(Hero, "19m19p19s1234566z"));
+        state
+            .apply(&event(Hero, RoundAction::Draw(t("6z").into())))
+            .unwrap();
+        let mut nine = state.clone();
+        nine.apply(&abort(Hero, AbortiveDraw::KyuushuKyuuhai))
+            .unwrap();
+        assert!(nine.is_finished());
+
+        let mut state = RoundState::new(&config(Hero, "23m45p67s1234566z"));
+        state
+            .apply(&event(Hero, RoundAction::Draw(t("6z").into())))
+            .unwrap();
+        assert_eq!(
+            state.apply(&abort(Hero, AbortiveDraw::KyuushuKyuuhai)),
+            Err(StateError::AbortNotPossible(AbortiveDraw::KyuushuKyuuhai))
+        );
+
+        let mut events = vec![event(Hero, RoundAction::Discard(t("1z")))];
+        for seat in [Right, Across, Left] {
+            events.push(event(seat, RoundAction::Draw(ObservedTile::Hidden)));
+            events.push(event(seat, RoundAction::Discard(t("1z"))));
+        }
+        let (last, events) = events.split_last().unwrap();
+        apply_all(&mut state, events);
+        assert_eq!(
+            state
+                .clone()
+                .apply(&abort(Across, AbortiveDraw::SuufonRenda)),
+            Err(StateError::AbortNotPossible(AbortiveDraw::SuufonRenda))
+        );
+        state.apply(last).unwrap();
+        assert_eq!(
+            state
+                .clone()
+                .apply(&abort(Left, AbortiveDraw::SuuchaRiichi)),
+            Err(StateError::AbortNotPossible(AbortiveDraw::SuuchaRiichi))
+        );
+        state
+            .apply(&abort(Left, AbortiveDraw::SuufonRenda))
+            .unwrap();
+        assert_eq!(
+            state.apply(&abort(Left, AbortiveDraw::SanchaHou)),
+            Err(StateError::RoundOver)
+        );
+    }
+
+    #[test]
+    fn several_players_can_ron_one_discard() {
+        let mut state = RoundState::new(&config(Hero, "123m456p789s1234z"));
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("5z").into())),
+                event(Hero, RoundAction::Discard(t("5z"))),
+            ],
+        );
+        let ron = |seat| event(seat, RoundAction::Ron(Hand::default(), None));
+        state.apply(&ron(Across)).unwrap();
+        assert!(state.is_finished());
+        assert_eq!(state.apply(&ron(Right)), Err(StateError::OutOfTurn(Right)));
+        assert_eq!(state.apply(&ron(Across)), Err(StateError::RoundOver));
+        assert_eq!(state.apply(&ron(Hero)), Err(StateError::RoundOver));
+        state.apply(&ron(Left)).unwrap();
+        assert_eq!(state.ron_winners(), [Across, Left]);
+    }
+
+    #[test]
+    fn time_banks_pay_for_slow_actions() {
+        let secs = Duration::from_secs;
+        let state = RoundState::new(&config(Right, "123m456p789s1234z"));
+        let mut state = state.with_time_bank(secs(5), [secs(20); 4]);
+        apply_all(
+            &mut state,
+            &[
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)).with_elapsed(secs(1)),
+                event(Right, RoundAction::Discard(t("1z"))).with_elapsed(secs(12)),
+                event(Across, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Across, RoundAction::Discard(t("9m"))).with_elapsed(secs(40)),
+            ],
+        );
+        assert_eq!(state.player(Right).elapsed, secs(13));
+        assert_eq!(state.player(Right).time_bank, secs(13));
+        assert_eq!(state.player(Across).time_bank, secs(0));
+        assert_eq!(state.player(Hero).time_bank, secs(20));
+    }
+
+    #[test]
+    fn hero_hand_at_each_event() {
+        let pon = PonMeld::new([t("3z"); 3], t("3z"), Right).unwrap();
+        let mut round = Round {
+            config: config(Hero, "123m456p789s1234z"),
+            events: vec![
+                event(Hero, RoundAction::Draw(t("3z").into())),
+                event(Hero, RoundAction::Discard(t("1z"))),
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Right, RoundAction::Discard(t("3z"))),
+                event(Hero, RoundAction::Pon(pon.clone())),
+                event(Hero, RoundAction::Discard(t("2z"))),
+            ],
+            annotation: None,
+        };
+        let hand = |s: &str| Hand::parse(s).unwrap();
+        assert_eq!(round.hero_hand_at(0), Ok(hand("123m456p789s1234z")));
+        assert_eq!(round.hero_hand_at(1), Ok(hand("123m456p789s12334z")));
+        assert_eq!(round.hero_hand_at(4), Ok(hand("123m456p789s2334z")));
+        let mut called = hand("123m456p789s24z");
+        called.melds.push(Meld::Pon(pon));
+        assert_eq!(round.hero_hand_at(5), Ok(called.clone()));
+        called.concealed.remove(called.concealed.len() - 2);
+        assert_eq!(round.hero_hand_at(100), Ok(called));
+
+        round.events[5] = event(Hero, RoundAction::Discard(t("7z")));
+        assert_eq!(round.hero_hand_at(5).map(|_| ()), Ok(()));
+        assert_eq!(
+            round.hero_hand_at(6),
+            Err(StateError::TileNotInHand(t("7z")))
+        );
+        round.config.initial_hero_hand_state = Hand::default();
+        assert_eq!(round.hero_hand_at(0), Err(StateError::HeroHandUnknown));
+    }
+
+    #[test]
+    fn added_kans_can_be_robbed() {
+        let pon = PonMeld::new([t("1z"); 3], t("1z"), Right).unwrap();
+        let kan = AddedKanMeld::new(pon.clone(), t("1z")).unwrap();
+        let mut state = RoundState::new(&config(Hero, "123m456p789s1134z"));
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("5z").into())),
+                event(Hero, RoundAction::Discard(t("5z"))),
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Right, RoundAction::Discard(t("1z"))),
+                event(Hero, RoundAction::Pon(pon.clone())),
+                event(Hero, RoundAction::Discard(t("3z"))),
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Right, RoundAction::Discard(t("6z"))),
+                event(Across, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Across, RoundAction::Discard(t("7z"))),
+                event(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Left, RoundAction::Discard(t("2m"))),
+                event(Hero, RoundAction::Draw(t("1z").into())),
+                event(Hero, RoundAction::AddedKan(kan)),
+            ],
+        );
+        assert_eq!(
+            state.phase(),
+            Phase::Kan {
+                by: Hero,
+                tile: t("1z")
+            }
+        );
+        assert!(state.is_chankan());
+        let mut drawn = state.clone();
+        apply_all(
+            &mut drawn,
+            &[event(Hero, RoundAction::Draw(t("6z").into()))],
+        );
+        assert!(drawn.is_rinshan() && !drawn.is_chankan());
+        assert_eq!(
+            state.apply(&event(Right, RoundAction::Pon(pon.clone()))),
+            Err(StateError::OutOfTurn(Right))
+        );
+
+        apply_all(
+            &mut state,
+            &[
+                event(Right, RoundAction::Ron(Hand::default(), None)),
+                event(Left, RoundAction::Ron(Hand::default(), None)),
+            ],
+        );
+        assert!(state.is_finished() && state.is_chankan());
+        assert_eq!(state.ron_winners(), &[Right, Left]);
+        assert_eq!(state.player(Hero).melds, vec![Meld::Pon(pon)]);
+    }
+
+    #[test]
+    fn ippatsu_lasts_a_turn_unless_someone_calls() {
+        let mut state = RoundState::new(&config(Hero, "123m456p789s1234z"));
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("5z").into())),
+                event(Hero, RoundAction::Riichi),
+            ],
+        );
+        assert!(!state.is_ippatsu(Hero));
+        apply_all(&mut state, &[event(Hero, RoundAction::Discard(t("5z")))]);
+        assert!(state.is_ippatsu(Hero));
+
+        let mut called = state.clone();
+        let kan = ClosedKanMeld::new([t("2s"); 4]).unwrap();
+        apply_all(
+            &mut called,
+            &[
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Right, RoundAction::ClosedKan(kan)),
+            ],
+        );
+        assert!(!called.is_ippatsu(Hero));
+
+        apply_all(
+            &mut state,
+            &[
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Right, RoundAction::Discard(t("6z"))),
+                event(Across, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Across, RoundAction::Discard(t("7z"))),
+                event(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Left, RoundAction::Discard(t("1m"))),
+                event(Hero, RoundAction::Draw(t("9s").into())),
+            ],
+        );
+        assert!(state.is_ippatsu(Hero));
+        apply_all(&mut state, &[event(Hero, RoundAction::Discard(t("9s")))]);
+        assert!(!state.is_ippatsu(Hero));
+    }
+
+    #[test]
+    fn kan_dora_turn_over_by_kan_type() {
+        let mut config = config(Hero, "1111m456p789s123z");
+        config.dora = vec![t("1m"), t("2m"), t("3m")];
+        let mut state = RoundState::new(&config);
+        assert_eq!(state.dora_indicators(), &[t("1m")]);
+        let closed = ClosedKanMeld::new([t("1m"); 4]).unwrap();
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("5z").into())),
+                event(Hero, RoundAction::ClosedKan(closed)),
+            ],
+        );
+        assert_eq!(state.dora_indicators(), &[t("1m"), t("2m")]);
+
+        let open = OpenKanMeld::new([t("5z"); 4], t("5z"), Left).unwrap();
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("2z").into())),
+                event(Hero, RoundAction::Discard(t("5z"))),
+                event(Right, RoundAction::OpenKan(open)),
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+            ],
+        );
+        assert_eq!(state.dora_indicators().len(), 2);
+        apply_all(&mut state, &[event(Right, RoundAction::Discard(t("6z")))]);
+        assert_eq!(state.dora_indicators(), &config.dora[..]);
+    }
+
+    #[test]
+    fn head_bump_picks_the_nearest_claimant() {
+        let mut state = RoundState::new(&config(Hero, "123m456p789s1234z"));
+        let default = RuleSet::default();
+        let head_bump = RuleSet::mleague();
+        assert_eq!(
+            state.resolve_rons(&[Right], &default),
+            RonOutcome::Wins(vec![])
+        );
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("5z").into())),
+                event(Hero, RoundAction::Discard(t("5z"))),
+            ],
+        );
+        assert_eq!(
+            state.resolve_rons(&[Left, Hero, Right], &default),
+            RonOutcome::Wins(vec![Right, Left])
+        );
+        assert_eq!(
+            state.resolve_rons(&[Left, Across], &head_bump),
+            RonOutcome::Wins(vec![Across])
+        );
+        assert_eq!(
+            state.resolve_rons(&[Left, Across, Right], &default),
+            RonOutcome::SanchaHou
+        );
+        let triple_ron = RuleSet {
+            abortive_draws: crate::rules::AbortiveDraws::none(),
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            state.resolve_rons(&[Left, Across, Right], &triple_ron),
+            RonOutcome::Wins(vec![Right, Across, Left])
+        );
+
+        apply_all(
+            &mut state,
+            &[event(Across, RoundAction::Ron(Hand::default(), None))],
+        );
+        assert_eq!(
+            state.resolve_rons(&[Left], &head_bump),
+            RonOutcome::Wins(vec![Across])
+        );
+        assert_eq!(
+            state.resolve_rons(&[Left], &default),
+            RonOutcome::Wins(vec![Across, Left])
+        );
+    }
+
+    #[test]
+    fn tsumogiri_is_recorded_or_derived() {
+        let mut state = RoundState::new(&config(Hero, "123m456p789s1234z"));
+        let pond = |state: &RoundState, player| -> Vec<Option<bool>> {
+            state
+                .player(player)
+                .discards
+                .iter()
+                .map(|d| d.tsumogiri)
+                .collect()
+        };
+        apply_all(
+            &mut state,
+            &[
+                event(Hero, RoundAction::Draw(t("5z").into())),
+                event(Hero, RoundAction::Riichi),
+                event(Hero, RoundAction::Discard(t("5z"))),
+                event(Right, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Right, RoundAction::Discard(t("1z"))),
+                event(Across, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Across, RoundAction::Discard(t("6z"))).with_tsumogiri(true),
+                event(Left, RoundAction::Draw(ObservedTile::Hidden)),
+                event(Left, RoundAction::Discard(t("7z"))).with_tsumogiri(false),
+                event(Hero, RoundAction::Draw(t("4z").into())),
+            ],
+        );
+        assert_eq!(state.player(Hero).drawn, Some(t("4z").into()));
+        // Either 4z could go.
+        let mut copy = state.clone();
+        apply_all(&mut copy, &[event(Hero, RoundAction::Discard(t("4z")))]);
+        assert_eq!(pond(&copy, Hero), [Some(true), None]);
+        assert_eq!(
+            state.apply(&event(Hero, RoundAction::Discard(t("1m"))).with_tsumogiri(true)),
+            Err(StateError::TsumogiriMismatch(true))
+        );
+        apply_all(&mut state, &[event(Hero, RoundAction::Discard(t("1m")))]);
+        assert_eq!(pond(&state, Hero), [Some(true), Some(false)]);
+        assert_eq!(pond(&state, Right), [None]);
+        assert_eq!(pond(&state, Across), [Some(true)]);
+        assert_eq!(pond(&state, Left), [Some(false)]);
+        assert_eq!(state.player(Hero).drawn, None);
+    }
+}
@@ -0,0 +1,1788 @@
+//! Tiles and their MPSZ notation.
+//!
+//! Tiles are written as a number followed by a suit character: `m` (manzu,
+//! characters), `p` (pinzu, circles), `s` (souzu, bamboo) and `z` (honors).
+//! Honors are numbered `1z`–`4z` for the East, South, West and North winds
+//! and `5z`–`7z` for the white, green and red dragons. A red five is written
+//! with the number `0`, e.g. `0p`.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::NumPlayers;
+
+#[cfg(feature = "render")]
+pub mod svg;
+
+// Tile constants, for building lookup tables at compile time.
+pub const MAN_1: MahjongTile = number_tile(NumberSuit::Man, 1);
+pub const MAN_2: MahjongTile = number_tile(NumberSuit::Man, 2);
+pub const MAN_3: MahjongTile = number_tile(NumberSuit::Man, 3);
+pub const MAN_4: MahjongTile = number_tile(NumberSuit::Man, 4);
+pub const MAN_5: MahjongTile = number_tile(NumberSuit::Man, 5);
+pub const MAN_6: MahjongTile = number_tile(NumberSuit::Man, 6);
+pub const MAN_7: MahjongTile = number_tile(NumberSuit::Man, 7);
+pub const MAN_8: MahjongTile = number_tile(NumberSuit::Man, 8);
+pub const MAN_9: MahjongTile = number_tile(NumberSuit::Man, 9);
+pub const PIN_1: MahjongTile = number_tile(NumberSuit::Pin, 1);
+pub const PIN_2: MahjongTile = number_tile(NumberSuit::Pin, 2);
+pub const PIN_3: MahjongTile = number_tile(NumberSuit::Pin, 3);
+pub const PIN_4: MahjongTile = number_tile(NumberSuit::Pin, 4);
+pub const PIN_5: MahjongTile = number_tile(NumberSuit::Pin, 5);
+pub const PIN_6: MahjongTile = number_tile(NumberSuit::Pin, 6);
+pub const PIN_7: MahjongTile = number_tile(NumberSuit::Pin, 7);
+pub const PIN_8: MahjongTile = number_tile(NumberSuit::Pin, 8);
+pub const PIN_9: MahjongTile = number_tile(NumberSuit::Pin, 9);
+pub const SOU_1: MahjongTile = number_tile(NumberSuit::Sou, 1);
+pub const SOU_2: MahjongTile = number_tile(NumberSuit::Sou, 2);
+pub const SOU_3: MahjongTile = number_tile(NumberSuit::Sou, 3);
+pub const SOU_4: MahjongTile = number_tile(NumberSuit::Sou, 4);
+pub const SOU_5: MahjongTile = number_tile(NumberSuit::Sou, 5);
+pub const SOU_6: MahjongTile = number_tile(NumberSuit::Sou, 6);
+pub const SOU_7: MahjongTile = number_tile(NumberSuit::Sou, 7);
+pub const SOU_8: MahjongTile = number_tile(NumberSuit::Sou, 8);
+pub const SOU_9: MahjongTile = number_tile(NumberSuit::Sou, 9);
+pub const RED_MAN_5: MahjongTile = MahjongTile::Number(NumberTile::red_five(NumberSuit::Man));
+pub const RED_PIN_5: MahjongTile = MahjongTile::Number(NumberTile::red_five(NumberSuit::Pin));
+pub const RED_SOU_5: MahjongTile = MahjongTile::Number(NumberTile::red_five(NumberSuit::Sou));
+pub const EAST: MahjongTile = MahjongTile::Honor(HonorTile::Wind(Direction::East));
+pub const SOUTH: MahjongTile = MahjongTile::Honor(HonorTile::Wind(Direction::South));
+pub const WEST: MahjongTile = MahjongTile::Honor(HonorTile::Wind(Direction::West));
+pub const NORTH: MahjongTile = MahjongTile::Honor(HonorTile::Wind(Direction::North));
+pub const WHITE_DRAGON: MahjongTile = MahjongTile::Honor(HonorTile::Dragon(DragonColor::White));
+pub const GREEN_DRAGON: MahjongTile = MahjongTile::Honor(HonorTile::Dragon(DragonColor::Green));
+pub const RED_DRAGON: MahjongTile = MahjongTile::Honor(HonorTile::Dragon(DragonColor::Red));
+
+/// Builds a plain number tile in a const context, failing to compile if
+/// the number is out of range.
+const fn number_tile(suit: NumberSuit, number: u8) -> MahjongTile {
+    match NumberTile::new(suit, number) {
+        Some(tile) => MahjongTile::Number(tile),
+        None => panic!("number tiles are numbered 1-9"),
+    }
+}
+
+/// Behaviour shared by every kind of tile.
+pub trait Tile {
+    /// Which suit the tile belongs to.
+    fn suit_kind(&self) -> Suit;
+
+    /// The suit character used in MPSZ notation: `m`, `p`, `s` or `z`.
+    fn suit(&self) -> char {
+        self.suit_kind().as_char()
+    }
+}
+
+/// The four suits, with honors treated as a suit as in MPSZ notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Suit {
+    Man,
+    Pin,
+    Sou,
+    Honor,
+}
+
+impl Suit {
+    /// The suit character used in MPSZ notation.
+    pub const fn as_char(&self) -> char {
+        match self {
+            Suit::Man => 'm',
+            Suit::Pin => 'p',
+            Suit::Sou => 's',
+            Suit::Honor => 'z',
+        }
+    }
+
+    /// The suit for an MPSZ suit character.
+    pub fn from_char(c: char) -> Option<Suit> {
+        match c {
+            'm' => Some(Suit::Man),
+            'p' => Some(Suit::Pin),
+            's' => Some(Suit::Sou),
+            'z' => Some(Suit::Honor),
+            _ => None,
+        }
+    }
+}
+
+impl From<NumberSuit> for Suit {
+    fn from(suit: NumberSuit) -> Self {
+        match suit {
+            NumberSuit::Man => Suit::Man,
+            NumberSuit::Pin => Suit::Pin,
+            NumberSuit::Sou => Suit::Sou,
+        }
+    }
+}
+
+/// Any mahjong tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MahjongTile {
+    Number(NumberTile),
+    Honor(HonorTile),
+}
+
+/// A tile of one of the three number suits.
+///
+/// Red fives carry an explicit flag and still count as a 5 for runs and
+/// rank comparisons; only their notation uses the number 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NumberTile {
+    suit: NumberSuit,
+    number: u8,
+    red: bool,
+}
+
+/// The three number suits, in `m`/`p`/`s` order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NumberSuit {
+    Man,
+    Pin,
+    Sou,
+}
+
+/// A wind or dragon tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HonorTile {
+    Wind(Direction),
+    Dragon(DragonColor),
+}
+
+/// The three dragons, in `5z`/`6z`/`7z` order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DragonColor {
+    White,
+    Green,
+    Red,
+}
+
+/// The four winds, in `1z`–`4z` order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    East,
+    South,
+    West,
+    North,
+}
+
+impl Direction {
+    /// The wind after this one, North going round to East.
+    pub fn next(self) -> Direction {
+        match self {
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+            Direction::North => Direction::East,
+        }
+    }
+}
+
+/// The wind's English name, e.g. `East`.
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// How many red fives a tile set contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RedFive {
+    /// No red fives.
+    Zero,
+    /// One red five in each number suit.
+    #[default]
+    Three,
+    /// One red five in manzu and souzu, two in pinzu.
+    Four,
+}
+
+impl RedFive {
+    /// How many red fives of `suit` the tile set contains.
+    pub fn copies(&self, suit: NumberSuit) -> u8 {
+        match (self, suit) {
+            (RedFive::Zero, _) => 0,
+            (RedFive::Four, NumberSuit::Pin) => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// How [`MahjongTile::to_unicode_with`] marks red fives, which have no
+/// glyph of their own in the Unicode Mahjong Tiles block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RedFiveGlyph {
+    /// Use the same glyph as a plain five.
+    #[default]
+    Plain,
+    /// Colour the five red with ANSI escape codes, for terminals.
+    Ansi,
+    /// Follow the five with a marker character, e.g. `'*'`.
+    Suffix(char),
+}
+
+impl MahjongTile {
+    /// Whether this is a wind or dragon.
+    pub const fn is_honor(&self) -> bool {
+        matches!(self, MahjongTile::Honor(_))
+    }
+
+    /// Whether this is a manzu, pinzu or souzu tile.
+    pub const fn is_number(&self) -> bool {
+        matches!(self, MahjongTile::Number(_))
+    }
+
+    pub const fn as_honor(&self) -> Option<&HonorTile> {
+        match self {
+            MahjongTile::Honor(tile) => Some(tile),
+            MahjongTile::Number(_) => None,
+        }
+    }
+
+    pub const fn as_number(&self) -> Option<&NumberTile> {
+        match self {
+            MahjongTile::Number(tile) => Some(tile),
+            MahjongTile::Honor(_) => None,
+        }
+    }
+
+    pub fn into_honor(self) -> Option<HonorTile> {
+        self.as_honor().copied()
+    }
+
+    pub fn into_number(self) -> Option<NumberTile> {
+        self.as_number().copied()
+    }
+
+    /// Whether this is a red five.
+    pub const fn is_red(&self) -> bool {
+        matches!(self, MahjongTile::Number(tile) if tile.is_red())
+    }
+
+    /// Whether this is a 1 or 9 of a number suit.
+    pub const fn is_terminal(&self) -> bool {
+        matches!(self, MahjongTile::Number(tile) if tile.is_terminal())
+    }
+
+    /// Whether this is a terminal or an honor (yaochuuhai).
+    pub const fn is_yaochuu(&self) -> bool {
+        self.is_terminal() || self.is_honor()
+    }
+
+    /// Whether this is a 2–8 of a number suit (chunchanpai).
+    pub const fn is_simple(&self) -> bool {
+        matches!(self, MahjongTile::Number(tile) if tile.is_simple())
+    }
+
+    /// Whether the tile set for `num_players` contains this tile. Three-player
+    /// games play without the 2–8 of manzu.
+    pub fn is_in_play(&self, num_players: NumPlayers) -> bool {
+        num_players == NumPlayers::Four
+            || !matches!(self, MahjongTile::Number(n) if n.number_suit() == NumberSuit::Man && n.is_simple())
+    }
+
+    /// Whether two tiles are the same kind, treating a red five as equal to
+    /// a plain five of its suit.
+    pub fn eq_rank(&self, other: &MahjongTile) -> bool {
+        self.rank_key() == other.rank_key()
+    }
+
+    /// A key identifying this tile's kind regardless of colour, for use in
+    /// maps and sets that should group `0p` with `5p`.
+    pub fn rank_key(&self) -> RankKey {
+        Tile34::from(self)
+    }
+
+    /// The dora indicated by this tile when it is a dora indicator: the next
+    /// tile in its suit, wrapping 9 to 1, North to East and red dragon to
+    /// white dragon.
+    ///
+    /// In three-player games manzu has only 1 and 9, so each indicates the
+    /// other. The result is never a red five.
+    pub fn dora_from_indicator(&self, num_players: NumPlayers) -> MahjongTile {
+        match self {
+            MahjongTile::Number(tile) => {
+                let skip = num_players == NumPlayers::Three
+                    && tile.number_suit() == NumberSuit::Man
+                    && tile.number() == 1;
+                let next = match (skip, tile.number()) {
+                    (true, _) => 9,
+                    (false, 9) => 1,
+                    (false, n) => n + 1,
+                };
+                number_tile(tile.number_suit(), next)
+            }
+            MahjongTile::Honor(HonorTile::Wind(direction)) => {
+                let next = match direction {
+                    Direction::East => Direction::South,
+                    Direction::South => Direction::West,
+                    Direction::West => Direction::North,
+                    Direction::North => Direction::East,
+                };
+                MahjongTile::Honor(HonorTile::Wind(next))
+            }
+            MahjongTile::Honor(HonorTile::Dragon(color)) => {
+                let next = match color {
+                    DragonColor::White => DragonColor::Green,
+                    DragonColor::Green => DragonColor::Red,
+                    DragonColor::Red => DragonColor::White,
+                };
+                MahjongTile::Honor(HonorTile::Dragon(next))
+            }
+        }
+    }
+
+    /// The next number tile in the same suit; `None` for 9s and honors.
+    pub fn succ(&self) -> Option<MahjongTile> {
+        match self {
+            MahjongTile::Number(tile) => tile.succ().map(MahjongTile::Number),
+            MahjongTile::Honor(_) => None,
+        }
+    }
+
+    /// The previous number tile in the same suit; `None` for 1s and honors.
+    pub fn pred(&self) -> Option<MahjongTile> {
+        match self {
+            MahjongTile::Number(tile) => tile.pred().map(MahjongTile::Number),
+            MahjongTile::Honor(_) => None,
+        }
+    }
+
+    /// Iterates over the 34 distinct tile kinds in canonical order, without
+    /// red fives.
+    pub fn all_kinds() -> impl Iterator<Item = MahjongTile> {
+        let numbers = [NumberSuit::Man, NumberSuit::Pin, NumberSuit::Sou]
+            .into_iter()
+            .flat_map(|suit| {
+                (1..=9).map(move |n| {
+                    MahjongTile::Number(NumberTile::new(suit, n).expect("1-9 are valid numbers"))
+                })
+            });
+        let honors = (1..=7).map(|n| from_parts(n, 'z').expect("1z-7z are valid honors"));
+        numbers.chain(honors)
+    }
+
+    /// Converts a Tenhou tile ID (0–135) into a tile.
+    ///
+    /// Tenhou numbers the 136 tiles as `kind * 4 + copy`, with kinds in
+    /// canonical order. The first copy of each five (IDs 16, 52 and 88) is
+    /// the red five in games played with red fives, and is returned as such;
+    /// use [`MahjongTile::from_tenhou_id_for`] for other red five settings.
+    pub fn from_tenhou_id(id: u8) -> Option<MahjongTile> {
+        Self::from_tenhou_id_for(id, RedFive::Three)
+    }
+
+    /// Like [`MahjongTile::from_tenhou_id`], for a game using the given red
+    /// five setting: the first copies of each five are red, as many as the
+    /// setting has of that suit, so with [`RedFive::Zero`] every five is
+    /// plain.
+    pub fn from_tenhou_id_for(id: u8, red_five: RedFive) -> Option<MahjongTile> {
+        if id >= 136 {
+            return None;
+        }
+        let red_suit = match id / 4 {
+            4 => Some(NumberSuit::Man),
+            13 => Some(NumberSuit::Pin),
+            22 => Some(NumberSuit::Sou),
+            _ => None,
+        };
+        match red_suit {
+            Some(suit) if id % 4 < red_five.copies(suit) => {
+                Some(MahjongTile::Number(NumberTile::red_five(suit)))
+            }
+            _ => Self::from_kind_index(id / 4),
+        }
+    }
+
+    /// Converts this tile into a Tenhou tile ID, given which of the four
+    /// copies (0–3) it is.
+    ///
+    /// The red copies of a five come first, so a red five's `copy_index`
+    /// counts among those: copy 1 is only the second red 5p of
+    /// [`RedFive::Four`]. Games with red fives should number plain fives
+    /// after the red ones. Returns `None` if `copy_index > 3`, or if no
+    /// tile set has that many red copies.
+    pub fn to_tenhou_id(&self, copy_index: u8) -> Option<u8> {
+        let copies = match self {
+            MahjongTile::Number(tile) if tile.is_red() => RedFive::Four.copies(tile.number_suit()),
+            _ => 4,
+        };
+        (copy_index < copies).then(|| self.kind_index() * 4 + copy_index)
+    }
+
+    /// Parses a tile string from a Mahjong Soul record.
+    ///
+    /// Mahjong Soul writes tiles in MPSZ notation with `0` for red fives.
+    /// Its honor numbering matches this crate's: `5z` is the white dragon
+    /// (haku), `6z` green (hatsu) and `7z` red (chun), so dragons map
+    /// directly. This function exists so importers state that
+    /// assumption in one place rather than calling [`build`] and hoping.
+    pub fn from_majsoul_str(s: &str) -> Result<MahjongTile, TileParseError> {
+        build(s)
+    }
+
+    /// Writes this tile the way Mahjong Soul records it; see
+    /// [`from_majsoul_str`](Self::from_majsoul_str).
+    pub fn to_majsoul_str(&self) -> String {
+        self.to_string()
+    }
+
+    /// The glyph for this tile from the Unicode Mahjong Tiles block, e.g.
+    /// `'🀙'` for `1p`. Red fives use the glyph of a plain five.
+    ///
+    /// The alternate Display form, `{:#}`, writes the same glyph.
+    pub fn to_unicode(&self) -> char {
+        let offset = match self {
+            MahjongTile::Number(tile) => {
+                let number = tile.number() as u32;
+                match tile.number_suit() {
+                    NumberSuit::Man => 0x07 + number - 1,
+                    NumberSuit::Sou => 0x10 + number - 1,
+                    NumberSuit::Pin => 0x19 + number - 1,
+                }
+            }
+            MahjongTile::Honor(HonorTile::Wind(direction)) => match direction {
+                Direction::East => 0x00,
+                Direction::South => 0x01,
+                Direction::West => 0x02,
+                Direction::North => 0x03,
+            },
+            MahjongTile::Honor(HonorTile::Dragon(color)) => match color {
+                DragonColor::Red => 0x04,
+                DragonColor::Green => 0x05,
+                DragonColor::White => 0x06,
+            },
+        };
+        char::from_u32(0x1F000 + offset).expect("mahjong glyphs are valid chars")
+    }
+
+    /// Like [`to_unicode`](Self::to_unicode), with red fives marked as chosen.
+    pub fn to_unicode_with(&self, red_five: RedFiveGlyph) -> String {
+        let glyph = self.to_unicode();
+        if !self.is_red() {
+            return glyph.to_string();
+        }
+        match red_five {
+            RedFiveGlyph::Plain => glyph.to_string(),
+            RedFiveGlyph::Ansi => format!("\x1b[31m{}\x1b[0m", glyph),
+            RedFiveGlyph::Suffix(marker) => format!("{}{}", glyph, marker),
+        }
+    }
+
+    /// The tile's English name, e.g. "Three of Circles", "Red Five of
+    /// Bamboo" or "West Wind".
+    pub fn english_name(&self) -> String {
+        match self {
+            MahjongTile::Number(tile) => {
+                const NUMBERS: [&str; 9] = [
+                    "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine",
+                ];
+                let suit = match tile.number_suit() {
+                    NumberSuit::Man => "Characters",
+                    NumberSuit::Pin => "Circles",
+                    NumberSuit::Sou => "Bamboo",
+                };
+                let red = if tile.is_red() { "Red " } else { "" };
+                format!("{}{} of {}", red, NUMBERS[tile.number() as usize - 1], suit)
+            }
+            MahjongTile::Honor(HonorTile::Wind(direction)) => {
+                let name = match direction {
+                    Direction::East => "East",
+                    Direction::South => "South",
+                    Direction::West => "West",
+                    Direction::North => "North",
+                };
+                format!("{} Wind", name)
+            }
+            MahjongTile::Honor(HonorTile::Dragon(color)) => {
+                let name = match color {
+                    DragonColor::White => "White",
+                    DragonColor::Green => "Green",
+                    DragonColor::Red => "Red",
+                };
+                format!("{} Dragon", name)
+            }
+        }
+    }
+
+    /// The tile's Japanese name, e.g. "三筒", "赤五索" or "西".
+    pub fn japanese_name(&self) -> String {
+        match self {
+            MahjongTile::Number(tile) => {
+                const NUMBERS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+                let suit = match tile.number_suit() {
+                    NumberSuit::Man => '萬',
+                    NumberSuit::Pin => '筒',
+                    NumberSuit::Sou => '索',
+                };
+                let red = if tile.is_red() { "赤" } else { "" };
+                format!("{}{}{}", red, NUMBERS[tile.number() as usize - 1], suit)
+            }
+            MahjongTile::Honor(tile) => {
+                const HONORS: [char; 7] = ['東', '南', '西', '北', '白', '發', '中'];
+                HONORS[tile.number() as usize - 1].to_string()
+            }
+        }
+    }
+
+    /// Position of this tile's kind in canonical order, 0–33. Red fives share
+    /// the index of their five.
+    fn kind_index(&self) -> u8 {
+        match self {
+            MahjongTile::Number(tile) => tile.number_suit() as u8 * 9 + tile.number() - 1,
+            MahjongTile::Honor(tile) => 27 + tile.number() - 1,
+        }
+    }
+
+    /// The inverse of `kind_index`, never producing red fives.
+    fn from_kind_index(index: u8) -> Option<MahjongTile> {
+        let (suit, number) = match index {
+            0..=8 => (NumberSuit::Man, index + 1),
+            9..=17 => (NumberSuit::Pin, index - 8),
+            18..=26 => (NumberSuit::Sou, index - 17),
+            27..=33 => return from_parts(index - 26, 'z').ok(),
+            _ => return None,
+        };
+        NumberTile::new(suit, number).map(MahjongTile::Number)
+    }
+}
+
+impl NumberTile {
+    /// A plain (not red) tile, or `None` if `number` is not 1–9.
+    pub const fn new(suit: NumberSuit, number: u8) -> Option<NumberTile> {
+        match number {
+            1..=9 => Some(NumberTile {
+                suit,
+                number,
+                red: false,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The red five of a suit.
+    pub const fn red_five(suit: NumberSuit) -> NumberTile {
+        NumberTile {
+            suit,
+            number: 5,
+            red: true,
+        }
+    }
+
+    /// Which of the three number suits this tile belongs to.
+    pub const fn number_suit(&self) -> NumberSuit {
+        self.suit
+    }
+
+    /// The tile's rank, 1–9. Red fives are 5.
+    pub const fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Whether this is a red five.
+    pub const fn is_red(&self) -> bool {
+        self.red
+    }
+
+    /// The number written in notation, with 0 for a red five.
+    fn notation_number(&self) -> u8 {
+        if self.red {
+            0
+        } else {
+            self.number
+        }
+    }
+
+    /// The next number in the same suit, or `None` after a 9. The result is
+    /// never a red five.
+    pub fn succ(&self) -> Option<NumberTile> {
+        NumberTile::new(self.suit, self.number + 1)
+    }
+
+    /// The previous number in the same suit, or `None` before a 1. The
+    /// result is never a red five.
+    pub fn pred(&self) -> Option<NumberTile> {
+        NumberTile::new(self.suit, self.number - 1)
+    }
+
+    /// Whether this is a 1 or a 9.
+    pub const fn is_terminal(&self) -> bool {
+        matches!(self.number(), 1 | 9)
+    }
+
+    /// Whether this is a 2–8.
+    pub const fn is_simple(&self) -> bool {
+        !self.is_terminal()
+    }
+
+    /// Position within the suit for ordering: red fives sort between 4 and 5.
+    fn order_key(&self) -> (NumberSuit, u8) {
+        (self.suit, self.number * 2 - self.red as u8)
+    }
+}
+
+impl Ord for NumberTile {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order_key().cmp(&other.order_key())
+    }
+}
+
+impl PartialOrd for NumberTile {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl HonorTile {
+    /// The number used for this honor in `z` notation (1–7).
+    pub const fn number(&self) -> u8 {
+        match self {
+            HonorTile::Wind(Direction::East) => 1,
+            HonorTile::Wind(Direction::South) => 2,
+            HonorTile::Wind(Direction::West) => 3,
+            HonorTile::Wind(Direction::North) => 4,
+            HonorTile::Dragon(DragonColor::White) => 5,
+            HonorTile::Dragon(DragonColor::Green) => 6,
+            HonorTile::Dragon(DragonColor::Red) => 7,
+        }
+    }
+}
+
+impl Tile for MahjongTile {
+    fn suit_kind(&self) -> Suit {
+        match self {
+            MahjongTile::Number(tile) => tile.suit_kind(),
+            MahjongTile::Honor(tile) => tile.suit_kind(),
+        }
+    }
+}
+
+impl Tile for NumberTile {
+    fn suit_kind(&self) -> Suit {
+        self.suit.into()
+    }
+}
+
+impl Tile for HonorTile {
+    fn suit_kind(&self) -> Suit {
+        Suit::Honor
+    }
+}
+
+impl fmt::Display for MahjongTile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            return write!(f, "{}", self.to_unicode());
+        }
+        match self {
+            MahjongTile::Number(tile) => tile.fmt(f),
+            MahjongTile::Honor(tile) => tile.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for NumberTile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.notation_number(), self.suit())
+    }
+}
+
+impl fmt::Display for HonorTile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.number(), self.suit())
+    }
+}
+
+/// The complete 136-tile set, four copies of each kind in canonical order,
+/// with regular fives swapped for red ones as configured.
+pub fn full_set(red_five: RedFive) -> Vec<MahjongTile> {
+    let mut tiles: Vec<MahjongTile> = MahjongTile::all_kinds()
+        .flat_map(|tile| std::iter::repeat_n(tile, 4))
+        .collect();
+    let red_suits: &[NumberSuit] = match red_five {
+        RedFive::Zero => &[],
+        RedFive::Three => &[NumberSuit::Man, NumberSuit::Pin, NumberSuit::Sou],
+        RedFive::Four => &[
+            NumberSuit::Man,
+            NumberSuit::Pin,
+            NumberSuit::Pin,
+            NumberSuit::Sou,
+        ],
+    };
+    for &suit in red_suits {
+        let five = NumberTile::new(suit, 5).map(MahjongTile::Number);
+        let pos = tiles
+            .iter()
+            .position(|t| Some(*t) == five)
+            .expect("the set contains four of every five");
+        tiles[pos] = MahjongTile::Number(NumberTile::red_five(suit));
+    }
+    tiles
+}
+
+/// A tile as seen from one player's perspective: either known, or face
+/// down and unknown.
+///
+/// Replays recorded by one player can't see other players' draws and
+/// concealed tiles, or most of the wall. Written as the tile's notation, or
+/// `?` when hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObservedTile {
+    Hidden,
+    Known(MahjongTile),
+}
+
+impl ObservedTile {
+    /// The tile, if it is known.
+    pub const fn known(&self) -> Option<MahjongTile> {
+        match self {
+            ObservedTile::Known(tile) => Some(*tile),
+            ObservedTile::Hidden => None,
+        }
+    }
+
+    pub const fn is_hidden(&self) -> bool {
+        matches!(self, ObservedTile::Hidden)
+    }
+}
+
+impl From<MahjongTile> for ObservedTile {
+    fn from(tile: MahjongTile) -> Self {
+        ObservedTile::Known(tile)
+    }
+}
+
+impl From<Option<MahjongTile>> for ObservedTile {
+    fn from(tile: Option<MahjongTile>) -> Self {
+        tile.map_or(ObservedTile::Hidden, ObservedTile::Known)
+    }
+}
+
+impl From<ObservedTile> for Option<MahjongTile> {
+    fn from(tile: ObservedTile) -> Self {
+        tile.known()
+    }
+}
+
+impl fmt::Display for ObservedTile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObservedTile::Known(tile) => tile.fmt(f),
+            ObservedTile::Hidden => write!(f, "?"),
+        }
+    }
+}
+
+impl FromStr for ObservedTile {
+    type Err = TileParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "?" => Ok(ObservedTile::Hidden),
+            _ => build(s).map(ObservedTile::Known),
+        }
+    }
+}
+
+/// A tile kind as a flat index 0–33: manzu 1–9, pinzu 1–9, souzu 1–9, then
+/// the seven honors in `1z`–`7z` order.
+///
+/// Red fives map to the index of their five, so analysis code can count
+/// tiles in a `[u8; 34]` regardless of colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tile34(pub u8);
+
+impl Tile34 {
+    /// The number of distinct tile kinds.
+    pub const COUNT: usize = 34;
+
+    /// The index as a `usize`, for indexing flat arrays.
+    pub const fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    /// Iterates over all 34 kinds in order.
+    pub fn all() -> impl Iterator<Item = Tile34> {
+        (0..Self::COUNT as u8).map(Tile34)
+    }
+}
+
+impl From<MahjongTile> for Tile34 {
+    fn from(tile: MahjongTile) -> Self {
+        Tile34(tile.kind_index())
+    }
+}
+
+impl From<&MahjongTile> for Tile34 {
+    fn from(tile: &MahjongTile) -> Self {
+        Tile34(tile.kind_index())
+    }
+}
+
+impl TryFrom<Tile34> for MahjongTile {
+    type Error = Tile34OutOfRange;
+
+    /// Converts back to a (never red) tile.
+    fn try_from(tile: Tile34) -> Result<Self, Self::Error> {
+        MahjongTile::from_kind_index(tile.0).ok_or(Tile34OutOfRange(tile.0))
+    }
+}
+
+/// How many copies of each tile kind have been seen, with red fives counted
+/// as plain fives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileCounts([u8; Tile34::COUNT]);
+
+impl TileCounts {
+    /// No tiles seen.
+    pub fn new() -> TileCounts {
+        TileCounts([0; Tile34::COUNT])
+    }
+
+    /// Records one more copy of `tile`.
+    pub fn add(&mut self, tile: MahjongTile) {
+        self.0[Tile34::from(tile).index()] += 1;
+    }
+
+    /// How many copies of `tile`'s kind have been seen.
+    pub fn get(&self, tile: MahjongTile) -> u8 {
+        self.0[Tile34::from(tile).index()]
+    }
+
+    /// How many of the four copies of `tile`'s kind have not been seen.
+    pub fn remaining(&self, tile: MahjongTile) -> u8 {
+        4u8.saturating_sub(self.get(tile))
+    }
+
+    /// The number of tiles seen in total.
+    pub fn total(&self) -> usize {
+        self.0.iter().map(|&c| c as usize).sum()
+    }
+}
+
+impl Default for TileCounts {
+    fn default() -> Self {
+        TileCounts::new()
+    }
+}
+
+impl Extend<MahjongTile> for TileCounts {
+    fn extend<I: IntoIterator<Item = MahjongTile>>(&mut self, iter: I) {
+        for tile in iter {
+            self.add(tile);
+        }
+    }
+}
+
+impl FromIterator<MahjongTile> for TileCounts {
+    fn from_iter<I: IntoIterator<Item = MahjongTile>>(iter: I) -> Self {
+        let mut counts = TileCounts::new();
+        counts.extend(iter);
+        counts
+    }
+}
+
+/// The key returned by [`MahjongTile::rank_key`].
+pub type RankKey = Tile34;
+
+/// A [`Tile34`] index that is not in 0–33.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile34OutOfRange(pub u8);
+
+impl fmt::Display for Tile34OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tile index {} is out of range 0-33", self.0)
+    }
+}
+
+impl std::error::Error for Tile34OutOfRange {}
+
+/// Why a tile string could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileParseError {
+    /// The notation was not exactly two characters long.
+    InvalidLength(usize),
+    /// The number is not valid for the suit, e.g. `8z`.
+    InvalidNumber { number: u8, suit: char },
+    /// The suit character is not one of `m`, `p`, `s` or `z`.
+    InvalidSuit(char),
+    /// The number position holds something other than a digit.
+    NotADigit(char),
+    /// Shorthand ended with numbers that were not followed by a suit.
+    MissingSuit,
+    /// A suit character appeared without any numbers before it.
+    MissingNumber(char),
+    /// A red five was given for a game played without red fives.
+    RedFiveNotAllowed(char),
+    /// A Tenhou tile ID outside 0–135, as written.
+    InvalidTenhouId(String),
+}
+
+impl fmt::Display for TileParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TileParseError::InvalidLength(len) => {
+                write!(f, "tile notation must be 2 characters, got {}", len)
+            }
+            TileParseError::InvalidNumber { number, suit } => {
+                write!(f, "{} is not a valid number for suit '{}'", number, suit)
+            }
+            TileParseError::InvalidSuit(c) => write!(f, "{:?} is not a valid suit", c),
+            TileParseError::NotADigit(c) => write!(f, "{:?} is not a digit", c),
+            TileParseError::MissingSuit => write!(f, "tile numbers are missing a suit"),
+            TileParseError::MissingNumber(c) => write!(f, "suit {:?} has no tile numbers", c),
+            TileParseError::InvalidTenhouId(id) => {
+                write!(f, "{} is not a Tenhou tile ID (0-135)", id)
+            }
+            TileParseError::RedFiveNotAllowed(c) => {
+                write!(f, "0{} is a red five, but red fives are not in use", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TileParseError {}
+
+/// Builds a tile from its two-character notation, e.g. `"3p"` or `"7z"`.
+pub fn build(s: &str) -> Result<MahjongTile, TileParseError> {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() != 2 {
+        return Err(TileParseError::InvalidLength(chars.len()));
+    }
+    let number = chars[0]
+        .to_digit(10)
+        .ok_or(TileParseError::NotADigit(chars[0]))? as u8;
+    from_parts(number, chars[1])
+}
+
+/// Parses MPSZ shorthand for several tiles, where a run of digits shares the
+/// suit character that follows it: `"123m456p77z"`.
+///
+/// Whitespace between groups is ignored.
+pub fn parse_tiles(s: &str) -> Result<Vec<MahjongTile>, TileParseError> {
+    let mut tiles = Vec::new();
+    let mut pending: Vec<u8> = Vec::new();
+    for c in s.chars().filter(|c| !c.is_whitespace()) {
+        if let Some(digit) = c.to_digit(10) {
+            pending.push(digit as u8);
+            continue;
+        }
+        if pending.is_empty() {
+            return Err(TileParseError::MissingNumber(c));
+        }
+        for number in pending.drain(..) {
+            tiles.push(from_parts(number, c)?);
+        }
+    }
+    if !pending.is_empty() {
+        return Err(TileParseError::MissingSuit);
+    }
+    Ok(tiles)
+}
+
+/// Like [`build`], for a game using the given red five setting: with
+/// [`RedFive::Zero`] the red five notation `0m`/`0p`/`0s` is rejected.
+pub fn build_for(s: &str, red_five: RedFive) -> Result<MahjongTile, TileParseError> {
+    check_red_five(build(s)?, red_five)
+}
+
+/// Like [`parse_tiles`], for a game using the given red five setting.
+pub fn parse_tiles_for(s: &str, red_five: RedFive) -> Result<Vec<MahjongTile>, TileParseError> {
+    parse_tiles(s)?
+        .into_iter()
+        .map(|tile| check_red_five(tile, red_five))
+        .collect()
+}
+
+fn check_red_five(tile: MahjongTile, red_five: RedFive) -> Result<MahjongTile, TileParseError> {
+    if red_five == RedFive::Zero && tile.is_red() {
+        return Err(TileParseError::RedFiveNotAllowed(tile.suit()));
+    }
+    Ok(tile)
+}
+
+/// Sorts tiles into the conventional order: manzu, pinzu, souzu, then
+/// honors, by number within each suit. A red five sorts just before the
+/// plain fives of its suit.
+pub fn sort_hand(tiles: &mut [MahjongTile]) {
+    tiles.sort_unstable();
+}
+
+/// Writes tiles as compact MPSZ shorthand, the inverse of [`parse_tiles`].
+///
+/// Tiles are sorted first, so the output is canonical: `"123m456p77z"`.
+pub fn format_tiles(tiles: &[MahjongTile]) -> String {
+    let mut sorted = tiles.to_vec();
+    sort_hand(&mut sorted);
+    let mut out = String::new();
+    for (i, tile) in sorted.iter().enumerate() {
+        let number = match tile {
+            MahjongTile::Number(tile) => tile.notation_number(),
+            MahjongTile::Honor(tile) => tile.number(),
+        };
+        out.push(char::from(b'0' + number));
+        if sorted.get(i + 1).map(Tile::suit) != Some(tile.suit()) {
+            out.push(tile.suit());
+        }
+    }
+    out
+}
+
+/// Parses a comma-separated list of Tenhou tile IDs, as found in mjlog
+/// attributes such as `hai0="1,35,..."`.
+pub fn parse_tenhou_ids(s: &str) -> Result<Vec<MahjongTile>, TileParseError> {
+    parse_tenhou_ids_for(s, RedFive::Three)
+}
+
+/// Like [`parse_tenhou_ids`], for a game using the given red five setting.
+pub fn parse_tenhou_ids_for(
+    s: &str,
+    red_five: RedFive,
+) -> Result<Vec<MahjongTile>, TileParseError> {
+    if s.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    s.split(',')
+        .map(|part| {
+            let part = part.trim();
+            if part.is_empty() {
+                return Err(TileParseError::InvalidLength(0));
+            }
+            if let Some(c) = part.chars().find(|c| !c.is_ascii_digit()) {
+                return Err(TileParseError::NotADigit(c));
+            }
+            part.parse::<u8>()
+                .ok()
+                .and_then(|id| MahjongTile::from_tenhou_id_for(id, red_five))
+                .ok_or_else(|| TileParseError::InvalidTenhouId(part.to_string()))
+        })
+        .collect()
+}
+
+/// Writes tiles as a comma-separated list of Tenhou tile IDs, the inverse
+/// of [`parse_tenhou_ids`].
+///
+/// Each tile gets the lowest copy of its kind not already used in the list;
+/// plain fives skip copy 0, the red-five slot, unless all others are taken.
+/// Returns `None` if the list holds more than four copies of a kind.
+pub fn format_tenhou_ids(tiles: &[MahjongTile]) -> Option<String> {
+    format_tenhou_ids_for(tiles, RedFive::Three)
+}
+
+/// Like [`format_tenhou_ids`], for a game using the given red five
+/// setting: red fives take the red-five slots of their suit and plain
+/// fives the others first. Returns `None` if the list holds more red
+/// fives than the setting has.
+pub fn format_tenhou_ids_for(tiles: &[MahjongTile], red_five: RedFive) -> Option<String> {
+    let mut used = [[false; 4]; Tile34::COUNT];
+    let mut ids = Vec::with_capacity(tiles.len());
+    for tile in tiles {
+        let copies = &mut used[Tile34::from(tile).index()];
+        let order: Vec<u8> = match tile {
+            MahjongTile::Number(n) if n.number() == 5 => {
+                let red = red_five.copies(n.number_suit());
+                if n.is_red() {
+                    (0..red).collect()
+                } else {
+                    (red..4).chain(0..red).collect()
+                }
+            }
+            _ => (0..4).collect(),
+        };
+        let copy = *order.iter().find(|&&c| !copies[c as usize])?;
+        copies[copy as usize] = true;
+        ids.push(tile.to_tenhou_id(copy)?.to_string());
+    }
+    Some(ids.join(","))
+}
+
+/// Builds a tile from an already separated number and suit character.
+fn from_parts(number: u8, suit: char) -> Result<MahjongTile, TileParseError> {
+    let number_suit = match suit {
+        'm' => Some(NumberSuit::Man),
+        'p' => Some(NumberSuit::Pin),
+        's' => Some(NumberSuit::Sou),
+        _ => None,
+    };
+    if let Some(number_suit) = number_suit {
+        let tile = match number {
+            0 => NumberTile::red_five(number_suit),
+            _ => NumberTile::new(number_suit, number)
+                .ok_or(TileParseError::InvalidNumber { number, suit })?,
+        };
+        return Ok(MahjongTile::Number(tile));
+    }
+    let tile = match suit {
+        'z' => {
+            let honor = match number {
+                1 => HonorTile::Wind(Direction::East),
+                2 => HonorTile::Wind(Direction::South),
+                3 => HonorTile::Wind(Direction::West),
+                4 => HonorTile::Wind(Direction::North),
+                5 => HonorTile::Dragon(DragonColor::White),
+                6 => HonorTile::Dragon(DragonColor::Green),
+                7 => HonorTile::Dragon(DragonColor::Red),
+                _ => return Err(TileParseError::InvalidNumber { number, suit }),
+            };
+            MahjongTile::Honor(honor)
+        }
+        c => return Err(TileParseError::InvalidSuit(c)),
+    };
+    Ok(tile)
+}
+
+impl FromStr for MahjongTile {
+    type Err = TileParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        build(s)
+    }
+}
+
+impl FromStr for NumberTile {
+    type Err = TileParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match build(s)? {
+            MahjongTile::Number(tile) => Ok(tile),
+            MahjongTile::Honor(tile) => Err(TileParseError::InvalidSuit(tile.suit())),
+        }
+    }
+}
+
+impl FromStr for HonorTile {
+    type Err = TileParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match build(s)? {
+            MahjongTile::Honor(tile) => Ok(tile),
+            MahjongTile::Number(tile) => Err(TileParseError::InvalidSuit(tile.suit())),
+        }
+    }
+}
+
+impl TryFrom<&str> for MahjongTile {
+    type Error = TileParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<&str> for NumberTile {
+    type Error = TileParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<&str> for HonorTile {
+    type Error = TileParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Tiles serialize as their shorthand strings (`"3p"`, `"0s"`, `"7z"`).
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{HonorTile, MahjongTile, NumberTile, ObservedTile};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    macro_rules! shorthand_serde {
+        ($($tile:ty),*) => {$(
+            impl Serialize for $tile {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $tile {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+                    s.parse().map_err(D::Error::custom)
+                }
+            }
+        )*};
+    }
+
+    shorthand_serde!(MahjongTile, NumberTile, HonorTile, ObservedTile);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn build_number_tiles() {
+        assert_eq!(
+            build("3p"),
+            Ok(MahjongTile::Number(
+                NumberTile::new(NumberSuit::Pin, 3).unwrap()
+            ))
+        );
+        assert_eq!(
+            build("9m"),
+            Ok(MahjongTile::Number(
+                NumberTile::new(NumberSuit::Man, 9).unwrap()
+            ))
+        );
+        assert_eq!(
+            build("0s"),
+            Ok(MahjongTile::Number(NumberTile::red_five(NumberSuit::Sou)))
+        );
+    }
+
+    #[test]
+    fn build_honor_tiles() {
+        assert_eq!(
+            build("1z"),
+            Ok(MahjongTile::Honor(HonorTile::Wind(Direction::East)))
+        );
+        assert_eq!(
+            build("7z"),
+            Ok(MahjongTile::Honor(HonorTile::Dragon(DragonColor::Red)))
+        );
+    }
+
+    #[test]
+    fn build_rejects_bad_input() {
+        assert_eq!(build(""), Err(TileParseError::InvalidLength(0)));
+        assert_eq!(build("3"), Err(TileParseError::InvalidLength(1)));
+        assert_eq!(build("33p"), Err(TileParseError::InvalidLength(3)));
+        assert_eq!(build("xp"), Err(TileParseError::NotADigit('x')));
+        assert_eq!(build("3x"), Err(TileParseError::InvalidSuit('x')));
+        assert_eq!(
+            build("8z"),
+            Err(TileParseError::InvalidNumber {
+                number: 8,
+                suit: 'z'
+            })
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["1m", "0p", "9s", "4z", "6z"] {
+            assert_eq!(build(s).unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn tiles_are_copy_and_hashable() {
+        let tile = build("5m").unwrap();
+        let copy = tile;
+        let set: HashSet<MahjongTile> = [tile, copy].into_iter().collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn ordering_is_suit_then_number() {
+        let mut tiles: Vec<MahjongTile> = ["1z", "5p", "9s", "0p", "4p", "1m", "5z"]
+            .iter()
+            .map(|s| build(s).unwrap())
+            .collect();
+        tiles.sort();
+        let sorted: Vec<String> = tiles.iter().map(|t| t.to_string()).collect();
+        assert_eq!(sorted, ["1m", "4p", "0p", "5p", "9s", "1z", "5z"]);
+    }
+
+    #[test]
+    fn parse_via_from_str() {
+        assert_eq!("3p".parse::<MahjongTile>(), build("3p"));
+        assert_eq!(
+            "3p".parse::<NumberTile>().ok(),
+            NumberTile::new(NumberSuit::Pin, 3)
+        );
+        assert_eq!(
+            "6z".parse::<HonorTile>(),
+            Ok(HonorTile::Dragon(DragonColor::Green))
+        );
+        assert_eq!(
+            "1z".parse::<NumberTile>(),
+            Err(TileParseError::InvalidSuit('z'))
+        );
+        assert_eq!(
+            "1s".parse::<HonorTile>(),
+            Err(TileParseError::InvalidSuit('s'))
+        );
+    }
+
+    #[test]
+    fn parse_via_try_from() {
+        assert_eq!(MahjongTile::try_from("2z"), build("2z"));
+        assert_eq!(
+            NumberTile::try_from("0m"),
+            Ok(NumberTile::red_five(NumberSuit::Man))
+        );
+        assert!(HonorTile::try_from("9z").is_err());
+    }
+
+    #[test]
+    fn parse_shorthand() {
+        let tiles = parse_tiles("123m456p77z").unwrap();
+        let expected: Vec<MahjongTile> = ["1m", "2m", "3m", "4p", "5p", "6p", "7z", "7z"]
+            .iter()
+            .map(|s| build(s).unwrap())
+            .collect();
+        assert_eq!(tiles, expected);
+        assert_eq!(parse_tiles("05s 1z").unwrap().len(), 3);
+        assert_eq!(parse_tiles(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_shorthand_errors() {
+        assert_eq!(parse_tiles("123"), Err(TileParseError::MissingSuit));
+        assert_eq!(parse_tiles("12mp"), Err(TileParseError::MissingNumber('p')));
+        assert_eq!(parse_tiles("12x"), Err(TileParseError::InvalidSuit('x')));
+        assert_eq!(
+            parse_tiles("18z"),
+            Err(TileParseError::InvalidNumber {
+                number: 8,
+                suit: 'z'
+            })
+        );
+    }
+
+    #[test]
+    fn format_shorthand() {
+        let tiles = parse_tiles("7z 654p 77z 321m").unwrap();
+        assert_eq!(format_tiles(&tiles), "123m456p777z");
+        assert_eq!(format_tiles(&parse_tiles("505m").unwrap()), "055m");
+        assert_eq!(format_tiles(&parse_tiles("64m").unwrap()), "46m");
+        assert_eq!(format_tiles(&[]), "");
+    }
+
+    #[test]
+    fn format_round_trips_parse() {
+        let s = "1199m406p5s12345z";
+        assert_eq!(format_tiles(&parse_tiles(s).unwrap()), "1199m406p5s12345z");
+    }
+
+    #[test]
+    fn all_kinds_has_34_distinct_tiles() {
+        let kinds: Vec<MahjongTile> = MahjongTile::all_kinds().collect();
+        assert_eq!(kinds.len(), 34);
+        assert_eq!(kinds.iter().collect::<HashSet<_>>().len(), 34);
+        assert_eq!(kinds[0].to_string(), "1m");
+        assert_eq!(kinds[33].to_string(), "7z");
+        assert!(kinds.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn full_set_contains_configured_red_fives() {
+        let count = |set: &[MahjongTile], s: &str| {
+            let tile = build(s).unwrap();
+            set.iter().filter(|t| **t == tile).count()
+        };
+        for red_five in [RedFive::Zero, RedFive::Three, RedFive::Four] {
+            assert_eq!(full_set(red_five).len(), 136);
+        }
+        let plain = full_set(RedFive::Zero);
+        assert_eq!(count(&plain, "5p"), 4);
+        assert_eq!(count(&plain, "0p"), 0);
+        let three = full_set(RedFive::Three);
+        assert_eq!((count(&three, "5m"), count(&three, "0m")), (3, 1));
+        assert_eq!((count(&three, "5s"), count(&three, "0s")), (3, 1));
+        let four = full_set(RedFive::Four);
+        assert_eq!((count(&four, "5p"), count(&four, "0p")), (2, 2));
+        assert_eq!(count(&four, "7z"), 4);
+        for red_five in [RedFive::Zero, RedFive::Three, RedFive::Four] {
+            let set = full_set(red_five);
+            for (suit, red) in [
+                (NumberSuit::Man, "0m"),
+                (NumberSuit::Pin, "0p"),
+                (NumberSuit::Sou, "0s"),
+            ] {
+                assert_eq!(red_five.copies(suit) as usize, count(&set, red));
+            }
+        }
+    }
+
+    #[test]
+    fn tenhou_ids() {
+        let tile = |id| MahjongTile::from_tenhou_id(id).unwrap().to_string();
+        assert_eq!(tile(0), "1m");
+        assert_eq!(tile(3), "1m");
+        assert_eq!(tile(16), "0m");
+        assert_eq!(tile(17), "5m");
+        assert_eq!(tile(52), "0p");
+        assert_eq!(tile(88), "0s");
+        assert_eq!(tile(108), "1z");
+        assert_eq!(tile(135), "7z");
+        assert_eq!(MahjongTile::from_tenhou_id(136), None);
+
+        let tile = |id, red_five| {
+            MahjongTile::from_tenhou_id_for(id, red_five)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(tile(16, RedFive::Zero), "5m");
+        assert_eq!(tile(52, RedFive::Zero), "5p");
+        assert_eq!(tile(53, RedFive::Three), "5p");
+        assert_eq!(tile(53, RedFive::Four), "0p");
+        assert_eq!(
+            parse_tenhou_ids_for("16,52,88", RedFive::Zero).unwrap(),
+            parse_tiles("5m5p5s").unwrap()
+        );
+    }
+
+    #[test]
+    fn tenhou_ids_round_trip() {
+        for id in 0..136 {
+            let tile = MahjongTile::from_tenhou_id(id).unwrap();
+            assert_eq!(tile.to_tenhou_id(id % 4), Some(id));
+        }
+        assert_eq!(build("0p").unwrap().to_tenhou_id(1), Some(53));
+        assert_eq!(build("0m").unwrap().to_tenhou_id(1), None);
+        assert_eq!(build("1m").unwrap().to_tenhou_id(4), None);
+    }
+
+    #[test]
+    fn unicode_glyphs() {
+        let glyph = |s: &str| build(s).unwrap().to_unicode();
+        assert_eq!(glyph("1m"), '🀇');
+        assert_eq!(glyph("9m"), '🀏');
+        assert_eq!(glyph("1s"), '🀐');
+        assert_eq!(glyph("1p"), '🀙');
+        assert_eq!(glyph("9p"), '🀡');
+        assert_eq!(glyph("1z"), '🀀');
+        assert_eq!(glyph("4z"), '🀃');
+        assert_eq!(glyph("5z"), '🀆');
+        assert_eq!(glyph("6z"), '🀅');
+        assert_eq!(glyph("7z"), '🀄');
+        assert_eq!(glyph("0p"), glyph("5p"));
+        assert_eq!(format!("{:#}", build("3s").unwrap()), "🀒");
+    }
+
+    #[test]
+    fn unicode_red_five_styles() {
+        let red = build("0m").unwrap();
+        assert_eq!(red.to_unicode_with(RedFiveGlyph::Plain), "🀋");
+        assert_eq!(red.to_unicode_with(RedFiveGlyph::Suffix('*')), "🀋*");
+        assert_eq!(red.to_unicode_with(RedFiveGlyph::Ansi), "\x1b[31m🀋\x1b[0m");
+        let plain = build("5m").unwrap();
+        assert_eq!(plain.to_unicode_with(RedFiveGlyph::Suffix('*')), "🀋");
+    }
+
+    #[test]
+    fn classification_predicates() {
+        let tile = |s: &str| build(s).unwrap();
+        assert!(tile("1m").is_terminal() && tile("9s").is_terminal());
+        assert!(!tile("2p").is_terminal() && !tile("1z").is_terminal());
+        assert!(tile("3z").is_honor() && !tile("3s").is_honor());
+        assert!(tile("9p").is_yaochuu() && tile("7z").is_yaochuu());
+        assert!(!tile("8p").is_yaochuu());
+        assert!(tile("2m").is_simple() && tile("0p").is_simple());
+        assert!(!tile("1m").is_simple() && !tile("5z").is_simple());
+    }
+
+    #[test]
+    fn red_fives_count_as_fives() {
+        let red = "0p".parse::<NumberTile>().unwrap();
+        let plain = "5p".parse::<NumberTile>().unwrap();
+        assert!(red.is_red() && !plain.is_red());
+        assert_eq!(red.number(), 5);
+        assert_ne!(red, plain);
+        assert!(red < plain && red > "4p".parse().unwrap());
+        assert_eq!(red.to_string(), "0p");
+        assert!(build("0m").unwrap().is_red());
+        assert!(!build("5z").unwrap().is_red());
+    }
+
+    #[test]
+    fn suit_kinds() {
+        assert_eq!(build("3m").unwrap().suit_kind(), Suit::Man);
+        assert_eq!(build("0p").unwrap().suit_kind(), Suit::Pin);
+        assert_eq!(build("9s").unwrap().suit_kind(), Suit::Sou);
+        assert_eq!(build("6z").unwrap().suit_kind(), Suit::Honor);
+        assert_eq!(build("6z").unwrap().suit(), 'z');
+        for c in ['m', 'p', 's', 'z'] {
+            assert_eq!(Suit::from_char(c).map(|s| s.as_char()), Some(c));
+        }
+        assert_eq!(Suit::from_char('x'), None);
+    }
+
+    #[test]
+    fn tile_counts_merge_red_fives() {
+        let mut counts: TileCounts = parse_tiles("055m1z").unwrap().into_iter().collect();
+        assert_eq!(counts.get(MAN_5), 3);
+        assert_eq!(counts.get(RED_MAN_5), 3);
+        assert_eq!(counts.remaining(MAN_5), 1);
+        counts.extend([MAN_5, MAN_5]);
+        assert_eq!(counts.remaining(MAN_5), 0);
+        assert_eq!(counts.remaining(EAST), 3);
+        assert_eq!(counts.remaining(SOUTH), 4);
+        assert_eq!(counts.total(), 6);
+    }
+
+    #[test]
+    fn tile34_conversions() {
+        assert_eq!(Tile34::from(build("1m").unwrap()), Tile34(0));
+        assert_eq!(Tile34::from(build("9p").unwrap()), Tile34(17));
+        assert_eq!(Tile34::from(build("0s").unwrap()), Tile34(22));
+        assert_eq!(Tile34::from(build("5s").unwrap()), Tile34(22));
+        assert_eq!(Tile34::from(build("7z").unwrap()), Tile34(33));
+        for (index, tile) in MahjongTile::all_kinds().enumerate() {
+            let tile34 = Tile34::from(tile);
+            assert_eq!(tile34.index(), index);
+            assert_eq!(MahjongTile::try_from(tile34), Ok(tile));
+        }
+        assert_eq!(MahjongTile::try_from(Tile34(34)), Err(Tile34OutOfRange(34)));
+        assert_eq!(Tile34::all().count(), Tile34::COUNT);
+    }
+
+    #[test]
+    fn number_range_boundaries() {
+        for suit in ['m', 'p', 's'] {
+            for n in 0..=9 {
+                assert!(build(&format!("{}{}", n, suit)).is_ok());
+            }
+        }
+        for n in 1..=7 {
+            assert!(build(&format!("{}z", n)).is_ok());
+        }
+        let invalid = |number| TileParseError::InvalidNumber { number, suit: 'z' };
+        assert_eq!(build("0z"), Err(invalid(0)));
+        assert_eq!(build("8z"), Err(invalid(8)));
+        assert_eq!(build("9z"), Err(invalid(9)));
+        assert_eq!(parse_tiles("123450z"), Err(invalid(0)));
+        assert_eq!(NumberTile::new(NumberSuit::Man, 0), None);
+        assert_eq!(NumberTile::new(NumberSuit::Man, 10), None);
+    }
+
+    #[test]
+    fn red_fives_rejected_without_red_five_rule() {
+        assert_eq!(
+            build_for("0p", RedFive::Zero),
+            Err(TileParseError::RedFiveNotAllowed('p'))
+        );
+        assert!(build_for("5p", RedFive::Zero).is_ok());
+        assert!(build_for("0p", RedFive::Three).is_ok());
+        assert_eq!(
+            parse_tiles_for("123m405s", RedFive::Zero),
+            Err(TileParseError::RedFiveNotAllowed('s'))
+        );
+        assert_eq!(parse_tiles_for("123m405s", RedFive::Four).unwrap().len(), 6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn tiles_serialize_as_shorthand() {
+        let tiles = parse_tiles("0p7z").unwrap();
+        let json = serde_json::to_string(&tiles).unwrap();
+        assert_eq!(json, r#"["0p","7z"]"#);
+        let back: Vec<MahjongTile> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, tiles);
+        assert!(serde_json::from_str::<MahjongTile>(r#""8z""#).is_err());
+        assert_eq!(
+            serde_json::to_string(&Direction::West).unwrap(),
+            r#""West""#
+        );
+    }
+
+    #[test]
+    fn english_names() {
+        let name = |s: &str| build(s).unwrap().english_name();
+        assert_eq!(name("3p"), "Three of Circles");
+        assert_eq!(name("1m"), "One of Characters");
+        assert_eq!(name("9s"), "Nine of Bamboo");
+        assert_eq!(name("0s"), "Red Five of Bamboo");
+        assert_eq!(name("3z"), "West Wind");
+        assert_eq!(name("6z"), "Green Dragon");
+    }
+
+    #[test]
+    fn japanese_names() {
+        let name = |s: &str| build(s).unwrap().japanese_name();
+        assert_eq!(name("3p"), "三筒");
+        assert_eq!(name("9m"), "九萬");
+        assert_eq!(name("0s"), "赤五索");
+        assert_eq!(name("3z"), "西");
+        assert_eq!(name("5z"), "白");
+        assert_eq!(name("7z"), "中");
+    }
+
+    #[test]
+    fn sort_hand_keeps_red_fives_with_fives() {
+        let mut tiles = parse_tiles("5p3z0p6m4p5s1m0s").unwrap();
+        sort_hand(&mut tiles);
+        assert_eq!(
+            tiles,
+            vec![
+                build("1m").unwrap(),
+                build("6m").unwrap(),
+                build("4p").unwrap(),
+                build("0p").unwrap(),
+                build("5p").unwrap(),
+                build("0s").unwrap(),
+                build("5s").unwrap(),
+                build("3z").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn successor_and_predecessor() {
+        let tile = |s: &str| build(s).unwrap();
+        assert_eq!(tile("3m").succ(), Some(tile("4m")));
+        assert_eq!(tile("3m").pred(), Some(tile("2m")));
+        assert_eq!(tile("0p").succ(), Some(tile("6p")));
+        assert_eq!(tile("6p").pred(), Some(tile("5p")));
+        assert_eq!(tile("9s").succ(), None);
+        assert_eq!(tile("1s").pred(), None);
+        assert_eq!(tile("1z").succ(), None);
+        assert_eq!(tile("7z").pred(), None);
+    }
+
+    #[test]
+    fn majsoul_dragons_keep_their_order() {
+        let tile = |s| MahjongTile::from_majsoul_str(s).unwrap();
+        assert_eq!(
+            tile("5z"),
+            MahjongTile::Honor(HonorTile::Dragon(DragonColor::White))
+        );
+        assert_eq!(
+            tile("6z"),
+            MahjongTile::Honor(HonorTile::Dragon(DragonColor::Green))
+        );
+        assert_eq!(
+            tile("7z"),
+            MahjongTile::Honor(HonorTile::Dragon(DragonColor::Red))
+        );
+        assert!(tile("0m").is_red());
+        for s in ["1m", "0p", "9s", "1z", "7z"] {
+            assert_eq!(tile(s).to_majsoul_str(), s);
+        }
+        assert!(MahjongTile::from_majsoul_str("8z").is_err());
+    }
+
+    #[test]
+    fn wrapper_accessors() {
+        let honor = build("2z").unwrap();
+        let number = build("0s").unwrap();
+        assert!(honor.is_honor() && !honor.is_number());
+        assert!(number.is_number() && !number.is_honor());
+        assert_eq!(honor.as_honor(), Some(&HonorTile::Wind(Direction::South)));
+        assert_eq!(honor.as_number(), None);
+        assert_eq!(number.into_number().map(|t| t.number()), Some(5));
+        assert_eq!(number.into_honor(), None);
+        assert_eq!(honor.into_honor(), Some(HonorTile::Wind(Direction::South)));
+    }
+
+    #[test]
+    fn constants_match_notation() {
+        const DRAGONS: [MahjongTile; 3] = [WHITE_DRAGON, GREEN_DRAGON, RED_DRAGON];
+        assert_eq!(DRAGONS.map(|t| t.to_string()), ["5z", "6z", "7z"]);
+        assert_eq!(MAN_1, build("1m").unwrap());
+        assert_eq!(PIN_5, build("5p").unwrap());
+        assert_eq!(SOU_9, build("9s").unwrap());
+        assert_eq!(RED_PIN_5, build("0p").unwrap());
+        assert_eq!(EAST, build("1z").unwrap());
+        assert_eq!(NORTH, build("4z").unwrap());
+        const { assert!(MAN_9.is_terminal() && !RED_SOU_5.is_terminal()) };
+    }
+
+    #[test]
+    fn rank_equality_ignores_red() {
+        assert!(RED_PIN_5.eq_rank(&PIN_5));
+        assert!(PIN_5.eq_rank(&RED_PIN_5));
+        assert!(!RED_PIN_5.eq_rank(&SOU_5));
+        assert!(!PIN_4.eq_rank(&PIN_5));
+        assert!(EAST.eq_rank(&EAST));
+        let keys: HashSet<RankKey> = [PIN_5, RED_PIN_5, PIN_5]
+            .iter()
+            .map(|t| t.rank_key())
+            .collect();
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[test]
+    fn observed_tiles() {
+        assert_eq!("?".parse(), Ok(ObservedTile::Hidden));
+        assert_eq!("3s".parse(), Ok(ObservedTile::Known(SOU_3)));
+        assert!("3x".parse::<ObservedTile>().is_err());
+        assert_eq!(ObservedTile::Hidden.to_string(), "?");
+        assert_eq!(ObservedTile::from(Some(EAST)).known(), Some(EAST));
+        assert!(ObservedTile::from(None).is_hidden());
+    }
+
+    #[test]
+    fn tenhou_id_lists() {
+        let tiles = parse_tenhou_ids("0, 17,16,135").unwrap();
+        assert_eq!(tiles, vec![MAN_1, MAN_5, RED_MAN_5, RED_DRAGON]);
+        assert_eq!(parse_tenhou_ids(""), Ok(vec![]));
+        assert_eq!(
+            parse_tenhou_ids("1,136"),
+            Err(TileParseError::InvalidTenhouId("136".to_string()))
+        );
+        assert_eq!(
+            parse_tenhou_ids("99999999999"),
+            Err(TileParseError::InvalidTenhouId("99999999999".to_string()))
+        );
+        assert_eq!(parse_tenhou_ids("1,x"), Err(TileParseError::NotADigit('x')));
+        assert_eq!(
+            parse_tenhou_ids("1,,2"),
+            Err(TileParseError::InvalidLength(0))
+        );
+    }
+
+    #[test]
+    fn tenhou_id_lists_round_trip() {
+        let hai = "4,17,18,52,53,108,109";
+        let tiles = parse_tenhou_ids(hai).unwrap();
+        assert_eq!(
+            format_tenhou_ids(&tiles).as_deref(),
+            Some("4,17,18,52,53,108,109")
+        );
+        assert_eq!(
+            format_tenhou_ids(&[EAST; 4]).as_deref(),
+            Some("108,109,110,111")
+        );
+        assert_eq!(format_tenhou_ids(&[EAST; 5]), None);
+        assert_eq!(format_tenhou_ids(&[RED_PIN_5, RED_PIN_5]), None);
+        let four_reds = "16,52,53,88,54";
+        let tiles = parse_tenhou_ids_for(four_reds, RedFive::Four).unwrap();
+        assert_eq!(
+            format_tenhou_ids_for(&tiles, RedFive::Four).as_deref(),
+            Some(four_reds)
+        );
+        assert_eq!(
+            format_tenhou_ids_for(&[PIN_5, PIN_5], RedFive::Zero).as_deref(),
+            Some("52,53")
+        );
+        assert_eq!(format_tenhou_ids_for(&[RED_MAN_5], RedFive::Zero), None);
+    }
+
+    #[test]
+    fn sanma_tile_set() {
+        assert!(MAN_1.is_in_play(NumPlayers::Three) && MAN_9.is_in_play(NumPlayers::Three));
+        assert!(!MAN_5.is_in_play(NumPlayers::Three));
+        assert!(MAN_5.is_in_play(NumPlayers::Four));
+        assert!(PIN_5.is_in_play(NumPlayers::Three) && EAST.is_in_play(NumPlayers::Three));
+    }
+
+    #[test]
+    fn dora_map() {
+        let dora = |t: MahjongTile| t.dora_from_indicator(NumPlayers::Four);
+        assert_eq!(dora(MAN_1), MAN_2);
+        assert_eq!(dora(RED_PIN_5), PIN_6);
+        assert_eq!(dora(SOU_9), SOU_1);
+        assert_eq!(dora(NORTH), EAST);
+        assert_eq!(dora(WEST), NORTH);
+        assert_eq!(dora(RED_DRAGON), WHITE_DRAGON);
+        assert_eq!(dora(WHITE_DRAGON), GREEN_DRAGON);
+        assert_eq!(MAN_1.dora_from_indicator(NumPlayers::Three), MAN_9);
+        assert_eq!(MAN_9.dora_from_indicator(NumPlayers::Three), MAN_1);
+        assert_eq!(PIN_1.dora_from_indicator(NumPlayers::Three), PIN_2);
+    }
+}
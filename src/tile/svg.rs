@@ -0,0 +1,329 @@
+//! SVG rendering of tiles, hands, melds and ponds.
+//!
+//! Every function returns a complete, self-contained `<svg>` document. Tile
+//! faces are drawn as rounded rectangles holding the tile's Unicode glyph,
+//! so the output needs no external images.
+
+use std::fmt::Write;
+
+use crate::game::PlayerLocation;
+use crate::hand::{Hand, Meld};
+use crate::state::PondTile;
+use crate::tile::MahjongTile;
+
+/// Sizes and colours used when drawing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgStyle {
+    /// Width of an upright tile in pixels.
+    pub tile_width: u32,
+    /// Height of an upright tile in pixels.
+    pub tile_height: u32,
+    /// Space between the concealed tiles and each meld.
+    pub group_gap: u32,
+    pub face_color: String,
+    pub back_color: String,
+    pub border_color: String,
+    /// Glyph colour for red fives.
+    pub red_five_color: String,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            tile_width: 30,
+            tile_height: 40,
+            group_gap: 10,
+            face_color: "#fdfbf3".to_string(),
+            back_color: "#e08a1e".to_string(),
+            border_color: "#333333".to_string(),
+            red_five_color: "#d01010".to_string(),
+        }
+    }
+}
+
+/// One tile as it should be drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileView {
+    pub tile: MahjongTile,
+    /// Draw the back of the tile instead of its face.
+    pub face_down: bool,
+    /// Turn the tile sideways, as for a called tile.
+    pub rotated: bool,
+}
+
+impl TileView {
+    /// An upright, face-up tile.
+    pub fn upright(tile: MahjongTile) -> TileView {
+        TileView {
+            tile,
+            face_down: false,
+            rotated: false,
+        }
+    }
+
+    /// A sideways, face-up tile.
+    pub fn rotated(tile: MahjongTile) -> TileView {
+        TileView {
+            rotated: true,
+            ..TileView::upright(tile)
+        }
+    }
+
+    /// An upright, face-down tile.
+    pub fn face_down(tile: MahjongTile) -> TileView {
+        TileView {
+            face_down: true,
+            ..TileView::upright(tile)
+        }
+    }
+}
+
+/// Renders a single upright, face-up tile.
+pub fn render_tile(tile: &MahjongTile, style: &SvgStyle) -> String {
+    render_views(&[TileView::upright(*tile)], style)
+}
+
+/// Renders a row of tiles with no gaps between them.
+pub fn render_views(views: &[TileView], style: &SvgStyle) -> String {
+    render_groups(&[views.to_vec()], style)
+}
+
+/// Renders a hand: the concealed tiles, then each meld separated by a gap.
+pub fn render_hand(hand: &Hand, style: &SvgStyle) -> String {
+    let mut groups = vec![hand
+        .concealed
+        .iter()
+        .copied()
+        .map(TileView::upright)
+        .collect::<Vec<_>>()];
+    groups.extend(hand.melds.iter().map(meld_views));
+    render_groups(&groups, style)
+}
+
+/// Renders a single meld.
+pub fn render_meld(meld: &Meld, style: &SvgStyle) -> String {
+    render_views(&meld_views(meld), style)
+}
+
+/// Lays out a meld as it sits on the table.
+///
+/// The called tile is turned sideways on the side of the player it came
+/// from: leftmost for the left player, in the middle for the player across
+/// and rightmost for the right player. A closed kan shows its two outer
+/// tiles face down.
+pub fn meld_views(meld: &Meld) -> Vec<TileView> {
+    match meld {
+        Meld::Chii(chii) => called_views(&chii.tiles, &chii.chii_tile, chii.source),
+        Meld::Pon(pon) => called_views(&pon.tiles, &pon.pon_tile, pon.source),
+        Meld::OpenKan(kan) => called_views(&kan.tiles, &kan.kan_tile, kan.source),
+        Meld::ClosedKan(kan) => vec![
+            TileView::face_down(kan.tiles[0]),
+            TileView::upright(kan.tiles[1]),
+            TileView::upright(kan.tiles[2]),
+            TileView::face_down(kan.tiles[3]),
+        ],
+        Meld::AddedKan(kan) => {
+            let mut views = called_views(&kan.pon.tiles, &kan.pon.pon_tile, kan.pon.source);
+            let called = views.iter().position(|view| view.rotated).unwrap_or(0);
+            views.insert(called + 1, TileView::rotated(kan.added_tile));
+            views
+        }
+    }
+}
+
+/// Renders the tiles still in a pond, in one row.
+pub fn render_pond(pond: &[PondTile], style: &SvgStyle) -> String {
+    render_views(&pond_views(pond), style)
+}
+
+/// Lays out the visible part of a pond.
+///
+/// Tiles called away are left out, since they now sit in the caller's
+/// meld. The riichi tile is turned sideways; if it was called, the next
+/// tile left in the pond is turned instead.
+pub fn pond_views(pond: &[PondTile]) -> Vec<TileView> {
+    let mut views = Vec::new();
+    let mut rotate = false;
+    for tile in pond {
+        rotate |= tile.riichi;
+        if tile.is_called() {
+            continue;
+        }
+        views.push(if rotate {
+            TileView::rotated(tile.tile)
+        } else {
+            TileView::upright(tile.tile)
+        });
+        rotate = false;
+    }
+    views
+}
+
+fn called_views(
+    tiles: &[MahjongTile],
+    called: &MahjongTile,
+    source: PlayerLocation,
+) -> Vec<TileView> {
+    let mut rest: Vec<MahjongTile> = tiles.to_vec();
+    if let Some(pos) = rest.iter().position(|t| t == called) {
+        rest.remove(pos);
+    }
+    let mut views: Vec<TileView> = rest.into_iter().map(TileView::upright).collect();
+    let index = match source {
+        PlayerLocation::Left => 0,
+        PlayerLocation::Across => 1,
+        PlayerLocation::Right | PlayerLocation::Hero => views.len(),
+    };
+    views.insert(index.min(views.len()), TileView::rotated(*called));
+    views
+}
+
+fn render_groups(groups: &[Vec<TileView>], style: &SvgStyle) -> String {
+    let (w, h) = (style.tile_width, style.tile_height);
+    let mut body = String::new();
+    let mut x = 0;
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            x += style.group_gap;
+        }
+        for view in group {
+            let (tw, th) = if view.rotated { (h, w) } else { (w, h) };
+            // Rotated tiles sit on the bottom edge, as on a real table.
+            let y = h - th;
+            write_tile(&mut body, view, x, y, tw, th, style);
+            x += tw;
+        }
+    }
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{x}\" height=\"{h}\" viewBox=\"0 0 {x} {h}\">{body}</svg>",
+    )
+}
+
+fn write_tile(out: &mut String, view: &TileView, x: u32, y: u32, w: u32, h: u32, style: &SvgStyle) {
+    let fill = if view.face_down {
+        &style.back_color
+    } else {
+        &style.face_color
+    };
+    let _ = write!(
+        out,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\" fill=\"{}\" stroke=\"{}\"/>",
+        x + 1,
+        y + 1,
+        w - 2,
+        h - 2,
+        fill,
+        style.border_color
+    );
+    if view.face_down {
+        return;
+    }
+    let color = if view.tile.is_red() {
+        &style.red_five_color
+    } else {
+        &style.border_color
+    };
+    let size = w.min(h) * 4 / 5;
+    let _ = write!(
+        out,
+        "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+        x + w / 2,
+        y + h / 2,
+        size,
+        color,
+        view.tile.to_unicode()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hand::{ClosedKanMeld, PonMeld};
+    use crate::tile::{build, parse_tiles};
+
+    fn count(haystack: &str, needle: &str) -> usize {
+        haystack.matches(needle).count()
+    }
+
+    #[test]
+    fn single_tile() {
+        let svg = render_tile(&build("1p").unwrap(), &SvgStyle::default());
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"30\" height=\"40\""));
+        assert!(svg.contains('🀙'));
+        assert_eq!(count(&svg, "<rect"), 1);
+    }
+
+    #[test]
+    fn red_five_and_face_down() {
+        let style = SvgStyle::default();
+        let svg = render_views(
+            &[
+                TileView::upright(build("0m").unwrap()),
+                TileView::face_down(build("3s").unwrap()),
+            ],
+            &style,
+        );
+        assert!(svg.contains(&style.red_five_color));
+        assert!(svg.contains(&style.back_color));
+        assert!(!svg.contains('🀒'));
+    }
+
+    #[test]
+    fn pon_rotates_called_tile_toward_source() {
+        let tile = build("7z").unwrap();
+        let pon = |source| {
+            meld_views(&Meld::Pon(PonMeld {
+                tiles: [tile; 3],
+                pon_tile: tile,
+                source,
+            }))
+        };
+        let rotated = |views: Vec<TileView>| views.iter().position(|v| v.rotated);
+        assert_eq!(rotated(pon(PlayerLocation::Left)), Some(0));
+        assert_eq!(rotated(pon(PlayerLocation::Across)), Some(1));
+        assert_eq!(rotated(pon(PlayerLocation::Right)), Some(2));
+    }
+
+    #[test]
+    fn hand_with_closed_kan() {
+        let kan = build("9m").unwrap();
+        let hand = Hand {
+            concealed: parse_tiles("123p456s11z").unwrap(),
+            melds: vec![Meld::ClosedKan(ClosedKanMeld { tiles: [kan; 4] })],
+        };
+        let style = SvgStyle::default();
+        let svg = render_hand(&hand, &style);
+        assert_eq!(count(&svg, "<rect"), 12);
+        assert_eq!(count(&svg, &style.back_color), 2);
+        let width = 12 * style.tile_width + style.group_gap;
+        assert!(svg.contains(&format!("width=\"{}\"", width)));
+    }
+
+    #[test]
+    fn pond_hides_called_tiles_and_passes_on_riichi() {
+        let discard = |tile, riichi, called_by| PondTile {
+            tile: build(tile).unwrap(),
+            called_by,
+            riichi,
+            tsumogiri: None,
+        };
+        let pond = [
+            discard("1z", false, None),
+            discard("5p", true, Some(PlayerLocation::Right)),
+            discard("9s", false, None),
+            discard("2m", false, None),
+        ];
+        let views = pond_views(&pond);
+        assert_eq!(
+            views,
+            vec![
+                TileView::upright(build("1z").unwrap()),
+                TileView::rotated(build("9s").unwrap()),
+                TileView::upright(build("2m").unwrap()),
+            ]
+        );
+        let svg = render_pond(&pond, &SvgStyle::default());
+        assert_eq!(count(&svg, "<rect"), 3);
+    }
+}
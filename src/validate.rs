@@ -0,0 +1,1308 @@
+//! Checking that recorded games could have been played.
+//!
+//! [`Round::validate`] replays a round through [`RoundState`] and collects
+//! everything that breaks the rules, each tagged with the index of the
+//! offending event.
+
+use std::fmt;
+
+use crate::game::{AbortiveDraw, Game, Length, NumPlayers, PlayerLocation, Round, RoundAction};
+use crate::hand::{Meld, MeldError, RIICHI_DEPOSIT};
+use crate::rules::RuleSet;
+use crate::state::{RonOutcome, RoundState, StateError};
+use crate::tile::{self, Direction, MahjongTile, NumberSuit, NumberTile, RedFive, TileCounts};
+use crate::yaku::Yaku;
+
+/// A rule broken by one event of a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Index into [`Round::events`], or the number of events for problems
+    /// with how the round ends.
+    pub event: usize,
+    pub kind: ValidationErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The event could not have happened at this point, e.g. it was out of
+    /// turn or used a tile the player did not hold. The rest of the round
+    /// is not checked.
+    State(StateError),
+    /// A called or declared meld is not a real meld, such as a chii from a
+    /// player other than the one on the left.
+    InvalidMeld(MeldError),
+    /// More than four copies of this tile, counting red fives as fives,
+    /// have been seen.
+    TooManyCopies(MahjongTile),
+    /// More copies of this red five have been seen than the tile set has.
+    TooManyRedFives(MahjongTile),
+    /// This tile was seen in a three-player game, which plays without the
+    /// 2–8 of manzu.
+    NotInTileSet(MahjongTile),
+    /// A discard straight after a chii or pon that the rules' kuikae
+    /// setting forbids.
+    Kuikae(MahjongTile),
+    /// The hero declared riichi without being able to, e.g. while not
+    /// tenpai or with too few tiles left in the wall.
+    IllegalRiichi,
+    /// The hero declared riichi with a discard that doesn't leave the hand
+    /// tenpai.
+    RiichiDiscard(MahjongTile),
+    /// A ron after another on the same discard that the rules don't pay:
+    /// any second ron under head bump, or a third when a triple ron aborts
+    /// the round instead.
+    ExtraRon,
+    /// A hand revealed as tenpai at an exhaustive draw that isn't.
+    NotTenpai(PlayerLocation),
+    /// The round was abandoned by an abortive draw the rules don't play.
+    AbortNotPlayed(AbortiveDraw),
+    /// The round's result doesn't list exactly the occupied seats in
+    /// order.
+    ResultSeats,
+    /// The events stop before the round ends in a win or draw.
+    Unfinished,
+    /// A win's score breakdown lists a yaku that depends on how the hand
+    /// was won, such as rinshan kaihou, chankan or ippatsu, when the round
+    /// doesn't show it, or leaves it out when it does.
+    YakuMismatch(Yaku),
+    /// A player with an open hand scores all simples without kuitan.
+    OpenTanyao(PlayerLocation),
+    /// The game shouldn't have reached this round: it is past the game's
+    /// length and any extension, follows a round in the extension after
+    /// which someone had reached the target, or follows a knockout.
+    /// Reported at event 0.
+    BeyondGameLength,
+    /// The game has a final result but stops after this round, before its
+    /// scheduled end, with nobody knocked out and no agari-yame or
+    /// tenpai-yame. Reported at the round's last event.
+    EndedEarly,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "event {}: ", self.event)?;
+        match self.kind {
+            ValidationErrorKind::State(e) => e.fmt(f),
+            ValidationErrorKind::InvalidMeld(e) => e.fmt(f),
+            ValidationErrorKind::TooManyCopies(tile) => {
+                write!(f, "more than four copies of {}", tile)
+            }
+            ValidationErrorKind::TooManyRedFives(tile) => {
+                write!(f, "more copies of {} than the tile set has", tile)
+            }
+            ValidationErrorKind::NotInTileSet(tile) => {
+                write!(f, "{} is not used with this many players", tile)
+            }
+            ValidationErrorKind::Kuikae(tile) => {
+                write!(f, "{} may not be discarded straight after the call", tile)
+            }
+            ValidationErrorKind::IllegalRiichi => write!(f, "riichi cannot be declared"),
+            ValidationErrorKind::RiichiDiscard(tile) => {
+                write!(f, "discarding {} after riichi leaves the hand noten", tile)
+            }
+            ValidationErrorKind::ExtraRon => {
+                write!(f, "another ron on this discard is not paid")
+            }
+            ValidationErrorKind::NotTenpai(player) => {
+                write!(f, "{} showed a hand that is not tenpai", player)
+            }
+            ValidationErrorKind::AbortNotPlayed(kind) => {
+                write!(f, "{} is not played under these rules", kind)
+            }
+            ValidationErrorKind::ResultSeats => {
+                write!(f, "the result does not have one score per seat")
+            }
+            ValidationErrorKind::Unfinished => write!(f, "the round does not finish"),
+            ValidationErrorKind::YakuMismatch(yaku) => {
+                write!(f, "{:?} does not match how the hand was won", yaku)
+            }
+            ValidationErrorKind::OpenTanyao(player) => {
+                write!(f, "{} scores tanyao with an open hand", player)
+            }
+            ValidationErrorKind::BeyondGameLength => {
+                write!(f, "the game should have ended before this round")
+            }
+            ValidationErrorKind::EndedEarly => {
+                write!(f, "the game should not have ended after this round")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ValidationErrorKind::State(e) => Some(e),
+            ValidationErrorKind::InvalidMeld(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Game {
+    /// Validates every round under the game's rules, returning each problem
+    /// with the index of its round.
+    pub fn validate(&self) -> Vec<(usize, ValidationError)> {
+        self.validate_with(&self.config.rules)
+    }
+
+    /// Validates every round under `rules`, for the game's number of
+    /// players and red fives, and that the game neither goes on past its
+    /// length nor, once finished, stops short of it.
+    pub fn validate_with(&self, rules: &RuleSet) -> Vec<(usize, ValidationError)> {
+        let num_players = self.config.num_players;
+        let mut errors: Vec<(usize, ValidationError)> = self
+            .rounds
+            .iter()
+            .enumerate()
+            .flat_map(|(i, round)| {
+                round
+                    .validate_tiles_with(rules, num_players, self.config.red_five)
+                    .into_iter()
+                    .map(move |e| (i, e))
+            })
+            .collect();
+        let beyond = self.rounds_beyond_length(rules);
+        for &i in &beyond {
+            let error = ValidationError {
+                event: 0,
+                kind: ValidationErrorKind::BeyondGameLength,
+            };
+            let at = errors.partition_point(|&(round, _)| round <= i);
+            errors.insert(at, (i, error));
+        }
+        // A game that went on too long didn't also end too soon.
+        let last = self.rounds.len().saturating_sub(1);
+        if !beyond.contains(&last) && self.ends_early(rules) {
+            let error = ValidationError {
+                event: self.rounds[last].events.len().saturating_sub(1),
+                kind: ValidationErrorKind::EndedEarly,
+            };
+            errors.push((last, error));
+        }
+        errors
+    }
+
+    /// The rounds the game shouldn't have reached under its length and the
+    /// extension rule.
+    ///
+    /// A round is allowed in any wind up to the scheduled last one. It may
+    /// go one wind further if `rules` have an extension target and nobody
+    /// had reached it after the previous round, as far as the recorded
+    /// results show. No round may follow one that left a player knocked
+    /// out under [`RuleSet::tobi`].
+    fn rounds_beyond_length(&self, rules: &RuleSet) -> Vec<usize> {
+        let last_wind = match self.config.length {
+            Length::OneRound => {
+                return (1..self.rounds.len()).collect();
+            }
+            Length::Tonpuu => Direction::East as usize,
+            Length::Hanchan => Direction::South as usize,
+        };
+        let mut beyond = Vec::new();
+        let mut knocked_out = false;
+        for (i, round) in self.rounds.iter().enumerate() {
+            let wind = round.config.wind as usize;
+            if knocked_out {
+                beyond.push(i);
+                continue;
+            }
+            knocked_out = round.knocks_out(rules);
+            if wind <= last_wind {
+                continue;
+            }
+            let allowed = match rules.extension_target {
+                Some(target) if wind == last_wind + 1 => {
+                    i > 0 && !self.rounds[i - 1].reaches(target)
+                }
+                _ => false,
+            };
+            if !allowed {
+                beyond.push(i);
+            }
+        }
+        beyond
+    }
+
+    /// Whether a finished game stops short of its scheduled end. It may
+    /// stop early after a knockout, or when the dealer of the last hand
+    /// keeps the deal in first place and `rules` allow agari-yame or
+    /// tenpai-yame, as far as the last round's result shows.
+    ///
+    /// With an extension target the game can't end on schedule until
+    /// someone has reached it. In the extra wind it ends as soon as someone
+    /// has, or after the wind's last hand.
+    fn ends_early(&self, rules: &RuleSet) -> bool {
+        let Some(last) = self.rounds.last() else {
+            return false;
+        };
+        let config = &last.config;
+        let num_players = self.config.num_players;
+        let last_wind = match self.config.length {
+            Length::OneRound => return false,
+            Length::Tonpuu => Direction::East,
+            Length::Hanchan => Direction::South,
+        };
+        if self.config.result.is_none() || last.knocks_out(rules) {
+            return false;
+        }
+        let reached = rules
+            .extension_target
+            .is_none_or(|target| last.reaches(target));
+        if config.wind > last_wind {
+            let last_hand = config.number as usize + 1 == num_players.count();
+            return !reached && !last_hand;
+        }
+        if !self.config.length.is_last_hand(config, num_players) || !reached {
+            return true;
+        }
+        let dealer_won = last.events.iter().any(|e| {
+            e.subject == config.dealer
+                && matches!(e.action, RoundAction::Tsumo(..) | RoundAction::Ron(..))
+        });
+        let dealer_tenpai = last
+            .tenpai_players()
+            .is_some_and(|tenpai| tenpai.contains(&config.dealer));
+        let aborted = last
+            .events
+            .last()
+            .is_some_and(|e| matches!(e.action, RoundAction::AbortiveDraw(_)));
+        let may_stop = if dealer_won {
+            rules.agari_yame
+        } else if dealer_tenpai {
+            rules.tenpai_yame
+        } else {
+            // The deal passes on and the game is over, unless it was an
+            // abortive draw.
+            return aborted;
+        };
+        let top = config.result.as_ref().is_none_or(|result| {
+            let dealer = result.iter().find(|&&(p, _)| p == config.dealer);
+            dealer.is_some_and(|&(_, points)| {
+                result
+                    .iter()
+                    .all(|&(p, other)| p == config.dealer || other < points)
+            })
+        });
+        !(may_stop && top)
+    }
+}
+
+impl Round {
+    /// Whether the round's result leaves a player knocked out under
+    /// [`RuleSet::tobi`].
+    fn knocks_out(&self, rules: &RuleSet) -> bool {
+        self.config
+            .result
+            .as_ref()
+            .is_some_and(|result| result.iter().any(|&(_, points)| rules.tobi.is_bust(points)))
+    }
+
+    /// Whether the round's result leaves a player with at least `target`
+    /// points.
+    fn reaches(&self, target: i32) -> bool {
+        self.config
+            .result
+            .as_ref()
+            .is_some_and(|result| result.iter().any(|&(_, points)| points >= target))
+    }
+}
+
+impl Round {
+    /// Checks the round as a four-player round under the default
+    /// [`RuleSet`], returning every problem found in event order. An empty
+    /// list means the round is legal as far as the recorded information
+    /// shows.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        self.validate_with(&RuleSet::default(), NumPlayers::Four)
+    }
+
+    /// Checks the round under `rules` at a table of `num_players`.
+    ///
+    /// Replay stops at the first event the [`RoundState`] rejects, since
+    /// later events can't be checked against a state that is no longer
+    /// known. Riichi is only checked for the hero, when their hand is
+    /// known, and not against their score. Red fives are only checked
+    /// against the most any tile set has; [`Game::validate_with`] checks
+    /// them against the game's [`RedFive`].
+    pub fn validate_with(&self, rules: &RuleSet, num_players: NumPlayers) -> Vec<ValidationError> {
+        self.validate_tiles_with(rules, num_players, RedFive::Four)
+    }
+
+    fn validate_tiles_with(
+        &self,
+        rules: &RuleSet,
+        num_players: NumPlayers,
+        red_five: RedFive,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let mut state = RoundState::with_players(&self.config, num_players);
+        let mut last_call: Option<(PlayerLocation, Meld)> = None;
+        let mut called_away = TileCounts::new();
+        let mut overfull: Vec<MahjongTile> = Vec::new();
+        for (i, event) in self.events.iter().enumerate() {
+            let mut report = |kind| errors.push(ValidationError { event: i, kind });
+            let called = match &event.action {
+                RoundAction::Chii(m) => Some(Meld::Chii(m.clone())),
+                RoundAction::Pon(m) => Some(Meld::Pon(m.clone())),
+                RoundAction::OpenKan(m) => Some(Meld::OpenKan(m.clone())),
+                RoundAction::ClosedKan(m) => Some(Meld::ClosedKan(m.clone())),
+                RoundAction::AddedKan(m) => Some(Meld::AddedKan(m.clone())),
+                _ => None,
+            };
+            if let Some(Err(e)) = called.as_ref().map(Meld::validate) {
+                report(ValidationErrorKind::InvalidMeld(e));
+            }
+            if let RoundAction::Tsumo(_, Some(details)) | RoundAction::Ron(_, Some(details)) =
+                &event.action
+            {
+                let tanyao = details.yaku.iter().any(|(yaku, _)| *yaku == Yaku::Tanyao);
+                let open = state
+                    .player(event.subject)
+                    .melds
+                    .iter()
+                    .any(|m| !matches!(m, Meld::ClosedKan(_)));
+                if tanyao && open && !rules.kuitan {
+                    report(ValidationErrorKind::OpenTanyao(event.subject));
+                }
+            }
+            let situational = match &event.action {
+                RoundAction::Tsumo(_, Some(details)) => Some((
+                    details,
+                    [
+                        (Yaku::RinshanKaihou, state.is_rinshan()),
+                        (Yaku::Ippatsu, state.is_ippatsu(event.subject)),
+                    ],
+                )),
+                RoundAction::Ron(_, Some(details)) => Some((
+                    details,
+                    [
+                        (Yaku::Chankan, state.is_chankan()),
+                        (Yaku::Ippatsu, state.is_ippatsu(event.subject)),
+                    ],
+                )),
+                _ => None,
+            };
+            if let Some((details, checks)) = situational {
+                for (yaku, arose) in checks {
+                    if details.yaku.iter().any(|&(y, _)| y == yaku) != arose {
+                        report(ValidationErrorKind::YakuMismatch(yaku));
+                    }
+                }
+            }
+            match &event.action {
+                RoundAction::Discard(tile) => {
+                    if let Some((_, meld)) = last_call.as_ref().filter(|(p, _)| *p == event.subject)
+                    {
+                        if meld.forbids_discard(*tile, rules) {
+                            report(ValidationErrorKind::Kuikae(*tile));
+                        }
+                    }
+                    let hero = state.player(PlayerLocation::Hero);
+                    if event.subject == PlayerLocation::Hero && hero.riichi_pending() {
+                        // An illegal declaration has already been reported.
+                        let keeps_tenpai = hero.hand().map(|h| h.riichi_discards());
+                        if keeps_tenpai.is_some_and(|ok| !ok.is_empty() && !ok.contains(tile)) {
+                            report(ValidationErrorKind::RiichiDiscard(*tile));
+                        }
+                    }
+                }
+                RoundAction::Riichi if event.subject == PlayerLocation::Hero => {
+                    let legal = state
+                        .player(PlayerLocation::Hero)
+                        .hand()
+                        .is_none_or(|h| h.can_riichi(RIICHI_DEPOSIT, state.wall_remaining()));
+                    if !legal {
+                        report(ValidationErrorKind::IllegalRiichi);
+                    }
+                }
+                RoundAction::Ron(..) if !state.ron_winners().is_empty() => {
+                    let paid = match state.resolve_rons(&[event.subject], rules) {
+                        RonOutcome::Wins(winners) => winners.contains(&event.subject),
+                        RonOutcome::SanchaHou => false,
+                    };
+                    if !paid {
+                        report(ValidationErrorKind::ExtraRon);
+                    }
+                }
+                RoundAction::Exhaustive(hands) => {
+                    for (player, hand) in hands {
+                        if !hand.is_tenpai() {
+                            report(ValidationErrorKind::NotTenpai(*player));
+                        }
+                    }
+                }
+                RoundAction::AbortiveDraw(kind) if !rules.abortive_draws.allows(*kind) => {
+                    report(ValidationErrorKind::AbortNotPlayed(*kind));
+                }
+                _ => {}
+            }
+            if let Err(e) = state.apply(event) {
+                report(ValidationErrorKind::State(e));
+                return errors;
+            }
+            last_call = match called {
+                Some(meld @ (Meld::Chii(_) | Meld::Pon(_))) => Some((event.subject, meld)),
+                _ => None,
+            };
+            if let RoundAction::Chii(m) = &event.action {
+                called_away.add(m.chii_tile);
+            } else if let RoundAction::Pon(m) = &event.action {
+                called_away.add(m.pon_tile);
+            } else if let RoundAction::OpenKan(m) = &event.action {
+                called_away.add(m.kan_tile);
+            }
+            let seen = self.seen_tiles(&state);
+            for kind in MahjongTile::all_kinds() {
+                let error = if !kind.is_in_play(num_players) {
+                    ValidationErrorKind::NotInTileSet(kind)
+                } else {
+                    ValidationErrorKind::TooManyCopies(kind)
+                };
+                let limit = if kind.is_in_play(num_players) { 4 } else { 0 };
+                if seen.get(kind) > limit + called_away.get(kind) && !overfull.contains(&kind) {
+                    overfull.push(kind);
+                    errors.push(ValidationError {
+                        event: i,
+                        kind: error,
+                    });
+                }
+            }
+            for suit in [NumberSuit::Man, NumberSuit::Pin, NumberSuit::Sou] {
+                let red = MahjongTile::Number(NumberTile::red_five(suit));
+                if self.seen_red_fives(&state, suit) > red_five.copies(suit)
+                    && !overfull.contains(&red)
+                {
+                    overfull.push(red);
+                    errors.push(ValidationError {
+                        event: i,
+                        kind: ValidationErrorKind::TooManyRedFives(red),
+                    });
+                }
+            }
+        }
+        let mut report_end = |kind| {
+            errors.push(ValidationError {
+                event: self.events.len(),
+                kind,
+            })
+        };
+        if let Some(result) = &self.config.result {
+            if !num_players.matches_result(result) {
+                report_end(ValidationErrorKind::ResultSeats);
+            }
+        }
+        if !state.is_finished() {
+            report_end(ValidationErrorKind::Unfinished);
+        }
+        errors
+    }
+
+    /// Every tile seen so far: indicators, known concealed tiles, melds,
+    /// discards and nuki dora. Called discards are counted both in the
+    /// discarder's pond and in the caller's meld.
+    fn seen_tiles(&self, state: &RoundState) -> TileCounts {
+        let mut seen: TileCounts = self
+            .config
+            .dora
+            .iter()
+            .chain(&self.config.ura_dora)
+            .copied()
+            .collect();
+        for seat in PlayerLocation::ALL {
+            let player = state.player(seat);
+            seen.extend(player.known.iter().copied());
+            seen.extend(player.melds.iter().flat_map(Meld::tiles));
+            seen.extend(player.discards.iter().map(|d| d.tile));
+            seen.extend(std::iter::repeat_n(tile::NORTH, player.nuki as usize));
+        }
+        seen
+    }
+
+    /// The red fives of `suit` among the tiles [`Round::seen_tiles`]
+    /// counts, with called discards counted only in the caller's meld.
+    fn seen_red_fives(&self, state: &RoundState, suit: NumberSuit) -> u8 {
+        let red = MahjongTile::Number(NumberTile::red_five(suit));
+        let indicators = self.config.dora.iter().chain(&self.config.ura_dora);
+        let held = PlayerLocation::ALL.into_iter().flat_map(|seat| {
+            let player = state.player(seat);
+            let ponds = player.visible_pond().map(|d| d.tile);
+            let melds = player.melds.iter().flat_map(Meld::tiles);
+            player.known.iter().copied().chain(melds).chain(ponds)
+        });
+        indicators
+            .copied()
+            .chain(held)
+            .filter(|&t| t == red)
+            .count() as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerLocation::*;
+    use crate::game::{GameConfig, RoundConfig, RoundEvent, RoundNumber};
+    use crate::hand::{AddedKanMeld, ChiiMeld, ClosedKanMeld, Hand, PonMeld};
+    use crate::rules::{AbortiveDraws, Kuikae, Tobi};
+    use crate::scoring::{score, DoraCount, WinDetails, WinType};
+    use crate::state::LIVE_WALL_TILES;
+    use crate::tile::{build, Direction, ObservedTile};
+
+    fn t(s: &str) -> MahjongTile {
+        build(s).unwrap()
+    }
+
+    fn round(hand: &str, events: Vec<(PlayerLocation, RoundAction)>) -> Round {
+        Round {
+            config: RoundConfig {
+                wind: Direction::East,
+                number: RoundNumber::One,
+                repeat: 0,
+                dealer: Hero,
+                dora: vec![t("4z")],
+                ura_dora: vec![],
+                initial_hero_hand_state: Hand::parse(hand).unwrap(),
+                result: None,
+                wall: Default::default(),
+            },
+            events: events
+                .into_iter()
+                .map(|(subject, action)| RoundEvent::new(subject, action))
+                .collect(),
+            annotation: None,
+        }
+    }
+
+    fn kinds(errors: &[ValidationError]) -> Vec<(usize, ValidationErrorKind)> {
+        errors.iter().map(|e| (e.event, e.kind)).collect()
+    }
+
+    #[test]
+    fn legal_round() {
+        let round = round(
+            "123m456p789s1135z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("3z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("1z"))),
+                (Hero, RoundAction::Ron(Hand::default(), None)),
+            ],
+        );
+        assert_eq!(round.validate(), vec![]);
+        let game = Game {
+            rounds: vec![round.clone(), round],
+            ..Game::default()
+        };
+        assert!(game.validate().is_empty());
+    }
+
+    #[test]
+    fn state_errors_stop_the_replay() {
+        let round = round(
+            "123m456p789s1234z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("6z"))),
+                (Right, RoundAction::Discard(t("1z"))),
+            ],
+        );
+        assert_eq!(
+            kinds(&round.validate()),
+            vec![(
+                1,
+                ValidationErrorKind::State(StateError::TileNotInHand(t("6z")))
+            )]
+        );
+        let game = Game {
+            rounds: vec![round.clone()],
+            ..Game::default()
+        };
+        assert_eq!(game.validate()[0].0, 0);
+    }
+
+    #[test]
+    fn chii_from_across_and_kuikae() {
+        let mut from_across = ChiiMeld::from_called(t("3p"), [t("4p"), t("5p")]).unwrap();
+        from_across.source = Across;
+        let chii = ChiiMeld::from_called(t("3p"), [t("4p"), t("5p")]).unwrap();
+        let events = |chii: ChiiMeld| {
+            vec![
+                (Hero, RoundAction::Draw(t("1z").into())),
+                (Hero, RoundAction::Discard(t("1z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("3p"))),
+                (Across, RoundAction::Chii(chii)),
+                (Across, RoundAction::Discard(t("6p"))),
+            ]
+        };
+        let bad = round("123m456p789s1234z", events(from_across));
+        assert_eq!(
+            kinds(&bad.validate()),
+            vec![
+                (
+                    4,
+                    ValidationErrorKind::InvalidMeld(MeldError::InvalidSource(Across))
+                ),
+                (
+                    4,
+                    ValidationErrorKind::State(StateError::WrongSource(Across))
+                ),
+            ]
+        );
+        let suji = round("123m456p789s1234z", events(chii));
+        assert_eq!(
+            kinds(&suji.validate()),
+            vec![
+                (5, ValidationErrorKind::Kuikae(t("6p"))),
+                (6, ValidationErrorKind::Unfinished),
+            ]
+        );
+        let rules = RuleSet {
+            kuikae: Kuikae::SameTile,
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            kinds(&suji.validate_with(&rules, NumPlayers::Four)),
+            vec![(6, ValidationErrorKind::Unfinished)]
+        );
+    }
+
+    #[test]
+    fn too_many_copies_and_illegal_riichi() {
+        let pon = PonMeld::new([t("1z"); 3], t("1z"), Left).unwrap();
+        let round = round(
+            "159m159p159s1111z",
+            vec![
+                (Hero, RoundAction::Draw(t("2z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("2z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("1z"))),
+                (Across, RoundAction::Pon(pon)),
+            ],
+        );
+        assert_eq!(
+            kinds(&round.validate()),
+            vec![
+                (1, ValidationErrorKind::IllegalRiichi),
+                (4, ValidationErrorKind::TooManyCopies(t("1z"))),
+                (6, ValidationErrorKind::Unfinished),
+            ]
+        );
+    }
+
+    #[test]
+    fn riichi_discard_must_keep_tenpai() {
+        let events = |discard| {
+            vec![
+                (Hero, RoundAction::Draw(t("3z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t(discard))),
+            ]
+        };
+        let hand = "123m456p789s1134z";
+        assert_eq!(
+            kinds(&round(hand, events("4z")).validate()),
+            vec![(3, ValidationErrorKind::Unfinished)]
+        );
+        assert_eq!(
+            kinds(&round(hand, events("1z")).validate()),
+            vec![
+                (2, ValidationErrorKind::RiichiDiscard(t("1z"))),
+                (3, ValidationErrorKind::Unfinished),
+            ]
+        );
+    }
+
+    #[test]
+    fn three_player_tile_set() {
+        let round = round(
+            "119m19p19s123456z",
+            vec![
+                (Hero, RoundAction::Draw(t("7z").into())),
+                (Hero, RoundAction::Discard(t("7z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("5m"))),
+            ],
+        );
+        assert_eq!(
+            kinds(&round.validate_with(&RuleSet::default(), NumPlayers::Three)),
+            vec![
+                (3, ValidationErrorKind::NotInTileSet(t("5m"))),
+                (4, ValidationErrorKind::Unfinished),
+            ]
+        );
+        assert_eq!(
+            kinds(&round.validate()),
+            vec![(4, ValidationErrorKind::Unfinished)]
+        );
+    }
+
+    #[test]
+    fn abortive_draws_follow_the_rules() {
+        let round = round(
+            "19m19p19s1234567z",
+            vec![
+                (Hero, RoundAction::Draw(t("1z").into())),
+                (
+                    Hero,
+                    RoundAction::AbortiveDraw(AbortiveDraw::KyuushuKyuuhai),
+                ),
+            ],
+        );
+        assert_eq!(round.validate(), vec![]);
+        let rules = RuleSet {
+            abortive_draws: AbortiveDraws::none(),
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            kinds(&round.validate_with(&rules, NumPlayers::Four)),
+            vec![(
+                1,
+                ValidationErrorKind::AbortNotPlayed(AbortiveDraw::KyuushuKyuuhai)
+            )]
+        );
+    }
+
+    #[test]
+    fn extra_rons_under_head_bump() {
+        let round = round(
+            "123m456p789s1234z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("5z"))),
+                (Right, RoundAction::Ron(Hand::default(), None)),
+                (Across, RoundAction::Ron(Hand::default(), None)),
+            ],
+        );
+        assert_eq!(round.validate(), vec![]);
+        let rules = RuleSet {
+            multiple_ron: false,
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            kinds(&round.validate_with(&rules, NumPlayers::Four)),
+            vec![(3, ValidationErrorKind::ExtraRon)]
+        );
+        let mut triple = round.clone();
+        triple.events.push(RoundEvent::new(
+            Left,
+            RoundAction::Ron(Hand::default(), None),
+        ));
+        assert_eq!(
+            kinds(&triple.validate()),
+            vec![(4, ValidationErrorKind::ExtraRon)]
+        );
+    }
+
+    #[test]
+    fn revealed_hands_must_be_tenpai() {
+        let hands = vec![
+            (Right, Hand::parse("123m456p789s1122z").unwrap()),
+            (Left, Hand::parse("123m456p789s1234z").unwrap()),
+        ];
+        let round = round(
+            "123m456p789s1234z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("5z"))),
+                (Right, RoundAction::Exhaustive(hands)),
+            ],
+        );
+        // The wall isn't empty either, which stops the replay.
+        assert_eq!(
+            kinds(&round.validate()),
+            vec![
+                (2, ValidationErrorKind::NotTenpai(Left)),
+                (
+                    2,
+                    ValidationErrorKind::State(StateError::WallNotEmpty(LIVE_WALL_TILES - 1))
+                ),
+            ]
+        );
+        assert_eq!(round.tenpai_players(), Some(vec![Right]));
+    }
+
+    #[test]
+    fn extension_rounds_follow_the_target() {
+        let east = round(
+            "123m456p789s1135z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("3z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("1z"))),
+                (Hero, RoundAction::Ron(Hand::default(), None)),
+            ],
+        );
+        let with_result = |round: &Round, top: i32| {
+            let mut round = round.clone();
+            round.config.result = Some(vec![
+                (Hero, top),
+                (Right, 25000),
+                (Across, 25000),
+                (Left, 100000 - 50000 - top),
+            ]);
+            round
+        };
+        let in_wind = |round: &Round, wind: Direction| {
+            let mut round = round.clone();
+            round.config.wind = wind;
+            round
+        };
+        let game = |rounds: Vec<Round>| Game {
+            config: GameConfig {
+                length: Length::Tonpuu,
+                ..GameConfig::default()
+            },
+            rounds,
+            annotation: None,
+        };
+        let beyond = |game: &Game, rules: &RuleSet| -> Vec<usize> {
+            game.validate_with(rules)
+                .into_iter()
+                .filter(|(_, e)| e.kind == ValidationErrorKind::BeyondGameLength)
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let south = in_wind(&east, Direction::South);
+        let rules = RuleSet::default();
+
+        let extended = game(vec![with_result(&east, 29000), with_result(&south, 33000)]);
+        assert_eq!(beyond(&extended, &rules), Vec::<usize>::new());
+        let no_extension = RuleSet {
+            extension_target: None,
+            ..RuleSet::default()
+        };
+        assert_eq!(beyond(&extended, &no_extension), vec![1]);
+
+        let over = game(vec![
+            with_result(&east, 29000),
+            with_result(&south, 33000),
+            south.clone(),
+            in_wind(&east, Direction::West),
+        ]);
+        assert_eq!(beyond(&over, &rules), vec![2, 3]);
+
+        let mut one_round = game(vec![east.clone(), east.clone()]);
+        one_round.config.length = Length::OneRound;
+        assert_eq!(beyond(&one_round, &rules), vec![1]);
+    }
+
+    #[test]
+    fn no_rounds_after_tobi() {
+        let east = round(
+            "123m456p789s1135z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("5z"))),
+            ],
+        );
+        let left_on = |points: i32| {
+            let mut round = east.clone();
+            round.config.result = Some(vec![
+                (Hero, 75000 - points),
+                (Right, 25000),
+                (Across, 0),
+                (Left, points),
+            ]);
+            round
+        };
+        let beyond = |first: Round, rules: &RuleSet| -> Vec<usize> {
+            let game = Game {
+                config: GameConfig::default(),
+                rounds: vec![first, east.clone(), east.clone()],
+                annotation: None,
+            };
+            game.validate_with(rules)
+                .into_iter()
+                .filter(|(_, e)| e.kind == ValidationErrorKind::BeyondGameLength)
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let rules = RuleSet::default();
+        assert_eq!(beyond(left_on(-1000), &rules), vec![1, 2]);
+        assert_eq!(beyond(left_on(0), &rules), Vec::<usize>::new());
+        let at_zero = RuleSet {
+            tobi: Tobi::AtZero,
+            ..RuleSet::default()
+        };
+        assert_eq!(beyond(left_on(0), &at_zero), vec![1, 2]);
+        assert_eq!(
+            beyond(left_on(-1000), &RuleSet::mleague()),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn games_end_early_only_by_tobi_or_yame() {
+        let dealer_wins = round(
+            "123m456p789s1135z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("3z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("1z"))),
+                (Hero, RoundAction::Ron(Hand::default(), None)),
+            ],
+        );
+        let ended = |number: RoundNumber, hero: i32, rules: RuleSet| {
+            let mut round = dealer_wins.clone();
+            round.config.number = number;
+            round.config.result = Some(vec![
+                (Hero, hero),
+                (Right, 30000),
+                (Across, 25000),
+                (Left, 45000 - hero),
+            ]);
+            let game = Game {
+                config: GameConfig {
+                    length: Length::Tonpuu,
+                    result: round.config.result.clone(),
+                    rules,
+                    ..GameConfig::default()
+                },
+                rounds: vec![round],
+                annotation: None,
+            };
+            game.validate()
+                .into_iter()
+                .filter(|(_, e)| e.kind == ValidationErrorKind::EndedEarly)
+                .map(|(i, e)| (i, e.event))
+                .collect::<Vec<_>>()
+        };
+        let rules = RuleSet::default();
+        assert_eq!(ended(RoundNumber::One, 40000, rules.clone()), vec![(0, 5)]);
+        assert_eq!(ended(RoundNumber::One, 46000, rules.clone()), vec![]);
+        assert_eq!(ended(RoundNumber::Four, 40000, rules.clone()), vec![]);
+        assert_eq!(ended(RoundNumber::Four, 30000, rules), vec![(0, 5)]);
+        assert_eq!(
+            ended(RoundNumber::Four, 40000, RuleSet::mleague()),
+            vec![(0, 5)]
+        );
+    }
+
+    #[test]
+    fn extension_target_decides_where_games_end() {
+        let dealer_wins = round(
+            "123m456p789s1135z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("3z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("1z"))),
+                (Hero, RoundAction::Ron(Hand::default(), None)),
+            ],
+        );
+        // The ended-early and beyond-length errors, by round.
+        let check = |rounds: &[(Direction, RoundNumber, i32)], rules: &RuleSet| {
+            let rounds: Vec<Round> = rounds
+                .iter()
+                .map(|&(wind, number, hero)| {
+                    let mut round = dealer_wins.clone();
+                    round.config.wind = wind;
+                    round.config.number = number;
+                    round.config.result = Some(vec![
+                        (Hero, hero),
+                        (Right, 25000),
+                        (Across, 25000),
+                        (Left, 50000 - hero),
+                    ]);
+                    round
+                })
+                .collect();
+            let game = Game {
+                config: GameConfig {
+                    length: Length::Tonpuu,
+                    result: rounds.last().and_then(|r| r.config.result.clone()),
+                    ..GameConfig::default()
+                },
+                rounds,
+                annotation: None,
+            };
+            let errors = game.validate_with(rules);
+            let of_kind = |kind: ValidationErrorKind| -> Vec<usize> {
+                errors
+                    .iter()
+                    .filter(|(_, e)| e.kind == kind)
+                    .map(|&(i, _)| i)
+                    .collect()
+            };
+            (
+                of_kind(ValidationErrorKind::EndedEarly),
+                of_kind(ValidationErrorKind::BeyondGameLength),
+            )
+        };
+        let (east_4, south_1, south_4) = (
+            (Direction::East, RoundNumber::Four),
+            (Direction::South, RoundNumber::One),
+            (Direction::South, RoundNumber::Four),
+        );
+        let at = |(wind, number): (Direction, RoundNumber), hero| (wind, number, hero);
+        let rules = RuleSet::default();
+        let no_extension = RuleSet {
+            extension_target: None,
+            ..RuleSet::default()
+        };
+        let none: (Vec<usize>, Vec<usize>) = (vec![], vec![]);
+
+        // Nobody has 30000 at the scheduled end, so the game goes on.
+        assert_eq!(check(&[at(east_4, 29000)], &rules), (vec![0], vec![]));
+        assert_eq!(check(&[at(east_4, 29000)], &no_extension), none);
+        // In the extra wind it ends once someone has, or after South 4.
+        assert_eq!(
+            check(&[at(east_4, 29000), at(south_1, 29500)], &rules),
+            (vec![1], vec![])
+        );
+        assert_eq!(
+            check(&[at(east_4, 29000), at(south_1, 31000)], &rules),
+            none
+        );
+        assert_eq!(
+            check(&[at(east_4, 29000), at(south_4, 29500)], &rules),
+            none
+        );
+        // Extra rounds after someone reached the target, or without a
+        // target, shouldn't have been played at all.
+        assert_eq!(
+            check(&[at(east_4, 31000), at(south_1, 29500)], &rules),
+            (vec![], vec![1])
+        );
+        assert_eq!(
+            check(&[at(east_4, 29000), at(south_1, 29500)], &no_extension),
+            (vec![], vec![1])
+        );
+    }
+
+    #[test]
+    fn open_tanyao_needs_kuitan() {
+        let details = WinDetails {
+            winning_tile: t("2m"),
+            yaku: vec![(Yaku::Tanyao, 1)],
+            fu: 30,
+            dora: DoraCount::default(),
+            payment: score(1, 30, false, WinType::Ron),
+        };
+        let round = round(
+            "123m456p789s1135z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("5p"))),
+                (
+                    Right,
+                    RoundAction::Pon(PonMeld::new([t("5p"); 3], t("5p"), Left).unwrap()),
+                ),
+                (Right, RoundAction::Discard(t("6s"))),
+                (Across, RoundAction::Draw(ObservedTile::Hidden)),
+                (Across, RoundAction::Discard(t("2m"))),
+                (Right, RoundAction::Ron(Hand::default(), Some(details))),
+            ],
+        );
+        assert_eq!(round.validate(), vec![]);
+        let rules = RuleSet {
+            kuitan: false,
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            kinds(&round.validate_with(&rules, NumPlayers::Four)),
+            vec![(6, ValidationErrorKind::OpenTanyao(Right))]
+        );
+    }
+
+    #[test]
+    fn red_fives_follow_the_tile_set() {
+        let round = round(
+            "123m004p789s1234z",
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("5z"))),
+            ],
+        );
+        let red_errors =
+            |errors: Vec<(usize, ValidationError)>| -> Vec<(usize, usize, MahjongTile)> {
+                errors
+                    .into_iter()
+                    .filter_map(|(i, e)| match e.kind {
+                        ValidationErrorKind::TooManyRedFives(tile) => Some((i, e.event, tile)),
+                        _ => None,
+                    })
+                    .collect()
+            };
+        let game = |red_five| Game {
+            config: GameConfig {
+                red_five,
+                ..GameConfig::default()
+            },
+            rounds: vec![round.clone()],
+            annotation: None,
+        };
+        assert_eq!(red_errors(game(RedFive::Four).validate()), vec![]);
+        assert_eq!(
+            red_errors(game(RedFive::Three).validate()),
+            vec![(0, 0, t("0p"))]
+        );
+        assert!(round
+            .validate()
+            .iter()
+            .all(|e| !matches!(e.kind, ValidationErrorKind::TooManyRedFives(_))));
+    }
+
+    #[test]
+    fn rinshan_kaihou_needs_a_replacement_draw() {
+        let tsumo = |yaku: Vec<Yaku>| {
+            let details = WinDetails {
+                winning_tile: t("1z"),
+                yaku: yaku.into_iter().map(|y| (y, 1)).collect(),
+                fu: 40,
+                dora: DoraCount::default(),
+                payment: score(2, 40, true, WinType::Tsumo),
+            };
+            RoundAction::Tsumo(Hand::default(), Some(details))
+        };
+        let kan = ClosedKanMeld::new([t("1m"); 4]).unwrap();
+        let after_kan = |win| {
+            round(
+                "1111m234p567s112z",
+                vec![
+                    (Hero, RoundAction::Draw(t("2z").into())),
+                    (Hero, RoundAction::ClosedKan(kan.clone())),
+                    (Hero, RoundAction::Draw(t("1z").into())),
+                    (Hero, win),
+                ],
+            )
+        };
+        let rinshan = vec![Yaku::MenzenTsumo, Yaku::RinshanKaihou];
+        assert_eq!(after_kan(tsumo(rinshan.clone())).validate(), vec![]);
+        assert_eq!(
+            kinds(&after_kan(tsumo(vec![Yaku::MenzenTsumo])).validate()),
+            vec![(3, ValidationErrorKind::YakuMismatch(Yaku::RinshanKaihou))]
+        );
+        let no_kan = round(
+            "1112m234p567s112z",
+            vec![
+                (Hero, RoundAction::Draw(t("2z").into())),
+                (Hero, tsumo(rinshan)),
+            ],
+        );
+        assert_eq!(
+            kinds(&no_kan.validate()),
+            vec![(1, ValidationErrorKind::YakuMismatch(Yaku::RinshanKaihou))]
+        );
+    }
+
+    #[test]
+    fn chankan_needs_a_robbed_kan() {
+        let ron = |yaku: Yaku| {
+            let details = WinDetails {
+                winning_tile: t("1z"),
+                yaku: vec![(yaku, 1)],
+                fu: 30,
+                dora: DoraCount::default(),
+                payment: score(1, 30, false, WinType::Ron),
+            };
+            RoundAction::Ron(Hand::default(), Some(details))
+        };
+        let pon = PonMeld::new([t("1z"); 3], t("1z"), Right).unwrap();
+        let kan = AddedKanMeld::new(pon.clone(), t("1z")).unwrap();
+        let events = |win| {
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("5z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("1z"))),
+                (Hero, RoundAction::Pon(pon.clone())),
+                (Hero, RoundAction::Discard(t("3z"))),
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("6z"))),
+                (Across, RoundAction::Draw(ObservedTile::Hidden)),
+                (Across, RoundAction::Discard(t("7z"))),
+                (Left, RoundAction::Draw(ObservedTile::Hidden)),
+                (Left, RoundAction::Discard(t("2m"))),
+                (Hero, RoundAction::Draw(t("1z").into())),
+                (Hero, RoundAction::AddedKan(kan.clone())),
+                (Right, win),
+            ]
+        };
+        let hand = "123m456p789s1134z";
+        assert_eq!(round(hand, events(ron(Yaku::Chankan))).validate(), vec![]);
+        assert_eq!(
+            kinds(&round(hand, events(ron(Yaku::Tanyao))).validate()),
+            vec![(14, ValidationErrorKind::YakuMismatch(Yaku::Chankan))]
+        );
+        let on_discard = round(
+            hand,
+            vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Discard(t("5z"))),
+                (Right, ron(Yaku::Chankan)),
+            ],
+        );
+        assert_eq!(
+            kinds(&on_discard.validate()),
+            vec![(2, ValidationErrorKind::YakuMismatch(Yaku::Chankan))]
+        );
+    }
+
+    #[test]
+    fn ippatsu_needs_an_unbroken_window() {
+        let tsumo = |yaku: Vec<Yaku>| {
+            let details = WinDetails {
+                winning_tile: t("4z"),
+                yaku: yaku.into_iter().map(|y| (y, 1)).collect(),
+                fu: 40,
+                dora: DoraCount::default(),
+                payment: score(3, 40, true, WinType::Tsumo),
+            };
+            RoundAction::Tsumo(Hand::default(), Some(details))
+        };
+        let after_riichi = |hero_discard: Option<&str>, win| {
+            let mut events = vec![
+                (Hero, RoundAction::Draw(t("5z").into())),
+                (Hero, RoundAction::Riichi),
+                (Hero, RoundAction::Discard(t("5z"))),
+            ];
+            if let Some(tile) = hero_discard {
+                events.extend([
+                    (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                    (Right, RoundAction::Discard(t("6z"))),
+                    (Across, RoundAction::Draw(ObservedTile::Hidden)),
+                    (Across, RoundAction::Discard(t("6z"))),
+                    (Left, RoundAction::Draw(ObservedTile::Hidden)),
+                    (Left, RoundAction::Discard(t("6z"))),
+                    (Hero, RoundAction::Draw(t(tile).into())),
+                    (Hero, RoundAction::Discard(t(tile))),
+                ]);
+            }
+            events.extend([
+                (Right, RoundAction::Draw(ObservedTile::Hidden)),
+                (Right, RoundAction::Discard(t("7z"))),
+                (Across, RoundAction::Draw(ObservedTile::Hidden)),
+                (Across, RoundAction::Discard(t("7z"))),
+                (Left, RoundAction::Draw(ObservedTile::Hidden)),
+                (Left, RoundAction::Discard(t("7z"))),
+                (Hero, RoundAction::Draw(t("4z").into())),
+                (Hero, win),
+            ]);
+            round("123m456p789s1114z", events)
+        };
+        let ippatsu = vec![Yaku::Riichi, Yaku::Ippatsu, Yaku::MenzenTsumo];
+        assert_eq!(
+            after_riichi(None, tsumo(ippatsu.clone())).validate(),
+            vec![]
+        );
+        assert_eq!(
+            kinds(&after_riichi(None, tsumo(vec![Yaku::Riichi])).validate()),
+            vec![(10, ValidationErrorKind::YakuMismatch(Yaku::Ippatsu))]
+        );
+        assert_eq!(
+            kinds(&after_riichi(Some("9p"), tsumo(ippatsu)).validate()),
+            vec![(18, ValidationErrorKind::YakuMismatch(Yaku::Ippatsu))]
+        );
+    }
+}
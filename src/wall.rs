@@ -0,0 +1,459 @@
+//! The wall: shuffling, breaking and dealing tiles.
+//!
+//! The wall is modelled as a line of tiles in draw order. After
+//! [`Wall::break_at`], the live wall is drawn from the front and the last 14
+//! tiles form the [`DeadWall`], which holds the kan replacement tiles and the
+//! dora indicators.
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::{NumPlayers, RoundConfig};
+use crate::tile::{self, MahjongTile, ObservedTile, RedFive};
+
+/// Tiles kept out of play at the end of the wall.
+pub const DEAD_WALL_SIZE: usize = 14;
+
+/// Tiles dealt to each player before the dealer's first draw.
+pub const HAND_SIZE: usize = 13;
+
+/// Kan replacement tiles available in the dead wall.
+pub const MAX_REPLACEMENTS: usize = 4;
+
+/// Dora indicators that can be revealed: the initial one plus one per kan.
+pub const MAX_DORA_INDICATORS: usize = 1 + MAX_REPLACEMENTS;
+
+/// The dead wall slots holding the first dora and ura dora indicators.
+/// Later indicators follow in the next slots.
+const DORA_SLOT: usize = 4;
+const URA_DORA_SLOT: usize = 9;
+
+/// How a round's wall was shuffled and broken, as far as the log records
+/// it. With the seed the whole wall can be rebuilt, not just what the hero
+/// saw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WallSetup {
+    /// The seed given to [`Wall::from_seed`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub seed: Option<u64>,
+    /// The two dice rolled to pick where the wall is broken, each 1–6.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub dice: Option<[u8; 2]>,
+    /// Where drawing starts in the shuffled wall, for tables that don't
+    /// break it by [`Wall::break_at`]'s rule.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub break_position: Option<usize>,
+}
+
+impl WallSetup {
+    /// Whether nothing about the wall was recorded.
+    pub fn is_unknown(&self) -> bool {
+        *self == WallSetup::default()
+    }
+
+    /// Rebuilds the wall from the seed, broken at the break position or
+    /// else at the dice roll. `None` without a seed.
+    pub fn wall(&self, num_players: NumPlayers, red_five: RedFive) -> Option<Wall> {
+        let mut wall = Wall::from_seed(num_players, red_five, self.seed?);
+        match (self.break_position, self.dice) {
+            (Some(position), _) => wall.break_at_position(position),
+            (None, Some([a, b])) => wall.break_at(a + b),
+            (None, None) => {}
+        }
+        Some(wall)
+    }
+}
+
+/// A physical wall of tiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wall {
+    /// The live wall, in draw order once the wall has been broken.
+    live: Vec<MahjongTile>,
+    dead_wall: DeadWall,
+    num_players: NumPlayers,
+    /// Index of the next live wall tile.
+    next: usize,
+}
+
+impl Wall {
+    /// An unshuffled wall: 136 tiles, or 108 for three players (without the
+    /// 2–8 of manzu), with red fives as configured.
+    pub fn new(num_players: NumPlayers, red_five: RedFive) -> Wall {
+        let mut tiles = tile::full_set(red_five);
+        tiles.retain(|t| t.is_in_play(num_players));
+        Wall::from_tiles(tiles, num_players)
+    }
+
+    /// A wall shuffled with the caller's random number generator.
+    pub fn shuffled<R: Rng + ?Sized>(
+        num_players: NumPlayers,
+        red_five: RedFive,
+        rng: &mut R,
+    ) -> Wall {
+        let mut wall = Wall::new(num_players, red_five);
+        wall.shuffle(rng);
+        wall
+    }
+
+    /// A wall shuffled from a seed. The same seed always produces the same
+    /// wall, so storing it is enough to reproduce a simulated round.
+    pub fn from_seed(num_players: NumPlayers, red_five: RedFive, seed: u64) -> Wall {
+        Wall::shuffled(num_players, red_five, &mut ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Builds a wall from tiles in draw order, the last 14 being the dead
+    /// wall.
+    fn from_tiles(mut tiles: Vec<MahjongTile>, num_players: NumPlayers) -> Wall {
+        let dead: [MahjongTile; DEAD_WALL_SIZE] = tiles
+            .split_off(tiles.len() - DEAD_WALL_SIZE)
+            .try_into()
+            .expect("split_off leaves exactly DEAD_WALL_SIZE tiles");
+        Wall {
+            live: tiles,
+            dead_wall: DeadWall::new(dead),
+            num_players,
+            next: 0,
+        }
+    }
+
+    /// Shuffles every tile and resets all draws.
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut tiles = self.tiles();
+        tiles.shuffle(rng);
+        *self = Wall::from_tiles(tiles, self.num_players);
+    }
+
+    /// Breaks the wall according to a dice roll of 2–12, resetting all draws.
+    ///
+    /// The roll picks one of the four sides of the square wall (counting
+    /// counter-clockwise from the dealer, who is 1) and how many stacks in
+    /// from its right end to break. Drawing starts just after the break;
+    /// the 7 stacks before it become the dead wall.
+    pub fn break_at(&mut self, dice: u8) {
+        let len = self.tiles().len();
+        let side = (dice.saturating_sub(1) % 4) as usize;
+        self.break_at_position(side * len / 4 + 2 * dice as usize);
+    }
+
+    /// Breaks the wall so that drawing starts from the tile at `position`
+    /// (modulo the wall's size), resetting all draws.
+    pub fn break_at_position(&mut self, position: usize) {
+        let mut tiles = self.tiles();
+        let len = tiles.len();
+        tiles.rotate_left(position % len);
+        *self = Wall::from_tiles(tiles, self.num_players);
+    }
+
+    pub fn num_players(&self) -> NumPlayers {
+        self.num_players
+    }
+
+    /// Deals starting hands, four tiles at a time for three passes and then
+    /// one tile each. The first hand belongs to the dealer.
+    pub fn deal_hands(&mut self) -> Vec<Vec<MahjongTile>> {
+        let mut hands = vec![Vec::with_capacity(HAND_SIZE + 1); self.num_players.count()];
+        for chunk in [4, 4, 4, 1] {
+            for hand in hands.iter_mut() {
+                for _ in 0..chunk {
+                    hand.extend(self.draw());
+                }
+            }
+        }
+        hands
+    }
+
+    /// Draws the next tile from the live wall, or `None` once it is empty.
+    pub fn draw(&mut self) -> Option<MahjongTile> {
+        if self.remaining() == 0 {
+            return None;
+        }
+        let tile = self.live[self.next];
+        self.next += 1;
+        Some(tile)
+    }
+
+    /// Draws a kan replacement tile from the dead wall, or `None` after four
+    /// or when the live wall is empty.
+    ///
+    /// The dead wall always keeps 14 tiles, so each replacement draw also
+    /// shortens the live wall by one.
+    pub fn draw_replacement(&mut self) -> Option<MahjongTile> {
+        if self.remaining() == 0 {
+            return None;
+        }
+        self.dead_wall.draw_replacement()
+    }
+
+    /// Tiles left to draw from the live wall.
+    pub fn remaining(&self) -> usize {
+        self.live.len() - self.next - self.dead_wall.replacements_drawn()
+    }
+
+    pub fn dead_wall(&self) -> &DeadWall {
+        &self.dead_wall
+    }
+
+    pub fn dead_wall_mut(&mut self) -> &mut DeadWall {
+        &mut self.dead_wall
+    }
+
+    /// The dora indicators revealed so far.
+    pub fn dora_indicators(&self) -> &[MahjongTile] {
+        self.dead_wall.dora_indicators()
+    }
+
+    /// Every tile in draw order, dead wall last.
+    pub fn tiles(&self) -> Vec<MahjongTile> {
+        let mut tiles = self.live.clone();
+        tiles.extend(self.dead_wall.tiles);
+        tiles
+    }
+}
+
+/// The 14 tiles set aside at the end of the wall.
+///
+/// Slots 0–3 are the kan replacement tiles, slots 4–8 the dora indicators
+/// and slots 9–13 the ura dora indicators beneath them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadWall {
+    tiles: [MahjongTile; DEAD_WALL_SIZE],
+    replacements: usize,
+    revealed: usize,
+}
+
+impl DeadWall {
+    /// A dead wall with only the first dora indicator revealed.
+    pub fn new(tiles: [MahjongTile; DEAD_WALL_SIZE]) -> DeadWall {
+        DeadWall {
+            tiles,
+            replacements: 0,
+            revealed: 1,
+        }
+    }
+
+    /// Draws the next kan replacement tile, or `None` once all four have
+    /// been drawn.
+    pub fn draw_replacement(&mut self) -> Option<MahjongTile> {
+        if self.replacements == MAX_REPLACEMENTS {
+            return None;
+        }
+        let tile = self.tiles[self.replacements];
+        self.replacements += 1;
+        Some(tile)
+    }
+
+    /// Kan replacement tiles drawn so far.
+    pub fn replacements_drawn(&self) -> usize {
+        self.replacements
+    }
+
+    /// Reveals the next kan dora indicator and returns it.
+    ///
+    /// Indicators can only be revealed for kans that have happened, so this
+    /// returns `None` once there is one more indicator than replacement
+    /// draws. When the reveal happens relative to the replacement draw is
+    /// up to the rules and left to the caller.
+    pub fn reveal_kan_dora(&mut self) -> Option<MahjongTile> {
+        if self.revealed > self.replacements {
+            return None;
+        }
+        let tile = self.tiles[DORA_SLOT + self.revealed];
+        self.revealed += 1;
+        Some(tile)
+    }
+
+    /// The dora indicators revealed so far, to be stored as
+    /// [`RoundConfig::dora`](crate::game::RoundConfig::dora).
+    pub fn dora_indicators(&self) -> &[MahjongTile] {
+        &self.tiles[DORA_SLOT..DORA_SLOT + self.revealed]
+    }
+
+    /// The ura dora indicators under each revealed dora indicator, to be
+    /// stored as [`RoundConfig::ura_dora`](crate::game::RoundConfig::ura_dora)
+    /// when a riichi player wins.
+    pub fn ura_dora_indicators(&self) -> &[MahjongTile] {
+        &self.tiles[URA_DORA_SLOT..URA_DORA_SLOT + self.revealed]
+    }
+
+    /// Copies the revealed indicators into a round's configuration.
+    pub fn write_indicators(&self, config: &mut RoundConfig, include_ura_dora: bool) {
+        config.dora = self.dora_indicators().to_vec();
+        config.ura_dora = if include_ura_dora {
+            self.ura_dora_indicators().to_vec()
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// The dead wall as the players see it: revealed dora indicators face
+    /// up, everything else hidden.
+    pub fn observed(&self) -> [ObservedTile; DEAD_WALL_SIZE] {
+        let revealed = DORA_SLOT..DORA_SLOT + self.revealed;
+        std::array::from_fn(|slot| {
+            if revealed.contains(&slot) {
+                self.tiles[slot].into()
+            } else {
+                ObservedTile::Hidden
+            }
+        })
+    }
+
+    pub fn tiles(&self) -> &[MahjongTile; DEAD_WALL_SIZE] {
+        &self.tiles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{PlayerLocation, RoundNumber};
+    use crate::tile::{Direction, Suit, Tile, Tile34};
+
+    #[test]
+    fn wall_sizes() {
+        assert_eq!(
+            Wall::new(NumPlayers::Four, RedFive::Three).tiles().len(),
+            136
+        );
+        let sanma = Wall::new(NumPlayers::Three, RedFive::Three);
+        assert_eq!(sanma.tiles().len(), 108);
+        assert!(sanma
+            .tiles()
+            .iter()
+            .all(|t| !(t.suit_kind() == Suit::Man && t.is_simple())));
+    }
+
+    #[test]
+    fn seeded_walls_are_reproducible() {
+        let a = Wall::from_seed(NumPlayers::Four, RedFive::Three, 42);
+        let b = Wall::from_seed(NumPlayers::Four, RedFive::Three, 42);
+        let c = Wall::from_seed(NumPlayers::Four, RedFive::Three, 43);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        let mut counts = [0; Tile34::COUNT];
+        for t in &a.tiles() {
+            counts[Tile34::from(t).index()] += 1;
+        }
+        assert!(counts.iter().all(|&c| c == 4));
+    }
+
+    #[test]
+    fn deal_and_draw_everything() {
+        let mut wall = Wall::from_seed(NumPlayers::Four, RedFive::Three, 7);
+        wall.break_at(7);
+        let hands = wall.deal_hands();
+        assert_eq!(hands.len(), 4);
+        assert!(hands.iter().all(|h| h.len() == HAND_SIZE));
+        assert_eq!(wall.remaining(), 136 - 14 - 52);
+        let mut drawn = 0;
+        while wall.draw().is_some() {
+            drawn += 1;
+        }
+        assert_eq!(drawn, 70);
+        assert_eq!(wall.draw_replacement(), None);
+    }
+
+    #[test]
+    fn replacements_shorten_live_wall() {
+        let mut wall = Wall::from_seed(NumPlayers::Three, RedFive::Three, 1);
+        wall.break_at(5);
+        wall.deal_hands();
+        assert_eq!(wall.remaining(), 108 - 14 - 39);
+        for _ in 0..MAX_REPLACEMENTS {
+            assert!(wall.draw_replacement().is_some());
+        }
+        assert_eq!(wall.draw_replacement(), None);
+        assert_eq!(wall.remaining(), 108 - 14 - 39 - 4);
+        assert_eq!(wall.tiles().len(), 108);
+    }
+
+    #[test]
+    fn kan_dora_reveals_follow_replacements() {
+        let tiles: [MahjongTile; DEAD_WALL_SIZE] = tile::full_set(RedFive::Zero)[..DEAD_WALL_SIZE]
+            .try_into()
+            .unwrap();
+        let mut dead = DeadWall::new(tiles);
+        assert_eq!(dead.dora_indicators(), &tiles[4..5]);
+        assert_eq!(dead.ura_dora_indicators(), &tiles[9..10]);
+        assert_eq!(dead.reveal_kan_dora(), None);
+        for n in 1..=MAX_REPLACEMENTS {
+            assert_eq!(dead.draw_replacement(), Some(tiles[n - 1]));
+            assert_eq!(dead.reveal_kan_dora(), Some(tiles[4 + n]));
+            assert_eq!(dead.reveal_kan_dora(), None);
+        }
+        assert_eq!(dead.draw_replacement(), None);
+        assert_eq!(dead.dora_indicators().len(), MAX_DORA_INDICATORS);
+        assert_eq!(dead.ura_dora_indicators(), &tiles[9..14]);
+        let observed = dead.observed();
+        assert_eq!(observed.iter().filter(|t| !t.is_hidden()).count(), 5);
+        assert_eq!(observed[4], ObservedTile::Known(tiles[4]));
+        assert!(observed[9].is_hidden());
+    }
+
+    #[test]
+    fn round_config_indicators_come_from_dead_wall() {
+        let mut wall = Wall::from_seed(NumPlayers::Four, RedFive::Three, 3);
+        wall.draw_replacement();
+        wall.dead_wall_mut().reveal_kan_dora();
+        let mut config = RoundConfig {
+            wind: Direction::East,
+            number: RoundNumber::One,
+            repeat: 0,
+            dealer: PlayerLocation::Hero,
+            dora: vec![],
+            ura_dora: vec![],
+            initial_hero_hand_state: Default::default(),
+            result: None,
+            wall: Default::default(),
+        };
+        wall.dead_wall().write_indicators(&mut config, false);
+        assert_eq!(config.dora, wall.dora_indicators());
+        assert_eq!(config.dora.len(), 2);
+        assert!(config.ura_dora.is_empty());
+        wall.dead_wall().write_indicators(&mut config, true);
+        assert_eq!(config.ura_dora.len(), 2);
+    }
+
+    #[test]
+    fn break_rotates_the_wall() {
+        let mut wall = Wall::new(NumPlayers::Four, RedFive::Zero);
+        let before = wall.tiles().to_vec();
+        wall.break_at(2);
+        let position = 34 + 4;
+        assert_eq!(wall.tiles()[0], before[position]);
+        assert_eq!(wall.tiles()[135], before[position - 1]);
+    }
+
+    #[test]
+    fn wall_setup_rebuilds_the_wall() {
+        let mut setup = WallSetup::default();
+        assert!(setup.is_unknown());
+        assert_eq!(setup.wall(NumPlayers::Four, RedFive::Three), None);
+        setup.seed = Some(9);
+        let unbroken = Wall::from_seed(NumPlayers::Four, RedFive::Three, 9);
+        assert_eq!(
+            setup.wall(NumPlayers::Four, RedFive::Three),
+            Some(unbroken.clone())
+        );
+
+        setup.dice = Some([3, 5]);
+        let mut broken = unbroken.clone();
+        broken.break_at(8);
+        assert_eq!(setup.wall(NumPlayers::Four, RedFive::Three), Some(broken));
+
+        setup.break_position = Some(10);
+        let rebuilt = setup.wall(NumPlayers::Four, RedFive::Three).unwrap();
+        assert_eq!(rebuilt.tiles()[0], unbroken.tiles()[10]);
+    }
+}
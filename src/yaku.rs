@@ -0,0 +1,422 @@
+//! Recognising yakuman in winning hands, and naming the yaku a hand was
+//! scored with.
+
+use crate::hand::{Group, GroupKind, Hand, HandDecomposition};
+use crate::rules::RuleSet;
+use crate::scoring::WinType;
+use crate::tile::{self, Direction, DragonColor, HonorTile, MahjongTile, NumberSuit};
+
+/// The circumstances a hand was won in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinContext {
+    /// The tile that completed the hand. It is included in the hand.
+    pub winning_tile: MahjongTile,
+    pub win_type: WinType,
+    /// Whether the winner is the dealer.
+    pub dealer: bool,
+    /// Whether the hand was won on the player's first draw, before any call
+    /// was made by anyone.
+    pub first_draw: bool,
+}
+
+/// A yakuman the hand qualifies for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Yakuman {
+    /// Thirteen orphans.
+    KokushiMusou,
+    /// Thirteen orphans waiting on all thirteen kinds.
+    KokushiMusouJuusanmen,
+    /// Four concealed triplets.
+    Suuankou,
+    /// Four concealed triplets won on the pair wait.
+    SuuankouTanki,
+    /// Triplets of all three dragons.
+    Daisangen,
+    /// Three wind triplets and a wind pair.
+    Shousuushii,
+    /// Four wind triplets.
+    Daisuushii,
+    /// Only honor tiles.
+    Tsuuiisou,
+    /// Only terminals.
+    Chinroutou,
+    /// Only green tiles: 2, 3, 4, 6 and 8 of sou and the green dragon.
+    Ryuuiisou,
+    /// Nine gates: `1112345678999` plus any tile of the same suit, closed.
+    ChuurenPoutou,
+    /// Nine gates waiting on all nine kinds.
+    JunseiChuurenPoutou,
+    /// Four quads.
+    Suukantsu,
+    /// The dealer's win on their first draw.
+    Tenhou,
+    /// A non-dealer's tsumo on their first draw.
+    Chiihou,
+}
+
+/// A yaku a winning hand was scored with, as listed in a score breakdown.
+/// Dora are not yaku and are counted separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Yaku {
+    MenzenTsumo,
+    Riichi,
+    DoubleRiichi,
+    Ippatsu,
+    Chankan,
+    RinshanKaihou,
+    HaiteiRaoyue,
+    HouteiRaoyui,
+    Pinfu,
+    Tanyao,
+    Iipeikou,
+    /// A triplet of the player's seat wind.
+    SeatWind(Direction),
+    /// A triplet of the round wind.
+    RoundWind(Direction),
+    /// A dragon triplet.
+    Dragon(DragonColor),
+    Chiitoitsu,
+    Chanta,
+    Ittsuu,
+    SanshokuDoujun,
+    SanshokuDoukou,
+    Sankantsu,
+    Toitoi,
+    Sanankou,
+    Shousangen,
+    Honroutou,
+    Ryanpeikou,
+    Junchan,
+    Honitsu,
+    Chinitsu,
+    Yakuman(Yakuman),
+}
+
+impl Yakuman {
+    /// How many yakuman this is worth under `rules`.
+    pub fn multiple(&self, rules: &RuleSet) -> u8 {
+        match self {
+            Yakuman::KokushiMusouJuusanmen
+            | Yakuman::SuuankouTanki
+            | Yakuman::JunseiChuurenPoutou
+            | Yakuman::Daisuushii
+                if rules.double_yakuman =>
+            {
+                2
+            }
+            _ => 1,
+        }
+    }
+}
+
+/// The total yakuman multiple of `yakuman` under `rules`: the sum when
+/// yakuman stack, otherwise the single most valuable one.
+pub fn yakuman_multiple(yakuman: &[Yakuman], rules: &RuleSet) -> u8 {
+    let multiples = yakuman.iter().map(|y| y.multiple(rules));
+    if rules.stack_yakuman {
+        multiples.sum()
+    } else {
+        multiples.max().unwrap_or(0)
+    }
+}
+
+/// The yakuman a complete hand scores, or an empty list if it scores none
+/// or is not complete.
+///
+/// When a hand can be read in several ways, the reading with the highest
+/// total under `rules` is used.
+pub fn yakuman(hand: &Hand, context: &WinContext, rules: &RuleSet) -> Vec<Yakuman> {
+    let readings = hand.decompose();
+    if readings.is_empty() {
+        return vec![];
+    }
+    let mut common = whole_hand_yakuman(hand, context);
+    let best = readings
+        .iter()
+        .map(|reading| reading_yakuman(reading, context))
+        .max_by_key(|found| {
+            let all: Vec<Yakuman> = common.iter().chain(found).copied().collect();
+            yakuman_multiple(&all, rules)
+        })
+        .unwrap_or_default();
+    common.extend(best);
+    common.sort();
+    common.dedup();
+    common
+}
+
+/// Yakuman that depend only on the tiles, not on how they are grouped.
+fn whole_hand_yakuman(hand: &Hand, context: &WinContext) -> Vec<Yakuman> {
+    let tiles = hand.tiles();
+    let mut found = Vec::new();
+    if tiles.iter().all(MahjongTile::is_honor) {
+        found.push(Yakuman::Tsuuiisou);
+    }
+    if tiles.iter().all(MahjongTile::is_terminal) {
+        found.push(Yakuman::Chinroutou);
+    }
+    if tiles.iter().all(is_green) {
+        found.push(Yakuman::Ryuuiisou);
+    }
+    if let Some(pure) = nine_gates(hand, context.winning_tile) {
+        found.push(if pure {
+            Yakuman::JunseiChuurenPoutou
+        } else {
+            Yakuman::ChuurenPoutou
+        });
+    }
+    if context.first_draw && context.win_type == WinType::Tsumo && hand.melds.is_empty() {
+        found.push(if context.dealer {
+            Yakuman::Tenhou
+        } else {
+            Yakuman::Chiihou
+        });
+    }
+    found
+}
+
+/// Yakuman that depend on how the hand is read.
+fn reading_yakuman(reading: &HandDecomposition, context: &WinContext) -> Vec<Yakuman> {
+    let winning = context.winning_tile;
+    let (pair, groups) = match reading {
+        HandDecomposition::ThirteenOrphans { pair } => {
+            return vec![if pair.eq_rank(&winning) {
+                Yakuman::KokushiMusouJuusanmen
+            } else {
+                Yakuman::KokushiMusou
+            }];
+        }
+        HandDecomposition::SevenPairs(_) => return vec![],
+        HandDecomposition::Standard { pair, groups } => (pair, groups),
+    };
+    let sets: Vec<&Group> = groups
+        .iter()
+        .filter(|g| g.kind != GroupKind::Sequence)
+        .collect();
+    let mut found = Vec::new();
+
+    let tanki = pair.eq_rank(&winning);
+    let mut concealed = sets.iter().filter(|g| !g.open).count();
+    // A triplet completed by ron counts as open, unless the hand could also
+    // have been waiting on the pair.
+    if context.win_type == WinType::Ron
+        && !tanki
+        && sets.iter().any(|g| !g.open && g.tile.eq_rank(&winning))
+    {
+        concealed -= 1;
+    }
+    if concealed == 4 {
+        found.push(if tanki {
+            Yakuman::SuuankouTanki
+        } else {
+            Yakuman::Suuankou
+        });
+    }
+
+    let has_set = |tile: MahjongTile| sets.iter().any(|g| g.tile == tile);
+    if [tile::WHITE_DRAGON, tile::GREEN_DRAGON, tile::RED_DRAGON]
+        .into_iter()
+        .all(has_set)
+    {
+        found.push(Yakuman::Daisangen);
+    }
+    let winds = [tile::EAST, tile::SOUTH, tile::WEST, tile::NORTH];
+    let wind_sets = winds.into_iter().filter(|&w| has_set(w)).count();
+    if wind_sets == 4 {
+        found.push(Yakuman::Daisuushii);
+    } else if wind_sets == 3 && winds.contains(pair) {
+        found.push(Yakuman::Shousuushii);
+    }
+    if sets.iter().filter(|g| g.kind == GroupKind::Quad).count() == 4 {
+        found.push(Yakuman::Suukantsu);
+    }
+    found
+}
+
+fn is_green(tile: &MahjongTile) -> bool {
+    match tile {
+        MahjongTile::Number(n) => {
+            n.number_suit() == NumberSuit::Sou && matches!(n.number(), 2 | 3 | 4 | 6 | 8)
+        }
+        MahjongTile::Honor(h) => *h == HonorTile::Dragon(DragonColor::Green),
+    }
+}
+
+/// Whether a closed hand is nine gates, and if so whether it was the pure
+/// nine-sided wait.
+fn nine_gates(hand: &Hand, winning: MahjongTile) -> Option<bool> {
+    const GATES: [u8; 9] = [3, 1, 1, 1, 1, 1, 1, 1, 3];
+    if !hand.melds.is_empty() || hand.concealed.len() != 14 {
+        return None;
+    }
+    let suit = hand.concealed[0].as_number()?.number_suit();
+    let mut counts = [0u8; 9];
+    for tile in &hand.concealed {
+        let number = tile.as_number().filter(|n| n.number_suit() == suit)?;
+        counts[number.number() as usize - 1] += 1;
+    }
+    if counts.iter().zip(GATES).any(|(&c, g)| c < g) {
+        return None;
+    }
+    let extra = winning.as_number()?.number() as usize - 1;
+    counts[extra] -= 1;
+    Some(counts == GATES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::build;
+
+    fn ron(tile: &str) -> WinContext {
+        WinContext {
+            winning_tile: build(tile).unwrap(),
+            win_type: WinType::Ron,
+            dealer: false,
+            first_draw: false,
+        }
+    }
+
+    fn tsumo(tile: &str) -> WinContext {
+        WinContext {
+            win_type: WinType::Tsumo,
+            ..ron(tile)
+        }
+    }
+
+    fn find(hand: &str, context: WinContext) -> Vec<Yakuman> {
+        yakuman(&Hand::parse(hand).unwrap(), &context, &RuleSet::default())
+    }
+
+    #[test]
+    fn kokushi() {
+        let hand = "19m19p19s12345677z";
+        assert_eq!(find(hand, ron("1m")), vec![Yakuman::KokushiMusou]);
+        assert_eq!(find(hand, ron("7z")), vec![Yakuman::KokushiMusouJuusanmen]);
+        let plain = RuleSet {
+            double_yakuman: false,
+            ..RuleSet::default()
+        };
+        assert_eq!(Yakuman::KokushiMusouJuusanmen.multiple(&plain), 1);
+    }
+
+    #[test]
+    fn seven_pairs_are_not_concealed_triplets() {
+        assert!(find("1133557799m1122z", tsumo("1z")).is_empty());
+        assert_eq!(
+            find("11223344556677z", tsumo("1z")),
+            vec![Yakuman::Tsuuiisou]
+        );
+    }
+
+    #[test]
+    fn suuankou_depends_on_the_wait() {
+        let hand = "111m333p555s777z22z";
+        assert_eq!(find(hand, tsumo("1m")), vec![Yakuman::Suuankou]);
+        // Ron on a shanpon wait leaves only three concealed triplets.
+        assert!(find(hand, ron("1m")).is_empty());
+        assert_eq!(find(hand, ron("2z")), vec![Yakuman::SuuankouTanki]);
+        assert!(find("111m333p555s22z + p777z", tsumo("1m")).is_empty());
+        assert_eq!(
+            find("333p555s22z + ck1111m + ck7777z", tsumo("2z")),
+            vec![Yakuman::SuuankouTanki]
+        );
+    }
+
+    #[test]
+    fn dragons_and_winds() {
+        assert_eq!(
+            find("555666z123m44p + p777z", ron("4p")),
+            vec![Yakuman::Daisangen]
+        );
+        assert_eq!(
+            find("111222333z44z + c123m", ron("4z")),
+            vec![Yakuman::Shousuushii]
+        );
+        // Three wind triplets need a wind pair.
+        assert!(find("111222333z55z + c123m", ron("5z")).is_empty());
+        assert_eq!(
+            find("11m + p111z + p222z + p333z + p444z", ron("1m")),
+            vec![Yakuman::Daisuushii]
+        );
+    }
+
+    #[test]
+    fn tile_colour_yakuman() {
+        assert_eq!(find("11223344556677z", ron("7z")), vec![Yakuman::Tsuuiisou]);
+        assert_eq!(
+            find("111999m111p99s + p999p", ron("9s")),
+            vec![Yakuman::Chinroutou]
+        );
+        assert_eq!(
+            find("22334466688s666z", ron("8s")),
+            vec![Yakuman::Ryuuiisou]
+        );
+    }
+
+    #[test]
+    fn nine_gates() {
+        assert_eq!(
+            find("11123455678999p", ron("5p")),
+            vec![Yakuman::JunseiChuurenPoutou]
+        );
+        assert_eq!(
+            find("11123455678999p", ron("3p")),
+            vec![Yakuman::ChuurenPoutou]
+        );
+        assert!(find("11123456789999p", ron("9p")).contains(&Yakuman::JunseiChuurenPoutou));
+        assert!(find("11123455678999p", ron("5p"))
+            .iter()
+            .all(|&y| y != Yakuman::ChuurenPoutou));
+    }
+
+    #[test]
+    fn suukantsu_and_first_draw() {
+        assert_eq!(
+            find(
+                "11z + k1111m + ck2222p + k3333s(across) + ak4444z(right)",
+                ron("1z")
+            ),
+            vec![Yakuman::Suukantsu]
+        );
+        let first = WinContext {
+            first_draw: true,
+            dealer: true,
+            ..tsumo("1z")
+        };
+        assert_eq!(find("123m456p789s11z234s", first), vec![Yakuman::Tenhou]);
+        let first = WinContext {
+            dealer: false,
+            ..first
+        };
+        assert_eq!(find("123m456p789s11z234s", first), vec![Yakuman::Chiihou]);
+    }
+
+    #[test]
+    fn multiples_follow_the_rules() {
+        let found = find("111222333z44z + p555z", tsumo("4z"));
+        assert_eq!(found, vec![Yakuman::Shousuushii, Yakuman::Tsuuiisou]);
+        let rules = RuleSet::default();
+        assert_eq!(yakuman_multiple(&found, &rules), 2);
+        let single = RuleSet {
+            stack_yakuman: false,
+            ..rules.clone()
+        };
+        assert_eq!(yakuman_multiple(&found, &single), 1);
+        let tanki = [Yakuman::SuuankouTanki, Yakuman::Tsuuiisou];
+        assert_eq!(yakuman_multiple(&tanki, &rules), 3);
+        let plain = RuleSet {
+            double_yakuman: false,
+            ..rules
+        };
+        assert_eq!(yakuman_multiple(&tanki, &plain), 2);
+        assert_eq!(yakuman_multiple(&[], &plain), 0);
+    }
+
+    #[test]
+    fn incomplete_hands_score_nothing() {
+        assert!(find("19m19p19s1234567z", ron("1m")).is_empty());
+    }
+}